@@ -0,0 +1,67 @@
+//! ADC oneshot auto-ranging example, reading a pin at the best attenuation for its voltage
+//! requires ESP-IDF v5.0 or newer
+//!
+//! The pin is read without attenuation first, which gives the best resolution for low
+//! voltages. If that reading is saturated, the pin is read again with an attenuation of 11dB,
+//! which covers the full input voltage range.
+
+use std::thread;
+use std::time::Duration;
+
+#[cfg(not(esp_idf_version_major = "4"))]
+fn main() -> anyhow::Result<()> {
+    use esp_idf_hal::adc::attenuation::{DB_11, NONE};
+    use esp_idf_hal::adc::oneshot::config::AdcChannelConfig;
+    use esp_idf_hal::adc::oneshot::*;
+    use esp_idf_hal::peripherals::Peripherals;
+
+    // Raw readings from this value on are considered saturated
+    const SATURATED: u16 = 4000;
+
+    esp_idf_hal::sys::link_patches();
+
+    let peripherals = Peripherals::take()?;
+
+    let adc = AdcDriver::new(peripherals.adc1)?;
+
+    let config = AdcChannelConfig {
+        attenuation: NONE,
+        calibration: true,
+        ..Default::default()
+    };
+
+    #[cfg(not(esp32))]
+    let mut adc_pin = AdcChannelDriver::new(&adc, peripherals.pins.gpio2, &config)?;
+
+    #[cfg(esp32)]
+    let mut adc_pin = AdcChannelDriver::new(&adc, peripherals.pins.gpio34, &config)?;
+
+    loop {
+        adc_pin.set_attenuation(NONE)?;
+
+        let mut raw = adc_pin.read_raw()?;
+
+        if raw >= SATURATED {
+            adc_pin.set_attenuation(DB_11)?;
+
+            raw = adc_pin.read_raw()?;
+        }
+
+        println!(
+            "ADC value: {}mV (attenuation {})",
+            adc_pin.raw_to_cal(raw)?,
+            adc_pin.attenuation()
+        );
+
+        thread::sleep(Duration::from_millis(500));
+    }
+}
+
+#[cfg(esp_idf_version_major = "4")]
+fn main() -> anyhow::Result<()> {
+    println!("This example requires ESP-IDF v5.0 or newer");
+
+    loop {
+        thread::sleep(Duration::from_millis(1000));
+    }
+}
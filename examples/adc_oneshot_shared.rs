@@ -0,0 +1,65 @@
+//! ADC oneshot example, reading two pins of the same ADC unit from two threads
+//! requires ESP-IDF v5.0 or newer
+//!
+//! Each thread owns a channel driver borrowing the same ADC unit driver, which serializes
+//! the conversions of the two threads.
+
+use std::thread;
+use std::time::Duration;
+
+#[cfg(not(esp_idf_version_major = "4"))]
+fn main() -> anyhow::Result<()> {
+    use esp_idf_hal::adc::attenuation::DB_11;
+    use esp_idf_hal::adc::oneshot::config::AdcChannelConfig;
+    use esp_idf_hal::adc::oneshot::*;
+    use esp_idf_hal::peripherals::Peripherals;
+
+    esp_idf_hal::sys::link_patches();
+
+    let peripherals = Peripherals::take()?;
+
+    let adc = AdcDriver::new(peripherals.adc1)?;
+
+    let config = AdcChannelConfig {
+        attenuation: DB_11,
+        calibration: true,
+        ..Default::default()
+    };
+
+    #[cfg(not(esp32))]
+    let (pin_a, pin_b) = (peripherals.pins.gpio2, peripherals.pins.gpio3);
+
+    #[cfg(esp32)]
+    let (pin_a, pin_b) = (peripherals.pins.gpio34, peripherals.pins.gpio35);
+
+    let mut channel_a = AdcChannelDriver::new(&adc, pin_a, &config)?;
+    let mut channel_b = AdcChannelDriver::new(&adc, pin_b, &config)?;
+
+    thread::scope(|s| {
+        let a = s.spawn(move || -> anyhow::Result<()> {
+            loop {
+                println!("ADC value A: {}", channel_a.read()?);
+                thread::sleep(Duration::from_millis(100));
+            }
+        });
+
+        let b = s.spawn(move || -> anyhow::Result<()> {
+            loop {
+                println!("ADC value B: {}", channel_b.read()?);
+                thread::sleep(Duration::from_millis(150));
+            }
+        });
+
+        a.join().unwrap()?;
+        b.join().unwrap()
+    })
+}
+
+#[cfg(esp_idf_version_major = "4")]
+fn main() -> anyhow::Result<()> {
+    println!("This example requires ESP-IDF v5.0 or newer");
+
+    loop {
+        thread::sleep(Duration::from_millis(1000));
+    }
+}
@@ -0,0 +1,46 @@
+//! DAC continuous output
+//!
+//! Folowing pins are used:
+//! DAC1  GPIO25
+//!
+//! This example is for the ESP32; on the ESP32-S2 the first DAC channel is on GPIO17, and
+//! the DMA of SPI3 is used instead of the one of I2S0.
+//!
+//! This example streams a 440Hz triangle wave, sampled at 44.1kHz, to the first DAC channel.
+
+#[cfg(esp32)]
+fn main() -> anyhow::Result<()> {
+    use esp_idf_hal::dac::continuous::{config::Config, DacContinuousDriver};
+    use esp_idf_hal::peripherals::Peripherals;
+    use esp_idf_hal::prelude::*;
+
+    esp_idf_hal::sys::link_patches();
+
+    let peripherals = Peripherals::take()?;
+
+    let config = Config::new().sample_rate(44_100.Hz());
+    let mut dac = DacContinuousDriver::new(peripherals.i2s0, peripherals.pins.gpio25, &config)?;
+
+    // About one period of a 440Hz tone
+    let mut period = [0_u8; 100];
+    for (index, sample) in period.iter_mut().enumerate() {
+        *sample = if index < 50 {
+            (index * 255 / 50) as u8
+        } else {
+            ((100 - index) * 255 / 50) as u8
+        };
+    }
+
+    println!("Streaming a triangle wave");
+
+    loop {
+        dac.write_all(&period)?;
+    }
+}
+
+#[cfg(not(esp32))]
+fn main() {
+    esp_idf_hal::sys::link_patches();
+
+    println!("This example is for the ESP32 only");
+}
@@ -0,0 +1,57 @@
+//! DAC cosine generator
+//!
+//! Folowing pins are used:
+//! DAC1  GPIO25
+//! DAC2  GPIO26
+//!
+//! This example is for the ESP32; on the ESP32-S2 the DAC channels are on GPIO17 and GPIO18.
+//!
+//! This example outputs a 1kHz tone on the first DAC channel, and the same tone in opposite
+//! phase and at a quarter of the amplitude on the second one, for e.g. a differential beeper.
+//! Every few seconds the tone is switched off, and the channels output a constant voltage.
+
+#[cfg(esp32)]
+fn main() -> anyhow::Result<()> {
+    use esp_idf_hal::dac::config::*;
+    use esp_idf_hal::dac::DacDriver;
+    use esp_idf_hal::delay::FreeRtos;
+    use esp_idf_hal::peripherals::Peripherals;
+    use esp_idf_hal::prelude::*;
+
+    esp_idf_hal::sys::link_patches();
+
+    let peripherals = Peripherals::take()?;
+
+    let mut dac1 = DacDriver::new(peripherals.pins.gpio25)?;
+    let mut dac2 = DacDriver::new(peripherals.pins.gpio26)?;
+
+    dac1.write(128)?;
+    dac2.write(128)?;
+
+    loop {
+        let config = CosineConfig::new().frequency(1.kHz().into());
+
+        let frequency = dac1.enable_cosine(&config)?;
+        dac2.enable_cosine(
+            &config
+                .scale(CosineScale::Quarter)
+                .phase(CosinePhase::Phase180),
+        )?;
+
+        println!("Outputting a tone of {frequency}");
+        FreeRtos::delay_ms(3000);
+
+        dac1.disable_cosine()?;
+        dac2.disable_cosine()?;
+
+        println!("Tone off");
+        FreeRtos::delay_ms(3000);
+    }
+}
+
+#[cfg(not(esp32))]
+fn main() {
+    esp_idf_hal::sys::link_patches();
+
+    println!("This example is for the ESP32 only");
+}
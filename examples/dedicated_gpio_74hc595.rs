@@ -0,0 +1,67 @@
+//! Shifts out data to a 74HC595 shift register using a dedicated GPIO bundle
+//!
+//! This assumes that the 74HC595 is connected as follows:
+//! - SER (serial data input) to GPIO4
+//! - SRCLK (shift register clock) to GPIO5
+//! - RCLK (storage register / latch clock) to GPIO6
+//!
+//! As all three pins are mapped into the dedicated GPIO peripheral and written
+//! with single CPU instructions, the data is shifted out at several MHz.
+//!
+//! Requires a chip with dedicated GPIO support (esp32s2, esp32s3, esp32c2, esp32c3, esp32c6 or esp32h2).
+//! Depending on your target and the board you are using you should change the pins.
+
+use esp_idf_hal::delay::FreeRtos;
+
+#[cfg(esp_idf_soc_dedicated_gpio_supported)]
+fn main() -> anyhow::Result<()> {
+    use esp_idf_hal::gpio::dedicated::{Bundle, Direction};
+    use esp_idf_hal::gpio::IOPin;
+    use esp_idf_hal::peripherals::Peripherals;
+
+    const SER: u8 = 1 << 0;
+    const SRCLK: u8 = 1 << 1;
+    const RCLK: u8 = 1 << 2;
+
+    esp_idf_hal::sys::link_patches();
+
+    let peripherals = Peripherals::take()?;
+
+    let mut bundle = Bundle::new(
+        [
+            peripherals.pins.gpio4.downgrade(),
+            peripherals.pins.gpio5.downgrade(),
+            peripherals.pins.gpio6.downgrade(),
+        ],
+        Direction::Output,
+    )?;
+
+    let mut value: u8 = 1;
+
+    loop {
+        for bit in (0..8).rev() {
+            let ser = if value & (1 << bit) != 0 { SER } else { 0 };
+
+            bundle.write(ser);
+            bundle.write(ser | SRCLK);
+        }
+
+        // Latch the shifted-out byte to the outputs of the 74HC595
+        bundle.write(RCLK);
+        bundle.write(0);
+
+        value = value.rotate_left(1);
+
+        // we are sleeping here to make sure the watchdog isn't triggered
+        FreeRtos::delay_ms(100);
+    }
+}
+
+#[cfg(not(esp_idf_soc_dedicated_gpio_supported))]
+fn main() -> anyhow::Result<()> {
+    println!("This example requires a chip with dedicated GPIO support");
+
+    loop {
+        FreeRtos::delay_ms(1000);
+    }
+}
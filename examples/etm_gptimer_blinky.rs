@@ -0,0 +1,88 @@
+//! Blinks an LED from a GPTimer alarm via the Event Task Matrix (ETM), without any ISR
+//!
+//! This assumes that a LED is connected to GPIO4.
+//!
+//! The alarm event of the timer is connected to a "toggle" task of the LED pin, so once
+//! the timer is started, the LED blinks with no CPU involvement at all.
+//!
+//! Requires a chip with ETM support (i.e. esp32c6 or esp32h2) and ESP-IDF 5.1 or later.
+//! Depending on your target and the board you are using you should change the pin.
+//! If your board doesn't have on-board LEDs don't forget to add an appropriate resistor.
+
+use esp_idf_hal::delay::FreeRtos;
+
+#[cfg(all(
+    esp_idf_soc_etm_supported,
+    esp_idf_soc_gpio_support_etm,
+    not(any(esp_idf_version_major = "4", esp_idf_version = "5.0"))
+))]
+fn main() -> anyhow::Result<()> {
+    use esp_idf_hal::etm::{Channel, RawEvent};
+    use esp_idf_hal::gpio::etm::{EtmAction, EtmTask};
+    use esp_idf_hal::gpio::PinDriver;
+    use esp_idf_hal::peripherals::Peripherals;
+    use esp_idf_hal::sys::*;
+
+    esp_idf_hal::sys::link_patches();
+
+    let peripherals = Peripherals::take()?;
+    let mut led = PinDriver::output(peripherals.pins.gpio4)?;
+
+    // A 1MHz timer, with an auto-reloading alarm every 500ms
+    let mut timer: gptimer_handle_t = core::ptr::null_mut();
+
+    let timer_config = gptimer_config_t {
+        clk_src: soc_periph_gptimer_clk_src_t_GPTIMER_CLK_SRC_DEFAULT,
+        direction: gptimer_count_direction_t_GPTIMER_COUNT_UP,
+        resolution_hz: 1_000_000,
+        ..Default::default()
+    };
+
+    esp!(unsafe { gptimer_new_timer(&timer_config, &mut timer) })?;
+
+    let alarm_config = gptimer_alarm_config_t {
+        alarm_count: 500_000,
+        reload_count: 0,
+        flags: gptimer_alarm_config_t__bindgen_ty_1 {
+            _bitfield_1: gptimer_alarm_config_t__bindgen_ty_1::new_bitfield_1(1),
+            ..Default::default()
+        },
+    };
+
+    esp!(unsafe { gptimer_set_alarm_action(timer, &alarm_config) })?;
+
+    let event_config = gptimer_etm_event_config_t {
+        event_type: gptimer_etm_event_type_t_GPTIMER_ETM_EVENT_ALARM_MATCH,
+    };
+
+    let mut event = core::ptr::null_mut();
+    esp!(unsafe { gptimer_new_etm_event(timer, &event_config, &mut event) })?;
+
+    let alarm = unsafe { RawEvent::new(event) };
+    let toggle = EtmTask::new(&mut led, EtmAction::Toggle)?;
+
+    let _channel = Channel::connect(&alarm, &toggle)?;
+
+    esp!(unsafe { gptimer_enable(timer) })?;
+    esp!(unsafe { gptimer_start(timer) })?;
+
+    println!("LED blinking via ETM");
+
+    loop {
+        // The CPU is free to do anything else here
+        FreeRtos::delay_ms(1000);
+    }
+}
+
+#[cfg(not(all(
+    esp_idf_soc_etm_supported,
+    esp_idf_soc_gpio_support_etm,
+    not(any(esp_idf_version_major = "4", esp_idf_version = "5.0"))
+)))]
+fn main() -> anyhow::Result<()> {
+    println!("This example requires a chip with ETM support and ESP-IDF 5.1 or later");
+
+    loop {
+        FreeRtos::delay_ms(1000);
+    }
+}
@@ -0,0 +1,108 @@
+//! I2C command link benchmark
+//!
+//! Folowing pins are used:
+//! SDA     GPIO5
+//! SCL     GPIO6
+//!
+//! Depending on your target and the board you are using you have to change the pins.
+//!
+//! For this example you need to hook up any I2C device. The example scans the bus, and then
+//! repeatedly reads one byte from the first device found, first with command links allocated
+//! from the heap for each transaction, and then with command links placed in the buffer
+//! preallocated by the driver, which need no heap allocations in steady state.
+//!
+//! The heap allocations done while reading are counted with the ESP-IDF heap hooks, which
+//! require `CONFIG_HEAP_USE_HOOKS=y` in sdkconfig.
+
+use std::time::Instant;
+
+use esp_idf_hal::delay::{FreeRtos, BLOCK};
+use esp_idf_hal::i2c::*;
+use esp_idf_hal::peripheral::Peripheral;
+use esp_idf_hal::peripherals::Peripherals;
+use esp_idf_hal::prelude::*;
+
+const ROUNDS: u32 = 1000;
+
+fn main() -> anyhow::Result<()> {
+    esp_idf_hal::sys::link_patches();
+
+    let peripherals = Peripherals::take()?;
+    let mut i2c = peripherals.i2c0;
+    let mut sda = peripherals.pins.gpio5;
+    let mut scl = peripherals.pins.gpio6;
+
+    loop {
+        for buffer_size in [0, I2cConfig::new().command_link_buffer_size] {
+            let config = I2cConfig::new()
+                .baudrate(400.kHz().into())
+                .command_link_buffer_size(buffer_size);
+
+            let mut driver = I2cDriver::new(
+                unsafe { i2c.clone_unchecked() },
+                unsafe { sda.clone_unchecked() },
+                unsafe { scl.clone_unchecked() },
+                &config,
+            )?;
+
+            let Some(&addr) = driver.scan()?.first() else {
+                println!("No I2C device found");
+                break;
+            };
+
+            let mut byte = [0_u8; 1];
+            let allocations_before = allocations::count();
+            let start = Instant::now();
+
+            for _ in 0..ROUNDS {
+                driver.write_read(addr, &[0], &mut byte, BLOCK)?;
+            }
+
+            let elapsed = start.elapsed();
+            let allocations = allocations::count()
+                .zip(allocations_before)
+                .map(|(after, before)| after.wrapping_sub(before));
+
+            println!(
+                "Buffer of {buffer_size} bytes: {ROUNDS} reads from {addr:#04x} in {elapsed:?} ({:?} per read), heap allocations: {}",
+                elapsed / ROUNDS,
+                allocations.map_or_else(
+                    || "not counted (CONFIG_HEAP_USE_HOOKS disabled)".to_string(),
+                    |count| count.to_string()
+                ),
+            );
+        }
+
+        // we are using thread::sleep here to make sure the watchdog isn't triggered
+        FreeRtos::delay_ms(1000);
+    }
+}
+
+#[cfg(esp_idf_heap_use_hooks)]
+mod allocations {
+    use core::ffi::c_void;
+    use core::sync::atomic::{AtomicU32, Ordering};
+
+    static ALLOCATIONS: AtomicU32 = AtomicU32::new(0);
+
+    /// Called by ESP-IDF on every heap allocation, by any task (or ISR)
+    #[no_mangle]
+    extern "C" fn esp_heap_trace_alloc_hook(_ptr: *mut c_void, _size: usize, _caps: u32) {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[no_mangle]
+    extern "C" fn esp_heap_trace_free_hook(_ptr: *mut c_void) {}
+
+    /// Returns the number of heap allocations done so far
+    pub fn count() -> Option<u32> {
+        Some(ALLOCATIONS.load(Ordering::Relaxed))
+    }
+}
+
+#[cfg(not(esp_idf_heap_use_hooks))]
+mod allocations {
+    pub fn count() -> Option<u32> {
+        None
+    }
+}
@@ -0,0 +1,55 @@
+//! Reads an I2C temperature sensor over the LP I2C controller of the esp32c6,
+//! right before entering light sleep
+//!
+//! Folowing pins are used (these are fixed for the LP I2C controller):
+//! SDA     GPIO6
+//! SCL     GPIO7
+//!
+//! For this example you need to hook up a TMP102 (or a compatible, e.g. LM75) sensor,
+//! at address 0x48.
+
+#![allow(unused)]
+
+use esp_idf_hal::delay::BLOCK;
+use esp_idf_hal::i2c::*;
+use esp_idf_hal::peripherals::Peripherals;
+use esp_idf_hal::prelude::*;
+use esp_idf_hal::sys::{esp, esp_light_sleep_start, esp_sleep_enable_timer_wakeup};
+
+const SENSOR_ADDR: u8 = 0x48;
+const TEMPERATURE_REG: u8 = 0x00;
+
+#[cfg(not(esp32c6))]
+fn main() -> anyhow::Result<()> {
+    println!("The LP I2C controller is only available on the esp32c6");
+    Ok(())
+}
+
+#[cfg(esp32c6)]
+fn main() -> anyhow::Result<()> {
+    esp_idf_hal::sys::link_patches();
+
+    let peripherals = Peripherals::take()?;
+
+    let config = I2cConfig::new().baudrate(100.kHz().into());
+    let mut i2c = I2cDriver::new(
+        peripherals.lp_i2c0,
+        peripherals.pins.gpio6,
+        peripherals.pins.gpio7,
+        &config,
+    )?;
+
+    loop {
+        let mut raw = [0_u8; 2];
+        i2c.write_read(SENSOR_ADDR, &[TEMPERATURE_REG], &mut raw, BLOCK)?;
+
+        // 12-bit two's complement, 0.0625°C per LSB
+        let temperature = (i16::from_be_bytes(raw) >> 4) as f32 * 0.0625;
+        println!("Temperature: {temperature:.2}°C, sleeping for 5s");
+
+        unsafe {
+            esp!(esp_sleep_enable_timer_wakeup(5_000_000))?;
+            esp!(esp_light_sleep_start())?;
+        }
+    }
+}
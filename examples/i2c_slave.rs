@@ -0,0 +1,66 @@
+//! I2C slave example, exposing 16 one-byte registers to an I2C master on another board
+//!
+//! Folowing pins are used:
+//! SDA    GPIO21
+//! SCL    GPIO22
+//!
+//! Depending on your target and the board you are using you have to change the pins.
+//!
+//! Connect SDA, SCL and GND to the respective pins of the board acting as the I2C master
+//! (with pull-up resistors on SDA and SCL). The master either writes a register address
+//! followed by a value to store in the register, e.g. `i2c.write(0x22, &[5, 0x42], BLOCK)`, or
+//! writes a register address and then reads the value of the register, e.g.
+//! `i2c.write(0x22, &[5], BLOCK)` followed by `i2c.read(0x22, &mut buf, BLOCK)`.
+
+use esp_idf_hal::delay::{TickType, BLOCK};
+use esp_idf_hal::i2c::{I2cSlaveConfig, I2cSlaveDriver};
+use esp_idf_hal::peripherals::Peripherals;
+
+use core::time::Duration;
+
+const SLAVE_ADDR: u8 = 0x22;
+
+fn main() -> anyhow::Result<()> {
+    esp_idf_hal::sys::link_patches();
+
+    let peripherals = Peripherals::take()?;
+
+    let config = I2cSlaveConfig::new()
+        .rx_buffer_length(128)
+        .tx_buffer_length(128);
+
+    let mut slave = I2cSlaveDriver::new(
+        peripherals.i2c0,
+        peripherals.pins.gpio21,
+        peripherals.pins.gpio22,
+        SLAVE_ADDR,
+        &config,
+    )?;
+
+    println!("I2C slave listening on address {SLAVE_ADDR:#04x}");
+
+    let mut registers = [0_u8; 16];
+    let short = TickType::from(Duration::from_millis(10)).ticks();
+
+    loop {
+        // Blocks until the master writes something
+        let mut request = [0_u8; 2];
+        let len = slave.read(&mut request[..1], BLOCK)?;
+
+        if len == 0 {
+            continue;
+        }
+
+        let reg = request[0] as usize % registers.len();
+
+        // A value, if any, follows the register address in the same write
+        if slave.read(&mut request[1..], short).is_ok() {
+            registers[reg] = request[1];
+            println!("Register {reg} set to {:#04x}", request[1]);
+        } else {
+            // No value, so the master is about to read the register
+            slave.write(&registers[reg..reg + 1], BLOCK)?;
+            println!("Register {reg} read");
+        }
+    }
+}
@@ -0,0 +1,97 @@
+//! Two `IsrRawMutex` instances held at the same time from the two cores
+//! requires a dual-core chip (ESP32 or ESP32-S3) and the `embassy-sync` feature
+//!
+//! Each thread is pinned to its own core, locks its own mutex, and then - while still holding
+//! it - waits until the other thread holds the other mutex as well. As each mutex has its own
+//! spinlock, both threads get there. Were the two mutexes backed by the same critical section,
+//! the second thread would spin on the lock the first one holds, and both threads would give up
+//! waiting after `SPINS`.
+
+use std::thread;
+use std::time::Duration;
+
+#[cfg(all(any(esp32, esp32s3), feature = "embassy-sync"))]
+fn main() -> anyhow::Result<()> {
+    use core::cell::Cell;
+    use core::sync::atomic::{AtomicU32, Ordering};
+
+    use embassy_sync::blocking_mutex::Mutex;
+
+    use esp_idf_hal::cpu::Core;
+    use esp_idf_hal::interrupt::embassy_sync::IsrRawMutex;
+    use esp_idf_hal::task::thread::ThreadSpawnConfiguration;
+
+    const ROUNDS: u32 = 1000;
+    const SPINS: u32 = 10_000_000;
+
+    static MUTEX_A: Mutex<IsrRawMutex, Cell<u32>> = Mutex::new(Cell::new(0));
+    static MUTEX_B: Mutex<IsrRawMutex, Cell<u32>> = Mutex::new(Cell::new(0));
+
+    // The number of times the two threads got hold of their mutex, over all rounds
+    static HELD: AtomicU32 = AtomicU32::new(0);
+
+    fn run(mutex: &'static Mutex<IsrRawMutex, Cell<u32>>) -> u32 {
+        let mut timeouts = 0;
+
+        for round in 0..ROUNDS {
+            mutex.lock(|counter| {
+                counter.set(counter.get() + 1);
+
+                HELD.fetch_add(1, Ordering::SeqCst);
+
+                let mut spins = 0;
+                while HELD.load(Ordering::SeqCst) < (round + 1) * 2 {
+                    spins += 1;
+                    if spins == SPINS {
+                        timeouts += 1;
+                        break;
+                    }
+                }
+            });
+        }
+
+        timeouts
+    }
+
+    esp_idf_hal::sys::link_patches();
+
+    let spawn = |name: &'static [u8], core: Core, mutex| -> anyhow::Result<_> {
+        ThreadSpawnConfiguration {
+            name: Some(name),
+            pin_to_core: Some(core),
+            ..Default::default()
+        }
+        .set()?;
+
+        Ok(thread::Builder::new()
+            .stack_size(4096)
+            .spawn(move || run(mutex))?)
+    };
+
+    let thread_a = spawn(b"mutex-a\0", Core::Core0, &MUTEX_A)?;
+    let thread_b = spawn(b"mutex-b\0", Core::Core1, &MUTEX_B)?;
+
+    ThreadSpawnConfiguration::default().set()?;
+
+    let timeouts_a = thread_a.join().unwrap();
+    let timeouts_b = thread_b.join().unwrap();
+
+    println!(
+        "Rounds: {ROUNDS}, counters: {}/{}, rounds without the other mutex held at the same time: {timeouts_a}/{timeouts_b}",
+        MUTEX_A.lock(Cell::get),
+        MUTEX_B.lock(Cell::get),
+    );
+
+    loop {
+        thread::sleep(Duration::from_millis(1000));
+    }
+}
+
+#[cfg(not(all(any(esp32, esp32s3), feature = "embassy-sync")))]
+fn main() -> anyhow::Result<()> {
+    println!("This example requires a dual-core chip and the `embassy-sync` feature");
+
+    loop {
+        thread::sleep(Duration::from_millis(1000));
+    }
+}
@@ -0,0 +1,39 @@
+//! LEDC async breathing effect
+//!
+//! Folowing pins are used:
+//! LED   GPIO4
+//!
+//! This example chains hardware fades into a breathing effect, from a single async task.
+
+use esp_idf_hal::ledc::*;
+use esp_idf_hal::peripherals::Peripherals;
+use esp_idf_hal::prelude::*;
+use esp_idf_hal::sys::EspError;
+use esp_idf_hal::task::block_on;
+
+fn main() -> anyhow::Result<()> {
+    esp_idf_hal::sys::link_patches();
+
+    let peripherals = Peripherals::take()?;
+    let mut channel = LedcDriver::new(
+        peripherals.ledc.channel0,
+        LedcTimerDriver::new(
+            peripherals.ledc.timer0,
+            &config::TimerConfig::new().frequency(5.kHz().into()),
+        )?,
+        peripherals.pins.gpio4,
+    )?;
+
+    block_on(breathe(&mut channel))?;
+
+    Ok(())
+}
+
+async fn breathe(channel: &mut LedcDriver<'_>) -> Result<(), EspError> {
+    let max_duty = channel.get_max_duty();
+
+    loop {
+        channel.fade_with_time_async(max_duty, 1500).await?;
+        channel.fade_with_time_async(0, 1500).await?;
+    }
+}
@@ -0,0 +1,35 @@
+//! LEDC hardware fade
+//!
+//! Folowing pins are used:
+//! LED   GPIO4
+//!
+//! This example fades an LED in and out in hardware, with the CPU only starting each fade.
+
+use esp_idf_hal::ledc::*;
+use esp_idf_hal::peripherals::Peripherals;
+use esp_idf_hal::prelude::*;
+
+fn main() -> anyhow::Result<()> {
+    esp_idf_hal::sys::link_patches();
+
+    let peripherals = Peripherals::take()?;
+    let mut channel = LedcDriver::new(
+        peripherals.ledc.channel0,
+        LedcTimerDriver::new(
+            peripherals.ledc.timer0,
+            &config::TimerConfig::new().frequency(5.kHz().into()),
+        )?,
+        peripherals.pins.gpio4,
+    )?;
+
+    let max_duty = channel.get_max_duty();
+
+    loop {
+        channel.fade_with_time(max_duty, 1000, FadeMode::NonBlocking)?;
+        println!("Fading in");
+        channel.wait_fade_done();
+
+        channel.fade_with_time(0, 1000, FadeMode::Blocking)?;
+        println!("Faded out");
+    }
+}
@@ -0,0 +1,38 @@
+//! LEDC hobby servo
+//!
+//! Folowing pins are used:
+//! SERVO GPIO4
+//!
+//! This example sweeps a hobby servo between 0 and 180 degrees, and prints the actual
+//! pulse widths output for each angle.
+
+use esp_idf_hal::delay::FreeRtos;
+use esp_idf_hal::ledc::servo::{ServoConfig, ServoDriver};
+use esp_idf_hal::peripherals::Peripherals;
+
+fn main() -> anyhow::Result<()> {
+    esp_idf_hal::sys::link_patches();
+
+    let peripherals = Peripherals::take()?;
+    let mut servo = ServoDriver::new(
+        peripherals.ledc.timer0,
+        peripherals.ledc.channel0,
+        peripherals.pins.gpio4,
+        &ServoConfig::new(),
+    )?;
+
+    println!(
+        "Servo at {} with {} bits of resolution",
+        servo.frequency(),
+        servo.resolution()
+    );
+
+    loop {
+        for angle in (0..=180).step_by(30).chain((0..180).step_by(30).rev()) {
+            servo.set_angle(angle as f32)?;
+            println!("{angle} degrees: {:?}", servo.pulse_width());
+
+            FreeRtos::delay_ms(500);
+        }
+    }
+}
@@ -0,0 +1,48 @@
+//! Issues a 1-Wire reset pulse and checks for a presence pulse
+//!
+//! This assumes that the data line of a 1-Wire device (e.g. a DS18B20 temperature sensor)
+//! is connected to GPIO4, with an external 4.7K pull-up resistor to 3.3V.
+//!
+//! The pin is operated in input-output open-drain mode: setting it low pulls the line low,
+//! while setting it high releases the line, which is then pulled up by the resistor
+//! (or pulled low by the 1-Wire device). Reading the pin returns the actual line level.
+//!
+//! Depending on your target and the board you are using you should change the pin.
+
+use esp_idf_hal::delay::{Ets, FreeRtos};
+use esp_idf_hal::gpio::*;
+use esp_idf_hal::peripherals::Peripherals;
+
+fn main() -> anyhow::Result<()> {
+    esp_idf_hal::sys::link_patches();
+
+    let peripherals = Peripherals::take()?;
+    let mut line = PinDriver::input_output_od(peripherals.pins.gpio4)?;
+
+    // The internal pull-up is too weak for 1-Wire on its own, but does not hurt
+    line.set_pull(Pull::Up)?;
+    line.set_high()?;
+
+    loop {
+        // Reset pulse: pull the line low for at least 480us, then release it
+        line.set_low()?;
+        Ets::delay_us(480);
+        line.set_high()?;
+
+        // A present device pulls the line low 15us - 60us after the release, for 60us - 240us
+        Ets::delay_us(70);
+        let present = line.is_low();
+
+        // Wait for the end of the reset timeslot
+        Ets::delay_us(410);
+
+        if present {
+            println!("1-Wire device present");
+        } else {
+            println!("No 1-Wire device");
+        }
+
+        // we are sleeping here to make sure the watchdog isn't triggered
+        FreeRtos::delay_ms(1000);
+    }
+}
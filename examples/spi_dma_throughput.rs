@@ -0,0 +1,80 @@
+//! SPI DMA throughput test
+//!
+//! Folowing pins are used:
+//! SCLK   GPIO6
+//! SDI    GPIO2
+//! SDO    GPIO7
+//! CS     GPIO10
+//!
+//! Depending on your target and the board you are using you have to change the pins.
+//!
+//! This example writes a 32KB buffer via SPI, first without and then with DMA, and prints
+//! the achieved throughput. Without DMA, the buffer is split into 64 byte transactions, while
+//! with DMA it is split into 4092 byte ones.
+
+use std::time::Instant;
+
+use esp_idf_hal::delay::FreeRtos;
+use esp_idf_hal::dma::DmaBuffer;
+use esp_idf_hal::gpio::AnyIOPin;
+use esp_idf_hal::peripheral::Peripheral;
+use esp_idf_hal::peripherals::Peripherals;
+use esp_idf_hal::prelude::*;
+use esp_idf_hal::spi::*;
+
+const BUF_LEN: usize = 32 * 1024;
+const ROUNDS: usize = 16;
+
+fn main() -> anyhow::Result<()> {
+    esp_idf_hal::sys::link_patches();
+
+    let peripherals = Peripherals::take()?;
+    let mut spi = peripherals.spi2;
+
+    let mut sclk: AnyIOPin = peripherals.pins.gpio6.into();
+    let mut serial_in: AnyIOPin = peripherals.pins.gpio2.into(); // SDI
+    let mut serial_out: AnyIOPin = peripherals.pins.gpio7.into(); // SDO
+    let mut cs: AnyIOPin = peripherals.pins.gpio10.into();
+
+    // Allocated from the DMA-capable heap, so that no bounce buffers are necessary with DMA
+    let mut buf = DmaBuffer::new(BUF_LEN)?;
+    buf.fill(0xa5);
+
+    loop {
+        for dma in [Dma::Disabled, Dma::Auto(4092)] {
+            let driver = SpiDriver::new::<SPI2>(
+                unsafe { spi.clone_unchecked() },
+                unsafe { sclk.clone_unchecked() },
+                unsafe { serial_out.clone_unchecked() },
+                Some(unsafe { serial_in.clone_unchecked() }),
+                &SpiDriverConfig::new().dma(dma),
+            )?;
+
+            let config = config::Config::new()
+                .baudrate(40.MHz().into())
+                .queue_size(4)
+                .polling(false);
+
+            let mut device =
+                SpiDeviceDriver::new(&driver, Some(unsafe { cs.clone_unchecked() }), &config)?;
+
+            let start = Instant::now();
+
+            for _ in 0..ROUNDS {
+                device.write_dma(&buf)?;
+            }
+
+            let elapsed = start.elapsed();
+            let kbytes_per_sec = (BUF_LEN * ROUNDS) as f32 / 1024.0 / elapsed.as_secs_f32();
+
+            println!(
+                "{dma:?} (max transfer size {} bytes): wrote {}KB in {elapsed:?} - {kbytes_per_sec:.0}KB/s",
+                driver.max_transfer_size(),
+                BUF_LEN * ROUNDS / 1024,
+            );
+        }
+
+        // we are using thread::sleep here to make sure the watchdog isn't triggered
+        FreeRtos::delay_ms(1000);
+    }
+}
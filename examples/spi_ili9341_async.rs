@@ -0,0 +1,159 @@
+//! Async SPI example with an ILI9341 display
+//!
+//! Folowing pins are used:
+//! SCLK   GPIO6
+//! SDO    GPIO7
+//! CS     GPIO10
+//! DC     GPIO4
+//! RST    GPIO3
+//!
+//! Depending on your target and the board you are using you have to change the pins.
+//!
+//! The example repeatedly flushes a framebuffer to the display with DMA, using the async
+//! SPI API. While a flush is in progress, the flushing task does not occupy the CPU, so a
+//! second thread keeps running, which is reported after each flush.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use esp_idf_hal::delay::FreeRtos;
+use esp_idf_hal::gpio::*;
+use esp_idf_hal::peripherals::Peripherals;
+use esp_idf_hal::prelude::*;
+use esp_idf_hal::spi::*;
+use esp_idf_hal::sys::EspError;
+use esp_idf_hal::task::block_on;
+
+const WIDTH: usize = 320;
+const HEIGHT: usize = 240;
+const LINES_PER_CHUNK: usize = 40;
+
+const SWRESET: u8 = 0x01;
+const SLPOUT: u8 = 0x11;
+const DISPON: u8 = 0x29;
+const CASET: u8 = 0x2a;
+const PASET: u8 = 0x2b;
+const RAMWR: u8 = 0x2c;
+const MADCTL: u8 = 0x36;
+const COLMOD: u8 = 0x3a;
+
+struct Ili9341<'d, DC: OutputPin> {
+    device: SpiDeviceDriver<'d, SpiDriver<'d>>,
+    dc: PinDriver<'d, DC, Output>,
+}
+
+impl<'d, DC: OutputPin> Ili9341<'d, DC> {
+    async fn command(&mut self, command: u8, data: &[u8]) -> Result<(), EspError> {
+        self.dc.set_low()?;
+        self.device.write_async(&[command]).await?;
+
+        if !data.is_empty() {
+            self.dc.set_high()?;
+            self.device.write_async(data).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn init(&mut self) -> Result<(), EspError> {
+        self.command(SWRESET, &[]).await?;
+        FreeRtos::delay_ms(150);
+        self.command(SLPOUT, &[]).await?;
+        FreeRtos::delay_ms(150);
+        self.command(COLMOD, &[0x55]).await?; // 16 bit RGB565
+        self.command(MADCTL, &[0x28]).await?; // Landscape, BGR
+        self.command(DISPON, &[]).await
+    }
+
+    async fn flush(&mut self, framebuffer: &[u8]) -> Result<(), EspError> {
+        let x_end = (WIDTH - 1) as u16;
+        let y_end = (HEIGHT - 1) as u16;
+
+        self.command(CASET, &[0, 0, (x_end >> 8) as u8, x_end as u8])
+            .await?;
+        self.command(PASET, &[0, 0, (y_end >> 8) as u8, y_end as u8])
+            .await?;
+        self.command(RAMWR, &[]).await?;
+
+        self.dc.set_high()?;
+
+        for chunk in framebuffer.chunks(WIDTH * LINES_PER_CHUNK * 2) {
+            self.device.write_async(chunk).await?;
+        }
+
+        Ok(())
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    esp_idf_hal::sys::link_patches();
+
+    let peripherals = Peripherals::take()?;
+
+    let mut rst = PinDriver::output(peripherals.pins.gpio3)?;
+    let dc = PinDriver::output(peripherals.pins.gpio4)?;
+
+    let device = SpiDeviceDriver::new_single(
+        peripherals.spi2,
+        peripherals.pins.gpio6,
+        peripherals.pins.gpio7,
+        Option::<AnyIOPin>::None,
+        Some(peripherals.pins.gpio10),
+        &SpiDriverConfig::new().dma(Dma::Auto(4092)),
+        &config::Config::new()
+            .baudrate(40.MHz().into())
+            .write_only(true)
+            .queue_size(4)
+            .polling(false),
+    )?;
+
+    rst.set_low()?;
+    FreeRtos::delay_ms(10);
+    rst.set_high()?;
+    FreeRtos::delay_ms(120);
+
+    let mut display = Ili9341 { device, dc };
+
+    // The "other task": a thread which counts the milliseconds it gets to run
+    let progress = Arc::new(AtomicU32::new(0));
+
+    {
+        let progress = progress.clone();
+
+        std::thread::spawn(move || loop {
+            progress.fetch_add(1, Ordering::Relaxed);
+            FreeRtos::delay_ms(1);
+        });
+    }
+
+    // Allocated from the internal heap, so that no bounce buffers are necessary with DMA
+    let mut framebuffer = vec![0_u8; WIDTH * HEIGHT * 2];
+
+    block_on(async {
+        display.init().await?;
+
+        let mut color = 0_u16;
+
+        loop {
+            for pixel in framebuffer.chunks_mut(2) {
+                pixel.copy_from_slice(&color.to_be_bytes());
+            }
+
+            let before = progress.load(Ordering::Relaxed);
+            let start = Instant::now();
+
+            display.flush(&framebuffer).await?;
+
+            let elapsed = start.elapsed();
+            let other = progress.load(Ordering::Relaxed) - before;
+
+            println!("Flushed in {elapsed:?}, the other thread ran {other} times meanwhile");
+
+            color = color.wrapping_add(0x0841);
+
+            // Make sure the watchdog isn't triggered
+            FreeRtos::delay_ms(100);
+        }
+    })
+}
@@ -0,0 +1,69 @@
+//! SPI slave example
+//!
+//! Folowing pins are used:
+//! SCLK   GPIO6
+//! SDI    GPIO7 (MOSI)
+//! SDO    GPIO2 (MISO)
+//! CS     GPIO10
+//!
+//! Depending on your target and the board you are using you have to change the pins.
+//!
+//! This example needs two boards: this one, acting as the SPI slave, and a second one acting
+//! as the SPI master (e.g. running the `spi_loopback` example, with its SCLK, SDO, SDI and CS_1
+//! pins connected to the SCLK, SDI, SDO and CS pins of this board, and with common ground).
+//!
+//! The slave arms two transactions in advance, so that it is ready for the next transaction as
+//! soon as the master has finished the previous one. The bytes received in each slot are sent
+//! back to the master the next time the slot is used.
+
+use esp_idf_hal::delay::BLOCK;
+use esp_idf_hal::peripherals::Peripherals;
+use esp_idf_hal::spi::slave::*;
+use esp_idf_hal::spi::*;
+
+const LEN: usize = 4;
+
+fn main() -> anyhow::Result<()> {
+    esp_idf_hal::sys::link_patches();
+
+    let peripherals = Peripherals::take()?;
+
+    let mut slave = SpiSlaveDriver::new::<SPI2>(
+        peripherals.spi2,
+        peripherals.pins.gpio6,
+        peripherals.pins.gpio7,
+        Some(peripherals.pins.gpio2),
+        peripherals.pins.gpio10,
+        &SpiSlaveConfig::new().queue_size(2).dma(Dma::Auto(64)),
+    )?;
+
+    // With DMA, the buffers should be in internal RAM, and their length should be a multiple of 4
+    let mut rx = [[0_u8; LEN]; 2];
+    let mut tx = [[0_u8; LEN]; 2];
+
+    println!("Starting SPI slave, waiting for the master");
+
+    let mut slot = 0;
+
+    // Arm both slots before the master starts clocking
+    for (rx, tx) in rx.iter_mut().zip(tx.iter()) {
+        unsafe {
+            slave.queue_transaction(rx, tx, BLOCK)?;
+        }
+    }
+
+    loop {
+        let len = slave.wait_transaction(BLOCK)?;
+
+        println!("Received {:x?}", &rx[slot][..len]);
+
+        // Re-arm the slot, while the master clocks the transaction in the other one
+        tx[slot] = rx[slot];
+
+        unsafe {
+            slave.queue_transaction(&mut rx[slot], &tx[slot], BLOCK)?;
+        }
+
+        slot = 1 - slot;
+    }
+}
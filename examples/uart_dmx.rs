@@ -0,0 +1,52 @@
+//! UART DMX512 output
+//!
+//! Folowing pins are used:
+//! TX    GPIO12 (DI of an RS485 transceiver, with DE tied high)
+//!
+//! Depending on your target and the board you are using you have to change the pins.
+//!
+//! This example outputs DMX512 frames at 250Kbaud, 8N2: each frame starts with a break of at
+//! least 88us, followed by a mark of at least 8us, a zero start code and 512 channel values.
+//! The value of the first channel is ramped up, to fade e.g. a dimmer in.
+
+use core::time::Duration;
+
+use esp_idf_hal::delay::{Ets, FreeRtos, BLOCK};
+use esp_idf_hal::gpio;
+use esp_idf_hal::peripherals::Peripherals;
+use esp_idf_hal::prelude::*;
+use esp_idf_hal::uart::*;
+
+fn main() -> anyhow::Result<()> {
+    esp_idf_hal::sys::link_patches();
+
+    let peripherals = Peripherals::take()?;
+    let tx = peripherals.pins.gpio12;
+
+    println!("Starting DMX512 output");
+    let config = config::Config::new()
+        .baudrate(Hertz(250_000))
+        .stop_bits(config::StopBits::STOP2);
+    let mut uart = UartTxDriver::new(
+        peripherals.uart1,
+        tx,
+        Option::<gpio::Gpio0>::None,
+        Option::<gpio::Gpio1>::None,
+        &config,
+    )?;
+
+    let mut frame = [0_u8; 513];
+
+    loop {
+        frame[1] = frame[1].wrapping_add(1);
+
+        // Break and mark after break
+        uart.send_break(Duration::from_micros(120))?;
+        Ets::delay_us(12);
+
+        uart.write(&frame)?;
+        uart.wait_done(BLOCK)?;
+
+        FreeRtos::delay_ms(25);
+    }
+}
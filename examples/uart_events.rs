@@ -0,0 +1,59 @@
+//! UART events
+//!
+//! Folowing pins are used:
+//! TX    GPIO12
+//! RX    GPIO13
+//!
+//! Depending on your target and the board you are using you have to change the pins.
+//!
+//! This example prints the events reported by the UART driver, and reads the received data.
+//! Connect TX and RX pins, and the data sent is received back.
+
+use esp_idf_hal::delay::{TickType, NON_BLOCK};
+use esp_idf_hal::gpio;
+use esp_idf_hal::peripherals::Peripherals;
+use esp_idf_hal::prelude::*;
+use esp_idf_hal::uart::*;
+
+fn main() -> anyhow::Result<()> {
+    esp_idf_hal::sys::link_patches();
+
+    let peripherals = Peripherals::take()?;
+    let tx = peripherals.pins.gpio12;
+    let rx = peripherals.pins.gpio13;
+
+    println!("Starting UART events test");
+    let config = config::Config::new().baudrate(Hertz(115_200));
+    let uart = UartDriver::new(
+        peripherals.uart1,
+        tx,
+        rx,
+        Option::<gpio::Gpio0>::None,
+        Option::<gpio::Gpio1>::None,
+        &config,
+    )?;
+
+    let mut events = uart.listen()?;
+
+    loop {
+        uart.write(b"hello")?;
+
+        while let Some(event) = events.recv(TickType::new_millis(1000).ticks()) {
+            println!("{event:?}");
+
+            match event.payload() {
+                UartEventPayload::Data { size, .. } => {
+                    let mut buf = [0_u8; 128];
+                    let len = uart.read(&mut buf[..size.min(128)], NON_BLOCK)?;
+
+                    println!("Read {:?}", core::str::from_utf8(&buf[..len]));
+                }
+                payload if payload.data_lost() => {
+                    println!("Received data was lost");
+                    uart.clear_rx()?;
+                }
+                _ => (),
+            }
+        }
+    }
+}
@@ -0,0 +1,53 @@
+//! UART IrDA
+//!
+//! Folowing pins are used:
+//! TX    GPIO12 (TXD of an IrDA transceiver)
+//! RX    GPIO13 (RXD of an IrDA transceiver)
+//!
+//! Depending on your target and the board you are using you have to change the pins.
+//!
+//! This example sends a message every second at 115200 baud via an IrDA (SIR) transceiver,
+//! e.g. a TFDU4101, and prints whatever is received from the other side in between. As IrDA
+//! is half duplex, the echo of the sent message is discarded.
+
+use esp_idf_hal::delay::{TickType, BLOCK};
+use esp_idf_hal::gpio;
+use esp_idf_hal::peripherals::Peripherals;
+use esp_idf_hal::prelude::*;
+use esp_idf_hal::uart::*;
+
+fn main() -> anyhow::Result<()> {
+    esp_idf_hal::sys::link_patches();
+
+    let peripherals = Peripherals::take()?;
+    let tx = peripherals.pins.gpio12;
+    let rx = peripherals.pins.gpio13;
+
+    println!("Starting UART IrDA test");
+    let config = config::Config::new()
+        .baudrate(Hertz(115_200))
+        .mode(config::Mode::IrDA);
+    let uart = UartDriver::new(
+        peripherals.uart1,
+        tx,
+        rx,
+        Option::<gpio::Gpio0>::None,
+        Option::<gpio::Gpio1>::None,
+        &config,
+    )?;
+
+    loop {
+        uart.write(b"hello over IrDA\r\n")?;
+        uart.wait_tx_done(BLOCK)?;
+
+        // Discard the echo of the sent message
+        uart.clear_rx()?;
+
+        let mut buf = [0_u8; 64];
+        let len = uart.read(&mut buf, TickType::new_millis(1000).ticks())?;
+
+        if len > 0 {
+            println!("Received {:?}", core::str::from_utf8(&buf[..len]));
+        }
+    }
+}
@@ -0,0 +1,52 @@
+//! UART RS485 half-duplex
+//!
+//! Folowing pins are used:
+//! TX    GPIO12 (DI of the transceiver)
+//! RX    GPIO13 (RO of the transceiver)
+//! RTS   GPIO14 (DE and /RE of the transceiver)
+//!
+//! Depending on your target and the board you are using you have to change the pins.
+//!
+//! This example sends a request over an RS485 bus and waits for a reply. The direction of
+//! the transceiver is switched by the UART itself, via the RTS pin, so that the bus is
+//! released as soon as the last byte of the request was sent.
+
+use esp_idf_hal::delay::{FreeRtos, TickType};
+use esp_idf_hal::gpio;
+use esp_idf_hal::peripherals::Peripherals;
+use esp_idf_hal::prelude::*;
+use esp_idf_hal::uart::*;
+
+fn main() -> anyhow::Result<()> {
+    esp_idf_hal::sys::link_patches();
+
+    let peripherals = Peripherals::take()?;
+    let tx = peripherals.pins.gpio12;
+    let rx = peripherals.pins.gpio13;
+    let rts = peripherals.pins.gpio14;
+
+    println!("Starting UART RS485 test");
+    let config = config::Config::new()
+        .baudrate(Hertz(9_600))
+        .mode(config::Mode::RS485HalfDuplex);
+    let uart = UartDriver::new(
+        peripherals.uart1,
+        tx,
+        rx,
+        Option::<gpio::Gpio0>::None,
+        Some(rts),
+        &config,
+    )?;
+
+    loop {
+        uart.write(b"ping")?;
+        uart.wait_tx_done(TickType::new_millis(100).ticks())?;
+
+        let mut buf = [0_u8; 16];
+        let len = uart.read(&mut buf, TickType::new_millis(500).ticks())?;
+
+        println!("Received {:?}", &buf[..len]);
+
+        FreeRtos::delay_ms(1000);
+    }
+}
@@ -0,0 +1,95 @@
+//! UART SBUS receiver
+//!
+//! Folowing pins are used:
+//! RX    GPIO13 (SBUS output of the RC receiver)
+//!
+//! Depending on your target and the board you are using you have to change the pins.
+//!
+//! This example decodes the frames of an SBUS RC receiver, sent at 100Kbaud, 8E2 on an
+//! inverted line. The inversion is done by the UART itself, so no external inverter is needed.
+//! Each frame is 25 bytes long: a 0x0F header, 16 channels of 11 bits each, a flags byte
+//! and a zero footer.
+
+use esp_idf_hal::delay::TickType;
+use esp_idf_hal::gpio;
+use esp_idf_hal::peripherals::Peripherals;
+use esp_idf_hal::prelude::*;
+use esp_idf_hal::uart::*;
+
+const FRAME_LEN: usize = 25;
+
+fn main() -> anyhow::Result<()> {
+    esp_idf_hal::sys::link_patches();
+
+    let peripherals = Peripherals::take()?;
+    let rx = peripherals.pins.gpio13;
+
+    println!("Starting SBUS receiver");
+    let config = config::Config::new()
+        .baudrate(Hertz(100_000))
+        .parity_even()
+        .stop_bits(config::StopBits::STOP2)
+        .signal_inversion(config::SignalInversion::Rxd.into());
+    let uart = UartRxDriver::new(
+        peripherals.uart1,
+        rx,
+        Option::<gpio::Gpio0>::None,
+        Option::<gpio::Gpio1>::None,
+        &config,
+    )?;
+
+    let mut frame = [0_u8; FRAME_LEN];
+    let mut len = 0;
+
+    loop {
+        let mut byte = [0_u8];
+        if uart.read(&mut byte, TickType::new_millis(100).ticks())? == 0 {
+            println!("No signal");
+            len = 0;
+            continue;
+        }
+
+        // Resynchronize on the header
+        if len == 0 && byte[0] != 0x0f {
+            continue;
+        }
+
+        frame[len] = byte[0];
+        len += 1;
+
+        if len == FRAME_LEN {
+            len = 0;
+
+            if frame[FRAME_LEN - 1] != 0x00 {
+                continue;
+            }
+
+            let channels = decode_channels(&frame);
+            let flags = frame[23];
+
+            println!(
+                "Channels {channels:?}, frame lost: {}, failsafe: {}",
+                flags & 0x04 != 0,
+                flags & 0x08 != 0
+            );
+        }
+    }
+}
+
+fn decode_channels(frame: &[u8; FRAME_LEN]) -> [u16; 16] {
+    let mut channels = [0_u16; 16];
+
+    // The channels are packed LSB first into the 22 bytes following the header
+    for (index, channel) in channels.iter_mut().enumerate() {
+        let bit = index * 11;
+        let byte = 1 + bit / 8;
+        let shift = bit % 8;
+
+        let raw =
+            frame[byte] as u32 | (frame[byte + 1] as u32) << 8 | (frame[byte + 2] as u32) << 16;
+
+        *channel = ((raw >> shift) & 0x7ff) as u16;
+    }
+
+    channels
+}
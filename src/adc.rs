@@ -13,14 +13,57 @@ pub type AdcConfig = config::Config;
 ))]
 pub use continuous::{
     config as cont_config, config::Config as AdcContConfig, AdcChannels, AdcChannelsArray,
-    AdcDriver as AdcContDriver, AdcMeasurement, Atten11dB, Atten2p5dB, Atten6dB, AttenNone,
-    Attenuated, ChainedAdcChannels, EmptyAdcChannels,
+    AdcDriver as AdcContDriver, AdcFrame, AdcMeasurement, Atten11dB, Atten2p5dB, Atten6dB,
+    AttenNone, Attenuated, ChainedAdcChannels, EmptyAdcChannels,
 };
 
 pub trait Adc: Send {
     fn unit() -> adc_unit_t;
 }
 
+/// An error of the ADC drivers
+///
+/// ESP-IDF reports an ADC2 conversion which lost the arbitration to the Wi-Fi driver
+/// with `ESP_ERR_TIMEOUT`, which the ADC2 read paths report as `AdcError::Busy`. Any other
+/// `ESP_ERR_TIMEOUT` is reported as `AdcError::Other`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AdcError {
+    /// ADC2 is in use by the Wi-Fi driver
+    Busy,
+    /// The channels of a scan belong to a different ADC unit than the driver
+    UnitMismatch,
+    Other(EspError),
+}
+
+impl From<EspError> for AdcError {
+    fn from(e: EspError) -> Self {
+        Self::Other(e)
+    }
+}
+
+impl From<AdcError> for EspError {
+    fn from(e: AdcError) -> Self {
+        match e {
+            AdcError::Busy => EspError::from_infallible::<ESP_ERR_TIMEOUT>(),
+            AdcError::UnitMismatch => EspError::from_infallible::<ESP_ERR_INVALID_ARG>(),
+            AdcError::Other(e) => e,
+        }
+    }
+}
+
+impl core::fmt::Display for AdcError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Busy => write!(f, "ADC2 is in use by the Wi-Fi driver"),
+            Self::UnitMismatch => write!(f, "Channel of a different ADC unit"),
+            Self::Other(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AdcError {}
+
 // NOTE: Will be changed to an enum once C-style enums are usable as const generics
 pub mod attenuation {
     pub use esp_idf_sys::{
@@ -36,6 +79,8 @@ pub mod attenuation {
 
 /// ADC configuration
 pub mod config {
+    use core::time::Duration;
+
     use esp_idf_sys::*;
 
     /// The sampling/readout resolution of the ADC
@@ -82,11 +127,51 @@ pub mod config {
         }
     }
 
+    /// How conversions on ADC2 which lost the arbitration to the Wi-Fi driver are retried
+    ///
+    /// On the ESP32 and the ESP32-S2, the Wi-Fi driver uses ADC2 as well, and a conversion
+    /// fails while the Wi-Fi driver owns it. By default, such a conversion is not retried.
+    /// This has no effect on ADC1, or on chips where ADC2 is not shared with the Wi-Fi driver.
+    #[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+    pub struct BusyRetry {
+        /// How many times a conversion is retried
+        pub attempts: u32,
+        /// The delay before each retry
+        pub delay: Duration,
+    }
+
+    impl BusyRetry {
+        pub const fn new(attempts: u32, delay: Duration) -> Self {
+            Self { attempts, delay }
+        }
+    }
+
+    #[cfg(not(esp_idf_version_major = "4"))]
+    impl Resolution {
+        /// The resolution as expected by the ESP-IDF 5 drivers, which - unlike the legacy ones - encode it
+        /// as the number of bits
+        pub fn bitwidth(&self) -> adc_bitwidth_t {
+            match self {
+                #[cfg(esp32)]
+                Resolution::Resolution9Bit => adc_bitwidth_t_ADC_BITWIDTH_9,
+                #[cfg(esp32)]
+                Resolution::Resolution10Bit => adc_bitwidth_t_ADC_BITWIDTH_10,
+                #[cfg(esp32)]
+                Resolution::Resolution11Bit => adc_bitwidth_t_ADC_BITWIDTH_11,
+                #[cfg(any(esp32, esp32s3, esp32c3, esp32c2, esp32h2, esp32c5, esp32c6, esp32p4))]
+                Resolution::Resolution12Bit => adc_bitwidth_t_ADC_BITWIDTH_12,
+                #[cfg(esp32s2)]
+                Resolution::Resolution13Bit => adc_bitwidth_t_ADC_BITWIDTH_13,
+            }
+        }
+    }
+
     #[derive(Debug, Copy, Clone, Default)]
     pub struct Config {
         pub resolution: Resolution,
         #[cfg(any(esp_idf_comp_esp_adc_cal_enabled, esp_idf_comp_esp_adc_enabled))]
         pub calibration: bool,
+        pub busy_retry: BusyRetry,
     }
 
     impl Config {
@@ -106,7 +191,62 @@ pub mod config {
             self.calibration = calibration;
             self
         }
+
+        #[must_use]
+        pub fn busy_retry(mut self, attempts: u32, delay: Duration) -> Self {
+            self.busy_retry = BusyRetry::new(attempts, delay);
+            self
+        }
+    }
+}
+
+/// Performs a conversion, retrying it according to `retry` while ADC2 is in use by the
+/// Wi-Fi driver
+#[allow(dead_code)]
+fn convert_adc2<R>(
+    retry: &config::BusyRetry,
+    mut convert: impl FnMut() -> Result<R, EspError>,
+) -> Result<R, AdcError> {
+    if wifi_owns_adc2() {
+        return Err(AdcError::Busy);
+    }
+
+    let mut attempts = 0;
+
+    loop {
+        match convert() {
+            Err(e) if e.code() == ESP_ERR_TIMEOUT => {
+                if attempts == retry.attempts {
+                    return Err(AdcError::Busy);
+                }
+
+                attempts += 1;
+
+                crate::delay::Delay::new_default()
+                    .delay_us(retry.delay.as_micros().min(u32::MAX as _) as _);
+            }
+            other => return Ok(other?),
+        }
+    }
+}
+
+/// Whether ADC2 is known to be unusable, because the Wi-Fi driver is active
+///
+/// This is the case on the ESP32, where ADC2 cannot be used at all while Wi-Fi is on.
+/// Elsewhere, the arbitration is left to the ADC2 conversion itself.
+#[allow(dead_code)]
+fn wifi_owns_adc2() -> bool {
+    #[cfg(all(esp32, esp_idf_comp_esp_wifi_enabled))]
+    {
+        let mut mode: wifi_mode_t = wifi_mode_t_WIFI_MODE_NULL;
+
+        let result = unsafe { esp_wifi_get_mode(&mut mode) };
+
+        result == ESP_OK && mode != wifi_mode_t_WIFI_MODE_NULL
     }
+
+    #[cfg(not(all(esp32, esp_idf_comp_esp_wifi_enabled)))]
+    false
 }
 
 pub struct AdcChannelDriver<'d, const A: adc_atten_t, T: ADCPin> {
@@ -153,6 +293,8 @@ pub struct AdcDriver<'d, ADC: Adc> {
     _adc: PeripheralRef<'d, ADC>,
     #[allow(dead_code)]
     resolution: config::Resolution,
+    #[allow(dead_code)]
+    busy_retry: config::BusyRetry,
     #[cfg(all(
         any(esp32, esp32s2, esp32s3, esp32c3),
         any(esp_idf_comp_esp_adc_cal_enabled, esp_idf_comp_esp_adc_enabled)
@@ -210,6 +352,7 @@ impl<'d, ADC: Adc> AdcDriver<'d, ADC> {
         Ok(Self {
             _adc: adc,
             resolution: config.resolution,
+            busy_retry: config.busy_retry,
             #[cfg(all(
                 any(esp32, esp32s2, esp32s3, esp32c3),
                 any(esp_idf_comp_esp_adc_cal_enabled, esp_idf_comp_esp_adc_enabled)
@@ -222,11 +365,34 @@ impl<'d, ADC: Adc> AdcDriver<'d, ADC> {
         })
     }
 
+    pub fn resolution(&self) -> config::Resolution {
+        self.resolution
+    }
+
+    /// Changes the resolution of the unit, and re-derives the calibration
+    pub fn set_resolution(&mut self, resolution: config::Resolution) -> Result<(), EspError> {
+        if ADC::unit() == adc_unit_t_ADC_UNIT_1 {
+            esp!(unsafe { adc1_config_width(resolution.into()) })?;
+        }
+
+        self.resolution = resolution;
+
+        #[cfg(all(
+            any(esp32, esp32s2, esp32s3, esp32c3),
+            any(esp_idf_comp_esp_adc_cal_enabled, esp_idf_comp_esp_adc_enabled)
+        ))]
+        if let Some(characteristics) = &mut self.cal_characteristics {
+            *characteristics = Default::default();
+        }
+
+        Ok(())
+    }
+
     #[inline(always)]
     pub fn read<const A: adc_atten_t, T>(
         &mut self,
         pin: &mut AdcChannelDriver<'_, A, T>,
-    ) -> Result<u16, EspError>
+    ) -> Result<u16, AdcError>
     where
         T: ADCPin<Adc = ADC>,
     {
@@ -237,7 +403,7 @@ impl<'d, ADC: Adc> AdcDriver<'d, ADC> {
     pub fn read_raw<const A: adc_atten_t, T>(
         &mut self,
         pin: &mut AdcChannelDriver<'_, A, T>,
-    ) -> Result<u16, EspError>
+    ) -> Result<u16, AdcError>
     where
         T: ADCPin<Adc = ADC>,
     {
@@ -267,9 +433,9 @@ impl<'d, ADC: Adc> AdcDriver<'d, ADC> {
         unit: adc_unit_t,
         channel: adc_channel_t,
         atten: adc_atten_t,
-    ) -> Result<u16, EspError> {
+    ) -> Result<u16, AdcError> {
         let measurement = self.read_internal_raw(unit, channel)?;
-        self.raw_to_voltage(measurement, atten)
+        Ok(self.raw_to_voltage(measurement, atten)?)
     }
 
     #[inline(always)]
@@ -277,16 +443,20 @@ impl<'d, ADC: Adc> AdcDriver<'d, ADC> {
         &mut self,
         unit: adc_unit_t,
         channel: adc_channel_t,
-    ) -> Result<u16, EspError> {
+    ) -> Result<u16, AdcError> {
         if unit == adc_unit_t_ADC_UNIT_1 {
             Ok(unsafe { adc1_get_raw(channel) } as _)
         } else {
             #[cfg(not(any(esp32c2, esp32h2, esp32c5, esp32c6, esp32p4)))]
             {
-                let mut measurement = 0;
-                esp!(unsafe { adc2_get_raw(channel, self.resolution.into(), &mut measurement) })?;
+                convert_adc2(&self.busy_retry, || {
+                    let mut measurement = 0;
+                    esp!(unsafe {
+                        adc2_get_raw(channel, self.resolution.into(), &mut measurement)
+                    })?;
 
-                Ok(measurement as _)
+                    Ok(measurement as _)
+                })
             }
 
             #[cfg(any(esp32c2, esp32h2, esp32c5, esp32c6, esp32p4))]
@@ -397,7 +567,7 @@ where
 
     fn read(&mut self, pin: &mut AdcChannelDriver<'c, A, T>) -> nb::Result<u16, Self::Error> {
         self.read_internal(T::Adc::unit(), pin.pin.adc_channel(), A)
-            .map_err(to_nb_err)
+            .map_err(|e| to_nb_err(e.into()))
     }
 }
 
@@ -413,7 +583,7 @@ impl<'d> embedded_hal_0_2::adc::OneShot<ADC1, u16, crate::hall::HallSensor>
 }
 
 fn to_nb_err(err: EspError) -> nb::Error<EspError> {
-    if err.code() == ESP_ERR_INVALID_STATE {
+    if err.code() == ESP_ERR_INVALID_STATE || err.code() == ESP_ERR_TIMEOUT {
         nb::Error::WouldBlock
     } else {
         nb::Error::Other(err)
@@ -438,105 +608,97 @@ impl_adc!(ADC1: adc_unit_t_ADC_UNIT_1);
 impl_adc!(ADC2: adc_unit_t_ADC_UNIT_2);
 
 #[cfg(all(not(esp_idf_version_major = "4"), esp_idf_comp_esp_adc_enabled))]
-pub mod oneshot {
-    use core::borrow::Borrow;
+pub use calibration::{AdcCalibration, CalibrationScheme, CalibrationSource};
 
+/// Conversion of raw ADC readings to millivolts, based on the calibration data burnt
+/// into the eFuse of the chip
+#[cfg(all(not(esp_idf_version_major = "4"), esp_idf_comp_esp_adc_enabled))]
+pub mod calibration {
     use esp_idf_sys::*;
 
-    use crate::gpio::ADCPin;
-    use crate::peripheral::Peripheral;
-    use crate::peripheral::PeripheralRef;
-
     use super::attenuation::adc_atten_t;
     use super::config::Resolution;
-    use super::to_nb_err;
-    use super::Adc;
-
-    pub mod config {
-        use super::adc_atten_t;
-        use super::Resolution;
 
-        #[derive(Debug, Copy, Clone, Default)]
-        pub struct AdcChannelConfig {
-            pub attenuation: adc_atten_t,
-            pub resolution: Resolution,
-            #[cfg(any(esp_idf_comp_esp_adc_cal_enabled, esp_idf_comp_esp_adc_enabled))]
-            pub calibration: bool,
-        }
+    /// The calibration scheme used by an `AdcCalibration`
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub enum CalibrationScheme {
+        /// Curve fitting, available on the ESP32-S3, ESP32-C3 and ESP32-C6
+        CurveFitting,
+        /// Line fitting, available on the ESP32, ESP32-S2 and ESP32-C2
+        LineFitting,
+    }
 
-        impl AdcChannelConfig {
-            pub fn new() -> Self {
-                Default::default()
-            }
-        }
+    /// The eFuse data an `AdcCalibration` is based on
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub enum CalibrationSource {
+        /// The two point values burnt into the eFuse
+        EfuseTwoPoint,
+        /// The reference voltage burnt into the eFuse
+        EfuseVref,
+        /// No calibration data in the eFuse, a default reference voltage is used
+        DefaultVref,
     }
 
-    pub struct AdcChannelDriver<'d, T, M>
-    where
-        T: ADCPin,
-        M: Borrow<AdcDriver<'d, T::Adc>>,
-    {
-        adc: M,
-        _pin: PeripheralRef<'d, T>,
-        calibration: Option<adc_cali_handle_t>,
+    /// A calibration of an ADC unit for a given attenuation and resolution
+    ///
+    /// The best scheme available on the chip is used, i.e. curve fitting if supported,
+    /// and line fitting otherwise. The calibration can be applied to any raw reading of that
+    /// unit (oneshot, continuous or stored) taken with the same attenuation and resolution.
+    pub struct AdcCalibration {
+        handle: adc_cali_handle_t,
+        scheme: CalibrationScheme,
+        unit: adc_unit_t,
+        attenuation: adc_atten_t,
+        resolution: Resolution,
     }
 
-    impl<'d, T, M> AdcChannelDriver<'d, T, M>
-    where
-        T: ADCPin,
-        M: Borrow<AdcDriver<'d, T::Adc>>,
-    {
+    impl AdcCalibration {
+        /// Creates a calibration for the given unit, attenuation and resolution
+        ///
+        /// Returns `ESP_ERR_NOT_SUPPORTED` if the chip does not support any calibration
+        /// scheme, or if the calibration data is missing from the eFuse.
         pub fn new(
-            adc: M,
-            pin: impl Peripheral<P = T> + 'd,
-            config: &config::AdcChannelConfig,
+            unit: adc_unit_t,
+            attenuation: adc_atten_t,
+            resolution: Resolution,
         ) -> Result<Self, EspError> {
-            crate::into_ref!(pin);
-
-            unsafe {
-                crate::gpio::rtc_reset_pin(pin.pin())?;
-            }
+            Self::new_for_channel(unit, 0, attenuation, resolution)
+        }
 
-            let chan_config = adc_oneshot_chan_cfg_t {
-                atten: config.attenuation,
-                bitwidth: config.resolution.into(),
-            };
+        /// Same as `new`, for chips and ESP-IDF versions where the curve fitting
+        /// calibration compensates per channel
+        pub fn new_for_channel(
+            unit: adc_unit_t,
+            channel: adc_channel_t,
+            attenuation: adc_atten_t,
+            resolution: Resolution,
+        ) -> Result<Self, EspError> {
+            let curve_fitting = Self::curve_fitting(unit, channel, attenuation, resolution)?;
 
-            unsafe {
-                esp!(adc_oneshot_config_channel(
-                    adc.borrow().handle,
-                    pin.adc_channel(),
-                    &chan_config
-                ))?
+            let (handle, scheme) = if let Some(handle) = curve_fitting {
+                (handle, CalibrationScheme::CurveFitting)
+            } else if let Some(handle) = Self::line_fitting(unit, attenuation, resolution)? {
+                (handle, CalibrationScheme::LineFitting)
+            } else {
+                return Err(EspError::from_infallible::<ESP_ERR_NOT_SUPPORTED>());
             };
 
-            let mut calibration = Self::get_curve_calibration_handle(
-                T::Adc::unit() as u8,
-                pin.adc_channel(),
-                config.attenuation,
-                config.resolution.into(),
-            );
-            if calibration.is_none() {
-                calibration = Self::get_line_calibration_handle(
-                    T::Adc::unit() as u8,
-                    config.attenuation,
-                    config.resolution.into(),
-                );
-            }
             Ok(Self {
-                adc,
-                _pin: pin,
-                calibration,
+                handle,
+                scheme,
+                unit,
+                attenuation,
+                resolution,
             })
         }
 
         #[allow(unused_variables)]
-        fn get_curve_calibration_handle(
-            unit_id: u8,
-            chan: adc_channel_t,
-            atten: adc_atten_t,
-            bitwidth: adc_bits_width_t,
-        ) -> Option<adc_cali_handle_t> {
+        fn curve_fitting(
+            unit: adc_unit_t,
+            channel: adc_channel_t,
+            attenuation: adc_atten_t,
+            resolution: Resolution,
+        ) -> Result<Option<adc_cali_handle_t>, EspError> {
             // it would be nice if esp-idf-sys could export some cfg values to replicate these two defines
             // from esp-idf:
             // ADC_CALI_SCHEME_CURVE_FITTING_SUPPORTED
@@ -553,29 +715,23 @@ pub mod oneshot {
             ))]
             {
                 let cal_config = adc_cali_curve_fitting_config_t {
-                    unit_id: unit_id as u32,
+                    unit_id: unit,
                     #[cfg(all(
                         esp_idf_version_major = "5",
                         not(esp_idf_version_minor = "0"),
                         not(all(esp_idf_version_minor = "1", esp_idf_version_patch = "0"))
                     ))]
-                    chan,
-                    atten,
-                    bitwidth,
+                    chan: channel,
+                    atten: attenuation,
+                    bitwidth: resolution.bitwidth(),
                 };
-                let mut cal_handle: adc_cali_handle_t = core::ptr::null_mut();
-                if let Err(_err) = unsafe {
-                    esp!(esp_idf_sys::adc_cali_create_scheme_curve_fitting(
-                        &cal_config,
-                        &mut cal_handle
-                    ))
-                } {
-                    // I'd log a warning but the log crate is not available here
-                    None
-                } else {
-                    Some(cal_handle)
-                }
+
+                let mut handle: adc_cali_handle_t = core::ptr::null_mut();
+                esp!(unsafe { adc_cali_create_scheme_curve_fitting(&cal_config, &mut handle) })?;
+
+                Ok(Some(handle))
             }
+
             #[cfg(not(any(
                 esp32c3,
                 all(
@@ -585,15 +741,15 @@ pub mod oneshot {
                 ),
                 esp32s3,
             )))]
-            None
+            Ok(None)
         }
 
         #[allow(unused_variables)]
-        fn get_line_calibration_handle(
-            unit_id: u8,
-            atten: adc_atten_t,
-            bitwidth: adc_bits_width_t,
-        ) -> Option<adc_cali_handle_t> {
+        fn line_fitting(
+            unit: adc_unit_t,
+            attenuation: adc_atten_t,
+            resolution: Resolution,
+        ) -> Result<Option<adc_cali_handle_t>, EspError> {
             #[cfg(any(esp32, esp32c2, esp32s2))]
             {
                 // esp32 has an additional field that the exanple defalts
@@ -601,168 +757,644 @@ pub mod oneshot {
                 // this as a config option?
                 #[allow(clippy::needless_update)]
                 let cal_config = adc_cali_line_fitting_config_t {
-                    unit_id: unit_id as u32,
-                    atten,
-                    bitwidth,
+                    unit_id: unit,
+                    atten: attenuation,
+                    bitwidth: resolution.bitwidth(),
                     ..Default::default()
                 };
-                let mut cal_handle: adc_cali_handle_t = core::ptr::null_mut();
-                if let Err(_err) = unsafe {
-                    esp!(esp_idf_sys::adc_cali_create_scheme_line_fitting(
-                        &cal_config,
-                        &mut cal_handle
-                    ))
-                } {
-                    // I'd log a warning but the log crate is not available here
-                    None
-                } else {
-                    Some(cal_handle)
-                }
+
+                let mut handle: adc_cali_handle_t = core::ptr::null_mut();
+                esp!(unsafe { adc_cali_create_scheme_line_fitting(&cal_config, &mut handle) })?;
+
+                Ok(Some(handle))
             }
+
             #[cfg(not(any(esp32, esp32c2, esp32s2)))]
-            None
+            Ok(None)
         }
 
-        #[inline(always)]
-        pub fn read(&mut self) -> Result<u16, EspError> {
-            let raw = self.read_raw()?;
-            self.raw_to_cal(raw)
+        /// The scheme used by this calibration
+        pub fn scheme(&self) -> CalibrationScheme {
+            self.scheme
         }
 
-        #[inline(always)]
-        pub fn read_raw(&mut self) -> Result<u16, EspError> {
-            let channel = T::CHANNEL;
-            self.adc.borrow().read_raw_internal(channel)
+        /// The eFuse data this calibration is based on
+        #[cfg(esp32)]
+        #[allow(non_upper_case_globals)]
+        pub fn source(&self) -> Result<CalibrationSource, EspError> {
+            let mut value: adc_cali_line_fitting_efuse_val_t = 0;
+            esp!(unsafe { adc_cali_scheme_line_fitting_check_efuse(&mut value) })?;
+
+            Ok(match value {
+                adc_cali_line_fitting_efuse_val_t_ADC_CALI_LINE_FITTING_EFUSE_VAL_EFUSE_TP => {
+                    CalibrationSource::EfuseTwoPoint
+                }
+                adc_cali_line_fitting_efuse_val_t_ADC_CALI_LINE_FITTING_EFUSE_VAL_EFUSE_VREF => {
+                    CalibrationSource::EfuseVref
+                }
+                _ => CalibrationSource::DefaultVref,
+            })
         }
 
-        #[inline(always)]
-        pub fn raw_to_cal(&self, raw: u16) -> Result<u16, EspError> {
-            if let Some(calibration) = &self.calibration {
-                self.adc.borrow().raw_to_cal_internal(*calibration, raw)
-            } else {
-                Ok(raw)
-            }
+        /// The eFuse data this calibration is based on
+        #[cfg(not(esp32))]
+        pub fn source(&self) -> Result<CalibrationSource, EspError> {
+            // All other chips only support calibration based on the two point eFuse values
+            Ok(CalibrationSource::EfuseTwoPoint)
         }
-    }
-
-    impl<'d, T, M> embedded_hal_0_2::adc::Channel<T::Adc> for AdcChannelDriver<'d, T, M>
-    where
-        T: ADCPin,
-        M: Borrow<AdcDriver<'d, T::Adc>>,
-    {
-        type ID = adc_channel_t;
 
-        fn channel() -> Self::ID {
-            T::CHANNEL
+        pub fn unit(&self) -> adc_unit_t {
+            self.unit
         }
-    }
-
-    unsafe impl<'d, T, M> Send for AdcChannelDriver<'d, T, M>
-    where
-        T: ADCPin,
-        M: Borrow<AdcDriver<'d, T::Adc>>,
-    {
-    }
-
-    pub struct AdcDriver<'d, ADC: Adc> {
-        handle: adc_oneshot_unit_handle_t,
-        _adc: PeripheralRef<'d, ADC>,
-    }
 
-    impl<'d, ADC: Adc> AdcDriver<'d, ADC> {
-        pub fn new(adc: impl Peripheral<P = ADC> + 'd) -> Result<Self, EspError> {
-            crate::into_ref!(adc);
-            let config = adc_oneshot_unit_init_cfg_t {
-                unit_id: ADC::unit(),
-                ..Default::default()
-            };
-            let mut handle: adc_oneshot_unit_handle_t = core::ptr::null_mut();
-            unsafe { esp!(adc_oneshot_new_unit(&config, &mut handle))? };
-            Ok(Self { handle, _adc: adc })
+        pub fn attenuation(&self) -> adc_atten_t {
+            self.attenuation
         }
 
-        #[inline(always)]
-        pub fn read<T, M>(&self, channel: &mut AdcChannelDriver<'d, T, M>) -> Result<u16, EspError>
-        where
-            T: ADCPin,
-            M: Borrow<AdcDriver<'d, T::Adc>>,
-        {
-            let raw = self.read_raw(channel)?;
-            self.raw_to_cal(channel, raw)
+        pub fn resolution(&self) -> Resolution {
+            self.resolution
         }
 
-        #[inline(always)]
-        pub fn read_raw<T, M>(
-            &self,
-            _channel: &mut AdcChannelDriver<'d, T, M>,
-        ) -> Result<u16, EspError>
-        where
-            T: ADCPin,
-            M: Borrow<AdcDriver<'d, T::Adc>>,
-        {
-            self.read_raw_internal(T::CHANNEL)
+        pub fn handle(&self) -> adc_cali_handle_t {
+            self.handle
         }
 
-        #[inline(always)]
-        fn read_raw_internal(&self, channel: adc_channel_t) -> Result<u16, EspError> {
-            let mut measurement = 0;
-            unsafe { esp!(adc_oneshot_read(self.handle, channel, &mut measurement)) }?;
-            Ok(measurement as u16)
-        }
+        /// Converts a raw reading to millivolts
+        pub fn raw_to_mv(&self, raw: u16) -> Result<u16, EspError> {
+            let mut mv = 0i32;
 
-        #[inline(always)]
-        pub fn raw_to_cal<T, M>(
-            &self,
-            channel: &AdcChannelDriver<'d, T, M>,
-            raw: u16,
-        ) -> Result<u16, EspError>
-        where
-            T: ADCPin,
-            M: Borrow<AdcDriver<'d, T::Adc>>,
-        {
-            if let Some(calibration) = &channel.calibration {
-                self.raw_to_cal_internal(*calibration, raw)
-            } else {
-                Ok(raw)
-            }
-        }
+            esp!(unsafe { adc_cali_raw_to_voltage(self.handle, raw as i32, &mut mv) })?;
 
-        #[inline(always)]
-        fn raw_to_cal_internal(
-            &self,
-            calibration: adc_cali_handle_t,
-            raw: u16,
-        ) -> Result<u16, EspError> {
-            let mut mv = 0i32;
-            unsafe {
-                esp!(adc_cali_raw_to_voltage(calibration, raw as i32, &mut mv))?;
-            };
             Ok(mv as u16)
         }
     }
 
-    impl<'d, ADC: Adc> Drop for AdcDriver<'d, ADC> {
+    impl Drop for AdcCalibration {
         fn drop(&mut self) {
-            unsafe { esp!(adc_oneshot_del_unit(self.handle)) }.unwrap();
+            match self.scheme {
+                #[cfg(any(
+                    esp32c3,
+                    all(
+                        esp32c6,
+                        not(all(esp_idf_version_major = "5", esp_idf_version_minor = "0")),
+                        not(esp_idf_version_full = "5.1.0")
+                    ),
+                    esp32s3,
+                ))]
+                CalibrationScheme::CurveFitting => {
+                    esp!(unsafe { adc_cali_delete_scheme_curve_fitting(self.handle) }).unwrap();
+                }
+                #[cfg(any(esp32, esp32c2, esp32s2))]
+                CalibrationScheme::LineFitting => {
+                    esp!(unsafe { adc_cali_delete_scheme_line_fitting(self.handle) }).unwrap();
+                }
+                #[allow(unreachable_patterns)]
+                _ => unreachable!(),
+            }
         }
     }
 
-    impl<'d, T, M> embedded_hal_0_2::adc::OneShot<T::Adc, u16, AdcChannelDriver<'d, T, M>>
-        for AdcDriver<'d, T::Adc>
-    where
-        T: ADCPin,
-        M: Borrow<AdcDriver<'d, T::Adc>>,
-    {
-        type Error = EspError;
+    unsafe impl Send for AdcCalibration {}
+    unsafe impl Sync for AdcCalibration {}
+}
 
-        fn read(&mut self, pin: &mut AdcChannelDriver<'d, T, M>) -> nb::Result<u16, Self::Error> {
-            AdcDriver::read(self, pin).map_err(to_nb_err)
-        }
-    }
+#[cfg(all(not(esp_idf_version_major = "4"), esp_idf_comp_esp_adc_enabled))]
+pub mod oneshot {
+    use core::borrow::Borrow;
 
-    unsafe impl<'d, ADC: Adc> Send for AdcDriver<'d, ADC> {}
-    unsafe impl<'d, ADC: Adc> Sync for AdcDriver<'d, ADC> {}
-}
+    use esp_idf_sys::*;
+
+    use crate::gpio::ADCPin;
+    use crate::peripheral::Peripheral;
+    use crate::peripheral::PeripheralRef;
+    use crate::task::CriticalSection;
+
+    use super::attenuation::adc_atten_t;
+    use super::config::Resolution;
+    use super::to_nb_err;
+    use super::{Adc, AdcCalibration, AdcError};
+
+    /// The maximum number of channels of an ADC unit, and thus of a scan
+    const MAX_CHANNELS: usize = 10;
+
+    /// How many conversions `AdcChannelDriver::read_stats` performs before yielding to the scheduler
+    pub const YIELD_EVERY: u16 = 256;
+
+    /// The statistics of a series of raw conversions
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    pub struct AdcStats {
+        pub samples: u16,
+        pub min: u16,
+        pub max: u16,
+        pub sum: u32,
+    }
+
+    impl AdcStats {
+        pub fn mean(&self) -> u16 {
+            (self.sum / self.samples as u32) as u16
+        }
+    }
+
+    /// A oneshot ADC channel, which can be read as part of a scan with `AdcDriver::read_multi`
+    pub trait AdcChannelRead {
+        fn unit(&self) -> adc_unit_t;
+
+        fn channel(&self) -> adc_channel_t;
+
+        fn raw_to_cal(&self, raw: u16) -> Result<u16, EspError>;
+    }
+
+    pub mod config {
+        use super::adc_atten_t;
+        use super::Resolution;
+
+        /// How `AdcChannelDriver::read_averaged` combines the conversions
+        #[derive(Debug, Copy, Clone, Default)]
+        pub struct AveragingConfig {
+            /// Whether to discard the lowest and the highest conversion, if there are
+            /// more than two of them
+            pub trim: bool,
+        }
+
+        impl AveragingConfig {
+            pub const fn new() -> Self {
+                Self { trim: false }
+            }
+
+            #[must_use]
+            pub fn trim(mut self, trim: bool) -> Self {
+                self.trim = trim;
+                self
+            }
+        }
+
+        #[derive(Debug, Copy, Clone)]
+        pub struct AdcChannelConfig {
+            pub attenuation: adc_atten_t,
+            pub resolution: Resolution,
+            /// Whether the readings are converted to millivolts, if the chip supports
+            /// calibration. Enabled by default.
+            #[cfg(any(esp_idf_comp_esp_adc_cal_enabled, esp_idf_comp_esp_adc_enabled))]
+            pub calibration: bool,
+            pub averaging: AveragingConfig,
+        }
+
+        impl AdcChannelConfig {
+            pub fn new() -> Self {
+                Default::default()
+            }
+        }
+
+        impl Default for AdcChannelConfig {
+            fn default() -> Self {
+                Self {
+                    attenuation: Default::default(),
+                    resolution: Default::default(),
+                    #[cfg(any(esp_idf_comp_esp_adc_cal_enabled, esp_idf_comp_esp_adc_enabled))]
+                    calibration: true,
+                    averaging: Default::default(),
+                }
+            }
+        }
+
+        pub use crate::adc::config::BusyRetry;
+
+        #[derive(Debug, Copy, Clone, Default)]
+        pub struct AdcDriverConfig {
+            pub busy_retry: BusyRetry,
+        }
+
+        impl AdcDriverConfig {
+            pub fn new() -> Self {
+                Default::default()
+            }
+
+            #[must_use]
+            pub fn busy_retry(mut self, attempts: u32, delay: core::time::Duration) -> Self {
+                self.busy_retry = BusyRetry::new(attempts, delay);
+                self
+            }
+        }
+    }
+
+    pub struct AdcChannelDriver<'d, T, M>
+    where
+        T: ADCPin,
+        M: Borrow<AdcDriver<'d, T::Adc>>,
+    {
+        adc: M,
+        _pin: PeripheralRef<'d, T>,
+        calibration: Option<AdcCalibration>,
+        config: config::AdcChannelConfig,
+    }
+
+    impl<'d, T, M> AdcChannelDriver<'d, T, M>
+    where
+        T: ADCPin,
+        M: Borrow<AdcDriver<'d, T::Adc>>,
+    {
+        pub fn new(
+            adc: M,
+            pin: impl Peripheral<P = T> + 'd,
+            config: &config::AdcChannelConfig,
+        ) -> Result<Self, EspError> {
+            crate::into_ref!(pin);
+
+            unsafe {
+                crate::gpio::rtc_reset_pin(pin.pin())?;
+            }
+
+            let calibration = Self::configure(adc.borrow(), config)?;
+
+            Ok(Self {
+                adc,
+                _pin: pin,
+                calibration,
+                config: *config,
+            })
+        }
+
+        fn configure(
+            adc: &AdcDriver<'d, T::Adc>,
+            config: &config::AdcChannelConfig,
+        ) -> Result<Option<AdcCalibration>, EspError> {
+            let chan_config = adc_oneshot_chan_cfg_t {
+                atten: config.attenuation,
+                bitwidth: config.resolution.bitwidth(),
+            };
+
+            {
+                let _guard = adc.lock.enter();
+
+                unsafe {
+                    esp!(adc_oneshot_config_channel(
+                        adc.handle,
+                        T::CHANNEL,
+                        &chan_config
+                    ))?
+                };
+            }
+
+            if !config.calibration {
+                return Ok(None);
+            }
+
+            match AdcCalibration::new_for_channel(
+                T::Adc::unit(),
+                T::CHANNEL,
+                config.attenuation,
+                config.resolution,
+            ) {
+                Ok(calibration) => Ok(Some(calibration)),
+                // No calibration scheme, or no calibration data in the eFuse
+                Err(err) if err.code() == ESP_ERR_NOT_SUPPORTED => Ok(None),
+                Err(err) => Err(err),
+            }
+        }
+
+        pub fn attenuation(&self) -> adc_atten_t {
+            self.config.attenuation
+        }
+
+        /// Changes the attenuation of the channel, and re-derives its calibration
+        pub fn set_attenuation(&mut self, attenuation: adc_atten_t) -> Result<(), EspError> {
+            self.reconfigure(config::AdcChannelConfig {
+                attenuation,
+                ..self.config
+            })
+        }
+
+        pub fn resolution(&self) -> Resolution {
+            self.config.resolution
+        }
+
+        /// Changes the resolution of the channel, and re-derives its calibration
+        pub fn set_resolution(&mut self, resolution: Resolution) -> Result<(), EspError> {
+            self.reconfigure(config::AdcChannelConfig {
+                resolution,
+                ..self.config
+            })
+        }
+
+        fn reconfigure(&mut self, config: config::AdcChannelConfig) -> Result<(), EspError> {
+            // The previous calibration is deleted before the new one is created, as
+            // ESP-IDF might not support several calibrations of a channel at the same time
+            self.calibration = None;
+            self.calibration = Self::configure(self.adc.borrow(), &config)?;
+            self.config = config;
+
+            Ok(())
+        }
+
+        /// The calibration used to convert the readings of this channel to millivolts,
+        /// if `calibration` is enabled in the config and supported by the chip
+        pub fn calibration(&self) -> Option<&AdcCalibration> {
+            self.calibration.as_ref()
+        }
+
+        #[inline(always)]
+        pub fn read(&mut self) -> Result<u16, AdcError> {
+            let raw = self.read_raw()?;
+            Ok(self.raw_to_cal(raw)?)
+        }
+
+        #[inline(always)]
+        pub fn read_raw(&mut self) -> Result<u16, AdcError> {
+            let channel = T::CHANNEL;
+            self.adc.borrow().read_raw_internal(channel)
+        }
+
+        /// Averages `samples` conversions of the channel, as configured by the `averaging`
+        /// setting of its config, and converts the average to millivolts if calibration is enabled
+        ///
+        /// The task yields to the scheduler every `YIELD_EVERY` conversions, so that long
+        /// averages do not trip the task watchdog.
+        pub fn read_averaged(&mut self, samples: u16) -> Result<u16, AdcError> {
+            let raw = self.read_averaged_raw(samples)?;
+            Ok(self.raw_to_cal(raw)?)
+        }
+
+        /// Same as `read_averaged`, without calibration
+        pub fn read_averaged_raw(&mut self, samples: u16) -> Result<u16, AdcError> {
+            let stats = self.read_stats(samples)?;
+
+            if self.config.averaging.trim && stats.samples > 2 {
+                Ok(
+                    ((stats.sum - stats.min as u32 - stats.max as u32) / (stats.samples as u32 - 2))
+                        as u16,
+                )
+            } else {
+                Ok(stats.mean())
+            }
+        }
+
+        /// Performs `samples` raw conversions of the channel, and returns their statistics,
+        /// e.g. to characterize the noise of the signal
+        pub fn read_stats(&mut self, samples: u16) -> Result<AdcStats, AdcError> {
+            if samples == 0 {
+                Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>())?;
+            }
+
+            let mut stats = AdcStats {
+                samples,
+                min: u16::MAX,
+                max: 0,
+                sum: 0,
+            };
+
+            for sample in 0..samples {
+                if sample > 0 && sample % YIELD_EVERY == 0 {
+                    crate::delay::FreeRtos::delay_ms(1);
+                }
+
+                let raw = self.read_raw()?;
+
+                stats.min = stats.min.min(raw);
+                stats.max = stats.max.max(raw);
+                // Cannot overflow, as `u16::MAX` conversions of at most 13 bits fit in 29 bits
+                stats.sum += raw as u32;
+            }
+
+            Ok(stats)
+        }
+
+        /// Same as `read`, but yields to the executor before the conversion
+        ///
+        /// A oneshot conversion is performed synchronously by ESP-IDF and only takes a few
+        /// microseconds, so there is no completion to wait for. Each conversion is done as a
+        /// whole, so dropping the future leaves the unit usable.
+        pub async fn read_async(&mut self) -> Result<u16, AdcError> {
+            let raw = self.read_raw_async().await?;
+            Ok(self.raw_to_cal(raw)?)
+        }
+
+        /// Same as `read_raw`, but yields to the executor before the conversion
+        pub async fn read_raw_async(&mut self) -> Result<u16, AdcError> {
+            crate::task::yield_now().await;
+
+            self.read_raw()
+        }
+
+        /// Averages `samples` conversions of the channel, yielding to the executor
+        /// before each of them, so that other tasks are not blocked for the whole duration
+        pub async fn read_averaged_async(&mut self, samples: u16) -> Result<u16, AdcError> {
+            if samples == 0 {
+                Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>())?;
+            }
+
+            let mut sum = 0_u32;
+
+            for _ in 0..samples {
+                sum += self.read_raw_async().await? as u32;
+            }
+
+            Ok(self.raw_to_cal((sum / samples as u32) as u16)?)
+        }
+
+        #[inline(always)]
+        pub fn raw_to_cal(&self, raw: u16) -> Result<u16, EspError> {
+            if let Some(calibration) = &self.calibration {
+                calibration.raw_to_mv(raw)
+            } else {
+                Ok(raw)
+            }
+        }
+    }
+
+    impl<'d, T, M> AdcChannelRead for AdcChannelDriver<'d, T, M>
+    where
+        T: ADCPin,
+        M: Borrow<AdcDriver<'d, T::Adc>>,
+    {
+        fn unit(&self) -> adc_unit_t {
+            T::Adc::unit()
+        }
+
+        fn channel(&self) -> adc_channel_t {
+            T::CHANNEL
+        }
+
+        fn raw_to_cal(&self, raw: u16) -> Result<u16, EspError> {
+            AdcChannelDriver::raw_to_cal(self, raw)
+        }
+    }
+
+    impl<'d, T, M> embedded_hal_0_2::adc::Channel<T::Adc> for AdcChannelDriver<'d, T, M>
+    where
+        T: ADCPin,
+        M: Borrow<AdcDriver<'d, T::Adc>>,
+    {
+        type ID = adc_channel_t;
+
+        fn channel() -> Self::ID {
+            T::CHANNEL
+        }
+    }
+
+    unsafe impl<'d, T, M> Send for AdcChannelDriver<'d, T, M>
+    where
+        T: ADCPin,
+        M: Borrow<AdcDriver<'d, T::Adc>>,
+    {
+    }
+
+    /// A oneshot driver of an ADC unit
+    ///
+    /// The conversions only need a shared reference to the driver, and are serialized by it,
+    /// so the driver can be shared by channel drivers living in different tasks, by creating
+    /// them with e.g. an `Arc<AdcDriver>` or a `&AdcDriver`.
+    pub struct AdcDriver<'d, ADC: Adc> {
+        handle: adc_oneshot_unit_handle_t,
+        busy_retry: config::BusyRetry,
+        lock: CriticalSection,
+        _adc: PeripheralRef<'d, ADC>,
+    }
+
+    impl<'d, ADC: Adc> AdcDriver<'d, ADC> {
+        pub fn new(adc: impl Peripheral<P = ADC> + 'd) -> Result<Self, EspError> {
+            Self::new_with_config(adc, &Default::default())
+        }
+
+        pub fn new_with_config(
+            adc: impl Peripheral<P = ADC> + 'd,
+            driver_config: &config::AdcDriverConfig,
+        ) -> Result<Self, EspError> {
+            crate::into_ref!(adc);
+            let config = adc_oneshot_unit_init_cfg_t {
+                unit_id: ADC::unit(),
+                ..Default::default()
+            };
+            let mut handle: adc_oneshot_unit_handle_t = core::ptr::null_mut();
+            unsafe { esp!(adc_oneshot_new_unit(&config, &mut handle))? };
+            Ok(Self {
+                handle,
+                busy_retry: driver_config.busy_retry,
+                lock: CriticalSection::new(),
+                _adc: adc,
+            })
+        }
+
+        #[inline(always)]
+        pub fn read<T, M>(&self, channel: &mut AdcChannelDriver<'d, T, M>) -> Result<u16, AdcError>
+        where
+            T: ADCPin,
+            M: Borrow<AdcDriver<'d, T::Adc>>,
+        {
+            let raw = self.read_raw(channel)?;
+            Ok(self.raw_to_cal(channel, raw)?)
+        }
+
+        #[inline(always)]
+        pub fn read_raw<T, M>(
+            &self,
+            _channel: &mut AdcChannelDriver<'d, T, M>,
+        ) -> Result<u16, AdcError>
+        where
+            T: ADCPin,
+            M: Borrow<AdcDriver<'d, T::Adc>>,
+        {
+            self.read_raw_internal(T::CHANNEL)
+        }
+
+        /// Reads several channels of this unit back-to-back, and stores the (calibrated)
+        /// reading of each channel in the slot of `values` with the same index
+        ///
+        /// The channels are converted one after the other, and calibrated only after all
+        /// of them were converted, so as to minimize the skew between the readings.
+        /// Returns `AdcError::UnitMismatch` if any of the channels belongs to another unit.
+        pub fn read_multi(
+            &self,
+            channels: &mut [&mut dyn AdcChannelRead],
+            values: &mut [u16],
+        ) -> Result<(), AdcError> {
+            self.read_multi_averaged(channels, values, 1)
+        }
+
+        /// Same as `read_multi`, but scans the channels `samples` times, and stores the
+        /// average reading of each channel
+        pub fn read_multi_averaged(
+            &self,
+            channels: &mut [&mut dyn AdcChannelRead],
+            values: &mut [u16],
+            samples: u16,
+        ) -> Result<(), AdcError> {
+            if channels.iter().any(|channel| channel.unit() != ADC::unit()) {
+                return Err(AdcError::UnitMismatch);
+            }
+
+            if channels.len() > MAX_CHANNELS || values.len() != channels.len() || samples == 0 {
+                Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>())?;
+            }
+
+            let mut sums = [0_u32; MAX_CHANNELS];
+
+            for _ in 0..samples {
+                for (channel, sum) in channels.iter().zip(sums.iter_mut()) {
+                    *sum += self.read_raw_internal(channel.channel())? as u32;
+                }
+            }
+
+            for ((channel, sum), value) in channels.iter().zip(sums).zip(values.iter_mut()) {
+                *value = channel.raw_to_cal((sum / samples as u32) as u16)?;
+            }
+
+            Ok(())
+        }
+
+        #[inline(always)]
+        fn read_raw_internal(&self, channel: adc_channel_t) -> Result<u16, AdcError> {
+            let read = || {
+                // ESP-IDF fails, rather than waits, if the unit is in use by another task
+                let _guard = self.lock.enter();
+
+                let mut measurement = 0;
+                unsafe { esp!(adc_oneshot_read(self.handle, channel, &mut measurement)) }?;
+                Ok(measurement as u16)
+            };
+
+            if ADC::unit() == adc_unit_t_ADC_UNIT_1 {
+                Ok(read()?)
+            } else {
+                super::convert_adc2(&self.busy_retry, read)
+            }
+        }
+
+        #[inline(always)]
+        pub fn raw_to_cal<T, M>(
+            &self,
+            channel: &AdcChannelDriver<'d, T, M>,
+            raw: u16,
+        ) -> Result<u16, EspError>
+        where
+            T: ADCPin,
+            M: Borrow<AdcDriver<'d, T::Adc>>,
+        {
+            channel.raw_to_cal(raw)
+        }
+    }
+
+    impl<'d, ADC: Adc> Drop for AdcDriver<'d, ADC> {
+        fn drop(&mut self) {
+            unsafe { esp!(adc_oneshot_del_unit(self.handle)) }.unwrap();
+        }
+    }
+
+    impl<'d, T, M> embedded_hal_0_2::adc::OneShot<T::Adc, u16, AdcChannelDriver<'d, T, M>>
+        for AdcDriver<'d, T::Adc>
+    where
+        T: ADCPin,
+        M: Borrow<AdcDriver<'d, T::Adc>>,
+    {
+        type Error = EspError;
+
+        fn read(&mut self, pin: &mut AdcChannelDriver<'d, T, M>) -> nb::Result<u16, Self::Error> {
+            AdcDriver::read(self, pin).map_err(|e| to_nb_err(e.into()))
+        }
+    }
+
+    unsafe impl<'d, ADC: Adc> Send for AdcDriver<'d, ADC> {}
+    unsafe impl<'d, ADC: Adc> Sync for AdcDriver<'d, ADC> {}
+}
 
 #[cfg(all(
     not(esp_idf_version_major = "4"),
@@ -781,6 +1413,11 @@ pub mod continuous {
     use crate::interrupt::asynch::HalIsrNotification;
     use crate::io::EspIOError;
     use crate::peripheral::Peripheral;
+    use crate::units::Hertz;
+
+    /// The maximum raw reading of the continuous mode, which uses 12 bits
+    #[allow(dead_code)]
+    const MAX_DATA: u16 = 4095;
 
     use super::{attenuation, Adc};
 
@@ -837,7 +1474,10 @@ pub mod continuous {
     {
         type Adc = <<P as Peripheral>::P as ADCPin>::Adc;
 
-        type Iterator<'a> = core::iter::Once<(adc_channel_t, adc_atten_t)> where Self: 'a;
+        type Iterator<'a>
+            = core::iter::Once<(adc_channel_t, adc_atten_t)>
+        where
+            Self: 'a;
 
         fn iter(&self) -> Self::Iterator<'_> {
             core::iter::once((P::P::CHANNEL, attenuation::NONE))
@@ -850,10 +1490,13 @@ pub mod continuous {
     {
         type Adc = C::Adc;
 
-        type Iterator<'a> = core::iter::Map<
+        type Iterator<'a>
+            = core::iter::Map<
             C::Iterator<'a>,
             fn((adc_channel_t, adc_atten_t)) -> (adc_channel_t, adc_atten_t),
-        > where Self: 'a;
+        >
+        where
+            Self: 'a;
 
         fn iter(&self) -> Self::Iterator<'_> {
             self.0.iter().map(Attenuated::<A, C>::atten)
@@ -868,7 +1511,14 @@ pub mod continuous {
     {
         type Adc = C::Adc;
 
-        type Iterator<'a> = core::iter::FlatMap<core::slice::Iter<'a, C>, <C as AdcChannels>::Iterator<'a>, fn(&'a C) -> C::Iterator<'a>> where Self: 'a;
+        type Iterator<'a>
+            = core::iter::FlatMap<
+            core::slice::Iter<'a, C>,
+            <C as AdcChannels>::Iterator<'a>,
+            fn(&'a C) -> C::Iterator<'a>,
+        >
+        where
+            Self: 'a;
 
         fn iter(&self) -> Self::Iterator<'_> {
             self.0.iter().flat_map(AdcChannels::iter)
@@ -896,7 +1546,10 @@ pub mod continuous {
     {
         type Adc = A;
 
-        type Iterator<'a> = core::iter::Empty<(adc_channel_t, adc_atten_t)> where Self: 'a;
+        type Iterator<'a>
+            = core::iter::Empty<(adc_channel_t, adc_atten_t)>
+        where
+            Self: 'a;
 
         fn iter(&self) -> Self::Iterator<'_> {
             core::iter::empty()
@@ -929,7 +1582,10 @@ pub mod continuous {
     {
         type Adc = F::Adc;
 
-        type Iterator<'a> = core::iter::Chain<F::Iterator<'a>, S::Iterator<'a>> where Self: 'a;
+        type Iterator<'a>
+            = core::iter::Chain<F::Iterator<'a>, S::Iterator<'a>>
+        where
+            Self: 'a;
 
         fn iter(&self) -> Self::Iterator<'_> {
             self.first.iter().chain(self.second.iter())
@@ -983,6 +1639,15 @@ pub mod continuous {
             unsafe { self.0.__bindgen_anon_1.type2.unit() as _ }
         }
 
+        /// The unit of the measurement
+        ///
+        /// The output format of these chips does not carry the unit, as only ADC1 can be
+        /// used in continuous mode.
+        #[cfg(any(esp32, esp32s2, esp32h2, esp32c6))]
+        pub fn unit(&self) -> adc_unit_t {
+            adc_unit_t_ADC_UNIT_1
+        }
+
         #[cfg(any(esp32, esp32s2))]
         pub fn nullify(&mut self) {
             self.0.__bindgen_anon_1.val = self.data();
@@ -999,16 +1664,58 @@ pub mod continuous {
                 measurement.nullify();
             }
 
-            unsafe { core::slice::from_raw_parts_mut(data.as_mut_ptr() as *mut _, data.len()) }
+            unsafe { core::slice::from_raw_parts_mut(data.as_mut_ptr() as *mut _, data.len()) }
+        }
+
+        #[cfg(not(any(esp32, esp32s2)))]
+        pub fn as_pcm32(data: &mut [AdcMeasurement]) -> &mut [u32] {
+            for measurement in data.iter_mut() {
+                measurement.nullify();
+            }
+
+            unsafe { core::slice::from_raw_parts_mut(data.as_mut_ptr() as *mut _, data.len()) }
+        }
+    }
+
+    /// The measurements of a frame read with `AdcDriver::read_frame`
+    ///
+    /// Iterates over the measurements in the buffer they were read into, skipping the
+    /// invalid ones, i.e. the ones of a unit or a channel not configured in the driver,
+    /// which ESP-IDF documents can occur.
+    pub struct AdcFrame<'a> {
+        measurements: core::slice::Iter<'a, AdcMeasurement>,
+        unit: adc_unit_t,
+        channels: u32,
+        invalid: usize,
+    }
+
+    impl<'a> AdcFrame<'a> {
+        /// The number of invalid measurements skipped so far
+        pub fn invalid(&self) -> usize {
+            self.invalid
+        }
+    }
+
+    impl<'a> Iterator for AdcFrame<'a> {
+        type Item = &'a AdcMeasurement;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            for measurement in self.measurements.by_ref() {
+                if measurement.unit() == self.unit
+                    && measurement.channel() < 32
+                    && self.channels & (1 << measurement.channel()) != 0
+                {
+                    return Some(measurement);
+                }
+
+                self.invalid += 1;
+            }
+
+            None
         }
 
-        #[cfg(not(any(esp32, esp32s2)))]
-        pub fn as_pcm32(data: &mut [AdcMeasurement]) -> &mut [u32] {
-            for measurement in data.iter_mut() {
-                measurement.nullify();
-            }
-
-            unsafe { core::slice::from_raw_parts_mut(data.as_mut_ptr() as *mut _, data.len()) }
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (0, self.measurements.size_hint().1)
         }
     }
 
@@ -1079,9 +1786,270 @@ pub mod continuous {
         }
     }
 
+    /// The coefficient of a hardware IIR filter, i.e. the weight of the past readings
+    #[cfg(all(any(esp32s3, esp32c3, esp32c6, esp32h2), not(esp_idf_version = "5.0")))]
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    pub enum FilterCoeff {
+        Coeff2,
+        Coeff4,
+        Coeff8,
+        Coeff16,
+        Coeff64,
+    }
+
+    #[cfg(all(any(esp32s3, esp32c3, esp32c6, esp32h2), not(esp_idf_version = "5.0")))]
+    impl From<FilterCoeff> for adc_digi_iir_filter_coeff_t {
+        fn from(coeff: FilterCoeff) -> Self {
+            match coeff {
+                FilterCoeff::Coeff2 => adc_digi_iir_filter_coeff_t_ADC_DIGI_IIR_FILTER_COEFF_2,
+                FilterCoeff::Coeff4 => adc_digi_iir_filter_coeff_t_ADC_DIGI_IIR_FILTER_COEFF_4,
+                FilterCoeff::Coeff8 => adc_digi_iir_filter_coeff_t_ADC_DIGI_IIR_FILTER_COEFF_8,
+                FilterCoeff::Coeff16 => adc_digi_iir_filter_coeff_t_ADC_DIGI_IIR_FILTER_COEFF_16,
+                FilterCoeff::Coeff64 => adc_digi_iir_filter_coeff_t_ADC_DIGI_IIR_FILTER_COEFF_64,
+            }
+        }
+    }
+
+    /// A filter added to a driver with `AdcDriver::add_filter`
+    ///
+    /// The handle is only valid for the driver it was returned by, and only until the filter
+    /// is removed; it is rejected with `ESP_ERR_INVALID_ARG` afterwards, even if another
+    /// filter reuses the same hardware filter.
+    #[cfg(all(any(esp32s3, esp32c3, esp32c6, esp32h2), not(esp_idf_version = "5.0")))]
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    pub struct FilterHandle {
+        index: usize,
+        generation: u32,
+    }
+
+    /// The number of hardware IIR filters (`SOC_ADC_DIGI_IIR_FILTER_NUM`)
+    #[cfg(all(any(esp32s3, esp32c3, esp32c6, esp32h2), not(esp_idf_version = "5.0")))]
+    const MAX_FILTERS: usize = 2;
+
+    /// The generation of the last filter added to any driver, so that the handles of removed
+    /// filters and of the filters of other drivers never match an existing filter
+    #[cfg(all(any(esp32s3, esp32c3, esp32c6, esp32h2), not(esp_idf_version = "5.0")))]
+    static FILTER_GENERATION: core::sync::atomic::AtomicU32 = core::sync::atomic::AtomicU32::new(0);
+
+    #[cfg(all(any(esp32s3, esp32c3, esp32c6, esp32h2), not(esp_idf_version = "5.0")))]
+    struct Filter {
+        handle: adc_iir_filter_handle_t,
+        channel: adc_channel_t,
+        generation: u32,
+    }
+
+    /// The threshold monitors of the driver
+    #[cfg(all(
+        any(esp32s2, esp32s3, esp32c3, esp32c6, esp32h2),
+        not(any(esp_idf_version = "5.0", esp_idf_version = "5.1")),
+        not(esp_idf_adc_continuous_isr_iram_safe),
+        feature = "alloc"
+    ))]
+    mod monitor {
+        extern crate alloc;
+        use alloc::boxed::Box;
+
+        use core::ffi::c_void;
+
+        use esp_idf_sys::*;
+
+        use super::{AdcDriver, MAX_DATA};
+
+        /// The event of a threshold monitor
+        #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+        pub enum MonitorEvent {
+            /// The reading of the channel went above the high threshold
+            AboveHigh,
+            /// The reading of the channel went below the low threshold
+            BelowLow,
+        }
+
+        /// A monitor added to a driver with `AdcDriver::add_monitor`
+        #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+        pub struct MonitorHandle(usize);
+
+        /// The number of threshold monitors (`SOC_ADC_DIGI_MONITOR_NUM`)
+        pub(super) const MAX_MONITORS: usize = 2;
+
+        type MonitorCallback = Box<dyn FnMut(MonitorEvent) + Send + 'static>;
+
+        /// The callback is only freed by `AdcDriver::remove_monitor`, once the ESP-IDF monitor
+        /// is deleted, as its ISR might use the callback until then. Otherwise, it is leaked.
+        pub(super) struct Monitor {
+            handle: adc_monitor_handle_t,
+            callback: *mut MonitorCallback,
+        }
+
+        impl<'d> AdcDriver<'d> {
+            /// Adds an enabled threshold monitor to `channel`, which has to be one of the
+            /// scanned channels, calling `callback` whenever a conversion of the channel is above
+            /// `high` or below `low`
+            ///
+            /// At least one of the thresholds has to be provided, and on the ESP32-S2 at most one.
+            /// Returns `ESP_ERR_NOT_FOUND` if all monitors are in use. The monitor is removed
+            /// when the driver is dropped.
+            ///
+            /// To get the events in an async context, notify e.g. a `Notification`
+            /// in the callback.
+            ///
+            /// # Safety
+            ///
+            /// Care should be taken not to call STD, libc or FreeRTOS APIs (except for a few allowed ones)
+            /// in the callback passed to this function, as it is executed in an ISR context.
+            pub unsafe fn add_monitor<F>(
+                &mut self,
+                channel: adc_channel_t,
+                high: Option<u16>,
+                low: Option<u16>,
+                callback: F,
+            ) -> Result<MonitorHandle, EspError>
+            where
+                F: FnMut(MonitorEvent) + Send + 'static,
+            {
+                if channel >= 32 || self.channels & (1 << channel) == 0 {
+                    return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
+                }
+
+                let valid = match (high, low) {
+                    (None, None) => false,
+                    (Some(high), Some(low)) => cfg!(not(esp32s2)) && low < high && high <= MAX_DATA,
+                    (Some(threshold), None) | (None, Some(threshold)) => threshold <= MAX_DATA,
+                };
+
+                if !valid {
+                    return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
+                }
+
+                let index = self
+                    .monitors
+                    .iter()
+                    .position(Option::is_none)
+                    .ok_or(EspError::from_infallible::<ESP_ERR_NOT_FOUND>())?;
+
+                let mut handle: adc_monitor_handle_t = core::ptr::null_mut();
+
+                esp!(adc_new_continuous_monitor(
+                    self.handle,
+                    &adc_monitor_config_t {
+                        adc_unit: self.unit(),
+                        channel,
+                        h_threshold: high.map(|high| high as i32).unwrap_or(-1),
+                        l_threshold: low.map(|low| low as i32).unwrap_or(-1),
+                    },
+                    &mut handle,
+                ))?;
+
+                // Only accessed through this pointer from now on - by the ISR - until the monitor
+                // is dropped
+                let callback: *mut MonitorCallback = Box::into_raw(Box::new(Box::new(callback)));
+
+                // The monitor is stored before registering the callbacks, so that it is
+                // deleted - and the callback freed - when the registration fails
+                self.monitors[index] = Some(Monitor { handle, callback });
+
+                let user_data = callback as *mut c_void;
+
+                let result = esp!(adc_continuous_monitor_register_event_callbacks(
+                    handle,
+                    &adc_monitor_evt_cbs_t {
+                        on_over_high_thresh: Some(Self::handle_monitor_high),
+                        on_below_low_thresh: Some(Self::handle_monitor_low),
+                    },
+                    user_data,
+                ))
+                .and_then(|_| esp!(adc_continuous_monitor_enable(handle)));
+
+                if let Err(err) = result {
+                    self.remove_monitor(MonitorHandle(index))?;
+
+                    return Err(err);
+                }
+
+                Ok(MonitorHandle(index))
+            }
+
+            /// Removes a monitor added with `add_monitor`
+            pub fn remove_monitor(&mut self, monitor: MonitorHandle) -> Result<(), EspError> {
+                let handle = self
+                    .monitors
+                    .get(monitor.0)
+                    .and_then(|monitor| monitor.as_ref().map(|monitor| monitor.handle))
+                    .ok_or(EspError::from_infallible::<ESP_ERR_INVALID_ARG>())?;
+
+                let _ = esp!(unsafe { adc_continuous_monitor_disable(handle) });
+                esp!(unsafe { adc_del_continuous_monitor(handle) })?;
+
+                if let Some(monitor) = self.monitors[monitor.0].take() {
+                    // The ESP-IDF monitor is deleted, so its ISR no longer uses the callback
+                    drop(unsafe { Box::from_raw(monitor.callback) });
+                }
+
+                Ok(())
+            }
+
+            unsafe extern "C" fn handle_monitor_high(
+                _handle: adc_monitor_handle_t,
+                _data: *const adc_monitor_evt_data_t,
+                user_data: *mut c_void,
+            ) -> bool {
+                Self::handle_monitor(user_data, MonitorEvent::AboveHigh)
+            }
+
+            unsafe extern "C" fn handle_monitor_low(
+                _handle: adc_monitor_handle_t,
+                _data: *const adc_monitor_evt_data_t,
+                user_data: *mut c_void,
+            ) -> bool {
+                Self::handle_monitor(user_data, MonitorEvent::BelowLow)
+            }
+
+            unsafe fn handle_monitor(user_data: *mut c_void, event: MonitorEvent) -> bool {
+                let callback = &mut *(user_data as *mut MonitorCallback);
+
+                callback(event);
+
+                false
+            }
+
+            /// Removes all the monitors, even if removing one of them fails, in which case
+            /// the last error is returned
+            pub(super) fn remove_monitors(&mut self) -> Result<(), EspError> {
+                let mut result = Ok(());
+
+                for index in 0..MAX_MONITORS {
+                    if self.monitors[index].is_some() {
+                        if let Err(err) = self.remove_monitor(MonitorHandle(index)) {
+                            result = Err(err);
+                        }
+                    }
+                }
+
+                result
+            }
+        }
+    }
+
+    #[cfg(all(
+        any(esp32s2, esp32s3, esp32c3, esp32c6, esp32h2),
+        not(any(esp_idf_version = "5.0", esp_idf_version = "5.1")),
+        not(esp_idf_adc_continuous_isr_iram_safe),
+        feature = "alloc"
+    ))]
+    pub use monitor::{MonitorEvent, MonitorHandle};
+
     pub struct AdcDriver<'d> {
         handle: adc_continuous_handle_t,
         adc: u8,
+        channels: u32,
+        #[cfg(all(
+            any(esp32s2, esp32s3, esp32c3, esp32c6, esp32h2),
+            not(any(esp_idf_version = "5.0", esp_idf_version = "5.1")),
+            not(esp_idf_adc_continuous_isr_iram_safe),
+            feature = "alloc"
+        ))]
+        monitors: [Option<monitor::Monitor>; monitor::MAX_MONITORS],
+        #[cfg(all(any(esp32s3, esp32c3, esp32c6, esp32h2), not(esp_idf_version = "5.0")))]
+        filters: [Option<Filter>; MAX_FILTERS],
         _ref: PhantomData<&'d ()>,
     }
 
@@ -1120,19 +2088,6 @@ pub mod continuous {
             config: &config::Config,
             channels: impl AdcChannels<Adc = A> + 'd,
         ) -> Result<Self, EspError> {
-            let mut patterns = [adc_digi_pattern_config_t::default(); 32];
-
-            for (index, (channel, atten)) in channels.iter().enumerate() {
-                if index >= patterns.len() {
-                    return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
-                }
-
-                patterns[index].atten = atten as _;
-                patterns[index].channel = channel as _;
-                patterns[index].unit = A::unit() as _;
-                patterns[index].bit_width = 12; // For now only 12 bits is supported
-            }
-
             let mut handle: adc_continuous_handle_t = core::ptr::null_mut();
 
             #[allow(clippy::needless_update)]
@@ -1150,7 +2105,69 @@ pub mod continuous {
                 )
             })?;
 
-            let conv_mode = if A::unit() == 0 {
+            let channels = match Self::configure(handle, A::unit(), config.sample_freq, &channels) {
+                Ok(channels) => channels,
+                Err(err) => {
+                    esp!(unsafe { adc_continuous_deinit(handle) }).unwrap();
+
+                    return Err(err);
+                }
+            };
+
+            #[cfg(not(esp_idf_adc_continuous_isr_iram_safe))]
+            {
+                esp!(unsafe {
+                    adc_continuous_register_event_callbacks(
+                        handle,
+                        &adc_continuous_evt_cbs_t {
+                            on_conv_done: Some(Self::handle_isr),
+                            on_pool_ovf: Some(Self::handle_isr),
+                        },
+                        &NOTIFIER[A::unit() as usize] as *const _ as *mut _,
+                    )
+                })?;
+            }
+
+            Ok(Self {
+                handle,
+                adc: A::unit() as _,
+                channels,
+                #[cfg(all(any(esp32s3, esp32c3, esp32c6, esp32h2), not(esp_idf_version = "5.0")))]
+                filters: [None, None],
+                #[cfg(all(
+                    any(esp32s2, esp32s3, esp32c3, esp32c6, esp32h2),
+                    not(any(esp_idf_version = "5.0", esp_idf_version = "5.1")),
+                    not(esp_idf_adc_continuous_isr_iram_safe),
+                    feature = "alloc"
+                ))]
+                monitors: [None, None],
+                _ref: PhantomData,
+            })
+        }
+
+        fn configure<C: AdcChannels>(
+            handle: adc_continuous_handle_t,
+            unit: adc_unit_t,
+            sample_freq: Hertz,
+            channels: &C,
+        ) -> Result<u32, EspError> {
+            let mut patterns = [adc_digi_pattern_config_t::default(); 32];
+            let mut mask = 0;
+
+            for (index, (channel, atten)) in channels.iter().enumerate() {
+                if index >= patterns.len() {
+                    return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
+                }
+
+                mask |= 1 << channel;
+
+                patterns[index].atten = atten as _;
+                patterns[index].channel = channel as _;
+                patterns[index].unit = unit as _;
+                patterns[index].bit_width = 12; // For now only 12 bits is supported
+            }
+
+            let conv_mode = if unit == 0 {
                 adc_digi_convert_mode_t_ADC_CONV_SINGLE_UNIT_1
             } else {
                 adc_digi_convert_mode_t_ADC_CONV_SINGLE_UNIT_2
@@ -1168,32 +2185,132 @@ pub mod continuous {
                     &adc_continuous_config_t {
                         pattern_num: channels.iter().count() as _,
                         adc_pattern: &patterns as *const _ as *mut _,
-                        sample_freq_hz: config.sample_freq.into(),
+                        sample_freq_hz: sample_freq.into(),
                         conv_mode,
                         format,
                     },
                 )
             })?;
 
-            #[cfg(not(esp_idf_adc_continuous_isr_iram_safe))]
-            {
-                esp!(unsafe {
-                    adc_continuous_register_event_callbacks(
-                        handle,
-                        &adc_continuous_evt_cbs_t {
-                            on_conv_done: Some(Self::handle_isr),
-                            on_pool_ovf: Some(Self::handle_isr),
-                        },
-                        &NOTIFIER[A::unit() as usize] as *const _ as *mut _,
-                    )
-                })?;
+            Ok(mask)
+        }
+
+        /// Changes the sample frequency and the scanned channels of a stopped driver
+        ///
+        /// The DMA pool and the frame size are kept as they were configured in `new`, so this
+        /// is cheap, and can be used for duty-cycled sampling with a different set of channels.
+        /// Returns `ESP_ERR_INVALID_STATE` if the driver is started, and `ESP_ERR_INVALID_ARG`
+        /// if the channels belong to a different ADC unit.
+        ///
+        /// The filters of the channels which are no longer scanned are removed.
+        pub fn reconfigure<C>(&mut self, sample_freq: Hertz, channels: C) -> Result<(), EspError>
+        where
+            C: AdcChannels + 'd,
+        {
+            if C::Adc::unit() != self.unit() {
+                return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
             }
 
-            Ok(Self {
+            self.channels = Self::configure(self.handle, self.unit(), sample_freq, &channels)?;
+
+            #[cfg(all(any(esp32s3, esp32c3, esp32c6, esp32h2), not(esp_idf_version = "5.0")))]
+            for index in 0..MAX_FILTERS {
+                let scanned = self.filters[index]
+                    .as_ref()
+                    .map_or(true, |filter| self.channels & (1 << filter.channel) != 0);
+
+                if !scanned {
+                    self.remove_filter_at(index)?;
+                }
+            }
+
+            Ok(())
+        }
+
+        /// Adds a hardware IIR filter to `channel`, which has to be one of the scanned channels
+        ///
+        /// The filter is created disabled, and can only be enabled or disabled while the
+        /// driver is stopped. It is removed when the driver is dropped.
+        #[cfg(all(any(esp32s3, esp32c3, esp32c6, esp32h2), not(esp_idf_version = "5.0")))]
+        pub fn add_filter(
+            &mut self,
+            channel: adc_channel_t,
+            coeff: FilterCoeff,
+        ) -> Result<FilterHandle, EspError> {
+            if channel >= 32 || self.channels & (1 << channel) == 0 {
+                return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
+            }
+
+            let index = self
+                .filters
+                .iter()
+                .position(Option::is_none)
+                .ok_or(EspError::from_infallible::<ESP_ERR_NOT_FOUND>())?;
+
+            let mut handle: adc_iir_filter_handle_t = core::ptr::null_mut();
+
+            esp!(unsafe {
+                adc_new_continuous_iir_filter(
+                    self.handle,
+                    &adc_continuous_iir_filter_config_t {
+                        unit: self.unit(),
+                        channel,
+                        coeff: coeff.into(),
+                    },
+                    &mut handle,
+                )
+            })?;
+
+            let generation = FILTER_GENERATION
+                .fetch_add(1, core::sync::atomic::Ordering::Relaxed)
+                .wrapping_add(1);
+
+            self.filters[index] = Some(Filter {
                 handle,
-                adc: A::unit() as _,
-                _ref: PhantomData,
-            })
+                channel,
+                generation,
+            });
+
+            Ok(FilterHandle { index, generation })
+        }
+
+        #[cfg(all(any(esp32s3, esp32c3, esp32c6, esp32h2), not(esp_idf_version = "5.0")))]
+        pub fn enable_filter(&mut self, filter: FilterHandle) -> Result<(), EspError> {
+            esp!(unsafe { adc_continuous_iir_filter_enable(self.filter(filter)?) })
+        }
+
+        #[cfg(all(any(esp32s3, esp32c3, esp32c6, esp32h2), not(esp_idf_version = "5.0")))]
+        pub fn disable_filter(&mut self, filter: FilterHandle) -> Result<(), EspError> {
+            esp!(unsafe { adc_continuous_iir_filter_disable(self.filter(filter)?) })
+        }
+
+        /// Removes a filter added with `add_filter`, disabling it first
+        #[cfg(all(any(esp32s3, esp32c3, esp32c6, esp32h2), not(esp_idf_version = "5.0")))]
+        pub fn remove_filter(&mut self, filter: FilterHandle) -> Result<(), EspError> {
+            self.filter(filter)?;
+            self.remove_filter_at(filter.index)
+        }
+
+        #[cfg(all(any(esp32s3, esp32c3, esp32c6, esp32h2), not(esp_idf_version = "5.0")))]
+        fn remove_filter_at(&mut self, index: usize) -> Result<(), EspError> {
+            if let Some(filter) = &self.filters[index] {
+                let _ = esp!(unsafe { adc_continuous_iir_filter_disable(filter.handle) });
+                esp!(unsafe { adc_del_continuous_iir_filter(filter.handle) })?;
+
+                self.filters[index] = None;
+            }
+
+            Ok(())
+        }
+
+        #[cfg(all(any(esp32s3, esp32c3, esp32c6, esp32h2), not(esp_idf_version = "5.0")))]
+        fn filter(&self, filter: FilterHandle) -> Result<adc_iir_filter_handle_t, EspError> {
+            match self.filters.get(filter.index) {
+                Some(Some(existing)) if existing.generation == filter.generation => {
+                    Ok(existing.handle)
+                }
+                _ => Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>()),
+            }
         }
 
         pub fn handle(&self) -> adc_continuous_handle_t {
@@ -1232,6 +2349,26 @@ pub mod continuous {
             Ok(read as usize / core::mem::size_of::<AdcMeasurement>())
         }
 
+        /// Same as `read`, returning an iterator over the valid measurements read into `buf`
+        pub fn read_frame<'b>(
+            &mut self,
+            buf: &'b mut [AdcMeasurement],
+            timeout: TickType_t,
+        ) -> Result<AdcFrame<'b>, EspError> {
+            let len = self.read(buf, timeout)?;
+
+            Ok(self.frame(&buf[..len]))
+        }
+
+        fn frame<'b>(&self, measurements: &'b [AdcMeasurement]) -> AdcFrame<'b> {
+            AdcFrame {
+                measurements: measurements.iter(),
+                unit: self.unit(),
+                channels: self.channels,
+                invalid: 0,
+            }
+        }
+
         pub fn read_bytes(
             &mut self,
             buf: &mut [u8],
@@ -1265,6 +2402,17 @@ pub mod continuous {
             }
         }
 
+        /// Same as `read_async`, returning an iterator over the valid measurements read into `buf`
+        #[cfg(not(esp_idf_adc_continuous_isr_iram_safe))]
+        pub async fn read_frame_async<'b>(
+            &mut self,
+            buf: &'b mut [AdcMeasurement],
+        ) -> Result<AdcFrame<'b>, EspError> {
+            let len = self.read_async(buf).await?;
+
+            Ok(self.frame(&buf[..len]))
+        }
+
         #[cfg(not(esp_idf_adc_continuous_isr_iram_safe))]
         pub async fn read_bytes_async(&mut self, buf: &mut [u8]) -> Result<usize, EspError> {
             loop {
@@ -1295,6 +2443,21 @@ pub mod continuous {
         fn drop(&mut self) {
             let _ = self.stop();
 
+            #[cfg(all(
+                any(esp32s2, esp32s3, esp32c3, esp32c6, esp32h2),
+                not(any(esp_idf_version = "5.0", esp_idf_version = "5.1")),
+                not(esp_idf_adc_continuous_isr_iram_safe),
+                feature = "alloc"
+            ))]
+            if let Err(err) = self.remove_monitors() {
+                ::log::error!("Failed to remove the ADC monitors, leaking their callbacks: {err}");
+            }
+
+            #[cfg(all(any(esp32s3, esp32c3, esp32c6, esp32h2), not(esp_idf_version = "5.0")))]
+            for index in 0..MAX_FILTERS {
+                self.remove_filter_at(index).unwrap();
+            }
+
             #[cfg(not(esp_idf_adc_continuous_isr_iram_safe))]
             {
                 esp!(unsafe {
@@ -1342,3 +2505,120 @@ pub mod continuous {
     static NOTIFIER: [HalIsrNotification; 2] =
         [HalIsrNotification::new(), HalIsrNotification::new()];
 }
+
+/// Configuration of ADC1 for sampling by the ULP coprocessor, e.g. during deep sleep
+#[cfg(any(
+    all(
+        not(any(esp_idf_version_major = "4", esp_idf_version = "5.0")),
+        esp_idf_ulp_coproc_enabled,
+        any(esp32, esp32s2, esp32s3)
+    ),
+    all(
+        esp_idf_version_major = "4",
+        any(
+            all(esp32, esp_idf_esp32_ulp_coproc_enabled),
+            all(esp32s2, esp_idf_esp32s2_ulp_coproc_enabled),
+            all(esp32s3, esp_idf_esp32s3_ulp_coproc_enabled)
+        )
+    )
+))]
+pub mod ulp {
+    use esp_idf_sys::*;
+
+    use crate::gpio::ADCPin;
+    use crate::peripheral::{Peripheral, PeripheralRef};
+
+    use super::attenuation::adc_atten_t;
+    use super::config::Resolution;
+    use super::ADC1;
+
+    /// Hands a channel of ADC1 over to the ULP coprocessor
+    ///
+    /// While the driver is alive, the ULP program can sample the channel, using the
+    /// parameters returned by `unit`, `channel`, `attenuation` and `resolution`. Dropping the
+    /// driver returns ADC1 to the main CPU, so that e.g. a oneshot driver can be created
+    /// for it again. The ULP program has to be halted before that.
+    pub struct UlpAdcDriver<'d, T: ADCPin<Adc = ADC1>> {
+        _adc: PeripheralRef<'d, ADC1>,
+        _pin: PeripheralRef<'d, T>,
+        attenuation: adc_atten_t,
+        resolution: Resolution,
+    }
+
+    impl<'d, T: ADCPin<Adc = ADC1>> UlpAdcDriver<'d, T> {
+        pub fn new(
+            adc: impl Peripheral<P = ADC1> + 'd,
+            pin: impl Peripheral<P = T> + 'd,
+            attenuation: adc_atten_t,
+            resolution: Resolution,
+        ) -> Result<Self, EspError> {
+            crate::into_ref!(adc, pin);
+
+            unsafe {
+                crate::gpio::rtc_reset_pin(pin.pin())?;
+            }
+
+            #[cfg(not(esp_idf_version_major = "4"))]
+            {
+                #[cfg(not(esp_idf_ulp_coproc_type_riscv))]
+                let ulp_mode = adc_ulp_mode_t_ADC_ULP_MODE_FSM;
+
+                #[cfg(esp_idf_ulp_coproc_type_riscv)]
+                let ulp_mode = adc_ulp_mode_t_ADC_ULP_MODE_RISCV;
+
+                esp!(unsafe {
+                    ulp_adc_init(&ulp_adc_cfg_t {
+                        adc_n: adc_unit_t_ADC_UNIT_1,
+                        channel: T::CHANNEL,
+                        atten: attenuation,
+                        width: resolution.bitwidth(),
+                        ulp_mode,
+                    })
+                })?;
+            }
+
+            #[cfg(esp_idf_version_major = "4")]
+            {
+                esp!(unsafe { adc1_config_width(resolution.into()) })?;
+                esp!(unsafe { adc1_config_channel_atten(T::CHANNEL, attenuation) })?;
+
+                unsafe { adc1_ulp_enable() };
+            }
+
+            Ok(Self {
+                _adc: adc,
+                _pin: pin,
+                attenuation,
+                resolution,
+            })
+        }
+
+        pub fn unit(&self) -> adc_unit_t {
+            adc_unit_t_ADC_UNIT_1
+        }
+
+        /// The channel index the ULP program has to sample
+        pub fn channel(&self) -> adc_channel_t {
+            T::CHANNEL
+        }
+
+        pub fn attenuation(&self) -> adc_atten_t {
+            self.attenuation
+        }
+
+        pub fn resolution(&self) -> Resolution {
+            self.resolution
+        }
+    }
+
+    impl<'d, T: ADCPin<Adc = ADC1>> Drop for UlpAdcDriver<'d, T> {
+        fn drop(&mut self) {
+            // Before ESP-IDF 5.2, the unit cannot be released, but the legacy
+            // oneshot driver takes it back from the ULP on its own
+            #[cfg(not(any(esp_idf_version_major = "4", esp_idf_version = "5.1")))]
+            esp!(unsafe { ulp_adc_deinit() }).unwrap();
+        }
+    }
+
+    unsafe impl<'d, T: ADCPin<Adc = ADC1>> Send for UlpAdcDriver<'d, T> {}
+}
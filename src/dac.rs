@@ -0,0 +1,1379 @@
+//! Digital-to-analog converter (DAC)
+//!
+//! Interface to the [DAC
+//! peripheral](https://docs.espressif.com/projects/esp-idf/en/latest/esp32/api-reference/peripherals/dac.html)
+//! of the ESP32 and the ESP32-S2, which has two 8-bit channels.
+//!
+//! # Examples
+//!
+//! Output about 1.65V on the first DAC channel (GPIO25 on the ESP32)
+//! ```
+//! use esp_idf_hal::dac::DacDriver;
+//! use esp_idf_hal::peripherals::Peripherals;
+//! # fn main() -> Result<(), esp_idf_hal::sys::EspError> {
+//!
+//! let peripherals = Peripherals::take()?;
+//! let mut driver = DacDriver::new(peripherals.pins.gpio25)?;
+//!
+//! driver.write(128)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use esp_idf_sys::*;
+
+use crate::gpio::{AnyIOPin, DACPin, IOPin, Pin};
+use crate::peripheral::{Peripheral, PeripheralRef};
+use crate::units::*;
+
+/// The nominal VDD3P3 voltage, which is the reference of the DAC
+pub const NOMINAL_REFERENCE_MV: u16 = 3300;
+
+/// The approximate frequency of the RC_FAST clock, which drives the cosine generator
+const RC_FAST_FREQ: u32 = 8_500_000;
+
+/// Types for configuring the DAC
+pub mod config {
+    use crate::units::*;
+
+    /// The scale of the cosine generator output, relative to the full range
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+    pub enum CosineScale {
+        #[default]
+        Full,
+        Half,
+        Quarter,
+        Eighth,
+    }
+
+    /// The phase of the cosine generator output
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+    pub enum CosinePhase {
+        #[default]
+        Phase0,
+        Phase180,
+    }
+
+    /// The configuration of the cosine generator
+    ///
+    /// The generator, and thus the frequency, is shared by the two channels, while the
+    /// scale, the phase and the offset are set per channel.
+    #[derive(Debug, Copy, Clone)]
+    pub struct CosineConfig {
+        /// The frequency of the tone, 130Hz at least
+        pub frequency: Hertz,
+        pub scale: CosineScale,
+        pub phase: CosinePhase,
+        /// The DC offset of the tone, in DAC steps
+        pub offset: i8,
+    }
+
+    impl CosineConfig {
+        pub const fn new() -> Self {
+            Self {
+                frequency: Hertz(1000),
+                scale: CosineScale::Full,
+                phase: CosinePhase::Phase0,
+                offset: 0,
+            }
+        }
+
+        #[must_use]
+        pub fn frequency(mut self, frequency: Hertz) -> Self {
+            self.frequency = frequency;
+            self
+        }
+
+        #[must_use]
+        pub fn scale(mut self, scale: CosineScale) -> Self {
+            self.scale = scale;
+            self
+        }
+
+        #[must_use]
+        pub fn phase(mut self, phase: CosinePhase) -> Self {
+            self.phase = phase;
+            self
+        }
+
+        #[must_use]
+        pub fn offset(mut self, offset: i8) -> Self {
+            self.offset = offset;
+            self
+        }
+
+        /// The frequency the generator actually outputs for `frequency`, as it is
+        /// derived from the RC_FAST clock with a frequency step of about 130Hz, and
+        /// saturates at the limit of the 16-bit step register
+        pub fn actual_frequency(&self) -> Hertz {
+            let step =
+                (self.frequency.0 as u64 * 0xffff / super::RC_FAST_FREQ as u64).clamp(1, 0xffff);
+
+            Hertz((step * super::RC_FAST_FREQ as u64 / 0xffff) as u32)
+        }
+    }
+
+    impl Default for CosineConfig {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+}
+
+/// A driver of a DAC channel, outputting either a constant voltage or a cosine tone
+///
+/// When dropped, the channel is powered down, i.e. the pin is left floating, unless
+/// `keep_output_on_drop` is set.
+pub struct DacDriver<'d> {
+    channel: dac_channel_t,
+    pin: PeripheralRef<'d, AnyIOPin>,
+    value: u8,
+    reference_mv: u16,
+    paused: bool,
+    keep_output_on_drop: bool,
+    #[cfg(not(esp_idf_version_major = "4"))]
+    oneshot: dac_oneshot_handle_t,
+    #[cfg(not(esp_idf_version_major = "4"))]
+    cosine: dac_cosine_handle_t,
+    #[cfg(esp_idf_version_major = "4")]
+    cosine: bool,
+}
+
+impl<'d> DacDriver<'d> {
+    pub fn new(pin: impl Peripheral<P = impl DACPin + IOPin> + 'd) -> Result<Self, EspError> {
+        crate::into_ref!(pin);
+
+        let channel = pin.dac_channel();
+
+        let mut driver = Self {
+            channel,
+            pin: pin.map_into(),
+            value: 0,
+            reference_mv: NOMINAL_REFERENCE_MV,
+            paused: true,
+            keep_output_on_drop: false,
+            #[cfg(not(esp_idf_version_major = "4"))]
+            oneshot: core::ptr::null_mut(),
+            #[cfg(not(esp_idf_version_major = "4"))]
+            cosine: core::ptr::null_mut(),
+            #[cfg(esp_idf_version_major = "4")]
+            cosine: false,
+        };
+
+        driver.resume()?;
+
+        Ok(driver)
+    }
+
+    pub fn channel(&self) -> dac_channel_t {
+        self.channel
+    }
+
+    /// Outputs `value`, i.e. a voltage of about `value / 256` of VDD3P3
+    ///
+    /// Returns `ESP_ERR_INVALID_STATE` while the cosine generator is enabled, or the channel
+    /// is paused.
+    pub fn write(&mut self, value: u8) -> Result<(), EspError> {
+        self.check_output()?;
+
+        self.output(value)?;
+        self.value = value;
+
+        Ok(())
+    }
+
+    /// Outputs the value closest to `mv`, saturating at the limits of the output range
+    ///
+    /// Returns `ESP_ERR_INVALID_STATE` while the cosine generator is enabled, or the channel
+    /// is paused.
+    pub fn write_mv(&mut self, mv: u16) -> Result<(), EspError> {
+        self.write(self.mv_to_value(mv))
+    }
+
+    /// The value last written with `write` or `write_mv`
+    pub fn value(&self) -> u8 {
+        self.value
+    }
+
+    /// The voltage output for the value last written with `write` or `write_mv`
+    pub fn mv(&self) -> u16 {
+        self.value_to_mv(self.value)
+    }
+
+    /// The voltage VDD3P3 of the chip, in millivolts, which the voltages of the
+    /// `*_mv` methods are based on
+    ///
+    /// The ESP32 and the ESP32-S2 have no calibrated VDD3P3 value in eFuse, so this is
+    /// `NOMINAL_REFERENCE_MV` unless set with `set_reference_mv`, e.g. to a measured value.
+    pub fn reference_mv(&self) -> u16 {
+        self.reference_mv
+    }
+
+    pub fn set_reference_mv(&mut self, reference_mv: u16) -> Result<(), EspError> {
+        if reference_mv == 0 {
+            return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
+        }
+
+        self.reference_mv = reference_mv;
+
+        Ok(())
+    }
+
+    /// The voltage output for `value`, in millivolts
+    pub fn value_to_mv(&self, value: u8) -> u16 {
+        ((value as u32 * self.reference_mv as u32 + 128) / 256) as u16
+    }
+
+    /// The value whose output is closest to `mv`, saturating at 255
+    pub fn mv_to_value(&self, mv: u16) -> u8 {
+        let reference_mv = self.reference_mv as u32;
+
+        ((mv as u32 * 256 + reference_mv / 2) / reference_mv).min(255) as u8
+    }
+
+    /// Outputs a cosine tone with the hardware cosine generator instead of a constant
+    /// voltage, and returns the actual frequency of the tone
+    ///
+    /// The frequency is shared by the two channels, so enabling the generator on the
+    /// second channel with a different frequency fails on ESP-IDF 5.
+    pub fn enable_cosine(&mut self, config: &config::CosineConfig) -> Result<Hertz, EspError> {
+        if config.frequency.0 < 130 {
+            return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
+        }
+
+        if self.paused {
+            return Err(EspError::from_infallible::<ESP_ERR_INVALID_STATE>());
+        }
+
+        if self.is_cosine_enabled() {
+            self.disable_cosine_generator()?;
+        }
+
+        self.enable_cosine_generator(config)?;
+
+        Ok(config.actual_frequency())
+    }
+
+    /// Stops the cosine tone, and outputs the last value written with `write` again
+    pub fn disable_cosine(&mut self) -> Result<(), EspError> {
+        if self.is_cosine_enabled() {
+            self.disable_cosine_generator()?;
+        }
+
+        Ok(())
+    }
+
+    /// Powers the channel down temporarily, leaving the pin floating, until `resume`
+    /// is called
+    pub fn pause(&mut self) -> Result<(), EspError> {
+        if self.paused {
+            return Ok(());
+        }
+
+        self.disable_cosine()?;
+
+        #[cfg(not(esp_idf_version_major = "4"))]
+        {
+            esp!(unsafe { dac_oneshot_del_channel(self.oneshot) })?;
+            self.oneshot = core::ptr::null_mut();
+        }
+
+        #[cfg(esp_idf_version_major = "4")]
+        esp!(unsafe { dac_output_disable(self.channel) })?;
+
+        self.paused = true;
+
+        Ok(())
+    }
+
+    /// Powers the channel up again after `pause`, and outputs the last written value
+    pub fn resume(&mut self) -> Result<(), EspError> {
+        if self.paused {
+            self.enable_oneshot()?;
+            self.paused = false;
+        }
+
+        Ok(())
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Whether the channel keeps outputting the last written value once the driver is
+    /// dropped, instead of being powered down
+    ///
+    /// On ESP-IDF 5, the channel then stays registered with the ESP-IDF DAC driver, so
+    /// that no other driver can be created for it anymore.
+    pub fn keep_output_on_drop(&mut self, keep: bool) {
+        self.keep_output_on_drop = keep;
+    }
+
+    /// Powers the channel down, and resets the pin to its default GPIO configuration,
+    /// reporting errors unlike dropping the driver
+    pub fn into_disabled(self) -> Result<(), EspError> {
+        let mut this = core::mem::ManuallyDrop::new(self);
+
+        this.pause()?;
+
+        esp!(unsafe { gpio_reset_pin(this.pin.pin()) })
+    }
+
+    #[cfg(not(esp_idf_version_major = "4"))]
+    pub fn is_cosine_enabled(&self) -> bool {
+        !self.cosine.is_null()
+    }
+
+    #[cfg(esp_idf_version_major = "4")]
+    pub fn is_cosine_enabled(&self) -> bool {
+        self.cosine
+    }
+
+    fn check_output(&self) -> Result<(), EspError> {
+        if self.paused || self.is_cosine_enabled() {
+            Err(EspError::from_infallible::<ESP_ERR_INVALID_STATE>())
+        } else {
+            Ok(())
+        }
+    }
+
+    #[cfg(not(esp_idf_version_major = "4"))]
+    fn enable_oneshot(&mut self) -> Result<(), EspError> {
+        esp!(unsafe {
+            dac_oneshot_new_channel(
+                &dac_oneshot_config_t {
+                    chan_id: self.channel,
+                },
+                &mut self.oneshot,
+            )
+        })?;
+
+        self.output(self.value)
+    }
+
+    #[cfg(esp_idf_version_major = "4")]
+    fn enable_oneshot(&mut self) -> Result<(), EspError> {
+        esp!(unsafe { dac_output_enable(self.channel) })?;
+
+        self.output(self.value)
+    }
+
+    #[cfg(not(esp_idf_version_major = "4"))]
+    fn output(&mut self, value: u8) -> Result<(), EspError> {
+        esp!(unsafe { dac_oneshot_output_voltage(self.oneshot, value) })
+    }
+
+    #[cfg(esp_idf_version_major = "4")]
+    fn output(&mut self, value: u8) -> Result<(), EspError> {
+        esp!(unsafe { dac_output_voltage(self.channel, value) })
+    }
+
+    #[cfg(not(esp_idf_version_major = "4"))]
+    fn enable_cosine_generator(&mut self, config: &config::CosineConfig) -> Result<(), EspError> {
+        // A channel can only be registered with one ESP-IDF driver at a time
+        esp!(unsafe { dac_oneshot_del_channel(self.oneshot) })?;
+        self.oneshot = core::ptr::null_mut();
+
+        #[allow(clippy::needless_update)]
+        let cosine_config = dac_cosine_config_t {
+            chan_id: self.channel,
+            freq_hz: config.frequency.0,
+            clk_src: soc_periph_dac_cosine_clk_src_t_DAC_COSINE_CLK_SRC_DEFAULT,
+            atten: match config.scale {
+                config::CosineScale::Full => dac_cosine_atten_t_DAC_COSINE_ATTEN_DB_0,
+                config::CosineScale::Half => dac_cosine_atten_t_DAC_COSINE_ATTEN_DB_6,
+                config::CosineScale::Quarter => dac_cosine_atten_t_DAC_COSINE_ATTEN_DB_12,
+                config::CosineScale::Eighth => dac_cosine_atten_t_DAC_COSINE_ATTEN_DB_18,
+            },
+            phase: match config.phase {
+                config::CosinePhase::Phase0 => dac_cosine_phase_t_DAC_COSINE_PHASE_0,
+                config::CosinePhase::Phase180 => dac_cosine_phase_t_DAC_COSINE_PHASE_180,
+            },
+            offset: config.offset,
+            ..Default::default()
+        };
+
+        let result = esp!(unsafe { dac_cosine_new_channel(&cosine_config, &mut self.cosine) })
+            .and_then(|_| esp!(unsafe { dac_cosine_start(self.cosine) }));
+
+        if let Err(err) = result {
+            if !self.cosine.is_null() {
+                esp!(unsafe { dac_cosine_del_channel(self.cosine) })?;
+                self.cosine = core::ptr::null_mut();
+            }
+
+            self.enable_oneshot()?;
+
+            return Err(err);
+        }
+
+        Ok(())
+    }
+
+    #[cfg(esp_idf_version_major = "4")]
+    fn enable_cosine_generator(&mut self, config: &config::CosineConfig) -> Result<(), EspError> {
+        esp!(unsafe {
+            dac_cw_generator_config(&mut dac_cw_config_t {
+                en_ch: self.channel,
+                scale: match config.scale {
+                    config::CosineScale::Full => dac_cw_scale_t_DAC_CW_SCALE_1,
+                    config::CosineScale::Half => dac_cw_scale_t_DAC_CW_SCALE_2,
+                    config::CosineScale::Quarter => dac_cw_scale_t_DAC_CW_SCALE_4,
+                    config::CosineScale::Eighth => dac_cw_scale_t_DAC_CW_SCALE_8,
+                },
+                phase: match config.phase {
+                    config::CosinePhase::Phase0 => dac_cw_phase_t_DAC_CW_PHASE_0,
+                    config::CosinePhase::Phase180 => dac_cw_phase_t_DAC_CW_PHASE_180,
+                },
+                freq: config.frequency.0,
+                offset: config.offset,
+            })
+        })?;
+
+        esp!(unsafe { dac_cw_generator_enable() })?;
+
+        self.cosine = true;
+        COSINE_CHANNELS.fetch_or(1 << self.channel, core::sync::atomic::Ordering::SeqCst);
+
+        Ok(())
+    }
+
+    #[cfg(not(esp_idf_version_major = "4"))]
+    fn disable_cosine_generator(&mut self) -> Result<(), EspError> {
+        esp!(unsafe { dac_cosine_stop(self.cosine) })?;
+        esp!(unsafe { dac_cosine_del_channel(self.cosine) })?;
+        self.cosine = core::ptr::null_mut();
+
+        self.enable_oneshot()
+    }
+
+    #[cfg(esp_idf_version_major = "4")]
+    fn disable_cosine_generator(&mut self) -> Result<(), EspError> {
+        // Writing a value switches the channel back from the generator to the value
+        self.output(self.value)?;
+        self.cosine = false;
+
+        let others = COSINE_CHANNELS
+            .fetch_and(!(1 << self.channel), core::sync::atomic::Ordering::SeqCst)
+            & !(1 << self.channel);
+
+        if others == 0 {
+            esp!(unsafe { dac_cw_generator_disable() })?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'d> Drop for DacDriver<'d> {
+    fn drop(&mut self) {
+        if self.keep_output_on_drop {
+            self.disable_cosine().unwrap();
+        } else {
+            self.pause().unwrap();
+        }
+    }
+}
+
+unsafe impl<'d> Send for DacDriver<'d> {}
+
+/// A driver of both DAC channels, updating them together
+pub struct DacPairDriver<'d> {
+    first: DacDriver<'d>,
+    second: DacDriver<'d>,
+}
+
+impl<'d> DacPairDriver<'d> {
+    pub fn new(
+        pin0: impl Peripheral<P = impl DACPin + IOPin> + 'd,
+        pin1: impl Peripheral<P = impl DACPin + IOPin> + 'd,
+    ) -> Result<Self, EspError> {
+        crate::into_ref!(pin0, pin1);
+
+        if pin0.dac_channel() == pin1.dac_channel() {
+            return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
+        }
+
+        Self::from_drivers(DacDriver::new(pin0)?, DacDriver::new(pin1)?)
+    }
+
+    /// Combines the drivers of the two channels, in any order
+    pub fn from_drivers(a: DacDriver<'d>, b: DacDriver<'d>) -> Result<Self, EspError> {
+        if a.channel == b.channel {
+            return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
+        }
+
+        let (first, second) = if a.channel < b.channel {
+            (a, b)
+        } else {
+            (b, a)
+        };
+
+        Ok(Self { first, second })
+    }
+
+    /// Splits the driver into the drivers of the first and the second channel
+    pub fn split(self) -> (DacDriver<'d>, DacDriver<'d>) {
+        (self.first, self.second)
+    }
+
+    /// The drivers of the first and the second channel
+    pub fn channels(&mut self) -> (&mut DacDriver<'d>, &mut DacDriver<'d>) {
+        (&mut self.first, &mut self.second)
+    }
+
+    /// Outputs `a` on the first and `b` on the second channel, back to back with
+    /// interrupts disabled, so that the outputs change within a few cycles of each other
+    ///
+    /// Returns `ESP_ERR_INVALID_STATE` while the cosine generator is enabled, or a channel
+    /// is paused.
+    pub fn write_both(&mut self, a: u8, b: u8) -> Result<(), EspError> {
+        self.first.check_output()?;
+        self.second.check_output()?;
+
+        crate::interrupt::free(|| {
+            self.first.output(a)?;
+            self.second.output(b)
+        })?;
+
+        self.first.value = a;
+        self.second.value = b;
+
+        Ok(())
+    }
+
+    /// Like `write_both`, with the voltages in millivolts
+    pub fn write_both_mv(&mut self, a: u16, b: u16) -> Result<(), EspError> {
+        self.write_both(self.first.mv_to_value(a), self.second.mv_to_value(b))
+    }
+
+    /// Outputs the same cosine tone on both channels, with the second one being shifted
+    /// by `phase` relative to the first one, and returns the actual frequency of the tone
+    ///
+    /// As the two channels are fed by the same generator, their phase relationship is
+    /// fixed.
+    pub fn enable_cosine(
+        &mut self,
+        config: &config::CosineConfig,
+        phase: config::CosinePhase,
+    ) -> Result<Hertz, EspError> {
+        self.disable_cosine()?;
+
+        let second_phase = match (config.phase, phase) {
+            (phase, config::CosinePhase::Phase0) => phase,
+            (config::CosinePhase::Phase0, config::CosinePhase::Phase180) => {
+                config::CosinePhase::Phase180
+            }
+            (config::CosinePhase::Phase180, config::CosinePhase::Phase180) => {
+                config::CosinePhase::Phase0
+            }
+        };
+
+        let frequency = self.first.enable_cosine(config)?;
+
+        if let Err(err) = self.second.enable_cosine(&config.phase(second_phase)) {
+            self.first.disable_cosine()?;
+
+            return Err(err);
+        }
+
+        Ok(frequency)
+    }
+
+    /// Stops the cosine tone on both channels
+    pub fn disable_cosine(&mut self) -> Result<(), EspError> {
+        self.first.disable_cosine()?;
+        self.second.disable_cosine()
+    }
+}
+
+/// The channels using the cosine generator, which has to be enabled while any does
+#[cfg(esp_idf_version_major = "4")]
+static COSINE_CHANNELS: core::sync::atomic::AtomicU8 = core::sync::atomic::AtomicU8::new(0);
+
+/// Continuous, DMA-fed output of 8-bit samples on one or both DAC channels
+///
+/// On the ESP32 the DMA of the I2S0 peripheral is used, and on the ESP32-S2 the DMA of
+/// the SPI3 peripheral, which is why the driver takes ownership of it.
+///
+/// # Underruns
+///
+/// On ESP-IDF 5, when all written data has been output, the DMA stops and the channels
+/// keep the voltage of the last sample, until more data is written. Ending a stream with
+/// the idle value of the signal (e.g. 128 for audio) thus avoids a DC offset.
+///
+/// On ESP-IDF 4, the DMA keeps running, and the channels output 0 until more data is
+/// written.
+#[cfg(any(esp32, not(esp_idf_version_major = "4")))]
+pub mod continuous {
+    use core::marker::PhantomData;
+
+    use esp_idf_sys::*;
+
+    use crate::gpio::DACPin;
+    use crate::peripheral::Peripheral;
+    use crate::units::*;
+
+    #[cfg(not(esp_idf_version_major = "4"))]
+    use crate::{delay::TickType, interrupt::asynch::HalIsrNotification};
+
+    /// The peripheral whose DMA feeds the DAC channels
+    #[cfg(esp32)]
+    pub type DacDma = crate::i2s::I2S0;
+    /// The peripheral whose DMA feeds the DAC channels
+    #[cfg(esp32s2)]
+    pub type DacDma = crate::spi::SPI3;
+
+    /// The lowest sample rate supported with the default clock source
+    #[cfg(esp32)]
+    pub const MIN_SAMPLE_RATE: Hertz = Hertz(19_600);
+    /// The lowest sample rate supported with the default clock source
+    #[cfg(esp32s2)]
+    pub const MIN_SAMPLE_RATE: Hertz = Hertz(77);
+
+    /// The highest sample rate supported with the default clock source
+    #[cfg(esp32)]
+    pub const MAX_SAMPLE_RATE: Hertz = Hertz(2_500_000);
+    /// The highest sample rate supported with the default clock source
+    #[cfg(esp32s2)]
+    pub const MAX_SAMPLE_RATE: Hertz = Hertz(2_500_000);
+
+    pub mod config {
+        use crate::units::*;
+
+        /// How the samples are distributed to the channels, when outputting on both
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+        pub enum ChannelMode {
+            /// Each sample is output on both channels
+            #[default]
+            Simultaneous,
+            /// The samples are output alternately on the first and the second channel,
+            /// i.e. the data is interleaved, and the sample rate is per channel
+            ///
+            /// Only supported on ESP-IDF 5.
+            Alternate,
+        }
+
+        /// The configuration of the continuous DAC driver
+        #[derive(Debug, Copy, Clone)]
+        pub struct Config {
+            /// Between `MIN_SAMPLE_RATE` and `MAX_SAMPLE_RATE`
+            pub sample_rate: Hertz,
+            pub channel_mode: ChannelMode,
+            /// The number of DMA buffers
+            pub buffer_count: u32,
+            /// The size of each DMA buffer, in bytes
+            pub buffer_size: usize,
+            /// The DC offset of the output, in DAC steps
+            ///
+            /// Only supported on ESP-IDF 5.
+            pub offset: i8,
+        }
+
+        impl Config {
+            pub const fn new() -> Self {
+                Self {
+                    sample_rate: Hertz(44_100),
+                    channel_mode: ChannelMode::Simultaneous,
+                    buffer_count: 4,
+                    buffer_size: 1024,
+                    offset: 0,
+                }
+            }
+
+            #[must_use]
+            pub fn sample_rate(mut self, sample_rate: Hertz) -> Self {
+                self.sample_rate = sample_rate;
+                self
+            }
+
+            #[must_use]
+            pub fn channel_mode(mut self, channel_mode: ChannelMode) -> Self {
+                self.channel_mode = channel_mode;
+                self
+            }
+
+            #[must_use]
+            pub fn buffer_count(mut self, buffer_count: u32) -> Self {
+                self.buffer_count = buffer_count;
+                self
+            }
+
+            #[must_use]
+            pub fn buffer_size(mut self, buffer_size: usize) -> Self {
+                self.buffer_size = buffer_size;
+                self
+            }
+
+            #[must_use]
+            pub fn offset(mut self, offset: i8) -> Self {
+                self.offset = offset;
+                self
+            }
+        }
+
+        impl Default for Config {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+    }
+
+    /// A driver outputting a stream of 8-bit samples on one or both DAC channels
+    pub struct DacContinuousDriver<'d> {
+        channel_mask: u32,
+        config: config::Config,
+        #[cfg(not(esp_idf_version_major = "4"))]
+        handle: dac_continuous_handle_t,
+        #[cfg(not(esp_idf_version_major = "4"))]
+        cyclic: bool,
+        _p: PhantomData<&'d mut ()>,
+    }
+
+    impl<'d> DacContinuousDriver<'d> {
+        /// Creates a driver outputting on the channel of `pin`
+        pub fn new(
+            dma: impl Peripheral<P = DacDma> + 'd,
+            pin: impl Peripheral<P = impl DACPin> + 'd,
+            config: &config::Config,
+        ) -> Result<Self, EspError> {
+            crate::into_ref!(dma, pin);
+
+            Self::new_internal(1 << channel_index(pin.dac_channel()), config)
+        }
+
+        /// Creates a driver outputting on both channels
+        pub fn new_both(
+            dma: impl Peripheral<P = DacDma> + 'd,
+            pin0: impl Peripheral<P = impl DACPin> + 'd,
+            pin1: impl Peripheral<P = impl DACPin> + 'd,
+            config: &config::Config,
+        ) -> Result<Self, EspError> {
+            crate::into_ref!(dma, pin0, pin1);
+
+            if pin0.dac_channel() == pin1.dac_channel() {
+                return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
+            }
+
+            Self::new_internal(0b11, config)
+        }
+
+        /// The mask of the channels the driver outputs on, bit 0 being the first channel
+        pub fn channel_mask(&self) -> u32 {
+            self.channel_mask
+        }
+
+        pub fn config(&self) -> &config::Config {
+            &self.config
+        }
+
+        /// Changes the sample rate, dropping the samples not output yet on ESP-IDF 5, and
+        /// leaving cyclic mode
+        #[cfg(not(esp_idf_version_major = "4"))]
+        pub fn set_sample_rate(&mut self, sample_rate: Hertz) -> Result<(), EspError> {
+            let config = self.config.sample_rate(sample_rate);
+
+            Self::check(self.channel_mask, &config)?;
+
+            self.delete();
+            self.config = config;
+
+            self.create()
+        }
+
+        /// Changes the sample rate, dropping the samples not output yet on ESP-IDF 5, and
+        /// leaving cyclic mode
+        #[cfg(esp_idf_version_major = "4")]
+        pub fn set_sample_rate(&mut self, sample_rate: Hertz) -> Result<(), EspError> {
+            let config = self.config.sample_rate(sample_rate);
+
+            Self::check(self.channel_mask, &config)?;
+
+            esp!(unsafe { i2s_set_sample_rates(i2s_port_t_I2S_NUM_0, sample_rate.0) })?;
+
+            self.config = config;
+
+            Ok(())
+        }
+
+        /// Outputs all samples of `data`, waiting for a free DMA buffer as long as
+        /// necessary
+        pub fn write_all(&mut self, data: &[u8]) -> Result<(), EspError> {
+            let mut offset = 0;
+
+            while offset < data.len() {
+                offset += self.write(&data[offset..], crate::delay::BLOCK)?;
+            }
+
+            Ok(())
+        }
+
+        /// Outputs as many samples of `data` as fit in DMA buffers freed within `timeout`,
+        /// and returns their number
+        #[cfg(not(esp_idf_version_major = "4"))]
+        pub fn write(&mut self, data: &[u8], timeout: TickType_t) -> Result<usize, EspError> {
+            if self.cyclic {
+                return Err(EspError::from_infallible::<ESP_ERR_INVALID_STATE>());
+            }
+
+            if data.is_empty() {
+                return Ok(0);
+            }
+
+            let mut bytes_loaded = 0;
+
+            let result = esp!(unsafe {
+                dac_continuous_write(
+                    self.handle,
+                    data.as_ptr() as *mut _,
+                    data.len(),
+                    &mut bytes_loaded,
+                    TickType(timeout).as_millis_u32() as _,
+                )
+            });
+
+            match result {
+                Err(err) if err.code() == ESP_ERR_TIMEOUT && bytes_loaded > 0 => Ok(bytes_loaded),
+                other => other.map(|_| bytes_loaded),
+            }
+        }
+
+        /// Outputs as many samples of `data` as fit in DMA buffers freed within `timeout`,
+        /// and returns their number
+        #[cfg(esp_idf_version_major = "4")]
+        pub fn write(&mut self, data: &[u8], timeout: TickType_t) -> Result<usize, EspError> {
+            // The built-in DAC mode of the I2S driver takes 16-bit samples per slot, of
+            // which the DAC outputs the upper 8 bits
+            let mut buf = [0_u16; 128];
+
+            let mut offset = 0;
+
+            for chunk in data.chunks(buf.len() / 2) {
+                for (index, sample) in chunk.iter().enumerate() {
+                    buf[index * 2] = (*sample as u16) << 8;
+                    buf[index * 2 + 1] = (*sample as u16) << 8;
+                }
+
+                let mut bytes_written = 0;
+
+                let result = esp!(unsafe {
+                    i2s_write(
+                        i2s_port_t_I2S_NUM_0,
+                        buf.as_ptr() as *const _,
+                        chunk.len() * 4,
+                        &mut bytes_written,
+                        timeout,
+                    )
+                });
+
+                offset += bytes_written / 4;
+
+                match result {
+                    Err(err) if err.code() == ESP_ERR_TIMEOUT && offset > 0 => break,
+                    Err(err) => return Err(err),
+                    Ok(_) if bytes_written < chunk.len() * 4 => break,
+                    Ok(_) => (),
+                }
+            }
+
+            Ok(offset)
+        }
+
+        /// Outputs the samples of `data` over and over, without any CPU involvement,
+        /// and returns the number of samples looped
+        ///
+        /// At most `buffer_count * buffer_size` samples are looped, as the data is
+        /// copied to the DMA buffers. The other `write` methods fail with
+        /// `ESP_ERR_INVALID_STATE` until `disable` and `enable` are called.
+        #[cfg(not(esp_idf_version_major = "4"))]
+        pub fn write_cyclically(&mut self, data: &[u8]) -> Result<usize, EspError> {
+            let mut bytes_loaded = 0;
+
+            esp!(unsafe {
+                dac_continuous_write_cyclically(
+                    self.handle,
+                    data.as_ptr() as *mut _,
+                    data.len(),
+                    &mut bytes_loaded,
+                )
+            })?;
+
+            self.cyclic = true;
+
+            Ok(bytes_loaded)
+        }
+
+        /// Outputs as many samples of `data` as fit in the free DMA buffers, waiting
+        /// asynchronously for a DMA buffer to be freed if there is none, and returns
+        /// their number
+        #[cfg(not(esp_idf_version_major = "4"))]
+        pub async fn write_async(&mut self, data: &[u8]) -> Result<usize, EspError> {
+            loop {
+                match self.write(data, crate::delay::NON_BLOCK) {
+                    Err(err) if err.code() == ESP_ERR_TIMEOUT => {
+                        NOTIFIER.wait().await;
+                    }
+                    other => break other,
+                }
+            }
+        }
+
+        /// Outputs all samples of `data`, waiting asynchronously for free DMA buffers
+        #[cfg(not(esp_idf_version_major = "4"))]
+        pub async fn write_all_async(&mut self, data: &[u8]) -> Result<(), EspError> {
+            let mut offset = 0;
+
+            while offset < data.len() {
+                offset += self.write_async(&data[offset..]).await?;
+            }
+
+            Ok(())
+        }
+
+        /// Stops the output
+        #[cfg(not(esp_idf_version_major = "4"))]
+        pub fn disable(&mut self) -> Result<(), EspError> {
+            esp!(unsafe { dac_continuous_disable(self.handle) })?;
+
+            self.cyclic = false;
+
+            Ok(())
+        }
+
+        /// Stops the output
+        #[cfg(esp_idf_version_major = "4")]
+        pub fn disable(&mut self) -> Result<(), EspError> {
+            esp!(unsafe { i2s_stop(i2s_port_t_I2S_NUM_0) })
+        }
+
+        /// Restarts the output after `disable`
+        #[cfg(not(esp_idf_version_major = "4"))]
+        pub fn enable(&mut self) -> Result<(), EspError> {
+            esp!(unsafe { dac_continuous_enable(self.handle) })
+        }
+
+        /// Restarts the output after `disable`
+        #[cfg(esp_idf_version_major = "4")]
+        pub fn enable(&mut self) -> Result<(), EspError> {
+            esp!(unsafe { i2s_start(i2s_port_t_I2S_NUM_0) })
+        }
+
+        fn check(channel_mask: u32, config: &config::Config) -> Result<(), EspError> {
+            if config.sample_rate < MIN_SAMPLE_RATE
+                || config.sample_rate > MAX_SAMPLE_RATE
+                || config.buffer_count < 2
+                || config.buffer_size == 0
+            {
+                return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
+            }
+
+            if config.channel_mode == config::ChannelMode::Alternate && channel_mask != 0b11 {
+                return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
+            }
+
+            Ok(())
+        }
+
+        #[cfg(not(esp_idf_version_major = "4"))]
+        fn new_internal(channel_mask: u32, config: &config::Config) -> Result<Self, EspError> {
+            Self::check(channel_mask, config)?;
+
+            let mut driver = Self {
+                channel_mask,
+                config: *config,
+                handle: core::ptr::null_mut(),
+                cyclic: false,
+                _p: PhantomData,
+            };
+
+            driver.create()?;
+
+            Ok(driver)
+        }
+
+        #[cfg(not(esp_idf_version_major = "4"))]
+        fn create(&mut self) -> Result<(), EspError> {
+            let config = &self.config;
+
+            #[allow(clippy::needless_update)]
+            let continuous_config = dac_continuous_config_t {
+                chan_mask: self.channel_mask as _,
+                desc_num: config.buffer_count,
+                buf_size: config.buffer_size,
+                freq_hz: config.sample_rate.0,
+                offset: config.offset,
+                clk_src: soc_periph_dac_digi_clk_src_t_DAC_DIGI_CLK_SRC_DEFAULT,
+                chan_mode: match config.channel_mode {
+                    config::ChannelMode::Simultaneous => {
+                        dac_continuous_channel_mode_t_DAC_CHANNEL_MODE_SIMUL
+                    }
+                    config::ChannelMode::Alternate => {
+                        dac_continuous_channel_mode_t_DAC_CHANNEL_MODE_ALTER
+                    }
+                },
+                ..Default::default()
+            };
+
+            esp!(unsafe { dac_continuous_new_channels(&continuous_config, &mut self.handle) })?;
+
+            self.cyclic = false;
+
+            let callbacks = dac_event_callbacks_t {
+                on_convert_done: Some(dispatch_convert_done),
+                on_stop: None,
+            };
+
+            NOTIFIER.reset();
+
+            // The callbacks can only be registered while the driver is disabled
+            esp!(unsafe {
+                dac_continuous_register_event_callback(
+                    self.handle,
+                    &callbacks,
+                    core::ptr::null_mut(),
+                )
+            })?;
+
+            esp!(unsafe { dac_continuous_enable(self.handle) })
+        }
+
+        #[cfg(not(esp_idf_version_major = "4"))]
+        fn delete(&mut self) {
+            if !self.handle.is_null() {
+                // Fails if the driver is already disabled, which is fine
+                unsafe { dac_continuous_disable(self.handle) };
+                esp!(unsafe { dac_continuous_del_channels(self.handle) }).unwrap();
+
+                self.handle = core::ptr::null_mut();
+            }
+        }
+
+        #[cfg(esp_idf_version_major = "4")]
+        fn new_internal(channel_mask: u32, config: &config::Config) -> Result<Self, EspError> {
+            Self::check(channel_mask, config)?;
+
+            if config.channel_mode != config::ChannelMode::Simultaneous
+                || config.offset != 0
+                || config.buffer_size > 1024 * 4
+            {
+                return Err(EspError::from_infallible::<ESP_ERR_NOT_SUPPORTED>());
+            }
+
+            #[allow(clippy::needless_update)]
+            let i2s_config = i2s_config_t {
+                mode: i2s_mode_t_I2S_MODE_MASTER
+                    | i2s_mode_t_I2S_MODE_TX
+                    | i2s_mode_t_I2S_MODE_DAC_BUILT_IN,
+                sample_rate: config.sample_rate.0,
+                bits_per_sample: i2s_bits_per_sample_t_I2S_BITS_PER_SAMPLE_16BIT,
+                channel_format: i2s_channel_fmt_t_I2S_CHANNEL_FMT_RIGHT_LEFT,
+                communication_format: i2s_comm_format_t_I2S_COMM_FORMAT_STAND_MSB,
+                dma_buf_count: config.buffer_count as _,
+                // Each frame of two 16-bit slots takes 4 bytes
+                dma_buf_len: (config.buffer_size / 4).max(8) as _,
+                tx_desc_auto_clear: true,
+                ..Default::default()
+            };
+
+            esp!(unsafe {
+                i2s_driver_install(i2s_port_t_I2S_NUM_0, &i2s_config, 0, core::ptr::null_mut())
+            })?;
+
+            let driver = Self {
+                channel_mask,
+                config: *config,
+                _p: PhantomData,
+            };
+
+            // GPIO25 is the right, and GPIO26 the left I2S channel
+            esp!(unsafe {
+                i2s_set_dac_mode(match channel_mask {
+                    0b01 => i2s_dac_mode_t_I2S_DAC_CHANNEL_RIGHT_EN,
+                    0b10 => i2s_dac_mode_t_I2S_DAC_CHANNEL_LEFT_EN,
+                    _ => i2s_dac_mode_t_I2S_DAC_CHANNEL_BOTH_EN,
+                })
+            })?;
+
+            Ok(driver)
+        }
+    }
+
+    impl<'d> Drop for DacContinuousDriver<'d> {
+        fn drop(&mut self) {
+            #[cfg(not(esp_idf_version_major = "4"))]
+            self.delete();
+
+            #[cfg(esp_idf_version_major = "4")]
+            {
+                esp!(unsafe { i2s_set_dac_mode(i2s_dac_mode_t_I2S_DAC_CHANNEL_DISABLE) }).unwrap();
+                esp!(unsafe { i2s_driver_uninstall(i2s_port_t_I2S_NUM_0) }).unwrap();
+            }
+        }
+    }
+
+    unsafe impl<'d> Send for DacContinuousDriver<'d> {}
+
+    /// The midpoint of the output range, which is the idle level of an audio signal
+    pub const MIDPOINT: u8 = 128;
+
+    /// The number of bytes of a ramp output at once
+    const RAMP_CHUNK: usize = 64;
+
+    /// The format of the PCM data played by `DacAudio`
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    pub enum AudioFormat {
+        /// One unsigned 8-bit sample per frame, output on all channels of the driver
+        Mono,
+        /// Two interleaved unsigned 8-bit samples per frame, the first one for the first
+        /// channel
+        ///
+        /// Requires a driver of both channels in `ChannelMode::Alternate`.
+        Stereo,
+    }
+
+    impl AudioFormat {
+        /// The number of samples per frame
+        pub fn channels(&self) -> usize {
+            match self {
+                Self::Mono => 1,
+                Self::Stereo => 2,
+            }
+        }
+    }
+
+    /// A sink of unsigned 8-bit PCM data, taking care of the clicks at the start and at
+    /// the end of the playback
+    ///
+    /// When created, the output ramps up from 0 to `MIDPOINT`, and `close` ramps it down to 0
+    /// again. Before playing, the output ramps from its current level to the first frame, and
+    /// `stop` ramps it back to `MIDPOINT`. Each ramp takes 10ms.
+    ///
+    /// Dropping the sink without closing it ramps the output down as well, blocking for the
+    /// duration of the ramp, and only logging its errors.
+    pub struct DacAudio<'d> {
+        driver: DacContinuousDriver<'d>,
+        format: AudioFormat,
+        level: [u8; 2],
+    }
+
+    impl<'d> DacAudio<'d> {
+        /// Creates a sink of `format` data, which has to match the channel mode of `driver`
+        pub fn new(driver: DacContinuousDriver<'d>, format: AudioFormat) -> Result<Self, EspError> {
+            let alternate = driver.config.channel_mode == config::ChannelMode::Alternate;
+
+            if alternate != (format == AudioFormat::Stereo) {
+                return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
+            }
+
+            let mut audio = Self {
+                driver,
+                format,
+                level: [0; 2],
+            };
+
+            audio.ramp([MIDPOINT; 2])?;
+
+            Ok(audio)
+        }
+
+        pub fn format(&self) -> AudioFormat {
+            self.format
+        }
+
+        pub fn sample_rate(&self) -> Hertz {
+            self.driver.config().sample_rate
+        }
+
+        /// Changes the sample rate, after ramping the output down to 0 on ESP-IDF 5, where
+        /// the channels are powered down meanwhile
+        pub fn set_sample_rate(&mut self, sample_rate: Hertz) -> Result<(), EspError> {
+            #[cfg(not(esp_idf_version_major = "4"))]
+            {
+                let level = self.level;
+
+                self.ramp([0; 2])?;
+                self.driver.set_sample_rate(sample_rate)?;
+                self.ramp(level)
+            }
+
+            #[cfg(esp_idf_version_major = "4")]
+            self.driver.set_sample_rate(sample_rate)
+        }
+
+        /// Plays `data`, waiting for free DMA buffers as long as necessary
+        ///
+        /// Returns `ESP_ERR_INVALID_SIZE` if `data` is not made of whole frames.
+        pub fn play(&mut self, data: &[u8]) -> Result<(), EspError> {
+            if let Some(first) = self.first_frame(data)? {
+                self.ramp(first)?;
+                self.driver.write_all(data)?;
+                self.level = self.last_frame(data);
+            }
+
+            Ok(())
+        }
+
+        /// Plays `data`, waiting asynchronously for free DMA buffers
+        ///
+        /// Returns `ESP_ERR_INVALID_SIZE` if `data` is not made of whole frames.
+        #[cfg(not(esp_idf_version_major = "4"))]
+        pub async fn play_async(&mut self, data: &[u8]) -> Result<(), EspError> {
+            if let Some(first) = self.first_frame(data)? {
+                self.ramp_async(first).await?;
+                self.driver.write_all_async(data).await?;
+                self.level = self.last_frame(data);
+            }
+
+            Ok(())
+        }
+
+        /// Ramps the output back to `MIDPOINT`, to end the playback
+        pub fn stop(&mut self) -> Result<(), EspError> {
+            self.ramp([MIDPOINT; 2])
+        }
+
+        /// Ramps the output back to `MIDPOINT`, to end the playback
+        #[cfg(not(esp_idf_version_major = "4"))]
+        pub async fn stop_async(&mut self) -> Result<(), EspError> {
+            self.ramp_async([MIDPOINT; 2]).await
+        }
+
+        /// Ramps the output down to 0, reporting errors unlike dropping the sink
+        pub fn close(mut self) -> Result<(), EspError> {
+            // Dropping the sink afterwards does not ramp anymore, as the output is at 0
+            self.ramp([0; 2])
+        }
+
+        /// Ramps the output down to 0, waiting asynchronously for free DMA buffers
+        #[cfg(not(esp_idf_version_major = "4"))]
+        pub async fn close_async(mut self) -> Result<(), EspError> {
+            self.ramp_async([0; 2]).await
+        }
+
+        fn first_frame(&self, data: &[u8]) -> Result<Option<[u8; 2]>, EspError> {
+            if data.len() % self.format.channels() != 0 {
+                Err(EspError::from_infallible::<ESP_ERR_INVALID_SIZE>())
+            } else if data.is_empty() {
+                Ok(None)
+            } else {
+                Ok(Some(self.frame(&data[..self.format.channels()])))
+            }
+        }
+
+        fn last_frame(&self, data: &[u8]) -> [u8; 2] {
+            self.frame(&data[data.len() - self.format.channels()..])
+        }
+
+        fn frame(&self, frame: &[u8]) -> [u8; 2] {
+            match self.format {
+                AudioFormat::Mono => [frame[0]; 2],
+                AudioFormat::Stereo => [frame[0], frame[1]],
+            }
+        }
+
+        fn ramp(&mut self, to: [u8; 2]) -> Result<(), EspError> {
+            let frames = self.ramp_frames(to);
+
+            let mut frame = 0;
+
+            while frame < frames {
+                let mut buf = [0; RAMP_CHUNK];
+                let len = self.ramp_chunk(&mut buf, to, frame, frames);
+
+                self.driver.write_all(&buf[..len])?;
+
+                frame += len / self.format.channels();
+            }
+
+            self.level = to;
+
+            Ok(())
+        }
+
+        #[cfg(not(esp_idf_version_major = "4"))]
+        async fn ramp_async(&mut self, to: [u8; 2]) -> Result<(), EspError> {
+            let frames = self.ramp_frames(to);
+
+            let mut frame = 0;
+
+            while frame < frames {
+                let mut buf = [0; RAMP_CHUNK];
+                let len = self.ramp_chunk(&mut buf, to, frame, frames);
+
+                self.driver.write_all_async(&buf[..len]).await?;
+
+                frame += len / self.format.channels();
+            }
+
+            self.level = to;
+
+            Ok(())
+        }
+
+        /// The number of frames of a ramp to `to`, 0 if the output is at `to` already
+        fn ramp_frames(&self, to: [u8; 2]) -> usize {
+            let channels = self.format.channels();
+
+            if self.level[..channels] == to[..channels] {
+                0
+            } else {
+                (self.sample_rate().0 as usize / 100).max(1)
+            }
+        }
+
+        /// Fills `buf` with the frames of a ramp to `to` starting at `frame`, and returns the
+        /// number of bytes filled
+        fn ramp_chunk(
+            &self,
+            buf: &mut [u8; RAMP_CHUNK],
+            to: [u8; 2],
+            frame: usize,
+            frames: usize,
+        ) -> usize {
+            let channels = self.format.channels();
+            let count = (RAMP_CHUNK / channels).min(frames - frame);
+
+            for index in 0..count {
+                let step = (frame + index + 1) as i32;
+
+                for channel in 0..channels {
+                    let from = self.level[channel] as i32;
+                    let delta = to[channel] as i32 - from;
+
+                    buf[index * channels + channel] = (from + delta * step / frames as i32) as u8;
+                }
+            }
+
+            count * channels
+        }
+    }
+
+    impl<'d> Drop for DacAudio<'d> {
+        fn drop(&mut self) {
+            if let Err(err) = self.ramp([0; 2]) {
+                ::log::error!("Failed to ramp the DAC audio output down: {err}");
+            }
+        }
+    }
+
+    /// The index of a DAC channel, with the first one being 0
+    fn channel_index(channel: dac_channel_t) -> u32 {
+        if cfg!(esp_idf_version_major = "4") {
+            channel - 1
+        } else {
+            channel
+        }
+    }
+
+    #[cfg(not(esp_idf_version_major = "4"))]
+    unsafe extern "C" fn dispatch_convert_done(
+        _handle: dac_continuous_handle_t,
+        _event: *const dac_event_data_t,
+        _user_data: *mut core::ffi::c_void,
+    ) -> bool {
+        NOTIFIER.notify_lsb()
+    }
+
+    #[cfg(not(esp_idf_version_major = "4"))]
+    static NOTIFIER: HalIsrNotification = HalIsrNotification::new();
+}
@@ -0,0 +1,103 @@
+//! DMA-capable buffers
+//!
+//! Peripherals using DMA (e.g. SPI with DMA enabled) can only access buffers placed in internal
+//! RAM. Buffers placed in flash (e.g. `static` constants) or in external PSRAM are either copied
+//! by the ESP-IDF drivers into temporary DMA-capable buffers - costing time and heap memory - or
+//! are rejected.
+//!
+//! [DmaBuffer] is a heap-allocated buffer, which is guaranteed to be DMA-capable, and
+//! [is_dma_capable] checks whether an arbitrary buffer is.
+
+use core::ops::{Deref, DerefMut};
+use core::ptr::NonNull;
+
+use esp_idf_sys::*;
+
+/// Returns `true` if the memory at `ptr` is accessible by DMA, i.e. it is in internal RAM
+///
+/// This is the equivalent of the ESP-IDF `esp_ptr_dma_capable` check.
+#[inline(always)]
+pub fn is_dma_capable(ptr: *const u8) -> bool {
+    let addr = ptr as usize;
+
+    addr >= SOC_DMA_LOW as usize && addr < SOC_DMA_HIGH as usize
+}
+
+/// Returns `true` if the whole `buf` is accessible by DMA
+pub fn is_dma_capable_slice(buf: &[u8]) -> bool {
+    buf.is_empty()
+        || (is_dma_capable(buf.as_ptr())
+            && is_dma_capable(buf.as_ptr().wrapping_add(buf.len() - 1)))
+}
+
+/// A zero-initialized buffer allocated from the DMA-capable internal heap
+/// (`MALLOC_CAP_DMA`), aligned to 4 bytes.
+pub struct DmaBuffer {
+    ptr: NonNull<u8>,
+    len: usize,
+}
+
+impl DmaBuffer {
+    /// Allocates a buffer of `len` bytes.
+    ///
+    /// Fails with `ESP_ERR_NO_MEM` if there is not enough DMA-capable memory.
+    pub fn new(len: usize) -> Result<Self, EspError> {
+        // An allocation of at least one byte, so that the pointer is never dangling
+        let ptr = unsafe { heap_caps_calloc(1, len.max(1), MALLOC_CAP_DMA | MALLOC_CAP_8BIT) };
+
+        let ptr =
+            NonNull::new(ptr as *mut u8).ok_or_else(EspError::from_infallible::<ESP_ERR_NO_MEM>)?;
+
+        Ok(Self { ptr, len })
+    }
+
+    /// Allocates a buffer with a copy of `data`.
+    pub fn from_slice(data: &[u8]) -> Result<Self, EspError> {
+        let mut buf = Self::new(data.len())?;
+
+        buf.copy_from_slice(data);
+
+        Ok(buf)
+    }
+}
+
+impl Deref for DmaBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { core::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl DerefMut for DmaBuffer {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { core::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl AsRef<[u8]> for DmaBuffer {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl AsMut<[u8]> for DmaBuffer {
+    fn as_mut(&mut self) -> &mut [u8] {
+        self
+    }
+}
+
+impl core::fmt::Debug for DmaBuffer {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("DmaBuffer").field("len", &self.len).finish()
+    }
+}
+
+impl Drop for DmaBuffer {
+    fn drop(&mut self) {
+        unsafe { heap_caps_free(self.ptr.as_ptr() as *mut _) }
+    }
+}
+
+unsafe impl Send for DmaBuffer {}
+unsafe impl Sync for DmaBuffer {}
@@ -0,0 +1,143 @@
+//! Event Task Matrix (ETM)
+//!
+//! The ETM allows an event of one peripheral (e.g. an edge on a GPIO or a timer alarm)
+//! to directly trigger a task of another peripheral (e.g. toggling a GPIO), without any
+//! CPU involvement.
+//!
+//! Events and tasks are peripheral-specific (see e.g. `gpio::etm`), and are tied
+//! together with a `Channel`.
+
+use core::marker::PhantomData;
+
+use esp_idf_sys::*;
+
+/// An ETM event, which can be connected to a task via a `Channel`
+pub trait Event {
+    fn handle(&self) -> esp_etm_event_handle_t;
+}
+
+/// An ETM task, which can be triggered by an event via a `Channel`
+pub trait Task {
+    fn handle(&self) -> esp_etm_task_handle_t;
+}
+
+/// An ETM event created with a raw ESP-IDF API, for peripherals without an ETM wrapper
+/// in this crate (e.g. `gptimer_new_etm_event`). The event is deleted when dropped.
+pub struct RawEvent(esp_etm_event_handle_t);
+
+impl RawEvent {
+    /// # Safety
+    ///
+    /// The handle should be a valid ETM event handle, which is not owned elsewhere
+    pub unsafe fn new(handle: esp_etm_event_handle_t) -> Self {
+        Self(handle)
+    }
+}
+
+impl Event for RawEvent {
+    fn handle(&self) -> esp_etm_event_handle_t {
+        self.0
+    }
+}
+
+impl Drop for RawEvent {
+    fn drop(&mut self) {
+        esp!(unsafe { esp_etm_del_event(self.0) }).unwrap();
+    }
+}
+
+unsafe impl Send for RawEvent {}
+
+/// An ETM task created with a raw ESP-IDF API, for peripherals without an ETM wrapper
+/// in this crate (e.g. `gptimer_new_etm_task`). The task is deleted when dropped.
+pub struct RawTask(esp_etm_task_handle_t);
+
+impl RawTask {
+    /// # Safety
+    ///
+    /// The handle should be a valid ETM task handle, which is not owned elsewhere
+    pub unsafe fn new(handle: esp_etm_task_handle_t) -> Self {
+        Self(handle)
+    }
+}
+
+impl Task for RawTask {
+    fn handle(&self) -> esp_etm_task_handle_t {
+        self.0
+    }
+}
+
+impl Drop for RawTask {
+    fn drop(&mut self) {
+        esp!(unsafe { esp_etm_del_task(self.0) }).unwrap();
+    }
+}
+
+unsafe impl Send for RawTask {}
+
+/// An ETM channel, connecting an event to a task
+pub struct Channel<'d> {
+    handle: esp_etm_channel_handle_t,
+    enabled: bool,
+    _p: PhantomData<&'d ()>,
+}
+
+impl<'d> Channel<'d> {
+    /// Allocates an ETM channel, connects `event` to `task` and enables the channel.
+    ///
+    /// The event and the task should outlive the channel.
+    pub fn connect<E: Event, T: Task>(event: &'d E, task: &'d T) -> Result<Self, EspError> {
+        let config: esp_etm_channel_config_t = Default::default();
+
+        let mut handle: esp_etm_channel_handle_t = core::ptr::null_mut();
+
+        esp!(unsafe { esp_etm_new_channel(&config, &mut handle) })?;
+
+        let mut channel = Self {
+            handle,
+            enabled: false,
+            _p: PhantomData,
+        };
+
+        esp!(unsafe { esp_etm_channel_connect(handle, event.handle(), task.handle()) })?;
+
+        channel.enable()?;
+
+        Ok(channel)
+    }
+
+    /// Enables the channel, if it was disabled with `Channel::disable`.
+    pub fn enable(&mut self) -> Result<(), EspError> {
+        if !self.enabled {
+            esp!(unsafe { esp_etm_channel_enable(self.handle) })?;
+            self.enabled = true;
+        }
+
+        Ok(())
+    }
+
+    /// Disables the channel, so that the event no longer triggers the task.
+    pub fn disable(&mut self) -> Result<(), EspError> {
+        if self.enabled {
+            esp!(unsafe { esp_etm_channel_disable(self.handle) })?;
+            self.enabled = false;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the raw ESP-IDF handle of the channel
+    pub fn handle(&self) -> esp_etm_channel_handle_t {
+        self.handle
+    }
+}
+
+impl Drop for Channel<'_> {
+    fn drop(&mut self) {
+        self.disable().unwrap();
+
+        esp!(unsafe { esp_etm_del_channel(self.handle) }).unwrap();
+    }
+}
+
+unsafe impl Send for Channel<'_> {}
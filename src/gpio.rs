@@ -1,6 +1,7 @@
 //! GPIO and pin configuration
 
 use core::marker::PhantomData;
+use core::mem::ManuallyDrop;
 
 #[cfg(feature = "alloc")]
 extern crate alloc;
@@ -12,6 +13,17 @@ use crate::peripheral::{Peripheral, PeripheralRef};
 
 pub use chip::*;
 
+#[cfg(feature = "alloc")]
+pub mod debounce;
+#[cfg(esp_idf_soc_dedicated_gpio_supported)]
+pub mod dedicated;
+#[cfg(all(
+    esp_idf_soc_etm_supported,
+    esp_idf_soc_gpio_support_etm,
+    not(any(esp_idf_version_major = "4", esp_idf_version = "5.0"))
+))]
+pub mod etm;
+
 /// A trait implemented by every pin instance
 pub trait Pin: Peripheral<P = Self> + Sized + Send + 'static {
     fn pin(&self) -> i32;
@@ -98,6 +110,26 @@ impl AnyIOPin {
         }
     }
 
+    /// Same as `AnyIOPin::new`, except that it checks that the pin exists on the current chip, that it is
+    /// not reserved for the SPI flash or PSRAM, and that it is capable of operating as an input and output pin.
+    ///
+    /// Useful when the pin number is only known at runtime (i.e. read from a configuration).
+    ///
+    /// # Safety
+    ///
+    /// Care should be taken not to instantiate this Pin, if it is
+    /// already instantiated and used elsewhere, or if it is not set
+    /// already in the mode of operation which is being instantiated
+    pub unsafe fn try_new(pin: i32) -> Result<Self, EspError> {
+        let caps = PinCapabilities::usable(pin)?;
+
+        if !(caps.input && caps.output) {
+            return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
+        }
+
+        Ok(Self::new(pin))
+    }
+
     /// Creates an `Option<AnyIOPin>::None` for pins that are
     /// optional in APIs.
     pub const fn none() -> Option<Self> {
@@ -136,6 +168,26 @@ impl AnyInputPin {
         }
     }
 
+    /// Same as `AnyInputPin::new`, except that it checks that the pin exists on the current chip, that it is
+    /// not reserved for the SPI flash or PSRAM, and that it is capable of operating as an input pin.
+    ///
+    /// Useful when the pin number is only known at runtime (i.e. read from a configuration).
+    ///
+    /// # Safety
+    ///
+    /// Care should be taken not to instantiate this Pin, if it is
+    /// already instantiated and used elsewhere, or if it is not set
+    /// already in the mode of operation which is being instantiated
+    pub unsafe fn try_new(pin: i32) -> Result<Self, EspError> {
+        let caps = PinCapabilities::usable(pin)?;
+
+        if !caps.input {
+            return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
+        }
+
+        Ok(Self::new(pin))
+    }
+
     /// Creates an `Option<AnyInputPin>::None` for pins that are
     /// optional in APIs.
     pub const fn none() -> Option<Self> {
@@ -178,6 +230,26 @@ impl AnyOutputPin {
         }
     }
 
+    /// Same as `AnyOutputPin::new`, except that it checks that the pin exists on the current chip, that it is
+    /// not reserved for the SPI flash or PSRAM, and that it is capable of operating as an output pin.
+    ///
+    /// Useful when the pin number is only known at runtime (i.e. read from a configuration).
+    ///
+    /// # Safety
+    ///
+    /// Care should be taken not to instantiate this Pin, if it is
+    /// already instantiated and used elsewhere, or if it is not set
+    /// already in the mode of operation which is being instantiated
+    pub unsafe fn try_new(pin: i32) -> Result<Self, EspError> {
+        let caps = PinCapabilities::usable(pin)?;
+
+        if !caps.output {
+            return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
+        }
+
+        Ok(Self::new(pin))
+    }
+
     /// Creates an `Option<AnyOutputPin>::None` for pins that are
     /// optional in APIs.
     pub const fn none() -> Option<Self> {
@@ -201,6 +273,42 @@ impl From<AnyIOPin> for AnyOutputPin {
     }
 }
 
+/// The capabilities of a pin on the current chip
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct PinCapabilities {
+    pub pin: i32,
+    pub input: bool,
+    pub output: bool,
+    pub rtc: bool,
+    pub adc: bool,
+    pub dac: bool,
+    pub touch: bool,
+    /// The pin is used by SPI0/SPI1 for the SPI flash or PSRAM of the chip
+    pub reserved: bool,
+}
+
+impl PinCapabilities {
+    /// Returns the capabilities of the pin with number `pin`, or `None` if the current chip has no such pin
+    pub fn of(pin: i32) -> Option<Self> {
+        chip::PIN_CAPABILITIES
+            .iter()
+            .find(|caps| caps.pin == pin)
+            .map(|caps| Self {
+                reserved: chip::RESERVED_PINS.contains(&pin),
+                ..*caps
+            })
+    }
+
+    /// Returns the capabilities of the pin with number `pin`, or an error if the current chip has no such pin
+    /// or if the pin is reserved for the SPI flash or PSRAM of the chip
+    pub fn usable(pin: i32) -> Result<Self, EspError> {
+        match Self::of(pin) {
+            Some(caps) if !caps.reserved => Ok(caps),
+            _ => Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>()),
+        }
+    }
+}
+
 /// Interrupt types
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
 pub enum InterruptType {
@@ -223,6 +331,16 @@ impl From<InterruptType> for gpio_int_type_t {
     }
 }
 
+/// The edge which triggered a GPIO interrupt, as reported to callbacks subscribed
+/// with `PinDriver::subscribe_with_edge`
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Edge {
+    Rising,
+    Falling,
+    /// The interrupt was not triggered by an edge (i.e. it is a level interrupt)
+    Unknown,
+}
+
 impl From<InterruptType> for u8 {
     fn from(interrupt_type: InterruptType) -> u8 {
         let int_type: gpio_int_type_t = interrupt_type.into();
@@ -240,6 +358,13 @@ pub enum DriveStrength {
     I40mA = 3,
 }
 
+impl Default for DriveStrength {
+    /// The drive strength of the pins after reset
+    fn default() -> Self {
+        Self::I20mA
+    }
+}
+
 impl From<DriveStrength> for gpio_drive_cap_t {
     fn from(strength: DriveStrength) -> gpio_drive_cap_t {
         match strength {
@@ -284,6 +409,67 @@ impl From<Pull> for gpio_pull_mode_t {
     }
 }
 
+/// The direction of a pin, as read back from the hardware with `PinDriver::direction`,
+/// or as set for light sleep with `PinDriver::set_sleep_direction`
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Direction {
+    Disabled,
+    Input,
+    Output,
+    InputOutput,
+}
+
+/// The configuration of a pin, as read back from the hardware with `PinDriver::dump_config`
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct PinConfig {
+    pub pin: i32,
+    pub direction: Direction,
+    pub open_drain: bool,
+    pub pull: Pull,
+    pub interrupt_type: Option<InterruptType>,
+    /// The pin is routed to the GPIO function of its IO MUX (as opposed to a dedicated peripheral function)
+    pub gpio_function: bool,
+    /// The index of the GPIO matrix output signal routed to the pin (`SIG_GPIO_OUT_IDX` for plain GPIO output)
+    pub out_signal: u32,
+}
+
+impl From<Direction> for gpio_mode_t {
+    fn from(direction: Direction) -> gpio_mode_t {
+        match direction {
+            Direction::Disabled => gpio_mode_t_GPIO_MODE_DISABLE,
+            Direction::Input => gpio_mode_t_GPIO_MODE_INPUT,
+            Direction::Output => gpio_mode_t_GPIO_MODE_OUTPUT,
+            Direction::InputOutput => gpio_mode_t_GPIO_MODE_INPUT_OUTPUT,
+        }
+    }
+}
+
+/// The configuration of a pin while the chip is in light sleep, as applied by `PinDriver::configure_for_sleep`
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct SleepConfig {
+    /// Whether the sleep configuration is applied at all during light sleep
+    pub enabled: bool,
+    pub pull: Pull,
+    pub direction: Direction,
+}
+
+impl SleepConfig {
+    /// A configuration which disables the pin and its pulls during light sleep
+    pub const fn new() -> Self {
+        Self {
+            enabled: true,
+            pull: Pull::Floating,
+            direction: Direction::Disabled,
+        }
+    }
+}
+
+impl Default for SleepConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Digital input or output level.
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
 pub enum Level {
@@ -356,6 +542,89 @@ impl From<Level> for embedded_hal::digital::PinState {
     }
 }
 
+/// Glitch filter configuration
+#[cfg(all(
+    any(esp32c6, esp32h2, esp32p4),
+    not(any(esp_idf_version_major = "4", esp_idf_version = "5.0"))
+))]
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum GlitchFilterConfig {
+    /// The fixed pin glitch filter, which filters out pulses shorter than two
+    /// cycles of the IO_MUX clock
+    Pin,
+    /// A flexible glitch filter, which - within each sampling window of `window_ns` nanoseconds -
+    /// filters out pulses shorter than `threshold_ns` nanoseconds
+    Flex { window_ns: u32, threshold_ns: u32 },
+}
+
+/// A glitch filter enabled on a pin with `PinDriver::enable_glitch_filter`.
+///
+/// The filter borrows the driver of the pin, so it cannot outlive it, and is disabled and deleted when dropped.
+#[cfg(all(
+    any(esp32c6, esp32h2, esp32p4),
+    not(any(esp_idf_version_major = "4", esp_idf_version = "5.0"))
+))]
+pub struct GlitchFilter<'d>(gpio_glitch_filter_handle_t, PhantomData<&'d mut ()>);
+
+#[cfg(all(
+    any(esp32c6, esp32h2, esp32p4),
+    not(any(esp_idf_version_major = "4", esp_idf_version = "5.0"))
+))]
+impl<'d> GlitchFilter<'d> {
+    fn new(pin: i32, config: GlitchFilterConfig) -> Result<Self, EspError> {
+        let mut handle: gpio_glitch_filter_handle_t = core::ptr::null_mut();
+
+        match config {
+            GlitchFilterConfig::Pin => {
+                let config = gpio_pin_glitch_filter_config_t {
+                    clk_src: soc_periph_glitch_filter_clk_src_t_GLITCH_FILTER_CLK_SRC_DEFAULT,
+                    gpio_num: pin,
+                };
+
+                esp!(unsafe { gpio_new_pin_glitch_filter(&config, &mut handle) })?;
+            }
+            GlitchFilterConfig::Flex {
+                window_ns,
+                threshold_ns,
+            } => {
+                let config = gpio_flex_glitch_filter_config_t {
+                    clk_src: soc_periph_glitch_filter_clk_src_t_GLITCH_FILTER_CLK_SRC_DEFAULT,
+                    gpio_num: pin,
+                    window_width_ns: window_ns,
+                    window_thres_ns: threshold_ns,
+                };
+
+                esp!(unsafe { gpio_new_flex_glitch_filter(&config, &mut handle) })?;
+            }
+        }
+
+        if let Err(err) = esp!(unsafe { gpio_glitch_filter_enable(handle) }) {
+            esp!(unsafe { gpio_del_glitch_filter(handle) })?;
+
+            return Err(err);
+        }
+
+        Ok(Self(handle, PhantomData))
+    }
+}
+
+#[cfg(all(
+    any(esp32c6, esp32h2, esp32p4),
+    not(any(esp_idf_version_major = "4", esp_idf_version = "5.0"))
+))]
+impl<'d> Drop for GlitchFilter<'d> {
+    fn drop(&mut self) {
+        esp!(unsafe { gpio_glitch_filter_disable(self.0) }).unwrap();
+        esp!(unsafe { gpio_del_glitch_filter(self.0) }).unwrap();
+    }
+}
+
+#[cfg(all(
+    any(esp32c6, esp32h2, esp32p4),
+    not(any(esp_idf_version_major = "4", esp_idf_version = "5.0"))
+))]
+unsafe impl<'d> Send for GlitchFilter<'d> {}
+
 pub trait GPIOMode {}
 
 #[cfg(not(any(esp32c3, esp32c2, esp32h2, esp32c5)))]
@@ -445,6 +714,7 @@ impl RTCMode for RtcOutput {}
 /// into output or input-output mode.
 pub struct PinDriver<'d, T: Pin, MODE> {
     pin: PeripheralRef<'d, T>,
+    keep_state: bool,
     _mode: PhantomData<MODE>,
 }
 
@@ -456,6 +726,7 @@ impl<'d, T: Pin> PinDriver<'d, T, Disabled> {
 
         Self {
             pin,
+            keep_state: false,
             _mode: PhantomData,
         }
         .into_disabled()
@@ -470,6 +741,7 @@ impl<'d, T: InputPin> PinDriver<'d, T, Input> {
 
         Self {
             pin,
+            keep_state: false,
             _mode: PhantomData,
         }
         .into_input()
@@ -484,6 +756,7 @@ impl<'d, T: InputPin + OutputPin> PinDriver<'d, T, InputOutput> {
 
         Self {
             pin,
+            keep_state: false,
             _mode: PhantomData,
         }
         .into_input_output()
@@ -498,6 +771,7 @@ impl<'d, T: InputPin + OutputPin> PinDriver<'d, T, InputOutput> {
 
         Self {
             pin,
+            keep_state: false,
             _mode: PhantomData,
         }
         .into_input_output_od()
@@ -512,6 +786,7 @@ impl<'d, T: OutputPin> PinDriver<'d, T, Output> {
 
         Self {
             pin,
+            keep_state: false,
             _mode: PhantomData,
         }
         .into_output()
@@ -526,6 +801,7 @@ impl<'d, T: OutputPin> PinDriver<'d, T, Output> {
 
         Self {
             pin,
+            keep_state: false,
             _mode: PhantomData,
         }
         .into_output_od()
@@ -541,6 +817,7 @@ impl<'d, T: Pin + RTCPin> PinDriver<'d, T, RtcDisabled> {
 
         Self {
             pin,
+            keep_state: false,
             _mode: PhantomData,
         }
         .into_rtc_disabled()
@@ -556,6 +833,7 @@ impl<'d, T: InputPin + RTCPin> PinDriver<'d, T, RtcInput> {
 
         Self {
             pin,
+            keep_state: false,
             _mode: PhantomData,
         }
         .into_rtc_input()
@@ -571,6 +849,7 @@ impl<'d, T: InputPin + OutputPin + RTCPin> PinDriver<'d, T, RtcInputOutput> {
 
         Self {
             pin,
+            keep_state: false,
             _mode: PhantomData,
         }
         .into_rtc_input_output()
@@ -586,6 +865,7 @@ impl<'d, T: InputPin + OutputPin + RTCPin> PinDriver<'d, T, RtcInputOutput> {
 
         Self {
             pin,
+            keep_state: false,
             _mode: PhantomData,
         }
         .into_rtc_input_output_od()
@@ -601,6 +881,7 @@ impl<'d, T: OutputPin + RTCPin> PinDriver<'d, T, RtcOutput> {
 
         Self {
             pin,
+            keep_state: false,
             _mode: PhantomData,
         }
         .into_rtc_output()
@@ -616,6 +897,7 @@ impl<'d, T: OutputPin + RTCPin> PinDriver<'d, T, RtcOutput> {
 
         Self {
             pin,
+            keep_state: false,
             _mode: PhantomData,
         }
         .into_rtc_output_od()
@@ -628,6 +910,30 @@ impl<'d, T: Pin, MODE> PinDriver<'d, T, MODE> {
         self.pin.pin()
     }
 
+    /// Sets whether the pin should keep its state (direction, level, pulls and GPIO matrix routing)
+    /// when the driver is dropped. By default - or when `keep` is `false` - the pin is reset on drop
+    /// (see the `Drop` implementation of the driver).
+    ///
+    /// Note that the pin is still reset when the driver changes its mode (i.e. with `PinDriver::into_output`).
+    #[must_use]
+    pub fn keep_state_on_drop(mut self, keep: bool) -> Self {
+        self.keep_state = keep;
+        self
+    }
+
+    /// Consumes the driver and returns the pin, leaving the hardware configuration of the pin untouched.
+    ///
+    /// Any ISR callback subscribed with `PinDriver::subscribe` is unsubscribed.
+    pub fn into_inert(mut self) -> Result<PeripheralRef<'d, T>, EspError> {
+        unsafe { unsubscribe_pin(self.pin.pin()) }?;
+
+        let pin = unsafe { self.pin.clone_unchecked() };
+
+        core::mem::forget(self);
+
+        Ok(pin)
+    }
+
     /// Put the pin into disabled mode.
     pub fn into_disabled(self) -> Result<PinDriver<'d, T, Disabled>, EspError> {
         self.into_mode(gpio_mode_t_GPIO_MODE_DISABLE)
@@ -657,6 +963,11 @@ impl<'d, T: Pin, MODE> PinDriver<'d, T, MODE> {
     /// the hardware will drive the line low if you set it to low, and will leave it floating if you set
     /// it to high, in which case you can read the input to figure out whether another device
     /// is driving the line low.
+    ///
+    /// In this mode, the driver implements both the `InputPin` and the `OutputPin` traits, and reading
+    /// the pin always returns the actual level of the line (as opposed to the last level set), which makes
+    /// the mode suitable for bit-banging protocols like 1-Wire or I2C (with clock stretching).
+    /// Use `PinDriver::set_pull` to additionally enable the internal pull-up, if the line has no external one.
     #[inline]
     pub fn into_input_output_od(self) -> Result<PinDriver<'d, T, InputOutput>, EspError>
     where
@@ -675,6 +986,12 @@ impl<'d, T: Pin, MODE> PinDriver<'d, T, MODE> {
     }
 
     /// Put the pin into output Open Drain mode.
+    ///
+    /// The hardware will drive the line low if you set it to low, and will leave it floating
+    /// (i.e. released, typically to an external pull-up) if you set it to high.
+    ///
+    /// Reading the actual level of the line is not possible in this mode;
+    /// use `into_input_output_od` for that.
     #[inline]
     pub fn into_output_od(self) -> Result<PinDriver<'d, T, Output>, EspError>
     where
@@ -758,7 +1075,13 @@ impl<'d, T: Pin, MODE> PinDriver<'d, T, MODE> {
     where
         T: Pin,
     {
+        let keep_state = self.keep_state;
+
+        // The pin is always reset when changing its mode
+        self.keep_state = false;
+
         let pin = unsafe { self.pin.clone_unchecked() };
+
         drop(self);
 
         if mode != gpio_mode_t_GPIO_MODE_DISABLE {
@@ -767,6 +1090,7 @@ impl<'d, T: Pin, MODE> PinDriver<'d, T, MODE> {
 
         Ok(PinDriver {
             pin,
+            keep_state,
             _mode: PhantomData,
         })
     }
@@ -777,6 +1101,11 @@ impl<'d, T: Pin, MODE> PinDriver<'d, T, MODE> {
     where
         T: RTCPin,
     {
+        let keep_state = self.keep_state;
+
+        // The pin is always reset when changing its mode
+        self.keep_state = false;
+
         let pin = unsafe { self.pin.clone_unchecked() };
 
         drop(self);
@@ -789,10 +1118,15 @@ impl<'d, T: Pin, MODE> PinDriver<'d, T, MODE> {
 
         Ok(PinDriver {
             pin,
+            keep_state,
             _mode: PhantomData,
         })
     }
 
+    /// Returns the current drive strength of the pin.
+    ///
+    /// Only available in output modes, so input-only pins (e.g. GPIO34 - GPIO39 on the esp32)
+    /// are rejected at compile time.
     #[inline]
     pub fn get_drive_strength(&self) -> Result<DriveStrength, EspError>
     where
@@ -813,6 +1147,10 @@ impl<'d, T: Pin, MODE> PinDriver<'d, T, MODE> {
         Ok(cap.into())
     }
 
+    /// Sets the drive strength of the pin (`DriveStrength::I20mA` after reset).
+    ///
+    /// Only available in output modes, so input-only pins (e.g. GPIO34 - GPIO39 on the esp32)
+    /// are rejected at compile time.
     #[inline]
     pub fn set_drive_strength(&mut self, strength: DriveStrength) -> Result<(), EspError>
     where
@@ -852,27 +1190,7 @@ impl<'d, T: Pin, MODE> PinDriver<'d, T, MODE> {
     where
         MODE: InputMode,
     {
-        let res;
-
-        if MODE::RTC {
-            #[cfg(not(any(esp32c3, esp32c2, esp32h2, esp32c5)))]
-            {
-                res = if unsafe { rtc_gpio_get_level(self.pin.pin()) } != 0 {
-                    Level::High
-                } else {
-                    Level::Low
-                };
-            }
-
-            #[cfg(any(esp32c3, esp32c2, esp32h2, esp32c5))]
-            unreachable!();
-        } else if unsafe { gpio_get_level(self.pin.pin()) } != 0 {
-            res = Level::High;
-        } else {
-            res = Level::Low;
-        }
-
-        res
+        get_level::<MODE>(self.pin.pin())
     }
 
     #[inline]
@@ -898,26 +1216,7 @@ impl<'d, T: Pin, MODE> PinDriver<'d, T, MODE> {
     where
         MODE: OutputMode,
     {
-        // TODO: Implement for RTC mode
-
-        let pin = self.pin.pin() as u32;
-
-        #[cfg(any(esp32c3, esp32c2, esp32h2, esp32c5))]
-        let is_set_high = unsafe { (*(GPIO_OUT_REG as *const u32) >> pin) & 0x01 != 0 };
-        #[cfg(not(any(esp32c3, esp32c2, esp32h2, esp32c5)))]
-        let is_set_high = if pin <= 31 {
-            // GPIO0 - GPIO31
-            unsafe { (*(GPIO_OUT_REG as *const u32) >> pin) & 0x01 != 0 }
-        } else {
-            // GPIO32+
-            unsafe { (*(GPIO_OUT1_REG as *const u32) >> (pin - 32)) & 0x01 != 0 }
-        };
-
-        if is_set_high {
-            Level::High
-        } else {
-            Level::Low
-        }
+        get_output_level(self.pin.pin())
     }
 
     #[inline]
@@ -942,22 +1241,7 @@ impl<'d, T: Pin, MODE> PinDriver<'d, T, MODE> {
     where
         MODE: OutputMode,
     {
-        let on = match level {
-            Level::Low => 0,
-            Level::High => 1,
-        };
-
-        if MODE::RTC {
-            #[cfg(not(any(esp32c3, esp32c2, esp32h2, esp32c5)))]
-            esp!(unsafe { rtc_gpio_set_level(self.pin.pin(), on) })?;
-
-            #[cfg(any(esp32c3, esp32c2, esp32h2, esp32c5))]
-            unreachable!();
-        } else {
-            esp!(unsafe { gpio_set_level(self.pin.pin(), on) })?;
-        }
-
-        Ok(())
+        set_level::<MODE>(self.pin.pin(), level)
     }
 
     /// Toggle pin output
@@ -1010,6 +1294,137 @@ impl<'d, T: Pin, MODE> PinDriver<'d, T, MODE> {
         Ok(())
     }
 
+    /// Returns the pull of the pin, as currently configured in the hardware.
+    pub fn pull(&self) -> Pull {
+        read_pull(self.pin.pin())
+    }
+
+    /// Returns the direction of the pin, as currently configured in the hardware.
+    pub fn direction(&self) -> Direction {
+        read_direction(self.pin.pin())
+    }
+
+    /// Returns `true` if the output of the pin is currently enabled in the hardware.
+    pub fn is_output_enabled(&self) -> bool {
+        is_output_enabled(self.pin.pin())
+    }
+
+    /// Returns the interrupt type of the pin as currently configured in the hardware, or `None`
+    /// if the pin has no interrupt type set.
+    ///
+    /// Note that the interrupt type stays set while the interrupt itself is disabled
+    /// (i.e. after each ISR notification).
+    pub fn interrupt_type(&self) -> Option<InterruptType> {
+        read_interrupt_type(self.pin.pin())
+    }
+
+    /// Returns a snapshot of the configuration of the pin, as currently configured in the hardware.
+    ///
+    /// As all values are read back from the hardware, they are correct even if the pin
+    /// was reconfigured with raw ESP-IDF calls.
+    pub fn dump_config(&self) -> PinConfig {
+        let pin = self.pin.pin();
+
+        let mux = read_reg(io_mux_reg(pin));
+        let out_sel = read_reg(GPIO_FUNC0_OUT_SEL_CFG_REG + pin as u32 * 4);
+
+        PinConfig {
+            pin,
+            direction: read_direction(pin),
+            open_drain: read_reg(gpio_pin_reg(pin)) & (1 << GPIO_PIN0_PAD_DRIVER_S) != 0,
+            pull: read_pull(pin),
+            interrupt_type: read_interrupt_type(pin),
+            gpio_function: (mux >> MCU_SEL_S) & MCU_SEL_V == PIN_FUNC_GPIO,
+            out_signal: out_sel & GPIO_FUNC0_OUT_SEL_V,
+        }
+    }
+
+    /// Enables or disables the hold function of the pin.
+    ///
+    /// While held, the pin keeps its current state (level, direction and pulls) regardless of
+    /// further configuration changes, a chip reset (except for a power-up reset), or light sleep.
+    /// For keeping the state during deep sleep, `deep_sleep_hold` needs to be enabled as well
+    /// on chips which do not support holding individual pins in deep sleep.
+    ///
+    /// A held pin is NOT reset when the driver is dropped, so that e.g. an output keeps its level
+    /// while the chip enters deep sleep.
+    ///
+    /// Returns an error if the pin does not have a hold capability.
+    pub fn set_hold(&mut self, hold: bool) -> Result<(), EspError> {
+        let pin = self.pin.pin();
+
+        if hold {
+            esp!(unsafe { gpio_hold_en(pin) })?;
+        } else {
+            esp!(unsafe { gpio_hold_dis(pin) })?;
+        }
+
+        set_held(pin, hold);
+
+        Ok(())
+    }
+
+    /// Returns `true` if the hold function of the pin was enabled with `PinDriver::set_hold`.
+    pub fn is_held(&self) -> bool {
+        is_held(self.pin.pin())
+    }
+
+    /// Sets the pull of the pin while the chip is in light sleep.
+    ///
+    /// Takes effect only if the sleep configuration of the pin is enabled with `PinDriver::sleep_sel`.
+    pub fn set_sleep_pull(&mut self, pull: Pull) -> Result<(), EspError> {
+        esp!(unsafe { gpio_sleep_set_pull_mode(self.pin.pin(), pull.into()) })
+    }
+
+    /// Sets the direction of the pin while the chip is in light sleep.
+    ///
+    /// Takes effect only if the sleep configuration of the pin is enabled with `PinDriver::sleep_sel`.
+    /// Note that input-only pins cannot be configured as outputs.
+    pub fn set_sleep_direction(&mut self, direction: Direction) -> Result<(), EspError> {
+        esp!(unsafe { gpio_sleep_set_direction(self.pin.pin(), direction.into()) })
+    }
+
+    /// Enables or disables applying the sleep configuration (pull and direction) of the pin
+    /// while the chip is in light sleep. When disabled, the pin keeps its regular configuration.
+    pub fn sleep_sel(&mut self, enable: bool) -> Result<(), EspError> {
+        if enable {
+            esp!(unsafe { gpio_sleep_sel_en(self.pin.pin()) })
+        } else {
+            esp!(unsafe { gpio_sleep_sel_dis(self.pin.pin()) })
+        }
+    }
+
+    /// Applies the provided light sleep configuration to the pin.
+    ///
+    /// Same as calling `PinDriver::set_sleep_pull`, `PinDriver::set_sleep_direction` and `PinDriver::sleep_sel`.
+    pub fn configure_for_sleep(&mut self, config: SleepConfig) -> Result<(), EspError> {
+        self.set_sleep_pull(config.pull)?;
+        self.set_sleep_direction(config.direction)?;
+        self.sleep_sel(config.enabled)
+    }
+
+    /// Enables a glitch filter on the input of the pin.
+    ///
+    /// The filter is applied in hardware before the input reaches the GPIO matrix,
+    /// so reading the pin level, as well as the interrupts delivered via `subscribe`
+    /// (or other peripherals like PCNT routed to the pin) all observe the filtered signal.
+    ///
+    /// The filter stays enabled for as long as the returned `GlitchFilter` is alive, which
+    /// keeps the driver borrowed, so that the pin cannot be reset or reconfigured meanwhile.
+    #[cfg(all(
+        any(esp32c6, esp32h2, esp32p4),
+        not(any(esp_idf_version_major = "4", esp_idf_version = "5.0"))
+    ))]
+    pub fn enable_glitch_filter(
+        &self,
+        config: GlitchFilterConfig,
+    ) -> Result<GlitchFilter<'_>, EspError>
+    where
+        MODE: InputMode,
+    {
+        GlitchFilter::new(self.pin.pin(), config)
+    }
+
     /// Subscribes the provided callback for ISR notifications.
     /// As a side effect, interrupts will be disabled, so to receive a notification, one has
     /// to also call `PinDriver::enable_interrupt` after calling this method.
@@ -1078,8 +1493,41 @@ impl<'d, T: Pin, MODE> PinDriver<'d, T, MODE> {
         self.internal_subscribe(callback)
     }
 
+    /// Same as `subscribe`, except that the callback is additionally provided with the edge
+    /// which triggered the interrupt.
+    ///
+    /// The level of the pin is sampled in the ISR, before the callback is called, so for `InterruptType::AnyEdge`
+    /// the reported edge is correct even if the level of the pin had changed again by the time the callback runs.
+    /// For level interrupt types, `Edge::Unknown` is reported.
+    ///
+    /// # Safety
+    ///
+    /// Care should be taken not to call STD, libc or FreeRTOS APIs (except for a few allowed ones)
+    /// in the callback passed to this function, as it is executed in an ISR context.
+    #[cfg(feature = "alloc")]
+    pub unsafe fn subscribe_with_edge<F: FnMut(Edge) + Send + 'static>(
+        &mut self,
+        callback: F,
+    ) -> Result<(), EspError>
+    where
+        MODE: InputMode,
+    {
+        self.internal_subscribe_with_edge(callback)
+    }
+
+    #[cfg(feature = "alloc")]
+    fn internal_subscribe(&mut self, mut callback: impl FnMut() + Send + 'd) -> Result<(), EspError>
+    where
+        MODE: InputMode,
+    {
+        self.internal_subscribe_with_edge(move |_| callback())
+    }
+
     #[cfg(feature = "alloc")]
-    fn internal_subscribe(&mut self, callback: impl FnMut() + Send + 'd) -> Result<(), EspError>
+    fn internal_subscribe_with_edge(
+        &mut self,
+        callback: impl FnMut(Edge) + Send + 'd,
+    ) -> Result<(), EspError>
     where
         MODE: InputMode,
     {
@@ -1087,17 +1535,64 @@ impl<'d, T: Pin, MODE> PinDriver<'d, T, MODE> {
 
         self.disable_interrupt()?;
 
-        let callback: alloc::boxed::Box<dyn FnMut() + Send + 'd> = alloc::boxed::Box::new(callback);
+        let callback: alloc::boxed::Box<dyn FnMut(Edge) + Send + 'd> =
+            alloc::boxed::Box::new(callback);
         unsafe {
             chip::PIN_ISR_HANDLER[self.pin.pin() as usize] = Some(core::mem::transmute::<
-                alloc::boxed::Box<dyn FnMut() + Send>,
-                alloc::boxed::Box<dyn FnMut() + Send>,
+                alloc::boxed::Box<dyn FnMut(Edge) + Send>,
+                alloc::boxed::Box<dyn FnMut(Edge) + Send>,
             >(callback));
         }
 
         Ok(())
     }
 
+    /// Subscribes the provided raw `handler` for ISR notifications, bypassing the callback dispatching
+    /// of `PinDriver::subscribe`. The handler is registered directly with the GPIO ISR service of ESP-IDF and
+    /// is called with `arg` as its only parameter.
+    ///
+    /// As with `PinDriver::subscribe`, interrupts will be disabled as a side effect, so to receive a notification,
+    /// one has to also call `PinDriver::enable_interrupt` after calling this method.
+    ///
+    /// In contrast to `PinDriver::subscribe` however, the interrupt is NOT automatically disabled each time
+    /// the handler is called. For level interrupt types, the handler should disable the interrupt itself
+    /// (i.e. with `gpio_intr_disable`), or else the Interrupt WatchDog will eventually kick in.
+    ///
+    /// Any callback subscribed with `PinDriver::subscribe` is unsubscribed.
+    ///
+    /// # Safety
+    ///
+    /// Care should be taken not to call STD, libc or FreeRTOS APIs (except for a few allowed ones)
+    /// in the handler passed to this function, as it is executed in an ISR context.
+    ///
+    /// `arg` should remain valid until the handler is unsubscribed (or until the driver is dropped).
+    ///
+    /// This is the only way to handle GPIO interrupts while the flash cache is disabled (e.g. during SPI flash writes).
+    /// That requires the `iram-gpio-isr` feature - which installs the GPIO ISR service with the `ESP_INTR_FLAG_IRAM`
+    /// flag, and needs ESP-IDF to be built with `CONFIG_GPIO_CTRL_FUNC_IN_IRAM=y` - and the handler, as well as
+    /// everything it calls, to be placed in IRAM (e.g. `#[link_section = ".iram1.my_handler"]`). Only plain
+    /// `fn` handlers can be placed there, so closures subscribed with `PinDriver::subscribe` are never called while
+    /// the cache is disabled.
+    pub unsafe fn subscribe_raw(
+        &mut self,
+        handler: unsafe extern "C" fn(*mut core::ffi::c_void),
+        arg: *mut core::ffi::c_void,
+    ) -> Result<(), EspError>
+    where
+        MODE: InputMode,
+    {
+        unsubscribe_pin(self.pin.pin())?;
+
+        enable_isr_service()?;
+
+        esp!(gpio_isr_handler_add(self.pin.pin(), Some(handler), arg))?;
+        esp!(gpio_intr_disable(self.pin.pin()))?;
+
+        RAW_ISR_PINS.set(self.pin.pin(), true);
+
+        Ok(())
+    }
+
     pub fn unsubscribe(&mut self) -> Result<(), EspError>
     where
         MODE: InputMode,
@@ -1117,10 +1612,21 @@ impl<'d, T: Pin, MODE> PinDriver<'d, T, MODE> {
     ///
     /// Therefore - to continue receiving ISR interrupts - user needs to call `enable_interrupt`
     /// - **from a non-ISR context** - after each successful interrupt triggering.
+    ///
+    /// Returns `ESP_ERR_INVALID_STATE` while the pin is held off by the flood protection
+    /// (see `PinDriver::enable_flood_protection`).
     pub fn enable_interrupt(&mut self) -> Result<(), EspError>
     where
         MODE: InputMode,
     {
+        if RAW_ISR_PINS.get(self.pin.pin()) {
+            return esp!(unsafe { gpio_intr_enable(self.pin.pin()) });
+        }
+
+        if !PIN_INTR_STATS[self.pin.pin() as usize].try_rearm() {
+            return Err(EspError::from_infallible::<ESP_ERR_INVALID_STATE>());
+        }
+
         enable_isr_service()?;
 
         unsafe {
@@ -1138,42 +1644,229 @@ impl<'d, T: Pin, MODE> PinDriver<'d, T, MODE> {
     {
         use core::sync::atomic::Ordering;
 
-        if ISR_SERVICE_ENABLED.load(Ordering::SeqCst) {
+        if RAW_ISR_PINS.get(self.pin.pin()) {
+            esp!(unsafe { gpio_intr_disable(self.pin.pin()) })?;
+        } else if ISR_SERVICE_ENABLED.load(Ordering::SeqCst) {
             esp!(unsafe { gpio_isr_handler_remove(self.pin.pin()) })?;
         }
 
         Ok(())
     }
 
-    pub fn set_interrupt_type(&mut self, interrupt_type: InterruptType) -> Result<(), EspError>
-    where
-        MODE: InputMode,
-    {
-        esp!(unsafe { gpio_set_intr_type(self.pin.pin(), interrupt_type.into()) })?;
+    /// Returns the number of interrupts received on the pin since the driver was created
+    /// (or since `PinDriver::reset_interrupt_count` was called). The counter wraps around on overflow.
+    ///
+    /// Interrupts delivered to a handler subscribed with `PinDriver::subscribe_raw` are not counted.
+    pub fn interrupt_count(&self) -> u32 {
+        PIN_INTR_STATS[self.pin.pin() as usize]
+            .count
+            .load(core::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// Resets the counter returned by `PinDriver::interrupt_count`.
+    pub fn reset_interrupt_count(&mut self) {
+        PIN_INTR_STATS[self.pin.pin() as usize]
+            .count
+            .store(0, core::sync::atomic::Ordering::Relaxed);
+    }
+
+    /// Enables the flood protection of the pin: if more than `max_interrupts` interrupts arrive within `window`,
+    /// the interrupt of the pin is masked, no further ISR notifications (nor subscribed callbacks) are delivered,
+    /// and the callback set with `set_flood_callback` (if any) is called.
+    ///
+    /// The pin is then held off: the interrupt stays masked - and `PinDriver::enable_interrupt` fails with
+    /// `ESP_ERR_INVALID_STATE` - until `window` has elapsed since the pin got flooded, or until the flooded
+    /// state is cleared with `PinDriver::clear_flood`.
+    ///
+    /// Flood protection does not apply to handlers subscribed with `PinDriver::subscribe_raw`.
+    pub fn enable_flood_protection(&mut self, max_interrupts: u32, window: core::time::Duration) {
+        use core::sync::atomic::Ordering;
+
+        let stats = &PIN_INTR_STATS[self.pin.pin() as usize];
+
+        stats.rearm();
+        stats.window_us.store(
+            window.as_micros().min(u32::MAX as u128) as u32,
+            Ordering::SeqCst,
+        );
+        stats.limit.store(max_interrupts.max(1), Ordering::SeqCst);
+    }
+
+    /// Disables the flood protection enabled with `PinDriver::enable_flood_protection`.
+    pub fn disable_flood_protection(&mut self) {
+        let stats = &PIN_INTR_STATS[self.pin.pin() as usize];
+
+        stats.limit.store(0, core::sync::atomic::Ordering::SeqCst);
+        stats.rearm();
+    }
+
+    /// Clears the flooded state of the pin, so that its interrupt can be re-enabled with
+    /// `PinDriver::enable_interrupt` right away, and restarts the flood protection window.
+    pub fn clear_flood(&mut self) {
+        PIN_INTR_STATS[self.pin.pin() as usize].rearm();
+    }
+
+    /// Returns `true` if the interrupt of the pin is masked by the flood protection.
+    pub fn is_flooded(&self) -> bool {
+        PIN_INTR_STATS[self.pin.pin() as usize]
+            .flooded
+            .load(core::sync::atomic::Ordering::SeqCst)
+    }
+
+    pub fn set_interrupt_type(&mut self, interrupt_type: InterruptType) -> Result<(), EspError>
+    where
+        MODE: InputMode,
+    {
+        esp!(unsafe { gpio_set_intr_type(self.pin.pin(), interrupt_type.into()) })?;
+
+        PIN_INTR_TYPE[self.pin.pin() as usize]
+            .store(interrupt_type.into(), core::sync::atomic::Ordering::SeqCst);
 
         Ok(())
     }
 
+    /// Enables the pin as a wakeup source from light sleep, when the pin is at `level`.
+    ///
+    /// Only level-triggered wakeups are supported by the hardware, and they share the interrupt
+    /// type setting of the pin. Therefore, the method returns `ESP_ERR_INVALID_STATE` if the pin
+    /// already has an interrupt type other than `level` set (i.e. with `PinDriver::set_interrupt_type`).
+    ///
+    /// The wakeup is disabled again with `PinDriver::disable_light_sleep_wakeup`, or when the driver is dropped.
+    pub fn enable_light_sleep_wakeup(&mut self, level: Level) -> Result<(), EspError>
+    where
+        MODE: InputMode,
+    {
+        let pin = self.pin.pin();
+
+        let interrupt_type = match level {
+            Level::Low => InterruptType::LowLevel,
+            Level::High => InterruptType::HighLevel,
+        };
+
+        let current = PIN_INTR_TYPE[pin as usize].load(core::sync::atomic::Ordering::SeqCst);
+
+        if current != gpio_int_type_t_GPIO_INTR_DISABLE as u8 && current != u8::from(interrupt_type)
+        {
+            return Err(EspError::from_infallible::<ESP_ERR_INVALID_STATE>());
+        }
+
+        esp!(unsafe { gpio_wakeup_enable(pin, interrupt_type.into()) })?;
+
+        PIN_INTR_TYPE[pin as usize]
+            .store(interrupt_type.into(), core::sync::atomic::Ordering::SeqCst);
+        LIGHT_SLEEP_WAKEUP_PINS.set(pin, true);
+
+        esp!(unsafe { esp_sleep_enable_gpio_wakeup() })
+    }
+
+    /// Disables the light sleep wakeup enabled with `PinDriver::enable_light_sleep_wakeup`.
+    ///
+    /// As a side effect, the interrupt type of the pin is reset to disabled.
+    pub fn disable_light_sleep_wakeup(&mut self) -> Result<(), EspError>
+    where
+        MODE: InputMode,
+    {
+        disable_light_sleep_wakeup(self.pin.pin())
+    }
+
+    // NOTE: The notification, statistics and callback code the dispatcher calls lives in flash. So with feature
+    // `iram-gpio-isr`, the dispatcher is placed in IRAM and returns right away while the flash cache is disabled.
+    // An edge arriving meanwhile is then lost, while a level interrupt keeps firing until the cache is enabled again
+    #[cfg_attr(feature = "iram-gpio-isr", link_section = ".iram1.gpio_handle_isr")]
     unsafe extern "C" fn handle_isr(user_ctx: *mut core::ffi::c_void) {
         let pin = user_ctx as u32;
 
+        #[cfg(feature = "iram-gpio-isr")]
+        if !spi_flash_cache_enabled() {
+            return;
+        }
+
+        // Sample the level as early as possible, so that the reported edge is accurate
+        #[cfg(feature = "alloc")]
+        let edge = isr_edge(pin as _);
+
         // IMPORTANT: MUST be done or else the ESP IDF GPIO driver will continue calling us in a loop
         // - particularly when the interrupt type is set to non-edge triggering (pin high or low) -
         // which will eventually cause the Interrupt WatchDog to kick in
         gpio_intr_disable(pin as _);
 
+        match PIN_INTR_STATS[pin as usize].on_interrupt() {
+            Some(true) => {
+                let callback = FLOOD_CALLBACK.load(core::sync::atomic::Ordering::SeqCst);
+
+                if !callback.is_null() {
+                    let callback: fn(i32) = core::mem::transmute(callback);
+
+                    callback(pin as _);
+                }
+
+                return;
+            }
+            Some(false) => return,
+            None => (),
+        }
+
         PIN_NOTIF[pin as usize].notify_lsb();
 
         #[cfg(feature = "alloc")]
         {
             if let Some(unsafe_callback) = unsafe { &mut PIN_ISR_HANDLER[pin as usize] } {
-                (unsafe_callback)();
+                (unsafe_callback)(edge);
             }
         }
     }
 }
 
+#[cfg(not(any(esp32c3, esp32c2, esp32h2, esp32c5)))]
+impl<T: RTCPin, MODE: RTCMode> PinDriver<'_, T, MODE> {
+    /// Isolates the pin from external circuits, by disabling its input, output, pull-up and
+    /// pull-down, and then enabling its hold function, so as to minimize the current consumption
+    /// during deep sleep.
+    ///
+    /// The pin stays isolated - and is not reset when the driver is dropped - until the hold
+    /// function of the pin is disabled again with `PinDriver::set_hold(false)`.
+    pub fn isolate(&mut self) -> Result<(), EspError> {
+        esp!(unsafe { rtc_gpio_isolate(self.pin.pin()) })?;
+
+        set_held(self.pin.pin(), true);
+
+        Ok(())
+    }
+
+    /// Enables the pin as the (single) EXT0 wakeup source for deep sleep:
+    /// the chip wakes up once the pin is at level `level`.
+    ///
+    /// Only one pin at a time can be an EXT0 wakeup source, so enabling the wakeup
+    /// on a pin disables it on the pin previously enabled.
+    #[cfg(any(esp32, esp32s2, esp32s3))]
+    pub fn enable_wakeup(&mut self, level: Level) -> Result<(), EspError> {
+        esp!(unsafe {
+            esp_sleep_enable_ext0_wakeup(self.pin.pin(), if level == Level::High { 1 } else { 0 })
+        })
+    }
+
+    /// Disables the EXT0 wakeup source enabled with `PinDriver::enable_wakeup`.
+    #[cfg(any(esp32, esp32s2, esp32s3))]
+    pub fn disable_wakeup(&mut self) -> Result<(), EspError> {
+        esp!(unsafe { esp_sleep_disable_wakeup_source(esp_sleep_source_t_ESP_SLEEP_WAKEUP_EXT0) })
+    }
+}
+
 impl<T: Pin, MODE: InputMode> PinDriver<'_, T, MODE> {
+    /// Waits asynchronously until the interrupt condition `interrupt_type` is met.
+    ///
+    /// For level interrupt types, the method completes immediately - without arming the interrupt -
+    /// if the pin is already at the requested level.
+    ///
+    /// As with any `async fn`, nothing happens until the returned future is first polled: the
+    /// interrupt is armed on that first poll, so an edge which happens after calling `wait_for`
+    /// but before the future is first polled is not detected. From then on, the ISR latches the
+    /// notification, so an edge which happens in-between two polls of the future is not lost.
+    ///
+    /// The method works with any executor, as the ISR wakes the future via the HAL ISR reactor
+    /// (`interrupt::asynch::HAL_ISR_REACTOR`).
+    ///
+    /// NOTE: Waiting replaces any interrupt type previously set with `PinDriver::set_interrupt_type`.
     pub async fn wait_for(&mut self, interrupt_type: InterruptType) -> Result<(), EspError> {
         self.disable_interrupt()?;
 
@@ -1203,232 +1896,1102 @@ impl<T: Pin, MODE: InputMode> PinDriver<'_, T, MODE> {
         Ok(())
     }
 
+    /// Waits asynchronously until the pin is at high level. See `PinDriver::wait_for`.
     pub async fn wait_for_high(&mut self) -> Result<(), EspError> {
         self.wait_for(InterruptType::HighLevel).await
     }
 
+    /// Waits asynchronously until the pin is at low level. See `PinDriver::wait_for`.
     pub async fn wait_for_low(&mut self) -> Result<(), EspError> {
         self.wait_for(InterruptType::LowLevel).await
     }
 
+    /// Waits asynchronously until a rising edge is detected on the pin. See `PinDriver::wait_for`.
     pub async fn wait_for_rising_edge(&mut self) -> Result<(), EspError> {
         self.wait_for(InterruptType::PosEdge).await
     }
 
+    /// Waits asynchronously until a falling edge is detected on the pin. See `PinDriver::wait_for`.
     pub async fn wait_for_falling_edge(&mut self) -> Result<(), EspError> {
         self.wait_for(InterruptType::NegEdge).await
     }
 
+    /// Waits asynchronously until an edge of any kind is detected on the pin. See `PinDriver::wait_for`.
     pub async fn wait_for_any_edge(&mut self) -> Result<(), EspError> {
         self.wait_for(InterruptType::AnyEdge).await
     }
 }
 
-impl<'d, T: Pin, MODE> Drop for PinDriver<'d, T, MODE> {
+fn get_level<MODE: InputMode>(pin: i32) -> Level {
+    let res;
+
+    if MODE::RTC {
+        #[cfg(not(any(esp32c3, esp32c2, esp32h2, esp32c5)))]
+        {
+            res = if unsafe { rtc_gpio_get_level(pin) } != 0 {
+                Level::High
+            } else {
+                Level::Low
+            };
+        }
+
+        #[cfg(any(esp32c3, esp32c2, esp32h2, esp32c5))]
+        unreachable!();
+    } else if unsafe { gpio_get_level(pin) } != 0 {
+        res = Level::High;
+    } else {
+        res = Level::Low;
+    }
+
+    res
+}
+
+fn set_level<MODE: OutputMode>(pin: i32, level: Level) -> Result<(), EspError> {
+    let on = match level {
+        Level::Low => 0,
+        Level::High => 1,
+    };
+
+    if MODE::RTC {
+        #[cfg(not(any(esp32c3, esp32c2, esp32h2, esp32c5)))]
+        esp!(unsafe { rtc_gpio_set_level(pin, on) })?;
+
+        #[cfg(any(esp32c3, esp32c2, esp32h2, esp32c5))]
+        unreachable!();
+    } else {
+        esp!(unsafe { gpio_set_level(pin, on) })?;
+    }
+
+    Ok(())
+}
+
+fn get_output_level(pin: i32) -> Level {
+    // TODO: Implement for RTC mode
+
+    let pin = pin as u32;
+
+    #[cfg(not(any(esp32, esp32s2, esp32s3, esp32p4)))]
+    let is_set_high = unsafe { (*(GPIO_OUT_REG as *const u32) >> pin) & 0x01 != 0 };
+    #[cfg(any(esp32, esp32s2, esp32s3, esp32p4))]
+    let is_set_high = if pin <= 31 {
+        // GPIO0 - GPIO31
+        unsafe { (*(GPIO_OUT_REG as *const u32) >> pin) & 0x01 != 0 }
+    } else {
+        // GPIO32+
+        unsafe { (*(GPIO_OUT1_REG as *const u32) >> (pin - 32)) & 0x01 != 0 }
+    };
+
+    if is_set_high {
+        Level::High
+    } else {
+        Level::Low
+    }
+}
+
+/// Unless the pin is held (see `PinDriver::set_hold`) or `PinDriver::keep_state_on_drop` is set,
+/// dropping the driver resets the pin, on all chips, as follows:
+/// - Input and output are disabled
+/// - Pull-up and pull-down are disabled
+/// - The interrupt is disabled, and any ISR callback is unsubscribed
+/// - The light sleep wakeup enabled with `PinDriver::enable_light_sleep_wakeup` is disabled (even if the pin is
+///   held or `PinDriver::keep_state_on_drop` is set)
+/// - The pin is routed to the GPIO function of its IO MUX, which disconnects it from the GPIO matrix signals
+///   and - for RTC pins - from the RTC IO MUX
+///
+/// Note that the drive strength of the pin is not reset.
+impl<'d, T: Pin, MODE> Drop for PinDriver<'d, T, MODE> {
+    fn drop(&mut self) {
+        drop_pin(self.pin.pin(), self.keep_state);
+    }
+}
+
+fn drop_pin(pin: i32, keep_state: bool) {
+    // A stale wakeup configuration should not wake the chip
+    disable_light_sleep_wakeup(pin).unwrap();
+
+    if is_held(pin) || keep_state {
+        unsafe { unsubscribe_pin(pin) }.unwrap();
+    } else {
+        gpio_reset_without_pull(pin).unwrap();
+    }
+}
+
+unsafe impl<'d, T: Pin, MODE> Send for PinDriver<'d, T, MODE> {}
+
+impl<'d, T: Pin, MODE> embedded_hal_0_2::digital::v2::InputPin for PinDriver<'d, T, MODE>
+where
+    MODE: InputMode,
+{
+    type Error = EspError;
+
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        Ok(PinDriver::is_high(self))
+    }
+
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        Ok(PinDriver::is_low(self))
+    }
+}
+
+use crate::embedded_hal_error;
+embedded_hal_error!(
+    GpioError,
+    embedded_hal::digital::Error,
+    embedded_hal::digital::ErrorKind
+);
+
+fn to_gpio_err(err: EspError) -> GpioError {
+    GpioError::other(err)
+}
+
+impl<'d, T: Pin, MODE> embedded_hal::digital::ErrorType for PinDriver<'d, T, MODE> {
+    type Error = GpioError;
+}
+
+impl<'d, T: Pin, MODE> embedded_hal::digital::InputPin for PinDriver<'d, T, MODE>
+where
+    MODE: InputMode,
+{
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(PinDriver::is_high(self))
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(PinDriver::is_low(self))
+    }
+}
+
+impl<'d, T: Pin, MODE> embedded_hal::digital::InputPin for &PinDriver<'d, T, MODE>
+where
+    MODE: InputMode,
+{
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(PinDriver::is_high(self))
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(PinDriver::is_low(self))
+    }
+}
+
+impl<'d, T: Pin, MODE> embedded_hal_0_2::digital::v2::OutputPin for PinDriver<'d, T, MODE>
+where
+    MODE: OutputMode,
+{
+    type Error = EspError;
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.set_level(Level::High)
+    }
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.set_level(Level::Low)
+    }
+}
+
+impl<'d, T: Pin, MODE> embedded_hal::digital::OutputPin for PinDriver<'d, T, MODE>
+where
+    MODE: OutputMode,
+{
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.set_level(Level::High).map_err(to_gpio_err)
+    }
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.set_level(Level::Low).map_err(to_gpio_err)
+    }
+}
+
+impl<'d, T: Pin, MODE> embedded_hal::digital::StatefulOutputPin for PinDriver<'d, T, MODE>
+where
+    MODE: OutputMode,
+{
+    fn is_set_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.get_output_level().into())
+    }
+
+    fn is_set_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(!bool::from(self.get_output_level()))
+    }
+}
+
+// TODO: Will become possible once the `PinDriver::setXXX`` methods become non-`&mut`, which they really are, internally
+// impl<'d, T: Pin, MODE> embedded_hal::digital::StatefulOutputPin for &PinDriver<'d, T, MODE>
+// where
+//     MODE: OutputMode,
+// {
+//     fn is_set_high(&mut self) -> Result<bool, Self::Error> {
+//         Ok(self.get_output_level().into())
+//     }
+
+//     fn is_set_low(&mut self) -> Result<bool, Self::Error> {
+//         Ok(!bool::from(self.get_output_level()))
+//     }
+// }
+
+impl<'d, T: Pin, MODE> embedded_hal_0_2::digital::v2::StatefulOutputPin for PinDriver<'d, T, MODE>
+where
+    MODE: OutputMode,
+{
+    fn is_set_high(&self) -> Result<bool, Self::Error> {
+        Ok(self.get_output_level().into())
+    }
+
+    fn is_set_low(&self) -> Result<bool, Self::Error> {
+        Ok(!bool::from(self.get_output_level()))
+    }
+}
+
+impl<'d, T: Pin, MODE> embedded_hal_0_2::digital::v2::ToggleableOutputPin for PinDriver<'d, T, MODE>
+where
+    MODE: OutputMode,
+{
+    type Error = EspError;
+
+    fn toggle(&mut self) -> Result<(), Self::Error> {
+        self.set_level(Level::from(!bool::from(self.get_output_level())))
+    }
+}
+
+impl<T: Pin, MODE: InputMode> embedded_hal_async::digital::Wait for PinDriver<'_, T, MODE> {
+    async fn wait_for_high(&mut self) -> Result<(), GpioError> {
+        self.wait_for_high().await?;
+
+        Ok(())
+    }
+
+    async fn wait_for_low(&mut self) -> Result<(), GpioError> {
+        self.wait_for_low().await?;
+
+        Ok(())
+    }
+
+    async fn wait_for_rising_edge(&mut self) -> Result<(), GpioError> {
+        self.wait_for_rising_edge().await?;
+
+        Ok(())
+    }
+
+    async fn wait_for_falling_edge(&mut self) -> Result<(), GpioError> {
+        self.wait_for_falling_edge().await?;
+
+        Ok(())
+    }
+
+    async fn wait_for_any_edge(&mut self) -> Result<(), GpioError> {
+        self.wait_for_any_edge().await?;
+
+        Ok(())
+    }
+}
+
+impl<'d, T: InputPin + OutputPin, MODE: InputMode + OutputMode> PinDriver<'d, T, MODE> {
+    /// Splits the driver into an input half and an output half, which can be owned - and used - independently,
+    /// i.e. by different tasks, without any synchronization, as reading the input and setting the output
+    /// of a pin are independent operations.
+    ///
+    /// The two halves share the ownership of the pin: the pin is reset - as when dropping the driver -
+    /// only once both halves are dropped. Use `InputHalf::join` to reunite the two halves back into a driver.
+    pub fn split(self) -> (InputHalf<'d, T, MODE>, OutputHalf<'d, T, MODE>) {
+        SPLIT_PINS.set(self.pin(), true);
+
+        let output = OutputHalf {
+            pin: self.pin(),
+            keep_state: self.keep_state,
+            _p: PhantomData,
+        };
+
+        (
+            InputHalf {
+                driver: ManuallyDrop::new(self),
+            },
+            output,
+        )
+    }
+}
+
+/// The input half of a `PinDriver`, as returned by `PinDriver::split`
+pub struct InputHalf<'d, T: Pin, MODE> {
+    driver: ManuallyDrop<PinDriver<'d, T, MODE>>,
+}
+
+impl<'d, T: Pin, MODE: InputMode> InputHalf<'d, T, MODE> {
+    /// Returns the pin number.
+    pub fn pin(&self) -> i32 {
+        self.driver.pin()
+    }
+
+    #[inline]
+    pub fn is_high(&self) -> bool {
+        self.driver.is_high()
+    }
+
+    #[inline]
+    pub fn is_low(&self) -> bool {
+        self.driver.is_low()
+    }
+
+    #[inline]
+    pub fn get_level(&self) -> Level {
+        self.driver.get_level()
+    }
+
+    /// See `PinDriver::wait_for`.
+    pub async fn wait_for(&mut self, interrupt_type: InterruptType) -> Result<(), EspError> {
+        self.driver.wait_for(interrupt_type).await
+    }
+
+    /// Reunites the input half with the output half split from the same driver.
+    ///
+    /// Gives the halves back if they were not split from the same driver.
+    #[allow(clippy::type_complexity)]
+    pub fn join(
+        self,
+        output: OutputHalf<'d, T, MODE>,
+    ) -> Result<PinDriver<'d, T, MODE>, (Self, OutputHalf<'d, T, MODE>)> {
+        if self.pin() != output.pin {
+            return Err((self, output));
+        }
+
+        let _ = ManuallyDrop::new(output);
+        SPLIT_PINS.set(self.pin(), false);
+
+        let mut this = ManuallyDrop::new(self);
+
+        Ok(unsafe { ManuallyDrop::take(&mut this.driver) })
+    }
+}
+
+impl<'d, T: Pin, MODE> Drop for InputHalf<'d, T, MODE> {
+    fn drop(&mut self) {
+        // The half dropped last drops the driver
+        if !SPLIT_PINS.take(self.driver.pin.pin()) {
+            unsafe { ManuallyDrop::drop(&mut self.driver) }
+        }
+    }
+}
+
+impl<'d, T: Pin, MODE> embedded_hal_0_2::digital::v2::InputPin for InputHalf<'d, T, MODE>
+where
+    MODE: InputMode,
+{
+    type Error = EspError;
+
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        Ok(InputHalf::is_high(self))
+    }
+
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        Ok(InputHalf::is_low(self))
+    }
+}
+
+impl<'d, T: Pin, MODE> embedded_hal::digital::ErrorType for InputHalf<'d, T, MODE> {
+    type Error = GpioError;
+}
+
+impl<'d, T: Pin, MODE> embedded_hal::digital::InputPin for InputHalf<'d, T, MODE>
+where
+    MODE: InputMode,
+{
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(InputHalf::is_high(self))
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(InputHalf::is_low(self))
+    }
+}
+
+impl<T: Pin, MODE: InputMode> embedded_hal_async::digital::Wait for InputHalf<'_, T, MODE> {
+    async fn wait_for_high(&mut self) -> Result<(), GpioError> {
+        self.driver.wait_for_high().await?;
+
+        Ok(())
+    }
+
+    async fn wait_for_low(&mut self) -> Result<(), GpioError> {
+        self.driver.wait_for_low().await?;
+
+        Ok(())
+    }
+
+    async fn wait_for_rising_edge(&mut self) -> Result<(), GpioError> {
+        self.driver.wait_for_rising_edge().await?;
+
+        Ok(())
+    }
+
+    async fn wait_for_falling_edge(&mut self) -> Result<(), GpioError> {
+        self.driver.wait_for_falling_edge().await?;
+
+        Ok(())
+    }
+
+    async fn wait_for_any_edge(&mut self) -> Result<(), GpioError> {
+        self.driver.wait_for_any_edge().await?;
+
+        Ok(())
+    }
+}
+
+/// The output half of a `PinDriver`, as returned by `PinDriver::split`
+pub struct OutputHalf<'d, T: Pin, MODE> {
+    pin: i32,
+    keep_state: bool,
+    _p: PhantomData<(&'d mut T, MODE)>,
+}
+
+impl<'d, T: Pin, MODE> Drop for OutputHalf<'d, T, MODE> {
+    fn drop(&mut self) {
+        // The half dropped last drops the driver
+        if !SPLIT_PINS.take(self.pin) {
+            drop_pin(self.pin, self.keep_state);
+        }
+    }
+}
+
+impl<'d, T: Pin, MODE: OutputMode> OutputHalf<'d, T, MODE> {
+    /// Returns the pin number.
+    pub fn pin(&self) -> i32 {
+        self.pin
+    }
+
+    #[inline]
+    pub fn is_set_high(&self) -> bool {
+        !self.is_set_low()
+    }
+
+    #[inline]
+    pub fn is_set_low(&self) -> bool {
+        get_output_level(self.pin) == Level::Low
+    }
+
+    #[inline]
+    pub fn set_high(&mut self) -> Result<(), EspError> {
+        self.set_level(Level::High)
+    }
+
+    #[inline]
+    pub fn set_low(&mut self) -> Result<(), EspError> {
+        self.set_level(Level::Low)
+    }
+
+    #[inline]
+    pub fn set_level(&mut self, level: Level) -> Result<(), EspError> {
+        set_level::<MODE>(self.pin, level)
+    }
+
+    #[inline]
+    pub fn toggle(&mut self) -> Result<(), EspError> {
+        if self.is_set_low() {
+            self.set_high()
+        } else {
+            self.set_low()
+        }
+    }
+}
+
+unsafe impl<'d, T: Pin, MODE> Send for OutputHalf<'d, T, MODE> {}
+
+impl<'d, T: Pin, MODE> embedded_hal_0_2::digital::v2::OutputPin for OutputHalf<'d, T, MODE>
+where
+    MODE: OutputMode,
+{
+    type Error = EspError;
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.set_level(Level::High)
+    }
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.set_level(Level::Low)
+    }
+}
+
+impl<'d, T: Pin, MODE> embedded_hal_0_2::digital::v2::StatefulOutputPin for OutputHalf<'d, T, MODE>
+where
+    MODE: OutputMode,
+{
+    fn is_set_high(&self) -> Result<bool, Self::Error> {
+        Ok(OutputHalf::is_set_high(self))
+    }
+
+    fn is_set_low(&self) -> Result<bool, Self::Error> {
+        Ok(OutputHalf::is_set_low(self))
+    }
+}
+
+impl<'d, T: Pin, MODE> embedded_hal::digital::ErrorType for OutputHalf<'d, T, MODE> {
+    type Error = GpioError;
+}
+
+impl<'d, T: Pin, MODE> embedded_hal::digital::OutputPin for OutputHalf<'d, T, MODE>
+where
+    MODE: OutputMode,
+{
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.set_level(Level::High).map_err(to_gpio_err)
+    }
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.set_level(Level::Low).map_err(to_gpio_err)
+    }
+}
+
+impl<'d, T: Pin, MODE> embedded_hal::digital::StatefulOutputPin for OutputHalf<'d, T, MODE>
+where
+    MODE: OutputMode,
+{
+    fn is_set_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(OutputHalf::is_set_high(self))
+    }
+
+    fn is_set_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(OutputHalf::is_set_low(self))
+    }
+}
+
+#[cfg(all(feature = "iram-gpio-isr", not(esp_idf_gpio_ctrl_func_in_iram)))]
+compile_error!(
+    "Feature `iram-gpio-isr` requires ESP-IDF to be built with `CONFIG_GPIO_CTRL_FUNC_IN_IRAM=y`"
+);
+
+#[cfg(feature = "iram-gpio-isr")]
+extern "C" {
+    // Placed in IRAM by the ESP-IDF SPI flash component, but not part of its public headers
+    fn spi_flash_cache_enabled() -> bool;
+}
+
+static ISR_ALLOC_FLAGS: core::sync::atomic::AtomicU32 = core::sync::atomic::AtomicU32::new(0);
+
+static ISR_SERVICE_ENABLED: core::sync::atomic::AtomicBool =
+    core::sync::atomic::AtomicBool::new(false);
+
+static ISR_SERVICE_ENABLED_CS: crate::task::CriticalSection = crate::task::CriticalSection::new();
+
+pub fn init_isr_alloc_flags(flags: enumset::EnumSet<crate::interrupt::InterruptType>) {
+    ISR_ALLOC_FLAGS.store(
+        crate::interrupt::InterruptType::to_native(flags),
+        core::sync::atomic::Ordering::SeqCst,
+    );
+}
+
+pub fn enable_isr_service() -> Result<(), EspError> {
+    use core::sync::atomic::Ordering;
+
+    if !ISR_SERVICE_ENABLED.load(Ordering::SeqCst) {
+        let _guard = ISR_SERVICE_ENABLED_CS.enter();
+
+        if !ISR_SERVICE_ENABLED.load(Ordering::SeqCst) {
+            #[allow(unused_mut)]
+            let mut flags = ISR_ALLOC_FLAGS.load(Ordering::SeqCst);
+
+            // Keeps the GPIO ISR service running while the flash cache is disabled
+            #[cfg(feature = "iram-gpio-isr")]
+            {
+                flags |= ESP_INTR_FLAG_IRAM;
+            }
+
+            esp!(unsafe { gpio_install_isr_service(flags as _) })?;
+
+            ISR_SERVICE_ENABLED.store(true, Ordering::SeqCst);
+        }
+    }
+
+    Ok(())
+}
+
+/// Port-level access to the GPIO registers, for reading or writing multiple pins at once.
+///
+/// Pins are addressed with 64-bit masks, where bit N corresponds to GPIO N.
+/// On chips with more than 32 GPIOs, pins 32+ live in a second register, so only pins
+/// in the same group of 32 are guaranteed to be read - or set high, or set low - in the same clock cycle.
+pub struct Port;
+
+impl Port {
+    /// Returns the mask of the provided pins
+    pub fn mask<P: Pin>(pins: &[&P]) -> u64 {
+        pins.iter().fold(0, |mask, pin| mask | (1 << pin.pin()))
+    }
+
+    /// Returns the input levels of all pins (as a bit per pin), masked with `mask`.
+    ///
+    /// This function is safe to call from an ISR context.
+    #[inline(always)]
+    #[link_section = ".iram1.gpio_port_read"]
+    pub fn read(mask: u64) -> u64 {
+        let low = unsafe { core::ptr::read_volatile(GPIO_IN_REG as *const u32) } as u64;
+
+        // Only the chips with more than 32 GPIOs have the registers of the second bank
+        #[cfg(not(any(esp32, esp32s2, esp32s3, esp32p4)))]
+        let high = 0;
+        #[cfg(any(esp32, esp32s2, esp32s3, esp32p4))]
+        let high = unsafe { core::ptr::read_volatile(GPIO_IN1_REG as *const u32) } as u64;
+
+        ((high << 32) | low) & mask
+    }
+
+    /// Sets high the output of all pins in `set_mask`, and sets low the output of all pins in `clear_mask`.
+    ///
+    /// The update is NOT atomic: the pins are set high and set low with separate writes to the
+    /// `W1TS` / `W1TC` registers (per group of 32 pins), so an observer might see the intermediate state
+    /// where the pins in `set_mask` are already high, while the pins in `clear_mask` are not yet low.
+    /// In exchange, the outputs of the pins not in the masks are never touched, even if other code
+    /// (i.e. on the other core) changes them at the same time. A pin in both masks ends up low.
+    ///
+    /// This function is safe to call from an ISR context.
+    ///
+    /// # Safety
+    ///
+    /// The pins in the masks should be in an output mode, and - since the pins are not owned by the caller -
+    /// care should be taken not to write to pins used elsewhere.
+    #[inline(always)]
+    #[link_section = ".iram1.gpio_port_write_masked"]
+    pub unsafe fn write_masked(set_mask: u64, clear_mask: u64) {
+        if set_mask as u32 != 0 {
+            core::ptr::write_volatile(GPIO_OUT_W1TS_REG as *mut u32, set_mask as u32);
+        }
+
+        if clear_mask as u32 != 0 {
+            core::ptr::write_volatile(GPIO_OUT_W1TC_REG as *mut u32, clear_mask as u32);
+        }
+
+        #[cfg(any(esp32, esp32s2, esp32s3, esp32p4))]
+        {
+            if (set_mask >> 32) as u32 != 0 {
+                core::ptr::write_volatile(GPIO_OUT1_W1TS_REG as *mut u32, (set_mask >> 32) as u32);
+            }
+
+            if (clear_mask >> 32) as u32 != 0 {
+                core::ptr::write_volatile(
+                    GPIO_OUT1_W1TC_REG as *mut u32,
+                    (clear_mask >> 32) as u32,
+                );
+            }
+        }
+    }
+}
+
+#[inline(always)]
+fn read_reg(addr: u32) -> u32 {
+    unsafe { core::ptr::read_volatile(addr as *const u32) }
+}
+
+fn io_mux_reg(pin: i32) -> u32 {
+    unsafe { GPIO_PIN_MUX_REG[pin as usize] }
+}
+
+fn gpio_pin_reg(pin: i32) -> u32 {
+    GPIO_PIN0_REG + pin as u32 * 4
+}
+
+fn read_pull(pin: i32) -> Pull {
+    // On the esp32, the pulls of the RTC pins are configured in the RTC IO MUX
+    #[cfg(esp32)]
+    let (up, down) = {
+        let rtc_pin = unsafe { rtc_io_num_map[pin as usize] };
+
+        if rtc_pin >= 0 {
+            let desc = unsafe { &rtc_io_desc[rtc_pin as usize] };
+            let reg = read_reg(desc.reg);
+
+            (reg & desc.pullup != 0, reg & desc.pulldown != 0)
+        } else {
+            let mux = read_reg(io_mux_reg(pin));
+
+            (mux & (1 << FUN_PU_S) != 0, mux & (1 << FUN_PD_S) != 0)
+        }
+    };
+
+    #[cfg(not(esp32))]
+    let (up, down) = {
+        let mux = read_reg(io_mux_reg(pin));
+
+        (mux & (1 << FUN_PU_S) != 0, mux & (1 << FUN_PD_S) != 0)
+    };
+
+    match (up, down) {
+        (false, false) => Pull::Floating,
+        (true, false) => Pull::Up,
+        (false, true) => Pull::Down,
+        (true, true) => Pull::UpDown,
+    }
+}
+
+fn is_output_enabled(pin: i32) -> bool {
+    #[cfg(not(any(esp32, esp32s2, esp32s3, esp32p4)))]
+    let enabled = read_reg(GPIO_ENABLE_REG) & (1 << pin) != 0;
+    #[cfg(any(esp32, esp32s2, esp32s3, esp32p4))]
+    let enabled = if pin <= 31 {
+        read_reg(GPIO_ENABLE_REG) & (1 << pin) != 0
+    } else {
+        read_reg(GPIO_ENABLE1_REG) & (1 << (pin - 32)) != 0
+    };
+
+    enabled
+}
+
+fn read_direction(pin: i32) -> Direction {
+    let input = read_reg(io_mux_reg(pin)) & (1 << FUN_IE_S) != 0;
+
+    match (input, is_output_enabled(pin)) {
+        (false, false) => Direction::Disabled,
+        (true, false) => Direction::Input,
+        (false, true) => Direction::Output,
+        (true, true) => Direction::InputOutput,
+    }
+}
+
+#[allow(non_upper_case_globals)]
+fn read_interrupt_type(pin: i32) -> Option<InterruptType> {
+    match (read_reg(gpio_pin_reg(pin)) >> GPIO_PIN0_INT_TYPE_S) & GPIO_PIN0_INT_TYPE_V {
+        gpio_int_type_t_GPIO_INTR_POSEDGE => Some(InterruptType::PosEdge),
+        gpio_int_type_t_GPIO_INTR_NEGEDGE => Some(InterruptType::NegEdge),
+        gpio_int_type_t_GPIO_INTR_ANYEDGE => Some(InterruptType::AnyEdge),
+        gpio_int_type_t_GPIO_INTR_LOW_LEVEL => Some(InterruptType::LowLevel),
+        gpio_int_type_t_GPIO_INTR_HIGH_LEVEL => Some(InterruptType::HighLevel),
+        _ => None,
+    }
+}
+
+/// A peripheral output signal of the GPIO matrix.
+///
+/// The signal indexes are chip-specific, and are available as the `*_OUT_IDX`
+/// constants in `esp_idf_sys` (e.g. `LEDC_LS_SIG_OUT0_IDX`).
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct OutputSignal(pub u32);
+
+/// A peripheral input signal of the GPIO matrix.
+///
+/// The signal indexes are chip-specific, and are available as the `*_IN_IDX`
+/// constants in `esp_idf_sys` (e.g. `PCNT_SIG_CH0_IN0_IDX`).
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct InputSignal(pub u32);
+
+/// A peripheral output signal routed to a pin with `connect_out_signal`.
+///
+/// When dropped, the pin is routed back to its plain GPIO output.
+pub struct OutSignalConnection<'a> {
+    pin: i32,
+    _p: PhantomData<&'a mut ()>,
+}
+
+impl Drop for OutSignalConnection<'_> {
+    fn drop(&mut self) {
+        unsafe {
+            esp_rom_gpio_connect_out_signal(self.pin as _, SIG_GPIO_OUT_IDX as _, false, false);
+        }
+    }
+}
+
+/// A pin routed to a peripheral input signal with `connect_in_signal`.
+///
+/// When dropped, the peripheral input signal is disconnected from the pin and tied low.
+pub struct InSignalConnection<'a> {
+    signal: InputSignal,
+    _p: PhantomData<&'a ()>,
+}
+
+impl Drop for InSignalConnection<'_> {
     fn drop(&mut self) {
-        gpio_reset_without_pull(self.pin.pin()).unwrap();
+        unsafe {
+            esp_rom_gpio_connect_in_signal(GPIO_MATRIX_CONST_ZERO_INPUT as _, self.signal.0, false);
+        }
     }
 }
 
-unsafe impl<'d, T: Pin, MODE> Send for PinDriver<'d, T, MODE> {}
-
-impl<'d, T: Pin, MODE> embedded_hal_0_2::digital::v2::InputPin for PinDriver<'d, T, MODE>
-where
-    MODE: InputMode,
-{
-    type Error = EspError;
+/// Routes the peripheral output signal `signal` to the pin of the provided driver via the GPIO matrix,
+/// optionally inverting the signal (`invert`) and the output-enable signal of the peripheral (`oen_invert`).
+///
+/// A peripheral output signal can be routed to more than one pin (i.e. mirrored),
+/// by calling this function for each of the pins.
+///
+/// The routing stays in effect for as long as the returned connection is alive.
+pub fn connect_out_signal<'a, T: OutputPin, MODE: OutputMode>(
+    pin: &'a mut PinDriver<'_, T, MODE>,
+    signal: OutputSignal,
+    invert: bool,
+    oen_invert: bool,
+) -> OutSignalConnection<'a> {
+    let pin = pin.pin();
 
-    fn is_high(&self) -> Result<bool, Self::Error> {
-        Ok(PinDriver::is_high(self))
+    unsafe {
+        esp_rom_gpio_connect_out_signal(pin as _, signal.0, invert, oen_invert);
     }
 
-    fn is_low(&self) -> Result<bool, Self::Error> {
-        Ok(PinDriver::is_low(self))
+    OutSignalConnection {
+        pin,
+        _p: PhantomData,
     }
 }
 
-use crate::embedded_hal_error;
-embedded_hal_error!(
-    GpioError,
-    embedded_hal::digital::Error,
-    embedded_hal::digital::ErrorKind
-);
+/// Routes the pin of the provided driver to the peripheral input signal `signal` via the GPIO matrix,
+/// optionally inverting the signal (`invert`).
+///
+/// A pin can be routed to more than one peripheral input signal, by calling this function for each of the signals.
+///
+/// The routing stays in effect for as long as the returned connection is alive.
+pub fn connect_in_signal<'a, T: InputPin, MODE: InputMode>(
+    pin: &'a PinDriver<'_, T, MODE>,
+    signal: InputSignal,
+    invert: bool,
+) -> InSignalConnection<'a> {
+    unsafe {
+        esp_rom_gpio_connect_in_signal(pin.pin() as _, signal.0, invert);
+    }
 
-fn to_gpio_err(err: EspError) -> GpioError {
-    GpioError::other(err)
+    InSignalConnection {
+        signal,
+        _p: PhantomData,
+    }
 }
 
-impl<'d, T: Pin, MODE> embedded_hal::digital::ErrorType for PinDriver<'d, T, MODE> {
-    type Error = GpioError;
+#[allow(clippy::declare_interior_mutable_const)] // OK because this is only used as an array initializer
+const PIN_INTR_TYPE_INIT: core::sync::atomic::AtomicU8 = core::sync::atomic::AtomicU8::new(0);
+
+// The interrupt type (as a `gpio_int_type_t`) last set on each pin
+static PIN_INTR_TYPE: [core::sync::atomic::AtomicU8; SOC_GPIO_PIN_COUNT as usize] =
+    [PIN_INTR_TYPE_INIT; SOC_GPIO_PIN_COUNT as usize];
+
+// Per-pin interrupt counting and flood protection bookkeeping, updated in the ISR
+struct PinIntrStats {
+    count: core::sync::atomic::AtomicU32,
+    // 0 = flood protection disabled
+    limit: core::sync::atomic::AtomicU32,
+    window_us: core::sync::atomic::AtomicU32,
+    window_start: core::sync::atomic::AtomicU32,
+    window_count: core::sync::atomic::AtomicU32,
+    flooded: core::sync::atomic::AtomicBool,
 }
 
-impl<'d, T: Pin, MODE> embedded_hal::digital::InputPin for PinDriver<'d, T, MODE>
-where
-    MODE: InputMode,
-{
-    fn is_high(&mut self) -> Result<bool, Self::Error> {
-        Ok(PinDriver::is_high(self))
+impl PinIntrStats {
+    const fn new() -> Self {
+        Self {
+            count: core::sync::atomic::AtomicU32::new(0),
+            limit: core::sync::atomic::AtomicU32::new(0),
+            window_us: core::sync::atomic::AtomicU32::new(0),
+            window_start: core::sync::atomic::AtomicU32::new(0),
+            window_count: core::sync::atomic::AtomicU32::new(0),
+            flooded: core::sync::atomic::AtomicBool::new(false),
+        }
     }
 
-    fn is_low(&mut self) -> Result<bool, Self::Error> {
-        Ok(PinDriver::is_low(self))
-    }
-}
+    fn reset(&self) {
+        use core::sync::atomic::Ordering;
 
-impl<'d, T: Pin, MODE> embedded_hal::digital::InputPin for &PinDriver<'d, T, MODE>
-where
-    MODE: InputMode,
-{
-    fn is_high(&mut self) -> Result<bool, Self::Error> {
-        Ok(PinDriver::is_high(self))
+        self.limit.store(0, Ordering::SeqCst);
+        self.count.store(0, Ordering::SeqCst);
+        self.rearm();
     }
 
-    fn is_low(&mut self) -> Result<bool, Self::Error> {
-        Ok(PinDriver::is_low(self))
+    fn rearm(&self) {
+        self.rearm_at(unsafe { esp_timer_get_time() } as u32);
     }
-}
 
-impl<'d, T: Pin, MODE> embedded_hal_0_2::digital::v2::OutputPin for PinDriver<'d, T, MODE>
-where
-    MODE: OutputMode,
-{
-    type Error = EspError;
+    fn rearm_at(&self, now: u32) {
+        use core::sync::atomic::Ordering;
 
-    fn set_high(&mut self) -> Result<(), Self::Error> {
-        self.set_level(Level::High)
+        self.window_count.store(0, Ordering::SeqCst);
+        self.window_start.store(now, Ordering::SeqCst);
+        self.flooded.store(false, Ordering::SeqCst);
     }
 
-    fn set_low(&mut self) -> Result<(), Self::Error> {
-        self.set_level(Level::Low)
+    // Re-arms the flood protection if the pin is flooded, but only once a whole window has elapsed
+    // since it got flooded. Returns `false` if the pin is still held off.
+    //
+    // A pin which is not flooded is left alone, so that the interrupts counted in the current window
+    // are not forgotten, as the interrupt is re-enabled after each interrupt
+    fn try_rearm(&self) -> bool {
+        self.try_rearm_at(unsafe { esp_timer_get_time() } as u32)
     }
-}
 
-impl<'d, T: Pin, MODE> embedded_hal::digital::OutputPin for PinDriver<'d, T, MODE>
-where
-    MODE: OutputMode,
-{
-    fn set_high(&mut self) -> Result<(), Self::Error> {
-        self.set_level(Level::High).map_err(to_gpio_err)
-    }
+    fn try_rearm_at(&self, now: u32) -> bool {
+        use core::sync::atomic::Ordering;
 
-    fn set_low(&mut self) -> Result<(), Self::Error> {
-        self.set_level(Level::Low).map_err(to_gpio_err)
-    }
-}
+        if self.flooded.load(Ordering::SeqCst) {
+            if now.wrapping_sub(self.window_start.load(Ordering::SeqCst))
+                <= self.window_us.load(Ordering::SeqCst)
+            {
+                return false;
+            }
 
-impl<'d, T: Pin, MODE> embedded_hal::digital::StatefulOutputPin for PinDriver<'d, T, MODE>
-where
-    MODE: OutputMode,
-{
-    fn is_set_high(&mut self) -> Result<bool, Self::Error> {
-        Ok(self.get_output_level().into())
+            self.rearm_at(now);
+        }
+
+        true
     }
 
-    fn is_set_low(&mut self) -> Result<bool, Self::Error> {
-        Ok(!bool::from(self.get_output_level()))
+    // Returns `Some(true)` if the pin just got flooded, and `Some(false)` if it was already flooded
+    #[inline(always)]
+    #[link_section = ".iram1.gpio_intr_stats_on_interrupt"]
+    fn on_interrupt(&self) -> Option<bool> {
+        self.on_interrupt_at(unsafe { esp_timer_get_time() } as u32)
     }
-}
 
-// TODO: Will become possible once the `PinDriver::setXXX`` methods become non-`&mut`, which they really are, internally
-// impl<'d, T: Pin, MODE> embedded_hal::digital::StatefulOutputPin for &PinDriver<'d, T, MODE>
-// where
-//     MODE: OutputMode,
-// {
-//     fn is_set_high(&mut self) -> Result<bool, Self::Error> {
-//         Ok(self.get_output_level().into())
-//     }
+    #[inline(always)]
+    #[link_section = ".iram1.gpio_intr_stats_on_interrupt_at"]
+    fn on_interrupt_at(&self, now: u32) -> Option<bool> {
+        use core::sync::atomic::Ordering;
 
-//     fn is_set_low(&mut self) -> Result<bool, Self::Error> {
-//         Ok(!bool::from(self.get_output_level()))
-//     }
-// }
+        self.count.fetch_add(1, Ordering::Relaxed);
 
-impl<'d, T: Pin, MODE> embedded_hal_0_2::digital::v2::StatefulOutputPin for PinDriver<'d, T, MODE>
-where
-    MODE: OutputMode,
-{
-    fn is_set_high(&self) -> Result<bool, Self::Error> {
-        Ok(self.get_output_level().into())
-    }
+        let limit = self.limit.load(Ordering::Relaxed);
+        if limit == 0 {
+            return None;
+        }
 
-    fn is_set_low(&self) -> Result<bool, Self::Error> {
-        Ok(!bool::from(self.get_output_level()))
-    }
-}
+        if self.flooded.load(Ordering::Relaxed) {
+            return Some(false);
+        }
 
-impl<'d, T: Pin, MODE> embedded_hal_0_2::digital::v2::ToggleableOutputPin for PinDriver<'d, T, MODE>
-where
-    MODE: OutputMode,
-{
-    type Error = EspError;
+        if now.wrapping_sub(self.window_start.load(Ordering::Relaxed))
+            > self.window_us.load(Ordering::Relaxed)
+        {
+            self.window_start.store(now, Ordering::Relaxed);
+            self.window_count.store(1, Ordering::Relaxed);
 
-    fn toggle(&mut self) -> Result<(), Self::Error> {
-        self.set_level(Level::from(!bool::from(self.get_output_level())))
+            None
+        } else if self.window_count.fetch_add(1, Ordering::Relaxed) + 1 > limit {
+            // The hold-off window starts when the pin gets flooded
+            self.window_start.store(now, Ordering::Relaxed);
+            self.flooded.store(true, Ordering::Relaxed);
+
+            Some(true)
+        } else {
+            None
+        }
     }
 }
 
-impl<T: Pin, MODE: InputMode> embedded_hal_async::digital::Wait for PinDriver<'_, T, MODE> {
-    async fn wait_for_high(&mut self) -> Result<(), GpioError> {
-        self.wait_for_high().await?;
+#[allow(clippy::declare_interior_mutable_const)] // OK because this is only used as an array initializer
+const PIN_INTR_STATS_INIT: PinIntrStats = PinIntrStats::new();
 
-        Ok(())
-    }
+static PIN_INTR_STATS: [PinIntrStats; SOC_GPIO_PIN_COUNT as usize] =
+    [PIN_INTR_STATS_INIT; SOC_GPIO_PIN_COUNT as usize];
 
-    async fn wait_for_low(&mut self) -> Result<(), GpioError> {
-        self.wait_for_low().await?;
+static FLOOD_CALLBACK: core::sync::atomic::AtomicPtr<()> =
+    core::sync::atomic::AtomicPtr::new(core::ptr::null_mut());
 
-        Ok(())
+/// Sets the callback which is called when the interrupts of a pin with flood protection enabled
+/// (see `PinDriver::enable_flood_protection`) get masked. The callback is called with the number of the pin.
+///
+/// # Safety
+///
+/// Care should be taken not to call STD, libc or FreeRTOS APIs (except for a few allowed ones)
+/// in the callback passed to this function, as it is executed in an ISR context.
+pub unsafe fn set_flood_callback(callback: Option<fn(i32)>) {
+    FLOOD_CALLBACK.store(
+        callback
+            .map(|callback| callback as *mut ())
+            .unwrap_or(core::ptr::null_mut()),
+        core::sync::atomic::Ordering::SeqCst,
+    );
+}
+
+#[cfg(feature = "alloc")]
+#[allow(non_upper_case_globals)]
+#[inline(always)]
+#[link_section = ".iram1.gpio_isr_edge"]
+fn isr_edge(pin: i32) -> Edge {
+    match PIN_INTR_TYPE[pin as usize].load(core::sync::atomic::Ordering::Relaxed) as gpio_int_type_t
+    {
+        gpio_int_type_t_GPIO_INTR_POSEDGE => Edge::Rising,
+        gpio_int_type_t_GPIO_INTR_NEGEDGE => Edge::Falling,
+        gpio_int_type_t_GPIO_INTR_ANYEDGE => {
+            if unsafe { gpio_get_level(pin) } != 0 {
+                Edge::Rising
+            } else {
+                Edge::Falling
+            }
+        }
+        _ => Edge::Unknown,
     }
+}
 
-    async fn wait_for_rising_edge(&mut self) -> Result<(), GpioError> {
-        self.wait_for_rising_edge().await?;
+// A bitmask with a bit per pin; pins 0 - 31 in the first word, pins 32+ in the second one
+struct PinMask([core::sync::atomic::AtomicU32; 2]);
 
-        Ok(())
+impl PinMask {
+    const fn new() -> Self {
+        Self([
+            core::sync::atomic::AtomicU32::new(0),
+            core::sync::atomic::AtomicU32::new(0),
+        ])
     }
 
-    async fn wait_for_falling_edge(&mut self) -> Result<(), GpioError> {
-        self.wait_for_falling_edge().await?;
-
-        Ok(())
+    #[inline(always)]
+    #[link_section = ".iram1.gpio_pin_mask_get"]
+    fn get(&self, pin: i32) -> bool {
+        self.0[pin as usize / 32].load(core::sync::atomic::Ordering::SeqCst) & (1 << (pin % 32))
+            != 0
     }
 
-    async fn wait_for_any_edge(&mut self) -> Result<(), GpioError> {
-        self.wait_for_any_edge().await?;
+    fn set(&self, pin: i32, value: bool) {
+        use core::sync::atomic::Ordering;
 
-        Ok(())
+        if value {
+            self.0[pin as usize / 32].fetch_or(1 << (pin % 32), Ordering::SeqCst);
+        } else {
+            self.0[pin as usize / 32].fetch_and(!(1 << (pin % 32)), Ordering::SeqCst);
+        }
     }
-}
 
-static ISR_ALLOC_FLAGS: core::sync::atomic::AtomicU32 = core::sync::atomic::AtomicU32::new(0);
+    // Clears the bit of the pin, returning its previous value
+    fn take(&self, pin: i32) -> bool {
+        let bit = 1 << (pin % 32);
 
-static ISR_SERVICE_ENABLED: core::sync::atomic::AtomicBool =
-    core::sync::atomic::AtomicBool::new(false);
+        self.0[pin as usize / 32].fetch_and(!bit, core::sync::atomic::Ordering::SeqCst) & bit != 0
+    }
+}
 
-static ISR_SERVICE_ENABLED_CS: crate::task::CriticalSection = crate::task::CriticalSection::new();
+// Pins whose hold function is enabled
+static HELD_PINS: PinMask = PinMask::new();
 
-pub fn init_isr_alloc_flags(flags: enumset::EnumSet<crate::interrupt::InterruptType>) {
-    ISR_ALLOC_FLAGS.store(
-        crate::interrupt::InterruptType::to_native(flags),
-        core::sync::atomic::Ordering::SeqCst,
-    );
-}
+// Pins split with `PinDriver::split`, whose two halves are both alive
+static SPLIT_PINS: PinMask = PinMask::new();
 
-pub fn enable_isr_service() -> Result<(), EspError> {
-    use core::sync::atomic::Ordering;
+// Pins subscribed with `PinDriver::subscribe_raw`
+static RAW_ISR_PINS: PinMask = PinMask::new();
 
-    if !ISR_SERVICE_ENABLED.load(Ordering::SeqCst) {
-        let _guard = ISR_SERVICE_ENABLED_CS.enter();
+// Pins enabled as light sleep wakeup sources with `PinDriver::enable_light_sleep_wakeup`
+static LIGHT_SLEEP_WAKEUP_PINS: PinMask = PinMask::new();
 
-        if !ISR_SERVICE_ENABLED.load(Ordering::SeqCst) {
-            esp!(unsafe { gpio_install_isr_service(ISR_ALLOC_FLAGS.load(Ordering::SeqCst) as _) })?;
+fn disable_light_sleep_wakeup(pin: i32) -> Result<(), EspError> {
+    if LIGHT_SLEEP_WAKEUP_PINS.get(pin) {
+        esp!(unsafe { gpio_wakeup_disable(pin) })?;
 
-            ISR_SERVICE_ENABLED.store(true, Ordering::SeqCst);
-        }
+        PIN_INTR_TYPE[pin as usize].store(
+            gpio_int_type_t_GPIO_INTR_DISABLE as _,
+            core::sync::atomic::Ordering::SeqCst,
+        );
+        LIGHT_SLEEP_WAKEUP_PINS.set(pin, false);
     }
 
     Ok(())
 }
 
+fn is_held(pin: i32) -> bool {
+    HELD_PINS.get(pin)
+}
+
+fn set_held(pin: i32, held: bool) {
+    HELD_PINS.set(pin, held);
+}
+
+/// Enables or disables the hold function of all digital pins during deep sleep.
+///
+/// Only the pins whose hold function was enabled with `PinDriver::set_hold` keep their state.
+/// This function should be called right before entering deep sleep.
+#[cfg(not(any(esp32c6, esp32h2, esp32p4, esp32c5)))]
+pub fn deep_sleep_hold(enable: bool) {
+    if enable {
+        unsafe { gpio_deep_sleep_hold_en() };
+    } else {
+        unsafe { gpio_deep_sleep_hold_dis() };
+    }
+}
+
 pub(crate) unsafe fn rtc_reset_pin(pin: i32) -> Result<(), EspError> {
     gpio_reset_without_pull(pin)?;
 
@@ -1454,8 +3017,21 @@ fn gpio_reset_without_pull(pin: gpio_num_t) -> Result<(), EspError> {
 
     unsafe {
         unsubscribe_pin(pin)?;
+
+        #[cfg(not(any(esp32c3, esp32c2, esp32h2, esp32c5)))]
+        if PinCapabilities::of(pin).is_some_and(|caps| caps.rtc) {
+            esp!(rtc_gpio_deinit(pin))?;
+        }
+
         esp!(gpio_config(&cfg))?;
     }
+
+    PIN_INTR_TYPE[pin as usize].store(
+        gpio_int_type_t_GPIO_INTR_DISABLE as _,
+        core::sync::atomic::Ordering::SeqCst,
+    );
+    PIN_INTR_STATS[pin as usize].reset();
+
     Ok(())
 }
 
@@ -1465,6 +3041,8 @@ unsafe fn unsubscribe_pin(pin: i32) -> Result<(), EspError> {
     if ISR_SERVICE_ENABLED.load(Ordering::SeqCst) {
         esp!(gpio_isr_handler_remove(pin))?;
 
+        RAW_ISR_PINS.set(pin, false);
+
         chip::PIN_NOTIF[pin as usize].reset();
 
         #[cfg(feature = "alloc")]
@@ -1478,7 +3056,7 @@ unsafe fn unsubscribe_pin(pin: i32) -> Result<(), EspError> {
 
 #[cfg(feature = "alloc")]
 #[allow(clippy::declare_interior_mutable_const)] // OK because this is only used as an array initializer
-const PIN_ISR_INIT: Option<alloc::boxed::Box<dyn FnMut() + Send + 'static>> = None;
+const PIN_ISR_INIT: Option<alloc::boxed::Box<dyn FnMut(Edge) + Send + 'static>> = None;
 
 #[allow(clippy::declare_interior_mutable_const)] // OK because this is only used as an array initializer
 const PIN_NOTIF_INIT: crate::interrupt::asynch::HalIsrNotification =
@@ -1576,7 +3154,12 @@ macro_rules! impl_dac {
         #[cfg(any(esp32, esp32s2))]
         impl DACPin for $pxi {
             fn dac_channel(&self) -> dac_channel_t {
-                $dac
+                // The channels are numbered from 1 on ESP-IDF 4, and from 0 on ESP-IDF 5
+                if cfg!(esp_idf_version_major = "4") {
+                    $dac
+                } else {
+                    $dac - 1
+                }
             }
         }
     };
@@ -1597,9 +3180,57 @@ macro_rules! impl_touch {
     ($pxi:ident: $pin:expr, NOTOUCH: $touch:expr) => {};
 }
 
+macro_rules! has_cap {
+    (RTC) => {
+        true
+    };
+    (NORTC) => {
+        false
+    };
+    (ADC1) => {
+        true
+    };
+    (ADC2) => {
+        true
+    };
+    (NOADC) => {
+        false
+    };
+    (DAC) => {
+        true
+    };
+    (NODAC) => {
+        false
+    };
+    (TOUCH) => {
+        true
+    };
+    (NOTOUCH) => {
+        false
+    };
+}
+
+macro_rules! impl_capabilities {
+    ($pxi:ident: $pin:expr, $output:expr, $rtc:ident, $adc:ident, $dac:ident, $touch:ident) => {
+        impl $pxi {
+            pub(crate) const CAPABILITIES: PinCapabilities = PinCapabilities {
+                pin: $pin,
+                input: true,
+                output: $output,
+                rtc: has_cap!($rtc),
+                adc: has_cap!($adc),
+                dac: has_cap!($dac),
+                touch: has_cap!($touch),
+                reserved: false,
+            };
+        }
+    };
+}
+
 macro_rules! pin {
     ($pxi:ident: $pin:expr, Input, $rtc:ident: $rtcno:expr, $adc:ident: $adcno:expr, $dac:ident: $dacno:expr, $touch:ident: $touchno:expr) => {
         impl_input!($pxi: $pin);
+        impl_capabilities!($pxi: $pin, false, $rtc, $adc, $dac, $touch);
         impl_rtc!($pxi: $pin, $rtc: $rtcno);
         impl_adc!($pxi: $pin, $adc: $adcno);
         impl_dac!($pxi: $pin, $dac: $dacno);
@@ -1608,6 +3239,7 @@ macro_rules! pin {
 
     ($pxi:ident: $pin:expr, IO, $rtc:ident: $rtcno:expr, $adc:ident: $adcno:expr, $dac:ident: $dacno:expr, $touch:ident: $touchno:expr) => {
         impl_input_output!($pxi: $pin);
+        impl_capabilities!($pxi: $pin, true, $rtc, $adc, $dac, $touch);
         impl_rtc!($pxi: $pin, $rtc: $rtcno);
         impl_adc!($pxi: $pin, $adc: $adcno);
         impl_dac!($pxi: $pin, $dac: $dacno);
@@ -1633,14 +3265,14 @@ mod chip {
 
     #[allow(clippy::type_complexity)]
     #[cfg(feature = "alloc")]
-    pub(crate) static mut PIN_ISR_HANDLER: [Option<Box<dyn FnMut() + Send + 'static>>; 40] =
+    pub(crate) static mut PIN_ISR_HANDLER: [Option<Box<dyn FnMut(Edge) + Send + 'static>>; 40] =
         [PIN_ISR_INIT; 40];
 
     #[allow(clippy::type_complexity)]
     pub(crate) static PIN_NOTIF: [HalIsrNotification; 40] = [PIN_NOTIF_INIT; 40];
 
-    // NOTE: Gpio26 - Gpio32 are used by SPI0/SPI1 for external PSRAM/SPI Flash and
-    //       are not recommended for other uses
+    // NOTE: Gpio6 - Gpio11 (and Gpio16 - Gpio17 if using PSRAM) are used by SPI0/SPI1
+    //       for external PSRAM/SPI Flash and are not recommended for other uses
     pin!(Gpio0:0, IO, RTC:11, ADC2:1, NODAC:0, TOUCH:1);
     pin!(Gpio1:1, IO, NORTC:0, NOADC:0, NODAC:0, NOTOUCH:0);
     pin!(Gpio2:2, IO, RTC:12, ADC2:2, NODAC:0, TOUCH:2);
@@ -1677,6 +3309,59 @@ mod chip {
     pin!(Gpio38:38, Input, RTC:2, ADC1:2, NODAC:0, NOTOUCH:0);
     pin!(Gpio39:39, Input, RTC:3, ADC1:3, NODAC:0, NOTOUCH:0);
 
+    // The capabilities of all pins of the chip
+    pub(crate) const PIN_CAPABILITIES: &[PinCapabilities] = &[
+        Gpio0::CAPABILITIES,
+        Gpio1::CAPABILITIES,
+        Gpio2::CAPABILITIES,
+        Gpio3::CAPABILITIES,
+        Gpio4::CAPABILITIES,
+        Gpio5::CAPABILITIES,
+        Gpio6::CAPABILITIES,
+        Gpio7::CAPABILITIES,
+        Gpio8::CAPABILITIES,
+        Gpio9::CAPABILITIES,
+        Gpio10::CAPABILITIES,
+        Gpio11::CAPABILITIES,
+        Gpio12::CAPABILITIES,
+        Gpio13::CAPABILITIES,
+        Gpio14::CAPABILITIES,
+        Gpio15::CAPABILITIES,
+        Gpio16::CAPABILITIES,
+        Gpio17::CAPABILITIES,
+        Gpio18::CAPABILITIES,
+        Gpio19::CAPABILITIES,
+        Gpio20::CAPABILITIES,
+        Gpio21::CAPABILITIES,
+        Gpio22::CAPABILITIES,
+        Gpio23::CAPABILITIES,
+        Gpio25::CAPABILITIES,
+        Gpio26::CAPABILITIES,
+        Gpio27::CAPABILITIES,
+        Gpio32::CAPABILITIES,
+        Gpio33::CAPABILITIES,
+        Gpio34::CAPABILITIES,
+        Gpio35::CAPABILITIES,
+        Gpio36::CAPABILITIES,
+        Gpio37::CAPABILITIES,
+        Gpio38::CAPABILITIES,
+        Gpio39::CAPABILITIES,
+    ];
+
+    // The pins used by SPI0/SPI1 for the SPI flash and PSRAM
+    pub(crate) const RESERVED_PINS: &[i32] = &[
+        6,
+        7,
+        8,
+        9,
+        10,
+        11,
+        #[cfg(esp_idf_spiram)]
+        16,
+        #[cfg(esp_idf_spiram)]
+        17,
+    ];
+
     pub struct Pins {
         pub gpio0: Gpio0,
         pub gpio1: Gpio1,
@@ -1780,7 +3465,7 @@ mod chip {
 
     #[allow(clippy::type_complexity)]
     #[cfg(feature = "alloc")]
-    pub(crate) static mut PIN_ISR_HANDLER: [Option<Box<dyn FnMut() + Send + 'static>>; 49] =
+    pub(crate) static mut PIN_ISR_HANDLER: [Option<Box<dyn FnMut(Edge) + Send + 'static>>; 49] =
         [PIN_ISR_INIT; 49];
 
     #[allow(clippy::type_complexity)]
@@ -1846,6 +3531,78 @@ mod chip {
     #[cfg(esp32s3)]
     pin!(Gpio48:48, IO, NORTC:0, NOADC:0, NODAC:0, NOTOUCH:0);
 
+    // The capabilities of all pins of the chip
+    pub(crate) const PIN_CAPABILITIES: &[PinCapabilities] = &[
+        Gpio0::CAPABILITIES,
+        Gpio1::CAPABILITIES,
+        Gpio2::CAPABILITIES,
+        Gpio3::CAPABILITIES,
+        Gpio4::CAPABILITIES,
+        Gpio5::CAPABILITIES,
+        Gpio6::CAPABILITIES,
+        Gpio7::CAPABILITIES,
+        Gpio8::CAPABILITIES,
+        Gpio9::CAPABILITIES,
+        Gpio10::CAPABILITIES,
+        Gpio11::CAPABILITIES,
+        Gpio12::CAPABILITIES,
+        Gpio13::CAPABILITIES,
+        Gpio14::CAPABILITIES,
+        Gpio15::CAPABILITIES,
+        Gpio16::CAPABILITIES,
+        Gpio17::CAPABILITIES,
+        Gpio18::CAPABILITIES,
+        Gpio19::CAPABILITIES,
+        Gpio20::CAPABILITIES,
+        Gpio21::CAPABILITIES,
+        Gpio26::CAPABILITIES,
+        Gpio27::CAPABILITIES,
+        Gpio28::CAPABILITIES,
+        Gpio29::CAPABILITIES,
+        Gpio30::CAPABILITIES,
+        Gpio31::CAPABILITIES,
+        Gpio32::CAPABILITIES,
+        Gpio33::CAPABILITIES,
+        Gpio34::CAPABILITIES,
+        Gpio35::CAPABILITIES,
+        Gpio36::CAPABILITIES,
+        Gpio37::CAPABILITIES,
+        Gpio38::CAPABILITIES,
+        Gpio39::CAPABILITIES,
+        Gpio40::CAPABILITIES,
+        Gpio41::CAPABILITIES,
+        Gpio42::CAPABILITIES,
+        Gpio43::CAPABILITIES,
+        Gpio44::CAPABILITIES,
+        Gpio45::CAPABILITIES,
+        Gpio46::CAPABILITIES,
+        #[cfg(esp32s3)]
+        Gpio47::CAPABILITIES,
+        #[cfg(esp32s3)]
+        Gpio48::CAPABILITIES,
+    ];
+
+    // The pins used by SPI0/SPI1 for the SPI flash and PSRAM
+    pub(crate) const RESERVED_PINS: &[i32] = &[
+        26,
+        27,
+        28,
+        29,
+        30,
+        31,
+        32,
+        #[cfg(all(esp32s3, esp_idf_spiram_mode_oct))]
+        33,
+        #[cfg(all(esp32s3, esp_idf_spiram_mode_oct))]
+        34,
+        #[cfg(all(esp32s3, esp_idf_spiram_mode_oct))]
+        35,
+        #[cfg(all(esp32s3, esp_idf_spiram_mode_oct))]
+        36,
+        #[cfg(all(esp32s3, esp_idf_spiram_mode_oct))]
+        37,
+    ];
+
     pub struct Pins {
         pub gpio0: Gpio0,
         pub gpio1: Gpio1,
@@ -1973,7 +3730,7 @@ mod chip {
 
     #[allow(clippy::type_complexity)]
     #[cfg(feature = "alloc")]
-    pub(crate) static mut PIN_ISR_HANDLER: [Option<Box<dyn FnMut() + Send + 'static>>; 22] =
+    pub(crate) static mut PIN_ISR_HANDLER: [Option<Box<dyn FnMut(Edge) + Send + 'static>>; 22] =
         [PIN_ISR_INIT; 22];
 
     pub(crate) static PIN_NOTIF: [HalIsrNotification; 22] = [PIN_NOTIF_INIT; 22];
@@ -2003,6 +3760,35 @@ mod chip {
     pin!(Gpio20:20, IO, NORTC:0, NOADC:0, NODAC:0, NOTOUCH:0);
     pin!(Gpio21:21, IO, NORTC:0, NOADC:0, NODAC:0, NOTOUCH:0);
 
+    // The capabilities of all pins of the chip
+    pub(crate) const PIN_CAPABILITIES: &[PinCapabilities] = &[
+        Gpio0::CAPABILITIES,
+        Gpio1::CAPABILITIES,
+        Gpio2::CAPABILITIES,
+        Gpio3::CAPABILITIES,
+        Gpio4::CAPABILITIES,
+        Gpio5::CAPABILITIES,
+        Gpio6::CAPABILITIES,
+        Gpio7::CAPABILITIES,
+        Gpio8::CAPABILITIES,
+        Gpio9::CAPABILITIES,
+        Gpio10::CAPABILITIES,
+        Gpio11::CAPABILITIES,
+        Gpio12::CAPABILITIES,
+        Gpio13::CAPABILITIES,
+        Gpio14::CAPABILITIES,
+        Gpio15::CAPABILITIES,
+        Gpio16::CAPABILITIES,
+        Gpio17::CAPABILITIES,
+        Gpio18::CAPABILITIES,
+        Gpio19::CAPABILITIES,
+        Gpio20::CAPABILITIES,
+        Gpio21::CAPABILITIES,
+    ];
+
+    // The pins used by SPI0/SPI1 for the SPI flash and PSRAM
+    pub(crate) const RESERVED_PINS: &[i32] = &[12, 13, 14, 15, 16, 17];
+
     pub struct Pins {
         pub gpio0: Gpio0,
         pub gpio1: Gpio1,
@@ -2080,7 +3866,7 @@ mod chip {
 
     #[allow(clippy::type_complexity)]
     #[cfg(feature = "alloc")]
-    pub(crate) static mut PIN_ISR_HANDLER: [Option<Box<dyn FnMut() + Send + 'static>>; 20] =
+    pub(crate) static mut PIN_ISR_HANDLER: [Option<Box<dyn FnMut(Edge) + Send + 'static>>; 20] =
         [PIN_ISR_INIT; 20];
 
     pub(crate) static PIN_NOTIF: [HalIsrNotification; 20] = [PIN_NOTIF_INIT; 20];
@@ -2109,6 +3895,34 @@ mod chip {
     pin!(Gpio19:19, IO, NORTC:0, NOADC:0, NODAC:0, NOTOUCH:0);
     pin!(Gpio20:20, IO, NORTC:0, NOADC:0, NODAC:0, NOTOUCH:0);
 
+    // The capabilities of all pins of the chip
+    pub(crate) const PIN_CAPABILITIES: &[PinCapabilities] = &[
+        Gpio0::CAPABILITIES,
+        Gpio1::CAPABILITIES,
+        Gpio2::CAPABILITIES,
+        Gpio3::CAPABILITIES,
+        Gpio4::CAPABILITIES,
+        Gpio5::CAPABILITIES,
+        Gpio6::CAPABILITIES,
+        Gpio7::CAPABILITIES,
+        Gpio8::CAPABILITIES,
+        Gpio9::CAPABILITIES,
+        Gpio10::CAPABILITIES,
+        Gpio11::CAPABILITIES,
+        Gpio12::CAPABILITIES,
+        Gpio13::CAPABILITIES,
+        Gpio14::CAPABILITIES,
+        Gpio15::CAPABILITIES,
+        Gpio16::CAPABILITIES,
+        Gpio17::CAPABILITIES,
+        Gpio18::CAPABILITIES,
+        Gpio19::CAPABILITIES,
+        Gpio20::CAPABILITIES,
+    ];
+
+    // The pins used by SPI0/SPI1 for the SPI flash and PSRAM
+    pub(crate) const RESERVED_PINS: &[i32] = &[12, 13, 14, 15, 16, 17];
+
     pub struct Pins {
         pub gpio0: Gpio0,
         pub gpio1: Gpio1,
@@ -2184,7 +3998,7 @@ mod chip {
 
     #[allow(clippy::type_complexity)]
     #[cfg(feature = "alloc")]
-    pub(crate) static mut PIN_ISR_HANDLER: [Option<Box<dyn FnMut() + Send + 'static>>; 20] =
+    pub(crate) static mut PIN_ISR_HANDLER: [Option<Box<dyn FnMut(Edge) + Send + 'static>>; 20] =
         [PIN_ISR_INIT; 20];
 
     pub(crate) static PIN_NOTIF: [HalIsrNotification; 20] = [PIN_NOTIF_INIT; 20];
@@ -2213,6 +4027,34 @@ mod chip {
     pin!(Gpio19:19, IO, NORTC:0, NOADC:0, NODAC:0, NOTOUCH:0);
     pin!(Gpio20:20, IO, NORTC:0, NOADC:0, NODAC:0, NOTOUCH:0);
 
+    // The capabilities of all pins of the chip
+    pub(crate) const PIN_CAPABILITIES: &[PinCapabilities] = &[
+        Gpio0::CAPABILITIES,
+        Gpio1::CAPABILITIES,
+        Gpio2::CAPABILITIES,
+        Gpio3::CAPABILITIES,
+        Gpio4::CAPABILITIES,
+        Gpio5::CAPABILITIES,
+        Gpio6::CAPABILITIES,
+        Gpio7::CAPABILITIES,
+        Gpio8::CAPABILITIES,
+        Gpio9::CAPABILITIES,
+        Gpio10::CAPABILITIES,
+        Gpio11::CAPABILITIES,
+        Gpio12::CAPABILITIES,
+        Gpio13::CAPABILITIES,
+        Gpio14::CAPABILITIES,
+        Gpio15::CAPABILITIES,
+        Gpio16::CAPABILITIES,
+        Gpio17::CAPABILITIES,
+        Gpio18::CAPABILITIES,
+        Gpio19::CAPABILITIES,
+        Gpio20::CAPABILITIES,
+    ];
+
+    // The pins used by SPI0/SPI1 for the SPI flash and PSRAM
+    pub(crate) const RESERVED_PINS: &[i32] = &[15, 16, 17, 18, 19, 20, 21];
+
     pub struct Pins {
         pub gpio0: Gpio0,
         pub gpio1: Gpio1,
@@ -2290,7 +4132,7 @@ mod chip {
 
     #[allow(clippy::type_complexity)]
     #[cfg(feature = "alloc")]
-    pub(crate) static mut PIN_ISR_HANDLER: [Option<Box<dyn FnMut() + Send + 'static>>; 30] =
+    pub(crate) static mut PIN_ISR_HANDLER: [Option<Box<dyn FnMut(Edge) + Send + 'static>>; 30] =
         [PIN_ISR_INIT; 30];
 
     #[allow(clippy::type_complexity)]
@@ -2331,6 +4173,48 @@ mod chip {
     pin!(Gpio29:29, IO, NORTC:0, NOADC:0, NODAC:0, NOTOUCH:0);
     pin!(Gpio30:30, IO, NORTC:0, NOADC:0, NODAC:0, NOTOUCH:0);
 
+    // The capabilities of all pins of the chip
+    pub(crate) const PIN_CAPABILITIES: &[PinCapabilities] = &[
+        Gpio0::CAPABILITIES,
+        Gpio1::CAPABILITIES,
+        Gpio2::CAPABILITIES,
+        Gpio3::CAPABILITIES,
+        Gpio4::CAPABILITIES,
+        Gpio5::CAPABILITIES,
+        Gpio6::CAPABILITIES,
+        Gpio7::CAPABILITIES,
+        Gpio8::CAPABILITIES,
+        Gpio9::CAPABILITIES,
+        Gpio10::CAPABILITIES,
+        Gpio11::CAPABILITIES,
+        Gpio12::CAPABILITIES,
+        Gpio13::CAPABILITIES,
+        Gpio14::CAPABILITIES,
+        Gpio15::CAPABILITIES,
+        Gpio16::CAPABILITIES,
+        Gpio17::CAPABILITIES,
+        Gpio18::CAPABILITIES,
+        Gpio19::CAPABILITIES,
+        Gpio20::CAPABILITIES,
+        Gpio21::CAPABILITIES,
+        Gpio22::CAPABILITIES,
+        Gpio23::CAPABILITIES,
+        Gpio24::CAPABILITIES,
+        Gpio25::CAPABILITIES,
+        Gpio26::CAPABILITIES,
+        Gpio27::CAPABILITIES,
+        Gpio28::CAPABILITIES,
+        Gpio29::CAPABILITIES,
+        Gpio30::CAPABILITIES,
+    ];
+
+    // The pins used by SPI0/SPI1 for the SPI flash and PSRAM
+    #[cfg(esp32c6)]
+    pub(crate) const RESERVED_PINS: &[i32] = &[24, 25, 26, 27, 28, 29, 30];
+
+    #[cfg(not(esp32c6))]
+    pub(crate) const RESERVED_PINS: &[i32] = &[];
+
     pub struct Pins {
         pub gpio0: Gpio0,
         pub gpio1: Gpio1,
@@ -2407,3 +4291,74 @@ mod chip {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use core::sync::atomic::Ordering;
+
+    use super::PinIntrStats;
+
+    fn stats(limit: u32, window_us: u32) -> PinIntrStats {
+        let stats = PinIntrStats::new();
+
+        stats.rearm_at(0);
+        stats.window_us.store(window_us, Ordering::SeqCst);
+        stats.limit.store(limit, Ordering::SeqCst);
+
+        stats
+    }
+
+    #[test]
+    fn flood_trips_although_reenabled_after_each_interrupt() {
+        let stats = stats(3, 1000);
+
+        for now in [10, 20, 30] {
+            assert_eq!(stats.on_interrupt_at(now), None);
+            assert!(stats.try_rearm_at(now));
+        }
+
+        assert_eq!(stats.on_interrupt_at(40), Some(true));
+        assert_eq!(stats.on_interrupt_at(50), Some(false));
+        assert!(stats.flooded.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn flooded_pin_is_held_off_for_a_window() {
+        let stats = stats(1, 1000);
+
+        assert_eq!(stats.on_interrupt_at(100), None);
+        assert_eq!(stats.on_interrupt_at(200), Some(true));
+
+        for now in [200, 700, 1200] {
+            assert!(!stats.try_rearm_at(now));
+            assert!(stats.flooded.load(Ordering::SeqCst));
+        }
+
+        assert!(stats.try_rearm_at(1201));
+        assert!(!stats.flooded.load(Ordering::SeqCst));
+        assert_eq!(stats.on_interrupt_at(1300), None);
+    }
+
+    #[test]
+    fn cleared_flood_is_rearmed_immediately() {
+        let stats = stats(1, 1000);
+
+        stats.on_interrupt_at(100);
+        assert_eq!(stats.on_interrupt_at(200), Some(true));
+
+        stats.rearm_at(300);
+
+        assert!(stats.try_rearm_at(300));
+        assert_eq!(stats.on_interrupt_at(400), None);
+    }
+
+    #[test]
+    fn interrupts_spread_over_windows_do_not_flood() {
+        let stats = stats(2, 1000);
+
+        for now in (0..10).map(|i| i * 600) {
+            assert_eq!(stats.on_interrupt_at(now), None);
+            assert!(stats.try_rearm_at(now));
+        }
+    }
+}
@@ -0,0 +1,363 @@
+//! Software debouncing of GPIO inputs
+//!
+//! `DebouncedInput` wraps a `PinDriver` in an input mode and reports the debounced
+//! presses and releases of e.g. a button connected to the pin. Each edge on the pin
+//! (re)starts a one-shot `esp_timer`, and the level of the pin is only confirmed once
+//! the pin has been stable for the whole debounce interval.
+//!
+//! The events are delivered both via a queue (see `DebouncedInput::wait_for_event`) and
+//! via an optional callback (see `DebouncedInput::subscribe`). The callback is called from
+//! the context of the `esp_timer` task, rather than from an ISR context.
+
+use core::cell::UnsafeCell;
+use core::mem::ManuallyDrop;
+use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+extern crate alloc;
+use alloc::boxed::Box;
+
+use esp_idf_sys::*;
+
+use crate::delay::BLOCK;
+use crate::task::queue::Queue;
+use crate::task::CriticalSection;
+
+use super::{InputMode, InputPin, InterruptType, Level, PinDriver};
+
+const IDLE: u8 = 0;
+const DEBOUNCING: u8 = 1;
+const LONG_PRESS: u8 = 2;
+
+const EVENTS_QUEUE_SIZE: usize = 8;
+
+/// A debounced input event
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum DebounceEvent {
+    /// The pin is confirmed to be at its pressed level
+    Pressed,
+    /// The pin is confirmed to be back at its released level
+    Released,
+    /// The pin is still at its pressed level, a long press interval after it was pressed
+    LongPress,
+}
+
+pub mod config {
+    use core::time::Duration;
+
+    use crate::gpio::Level;
+
+    /// Debounced input configuration
+    #[derive(Debug, Clone)]
+    pub struct Config {
+        /// The interval for which the pin should be stable, before its level is confirmed
+        pub debounce: Duration,
+        /// If set, a `DebounceEvent::LongPress` event is reported when the pin
+        /// is still pressed after this interval
+        pub long_press: Option<Duration>,
+        /// The level of the pin while pressed
+        pub pressed_level: Level,
+    }
+
+    impl Config {
+        pub const fn new() -> Self {
+            Self {
+                debounce: Duration::from_millis(20),
+                long_press: None,
+                pressed_level: Level::Low,
+            }
+        }
+
+        #[must_use]
+        pub fn debounce(mut self, debounce: Duration) -> Self {
+            self.debounce = debounce;
+            self
+        }
+
+        #[must_use]
+        pub fn long_press(mut self, long_press: Option<Duration>) -> Self {
+            self.long_press = long_press;
+            self
+        }
+
+        #[must_use]
+        pub fn pressed_level(mut self, pressed_level: Level) -> Self {
+            self.pressed_level = pressed_level;
+            self
+        }
+    }
+
+    impl Default for Config {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+}
+
+struct State {
+    pin: i32,
+    read: fn(i32) -> Level,
+    pressed_level: Level,
+    debounce_us: u64,
+    long_press_us: Option<u64>,
+    timer: esp_timer_handle_t,
+    phase: AtomicU8,
+    pressed: AtomicBool,
+    long_pressed: AtomicBool,
+    events: Queue<DebounceEvent>,
+    // Only accessed with `cs` entered
+    cs: CriticalSection,
+    #[allow(clippy::type_complexity)]
+    callback: UnsafeCell<Option<Box<dyn FnMut(DebounceEvent) + Send + 'static>>>,
+}
+
+impl State {
+    fn is_pressed_now(&self) -> bool {
+        (self.read)(self.pin) == self.pressed_level
+    }
+
+    fn emit(&self, event: DebounceEvent) {
+        // A full queue means that nobody is waiting for events, so the event is dropped
+        let _ = self.events.send_back(event, 0);
+
+        if let Some(callback) = unsafe { &mut *self.callback.get() } {
+            callback(event);
+        }
+    }
+
+    fn arm_long_press(&self) {
+        if let Some(long_press_us) = self.long_press_us {
+            if self
+                .phase
+                .compare_exchange(IDLE, LONG_PRESS, Ordering::SeqCst, Ordering::SeqCst)
+                .is_ok()
+            {
+                unsafe {
+                    esp_timer_start_once(
+                        self.timer,
+                        long_press_us.saturating_sub(self.debounce_us),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// A debounced input, reporting the presses and releases of e.g. a button connected to the pin
+///
+/// Dropping the input waits for the `esp_timer` task to finish any callback already
+/// dispatched for it, so it must not be dropped from an `esp_timer` callback.
+pub struct DebouncedInput<'d, T: InputPin, MODE: InputMode> {
+    driver: PinDriver<'d, T, MODE>,
+    // Only freed once no timer callback can be using it anymore, see `Drop`
+    state: ManuallyDrop<Box<State>>,
+}
+
+impl<'d, T: InputPin, MODE: InputMode> DebouncedInput<'d, T, MODE> {
+    /// Creates a debounced input from the provided driver.
+    ///
+    /// The driver is subscribed for interrupts on any edge, so any callback previously
+    /// subscribed with `PinDriver::subscribe` is unsubscribed.
+    pub fn new(driver: PinDriver<'d, T, MODE>, config: &config::Config) -> Result<Self, EspError> {
+        let pin = driver.pin();
+
+        let mut state = Box::new(State {
+            pin,
+            read: super::get_level::<MODE>,
+            pressed_level: config.pressed_level,
+            debounce_us: config.debounce.as_micros() as _,
+            long_press_us: config
+                .long_press
+                .map(|long_press| long_press.as_micros() as _),
+            timer: core::ptr::null_mut(),
+            phase: AtomicU8::new(DEBOUNCING),
+            pressed: AtomicBool::new(false),
+            long_pressed: AtomicBool::new(false),
+            events: Queue::new(EVENTS_QUEUE_SIZE),
+            cs: CriticalSection::new(),
+            callback: UnsafeCell::new(None),
+        });
+
+        let arg = &*state as *const State as *mut core::ffi::c_void;
+
+        let timer_args = esp_timer_create_args_t {
+            callback: Some(Self::handle_timer),
+            arg,
+            dispatch_method: esp_timer_dispatch_t_ESP_TIMER_TASK,
+            name: b"debounce\0".as_ptr() as *const _,
+            ..Default::default()
+        };
+
+        esp!(unsafe { esp_timer_create(&timer_args, &mut state.timer) })?;
+
+        state
+            .pressed
+            .store(state.is_pressed_now(), Ordering::SeqCst);
+        state.phase.store(IDLE, Ordering::SeqCst);
+
+        // From here on, `Drop` takes care of cleaning up
+        let mut this = Self {
+            driver,
+            state: ManuallyDrop::new(state),
+        };
+
+        this.driver.set_interrupt_type(InterruptType::AnyEdge)?;
+        unsafe {
+            this.driver.subscribe_raw(Self::handle_isr, arg)?;
+        }
+        this.driver.enable_interrupt()?;
+
+        Ok(this)
+    }
+
+    /// Returns `true` if the pin is - debounced - at its pressed level.
+    pub fn is_pressed(&self) -> bool {
+        self.state.pressed.load(Ordering::SeqCst)
+    }
+
+    /// Waits for up to `timeout` ticks for the next debounced event.
+    pub fn wait_for_event(&self, timeout: TickType_t) -> Option<DebounceEvent> {
+        self.state
+            .events
+            .recv_front(timeout)
+            .map(|(event, _)| event)
+    }
+
+    /// Blocks until the pin is pressed, discarding any other events received meanwhile.
+    pub fn wait_for_press(&self) {
+        while self.wait_for_event(BLOCK) != Some(DebounceEvent::Pressed) {}
+    }
+
+    /// Blocks until the pin is released, discarding any other events received meanwhile.
+    pub fn wait_for_release(&self) {
+        while self.wait_for_event(BLOCK) != Some(DebounceEvent::Released) {}
+    }
+
+    /// Subscribes the provided callback for the debounced events.
+    ///
+    /// The callback is called from the context of the `esp_timer` task, so it should
+    /// return quickly, as it delays all other `esp_timer` callbacks.
+    pub fn subscribe<F: FnMut(DebounceEvent) + Send + 'static>(&mut self, callback: F) {
+        let _guard = self.state.cs.enter();
+
+        unsafe {
+            *self.state.callback.get() = Some(Box::new(callback));
+        }
+    }
+
+    /// Unsubscribes the callback subscribed with `DebouncedInput::subscribe`, if any.
+    pub fn unsubscribe(&mut self) {
+        let _guard = self.state.cs.enter();
+
+        unsafe {
+            *self.state.callback.get() = None;
+        }
+    }
+
+    // Only calls IRAM-safe code, so it keeps running while the flash cache is disabled with feature `iram-gpio-isr`
+    #[cfg_attr(
+        feature = "iram-gpio-isr",
+        link_section = ".iram1.gpio_debounce_handle_isr"
+    )]
+    unsafe extern "C" fn handle_isr(arg: *mut core::ffi::c_void) {
+        let state = &*(arg as *const State);
+
+        // Restart the confirmation timer on each edge, which also cancels any pending long press check
+        state.phase.store(DEBOUNCING, Ordering::SeqCst);
+
+        esp_timer_stop(state.timer);
+        esp_timer_start_once(state.timer, state.debounce_us);
+    }
+
+    unsafe extern "C" fn handle_timer(arg: *mut core::ffi::c_void) {
+        let state = &*(arg as *const State);
+
+        let _guard = state.cs.enter();
+
+        let pressed = state.is_pressed_now();
+
+        match state.phase.swap(IDLE, Ordering::SeqCst) {
+            DEBOUNCING => {
+                if pressed != state.pressed.load(Ordering::SeqCst) {
+                    state.pressed.store(pressed, Ordering::SeqCst);
+
+                    if pressed {
+                        state.long_pressed.store(false, Ordering::SeqCst);
+                        state.emit(DebounceEvent::Pressed);
+                        state.arm_long_press();
+                    } else {
+                        state.emit(DebounceEvent::Released);
+                    }
+                } else if pressed && !state.long_pressed.load(Ordering::SeqCst) {
+                    // A glitch while pressed; the long press check it cancelled needs to be re-armed
+                    state.arm_long_press();
+                }
+            }
+            LONG_PRESS => {
+                if pressed && state.pressed.load(Ordering::SeqCst) {
+                    state.long_pressed.store(true, Ordering::SeqCst);
+                    state.emit(DebounceEvent::LongPress);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+impl<'d, T: InputPin, MODE: InputMode> Drop for DebouncedInput<'d, T, MODE> {
+    fn drop(&mut self) {
+        self.driver.unsubscribe().unwrap();
+
+        unsafe {
+            esp_timer_stop(self.state.timer);
+            esp!(esp_timer_delete(self.state.timer)).unwrap();
+        }
+
+        // A timer callback might have been dispatched already, without having entered `cs` yet,
+        // so the state is only freed once the `esp_timer` task is done with it
+        match flush_timer_task() {
+            Ok(()) => unsafe { ManuallyDrop::drop(&mut self.state) },
+            Err(err) => {
+                ::log::error!("Failed to wait for the debounce timer, leaking its state: {err}")
+            }
+        }
+    }
+}
+
+/// Waits until the `esp_timer` task is done with all the callbacks dispatched so far
+///
+/// The task runs its callbacks one after the other, so once the callback of a timer
+/// started now has run, any callback dispatched before has returned.
+fn flush_timer_task() -> Result<(), EspError> {
+    unsafe extern "C" fn handle_flush(arg: *mut core::ffi::c_void) {
+        let flushed = &*(arg as *const Queue<()>);
+
+        let _ = flushed.send_back((), BLOCK);
+    }
+
+    let flushed = Queue::<()>::new(1);
+
+    let timer_args = esp_timer_create_args_t {
+        callback: Some(handle_flush),
+        arg: &flushed as *const Queue<()> as *mut core::ffi::c_void,
+        dispatch_method: esp_timer_dispatch_t_ESP_TIMER_TASK,
+        name: b"debounce-flush\0".as_ptr() as *const _,
+        ..Default::default()
+    };
+
+    let mut timer: esp_timer_handle_t = core::ptr::null_mut();
+
+    esp!(unsafe { esp_timer_create(&timer_args, &mut timer) })?;
+
+    let started = esp!(unsafe { esp_timer_start_once(timer, 0) });
+
+    if started.is_ok() {
+        flushed.recv_front(BLOCK);
+    }
+
+    // The one-shot timer has fired, so it is not armed anymore and can be deleted
+    esp!(unsafe { esp_timer_delete(timer) })?;
+
+    started
+}
+
+unsafe impl<'d, T: InputPin, MODE: InputMode> Send for DebouncedInput<'d, T, MODE> {}
@@ -0,0 +1,219 @@
+//! Dedicated GPIO driver
+//!
+//! The dedicated GPIO peripheral maps up to 8 pins (a "bundle") directly to CPU registers,
+//! so that all pins of the bundle can be read or written at once, bypassing the latency of
+//! the GPIO matrix. This makes it possible to bit-bang fast protocols.
+//!
+//! Note that the bundle accessors go through the ESP-IDF `dedic_gpio_bundle_*` functions,
+//! which are placed in IRAM, so each access costs a function call, rather than the single
+//! CPU instruction of the underlying register access.
+//!
+//! Bit N of the values read from / written to a bundle corresponds to the N-th pin with which
+//! the bundle was created.
+
+use core::marker::PhantomData;
+
+#[cfg(all(feature = "alloc", esp_idf_soc_dedic_gpio_has_interrupt))]
+extern crate alloc;
+
+use esp_idf_sys::*;
+
+use crate::peripheral::Peripheral;
+
+use super::IOPin;
+
+/// The maximum number of pins in a bundle
+pub const MAX_PINS: usize = 8;
+
+/// The direction of the pins in a bundle
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Direction {
+    Input,
+    Output,
+    InputOutput,
+}
+
+impl Direction {
+    fn input(&self) -> bool {
+        matches!(self, Self::Input | Self::InputOutput)
+    }
+
+    fn output(&self) -> bool {
+        matches!(self, Self::Output | Self::InputOutput)
+    }
+}
+
+/// A bundle of up to 8 pins, mapped to the dedicated GPIO peripheral
+pub struct Bundle<'d, const N: usize> {
+    handle: dedic_gpio_bundle_handle_t,
+    pins: [i32; N],
+    #[cfg(all(feature = "alloc", esp_idf_soc_dedic_gpio_has_interrupt))]
+    callback: Option<alloc::boxed::Box<alloc::boxed::Box<dyn FnMut(u8) + Send + 'static>>>,
+    _p: PhantomData<&'d mut ()>,
+}
+
+impl<'d, const N: usize> Bundle<'d, N> {
+    /// Creates a new bundle from the provided pins.
+    ///
+    /// The first pin is mapped to bit 0 of the bundle, the second one - to bit 1 and so on.
+    pub fn new<T: IOPin>(
+        pins: [impl Peripheral<P = T> + 'd; N],
+        direction: Direction,
+    ) -> Result<Self, EspError> {
+        if N == 0 || N > MAX_PINS {
+            return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
+        }
+
+        let pins = pins.map(|pin| pin.into_ref().pin());
+
+        let config = dedic_gpio_bundle_config_t {
+            gpio_array: pins.as_ptr(),
+            array_size: N,
+            flags: dedic_gpio_bundle_config_t__bindgen_ty_1 {
+                _bitfield_1: dedic_gpio_bundle_config_t__bindgen_ty_1::new_bitfield_1(
+                    direction.input() as u32,
+                    0,
+                    direction.output() as u32,
+                    0,
+                ),
+                ..Default::default()
+            },
+        };
+
+        let mut handle: dedic_gpio_bundle_handle_t = core::ptr::null_mut();
+
+        esp!(unsafe { dedic_gpio_new_bundle(&config, &mut handle) })?;
+
+        Ok(Self {
+            handle,
+            pins,
+            #[cfg(all(feature = "alloc", esp_idf_soc_dedic_gpio_has_interrupt))]
+            callback: None,
+            _p: PhantomData,
+        })
+    }
+
+    /// Sets the output level of all pins in the bundle, as per the bits of `value`.
+    #[inline(always)]
+    #[link_section = ".iram1.gpio_dedicated_write"]
+    pub fn write(&mut self, value: u8) {
+        self.write_masked(u8::MAX, value)
+    }
+
+    /// Sets the output level of the pins in the bundle selected by `mask`, as per the bits of `value`.
+    #[inline(always)]
+    #[link_section = ".iram1.gpio_dedicated_write_masked"]
+    pub fn write_masked(&mut self, mask: u8, value: u8) {
+        unsafe { dedic_gpio_bundle_write(self.handle, mask as _, value as _) }
+    }
+
+    /// Returns the input level of all pins in the bundle.
+    #[inline(always)]
+    #[link_section = ".iram1.gpio_dedicated_read"]
+    pub fn read(&self) -> u8 {
+        unsafe { dedic_gpio_bundle_read_in(self.handle) as _ }
+    }
+
+    /// Returns the output level set for all pins in the bundle.
+    #[inline(always)]
+    #[link_section = ".iram1.gpio_dedicated_read_output"]
+    pub fn read_output(&self) -> u8 {
+        unsafe { dedic_gpio_bundle_read_out(self.handle) as _ }
+    }
+
+    /// Returns the raw ESP-IDF handle of the bundle
+    pub fn handle(&self) -> dedic_gpio_bundle_handle_t {
+        self.handle
+    }
+
+    /// Subscribes the provided callback for interrupts of type `interrupt_type` on the pins of the bundle
+    /// selected by `mask`. The callback is called with the index (in the bundle) of the pin which triggered
+    /// the interrupt.
+    ///
+    /// # Safety
+    ///
+    /// Care should be taken not to call STD, libc or FreeRTOS APIs (except for a few allowed ones)
+    /// in the callback passed to this function, as it is executed in an ISR context.
+    #[cfg(all(feature = "alloc", esp_idf_soc_dedic_gpio_has_interrupt))]
+    pub unsafe fn subscribe<F: FnMut(u8) + Send + 'static>(
+        &mut self,
+        mask: u8,
+        interrupt_type: super::InterruptType,
+        callback: F,
+    ) -> Result<(), EspError> {
+        self.unsubscribe()?;
+
+        let intr_type = match interrupt_type {
+            super::InterruptType::PosEdge => dedic_gpio_intr_type_t_DEDIC_GPIO_INTR_POS_EDGE,
+            super::InterruptType::NegEdge => dedic_gpio_intr_type_t_DEDIC_GPIO_INTR_NEG_EDGE,
+            super::InterruptType::AnyEdge => dedic_gpio_intr_type_t_DEDIC_GPIO_INTR_BOTH_EDGE,
+            super::InterruptType::LowLevel => dedic_gpio_intr_type_t_DEDIC_GPIO_INTR_LOW_LEVEL,
+            super::InterruptType::HighLevel => dedic_gpio_intr_type_t_DEDIC_GPIO_INTR_HIGH_LEVEL,
+        };
+
+        let mut callback: alloc::boxed::Box<alloc::boxed::Box<dyn FnMut(u8) + Send + 'static>> =
+            alloc::boxed::Box::new(alloc::boxed::Box::new(callback));
+
+        let arg = &mut *callback as *mut alloc::boxed::Box<dyn FnMut(u8) + Send + 'static>;
+
+        esp!(dedic_gpio_bundle_set_interrupt_and_callback(
+            self.handle,
+            mask as _,
+            intr_type,
+            Some(Self::handle_isr),
+            arg as *mut core::ffi::c_void,
+        ))?;
+
+        self.callback = Some(callback);
+
+        Ok(())
+    }
+
+    /// Unsubscribes the callback subscribed with `Bundle::subscribe`, if any.
+    #[cfg(all(feature = "alloc", esp_idf_soc_dedic_gpio_has_interrupt))]
+    pub fn unsubscribe(&mut self) -> Result<(), EspError> {
+        if self.callback.is_some() {
+            esp!(unsafe {
+                dedic_gpio_bundle_set_interrupt_and_callback(
+                    self.handle,
+                    ((1u32 << N) - 1) as _,
+                    dedic_gpio_intr_type_t_DEDIC_GPIO_INTR_NONE,
+                    None,
+                    core::ptr::null_mut(),
+                )
+            })?;
+
+            self.callback = None;
+        }
+
+        Ok(())
+    }
+
+    #[cfg(all(feature = "alloc", esp_idf_soc_dedic_gpio_has_interrupt))]
+    unsafe extern "C" fn handle_isr(
+        _bundle: dedic_gpio_bundle_handle_t,
+        index: u32,
+        arg: *mut core::ffi::c_void,
+    ) -> bool {
+        let callback = (arg as *mut alloc::boxed::Box<dyn FnMut(u8) + Send + 'static>)
+            .as_mut()
+            .unwrap();
+
+        crate::interrupt::with_isr_yield_signal(move || callback(index as _))
+    }
+}
+
+impl<'d, const N: usize> Drop for Bundle<'d, N> {
+    fn drop(&mut self) {
+        #[cfg(all(feature = "alloc", esp_idf_soc_dedic_gpio_has_interrupt))]
+        self.unsubscribe().unwrap();
+
+        esp!(unsafe { dedic_gpio_del_bundle(self.handle) }).unwrap();
+
+        for pin in self.pins {
+            super::gpio_reset_without_pull(pin).unwrap();
+        }
+    }
+}
+
+unsafe impl<'d, const N: usize> Send for Bundle<'d, N> {}
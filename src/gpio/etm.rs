@@ -0,0 +1,145 @@
+//! GPIO Event Task Matrix (ETM) events and tasks
+//!
+//! An `EtmEvent` is triggered by an edge on an input pin, and an `EtmTask` sets, clears
+//! or toggles an output pin. Either can be connected to the events and tasks of other
+//! peripherals with an `etm::Channel`.
+
+use core::marker::PhantomData;
+
+use esp_idf_sys::*;
+
+use crate::etm;
+
+use super::{InputMode, InputPin, OutputMode, OutputPin, PinDriver};
+
+/// The edge of an input pin which triggers an `EtmEvent`
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum EtmEdge {
+    Rising,
+    Falling,
+    Any,
+}
+
+impl From<EtmEdge> for gpio_etm_event_edge_t {
+    fn from(edge: EtmEdge) -> Self {
+        match edge {
+            EtmEdge::Rising => gpio_etm_event_edge_t_GPIO_ETM_EVENT_EDGE_POS,
+            EtmEdge::Falling => gpio_etm_event_edge_t_GPIO_ETM_EVENT_EDGE_NEG,
+            EtmEdge::Any => gpio_etm_event_edge_t_GPIO_ETM_EVENT_EDGE_ANY,
+        }
+    }
+}
+
+/// The action performed on an output pin by an `EtmTask`
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum EtmAction {
+    Set,
+    Clear,
+    Toggle,
+}
+
+impl From<EtmAction> for gpio_etm_task_action_t {
+    fn from(action: EtmAction) -> Self {
+        match action {
+            EtmAction::Set => gpio_etm_task_action_t_GPIO_ETM_TASK_ACTION_SET,
+            EtmAction::Clear => gpio_etm_task_action_t_GPIO_ETM_TASK_ACTION_CLR,
+            EtmAction::Toggle => gpio_etm_task_action_t_GPIO_ETM_TASK_ACTION_TOG,
+        }
+    }
+}
+
+/// An ETM event triggered by an edge on an input pin
+pub struct EtmEvent<'d> {
+    handle: esp_etm_event_handle_t,
+    _p: PhantomData<&'d ()>,
+}
+
+impl<'d> EtmEvent<'d> {
+    /// Creates an event, triggered by `edge` on the pin of the provided driver.
+    pub fn new<T: InputPin, MODE: InputMode>(
+        pin: &'d PinDriver<'_, T, MODE>,
+        edge: EtmEdge,
+    ) -> Result<Self, EspError> {
+        let config = gpio_etm_event_config_t { edge: edge.into() };
+
+        let mut handle: esp_etm_event_handle_t = core::ptr::null_mut();
+
+        esp!(unsafe { gpio_new_etm_event(&config, &mut handle) })?;
+
+        let event = Self {
+            handle,
+            _p: PhantomData,
+        };
+
+        esp!(unsafe { gpio_etm_event_bind_gpio(handle, pin.pin()) })?;
+
+        Ok(event)
+    }
+}
+
+impl etm::Event for EtmEvent<'_> {
+    fn handle(&self) -> esp_etm_event_handle_t {
+        self.handle
+    }
+}
+
+impl Drop for EtmEvent<'_> {
+    fn drop(&mut self) {
+        esp!(unsafe { esp_etm_del_event(self.handle) }).unwrap();
+    }
+}
+
+unsafe impl Send for EtmEvent<'_> {}
+
+/// An ETM task which sets, clears or toggles an output pin
+pub struct EtmTask<'d> {
+    handle: esp_etm_task_handle_t,
+    pin: i32,
+    _p: PhantomData<&'d mut ()>,
+}
+
+impl<'d> EtmTask<'d> {
+    /// Creates a task, performing `action` on the pin of the provided driver.
+    pub fn new<T: OutputPin, MODE: OutputMode>(
+        pin: &'d mut PinDriver<'_, T, MODE>,
+        action: EtmAction,
+    ) -> Result<Self, EspError> {
+        let config = gpio_etm_task_config_t {
+            action: action.into(),
+        };
+
+        let mut handle: esp_etm_task_handle_t = core::ptr::null_mut();
+
+        esp!(unsafe { gpio_new_etm_task(&config, &mut handle) })?;
+
+        let mut task = Self {
+            handle,
+            pin: -1,
+            _p: PhantomData,
+        };
+
+        esp!(unsafe { gpio_etm_task_add_gpio(handle, pin.pin()) })?;
+
+        task.pin = pin.pin();
+
+        Ok(task)
+    }
+}
+
+impl etm::Task for EtmTask<'_> {
+    fn handle(&self) -> esp_etm_task_handle_t {
+        self.handle
+    }
+}
+
+impl Drop for EtmTask<'_> {
+    fn drop(&mut self) {
+        if self.pin >= 0 {
+            esp!(unsafe { gpio_etm_task_rm_gpio(self.handle, self.pin) }).unwrap();
+        }
+
+        esp!(unsafe { esp_etm_del_task(self.handle) }).unwrap();
+    }
+}
+
+unsafe impl Send for EtmTask<'_> {}
@@ -1,23 +1,165 @@
+use core::borrow::BorrowMut;
+use core::ffi::CStr;
 use core::marker::PhantomData;
+use core::num::NonZeroU32;
+use core::sync::atomic::{AtomicBool, AtomicI32, Ordering};
 use core::time::Duration;
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+#[cfg(feature = "alloc")]
+use alloc::vec;
+
 use embedded_hal::i2c::{ErrorKind, NoAcknowledgeSource};
 
+use enumset::EnumSet;
+
 use esp_idf_sys::*;
 
+use crate::cpu::Core;
 use crate::delay::*;
 use crate::gpio::*;
 use crate::interrupt::InterruptType;
 use crate::peripheral::Peripheral;
+use crate::task;
+use crate::task::asynch::Notification;
 use crate::units::*;
 
 pub use embedded_hal::i2c::Operation;
 
-crate::embedded_hal_error!(
-    I2cError,
-    embedded_hal::i2c::Error,
-    embedded_hal::i2c::ErrorKind
-);
+pub mod software;
+
+/// The error type of the I2C drivers
+///
+/// The ESP-IDF I2C driver reports most failures with coarse error codes - e.g. `ESP_FAIL`
+/// for any missing ACK - so the drivers refine them with their knowledge of the failed
+/// transfer where possible: a NACK during a read or a zero-length write can only be the
+/// address not being ACKed, and the software driver always knows which byte was NACKed.
+///
+/// The original `EspError` is available via [Error::cause] or `EspError::from`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Error {
+    /// The address or a data byte was not ACKed (`ESP_FAIL`)
+    NoAcknowledge(NoAcknowledgeSource),
+    /// The arbitration was lost to another master
+    ///
+    /// Only reported by the software driver, as the ESP-IDF driver reports a lost
+    /// arbitration as a timeout.
+    ArbitrationLoss,
+    /// The bus is busy, e.g. held low by another master or a misbehaving slave
+    ///
+    /// Only reported by the software driver, as the ESP-IDF driver uses
+    /// `ESP_ERR_INVALID_STATE` for a driver in the wrong state as well, which is reported
+    /// as [Error::Other].
+    Bus,
+    /// A hardware timeout, a clock stretching timeout or an expired operation timeout
+    /// (`ESP_ERR_TIMEOUT`)
+    ///
+    /// `embedded-hal` has no timeout error kind, so this is reported as `ErrorKind::Other`.
+    Timeout,
+    /// Any other failure, e.g. an invalid argument or an uninstalled driver
+    Other(EspError),
+}
+
+/// The former name of [Error]
+#[deprecated(note = "Use `i2c::Error` instead")]
+pub type I2cError = Error;
+
+impl Error {
+    /// Creates an error of the given `embedded-hal` kind
+    ///
+    /// `cause` is kept only for the kinds without a variant of their own, and for
+    /// `ErrorKind::Other` with an `ESP_ERR_TIMEOUT` cause, which becomes [Error::Timeout].
+    pub fn new(kind: ErrorKind, cause: EspError) -> Self {
+        match kind {
+            ErrorKind::NoAcknowledge(source) => Self::NoAcknowledge(source),
+            ErrorKind::ArbitrationLoss => Self::ArbitrationLoss,
+            ErrorKind::Bus => Self::Bus,
+            _ if cause.code() == ESP_ERR_TIMEOUT => Self::Timeout,
+            _ => Self::Other(cause),
+        }
+    }
+
+    /// Creates an error of kind `ErrorKind::Other`
+    pub fn other(cause: EspError) -> Self {
+        Self::Other(cause)
+    }
+
+    fn from_cause(cause: EspError, nack_source: NoAcknowledgeSource) -> Self {
+        match cause.code() {
+            ESP_FAIL => Self::NoAcknowledge(nack_source),
+            ESP_ERR_TIMEOUT => Self::Timeout,
+            _ => Self::Other(cause),
+        }
+    }
+
+    /// Returns the `EspError` corresponding to this error
+    pub fn cause(&self) -> EspError {
+        match self {
+            Self::NoAcknowledge(_) => EspError::from_infallible::<ESP_FAIL>(),
+            Self::ArbitrationLoss | Self::Bus => {
+                EspError::from_infallible::<ESP_ERR_INVALID_STATE>()
+            }
+            Self::Timeout => EspError::from_infallible::<ESP_ERR_TIMEOUT>(),
+            Self::Other(cause) => *cause,
+        }
+    }
+
+    /// Returns `true` if the operation failed with `ESP_ERR_TIMEOUT`, i.e. because of a
+    /// hardware timeout (including a lost arbitration, which the ESP-IDF driver reports
+    /// the same way) or because the operation timeout expired
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, Self::Timeout)
+    }
+}
+
+impl From<EspError> for Error {
+    fn from(cause: EspError) -> Self {
+        Self::from_cause(cause, NoAcknowledgeSource::Unknown)
+    }
+}
+
+impl From<Error> for EspError {
+    fn from(error: Error) -> Self {
+        error.cause()
+    }
+}
+
+impl embedded_hal::i2c::Error for Error {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Self::NoAcknowledge(source) => ErrorKind::NoAcknowledge(*source),
+            Self::ArbitrationLoss => ErrorKind::ArbitrationLoss,
+            Self::Bus => ErrorKind::Bus,
+            Self::Timeout | Self::Other(_) => ErrorKind::Other,
+        }
+    }
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::NoAcknowledge(source) => write!(f, "I2C error: {source}"),
+            Self::ArbitrationLoss => write!(f, "I2C error: arbitration lost"),
+            Self::Bus => write!(f, "I2C error: bus busy"),
+            Self::Timeout => write!(f, "I2C error: timeout"),
+            Self::Other(cause) => write!(f, "I2C error: {cause}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Other(cause) => Some(cause),
+            _ => None,
+        }
+    }
+}
 
 const APB_TICK_PERIOD_NS: u32 = 1_000_000_000 / 80_000_000;
 #[derive(Copy, Clone, Debug)]
@@ -31,6 +173,23 @@ impl From<Duration> for APBTickType {
     }
 }
 
+/// The address of an I2C slave
+///
+/// `u8` addresses convert to 7-bit ones, so existing code passing `u8` addresses keeps working.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum I2cAddress {
+    /// A 7-bit address (`0x00..=0x7f`)
+    SevenBit(u8),
+    /// A 10-bit address (`0x000..=0x3ff`)
+    TenBit(u16),
+}
+
+impl From<u8> for I2cAddress {
+    fn from(addr: u8) -> Self {
+        Self::SevenBit(addr)
+    }
+}
+
 pub type I2cConfig = config::Config;
 #[cfg(not(esp32c2))]
 pub type I2cSlaveConfig = config::SlaveConfig;
@@ -39,8 +198,14 @@ pub type I2cSlaveConfig = config::SlaveConfig;
 pub mod config {
     use enumset::EnumSet;
 
+    use esp_idf_sys::TickType_t;
+
     use super::APBTickType;
-    use crate::{interrupt::InterruptType, units::*};
+    use crate::{
+        delay::BLOCK,
+        interrupt::{InterruptLevel, InterruptType},
+        units::*,
+    };
 
     /// I2C Master configuration
     #[derive(Debug, Clone)]
@@ -48,7 +213,26 @@ pub mod config {
         pub baudrate: Hertz,
         pub sda_pullup_enabled: bool,
         pub scl_pullup_enabled: bool,
+        /// The hardware timeout for the SCL line being held low (e.g. by a slave stretching
+        /// the clock) within a single bit; ESP-IDF default if `None`
         pub timeout: Option<APBTickType>,
+        /// The timeout in ticks used by the `embedded-hal` trait methods, which take no
+        /// timeout parameter
+        pub operation_timeout: TickType_t,
+        /// Whether to run `I2cDriver::recover_bus` automatically, after several
+        /// consecutive operations timed out
+        pub auto_recover: bool,
+        /// The size in bytes of the buffer preallocated by the driver for its command links
+        /// (see `I2C_LINK_RECOMMENDED_SIZE` in ESP-IDF); operations with more commands than
+        /// fit in the buffer allocate their command link from the heap instead
+        ///
+        /// With the default size, all operations except transactions of more than four
+        /// operations use the preallocated buffer. Set to 0 to always allocate from the heap.
+        /// Ignored without the `alloc` feature.
+        pub command_link_buffer_size: usize,
+        /// The threshold in APB cycles (`0..=7`) of the glitch filter of the SDA and SCL inputs,
+        /// or `None` to disable the filter
+        pub sda_scl_filter: Option<u8>,
         pub intr_flags: EnumSet<InterruptType>,
     }
 
@@ -75,17 +259,77 @@ pub mod config {
             self
         }
 
+        /// Sets the hardware (clock stretching) timeout, e.g. with
+        /// `.timeout(Duration::from_millis(20).into())`
+        ///
+        /// The maximum is chip-specific - about 13ms on the esp32 and about 200ms on the
+        /// esp32s2; on the other chips the timeout is rounded up to a power of two of XTAL
+        /// clock cycles, up to about 50s. `I2cDriver::new` fails with `ESP_ERR_INVALID_ARG`
+        /// for longer timeouts.
         #[must_use]
         pub fn timeout(mut self, timeout: APBTickType) -> Self {
             self.timeout = Some(timeout);
             self
         }
 
+        #[must_use]
+        pub fn operation_timeout(mut self, timeout: TickType_t) -> Self {
+            self.operation_timeout = timeout;
+            self
+        }
+
+        #[must_use]
+        pub fn auto_recover(mut self, auto_recover: bool) -> Self {
+            self.auto_recover = auto_recover;
+            self
+        }
+
+        #[must_use]
+        pub fn command_link_buffer_size(mut self, size: usize) -> Self {
+            self.command_link_buffer_size = size;
+            self
+        }
+
+        /// Enables the glitch filter of the SDA and SCL inputs, ignoring pulses shorter than
+        /// `cycles` APB cycles, or disables it with `None`
+        ///
+        /// Defaults to 7 cycles, like in ESP-IDF. `I2cDriver::new` fails with
+        /// `ESP_ERR_INVALID_ARG` for thresholds above 7.
+        #[must_use]
+        pub fn sda_scl_filter(mut self, cycles: Option<u8>) -> Self {
+            self.sda_scl_filter = cycles;
+            self
+        }
+
         #[must_use]
         pub fn intr_flags(mut self, flags: EnumSet<InterruptType>) -> Self {
             self.intr_flags = flags;
             self
         }
+
+        /// Places the ISR of the driver in IRAM, so that it keeps running while the flash
+        /// cache is disabled (e.g. during flash writes)
+        ///
+        /// Requires `CONFIG_I2C_ISR_IRAM_SAFE=y` in sdkconfig, otherwise `I2cDriver::new`
+        /// fails with `ESP_ERR_NOT_SUPPORTED`.
+        #[must_use]
+        pub fn isr_in_iram(mut self, iram: bool) -> Self {
+            if iram {
+                self.intr_flags |= InterruptType::Iram;
+            } else {
+                self.intr_flags -= InterruptType::Iram;
+            }
+            self
+        }
+
+        /// Sets the priority level of the ISR of the driver; with the default
+        /// `InterruptLevel::LowMed`, ESP-IDF picks any of the levels 1 to 3
+        #[must_use]
+        pub fn interrupt_level(mut self, level: InterruptLevel) -> Self {
+            self.intr_flags -= InterruptType::Level1.levels() | InterruptType::LowMed;
+            self.intr_flags |= InterruptType::from(level);
+            self
+        }
     }
 
     impl Default for Config {
@@ -95,6 +339,10 @@ pub mod config {
                 sda_pullup_enabled: true,
                 scl_pullup_enabled: true,
                 timeout: None,
+                operation_timeout: BLOCK,
+                auto_recover: false,
+                command_link_buffer_size: super::command_link_size(super::segments_commands(4)),
+                sda_scl_filter: Some(7),
                 intr_flags: EnumSet::<InterruptType>::empty(),
             }
         }
@@ -166,8 +414,23 @@ pub trait I2c: Send {
     fn port() -> i2c_port_t;
 }
 
+/// The number of consecutive timeouts after which the bus is recovered, if
+/// `Config::auto_recover` is enabled
+const AUTO_RECOVER_TIMEOUTS: u8 = 3;
+
 pub struct I2cDriver<'d> {
     i2c: u8,
+    timeout: TickType_t,
+    sys_config: i2c_config_t,
+    hw_timeout: Option<::core::ffi::c_int>,
+    filter: Option<u8>,
+    intr_flags: ::core::ffi::c_int,
+    auto_recover: bool,
+    timeouts: u8,
+    /// Whether the ESP-IDF driver is installed, i.e. `false` after a failed `I2cDriver::reset`
+    installed: bool,
+    #[cfg(feature = "alloc")]
+    link_buffer: Box<[u8]>,
     _p: PhantomData<&'d mut ()>,
 }
 
@@ -183,8 +446,19 @@ impl<'d> I2cDriver<'d> {
             return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
         }
 
+        check_intr_flags(config.intr_flags)?;
+
+        let hw_timeout = config.timeout.map(hw_timeout).transpose()?;
+
+        if matches!(config.sda_scl_filter, Some(cycles) if cycles > 7) {
+            return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
+        }
+
         crate::into_ref!(sda, scl);
 
+        #[cfg(esp32c6)]
+        lp::check_config(I2C::port(), sda.pin(), scl.pin(), config.baudrate)?;
+
         let sys_config = i2c_config_t {
             mode: i2c_mode_t_I2C_MODE_MASTER,
             sda_io_num: sda.pin(),
@@ -199,6 +473,8 @@ impl<'d> I2cDriver<'d> {
             ..Default::default()
         };
 
+        let intr_flags = InterruptType::to_native(config.intr_flags) as _;
+
         esp!(unsafe { i2c_param_config(I2C::port(), &sys_config) })?;
 
         esp!(unsafe {
@@ -207,198 +483,503 @@ impl<'d> I2cDriver<'d> {
                 i2c_mode_t_I2C_MODE_MASTER,
                 0, // Not used in master mode
                 0, // Not used in master mode
-                InterruptType::to_native(config.intr_flags) as _,
+                intr_flags,
             )
         })?;
 
-        if let Some(timeout) = config.timeout {
-            esp!(unsafe { i2c_set_timeout(I2C::port(), timeout.0) })?;
-        }
-
-        Ok(I2cDriver {
+        let driver = I2cDriver {
             i2c: I2C::port() as _,
+            timeout: config.operation_timeout,
+            sys_config,
+            hw_timeout,
+            filter: config.sda_scl_filter,
+            intr_flags,
+            auto_recover: config.auto_recover,
+            timeouts: 0,
+            installed: true,
+            #[cfg(feature = "alloc")]
+            link_buffer: vec![0; config.command_link_buffer_size].into_boxed_slice(),
             _p: PhantomData,
-        })
+        };
+
+        driver.apply_settings()?;
+
+        Ok(driver)
     }
 
     pub fn read(
         &mut self,
-        addr: u8,
+        addr: impl Into<I2cAddress>,
         buffer: &mut [u8],
         timeout: TickType_t,
     ) -> Result<(), EspError> {
-        let mut command_link = CommandLink::new()?;
+        let port = self.port();
+
+        let result = cmd_begin(
+            port,
+            &read_command_link(
+                self.command_link(segments_commands(1))?,
+                addr.into(),
+                buffer,
+            )?,
+            timeout,
+        );
+
+        self.track_result(result)
+    }
 
-        command_link.master_start()?;
-        command_link.master_write_byte((addr << 1) | (i2c_rw_t_I2C_MASTER_READ as u8), true)?;
+    pub fn write(
+        &mut self,
+        addr: impl Into<I2cAddress>,
+        bytes: &[u8],
+        timeout: TickType_t,
+    ) -> Result<(), EspError> {
+        let port = self.port();
 
-        if !buffer.is_empty() {
-            command_link.master_read(buffer, AckType::LastNack)?;
-        }
+        let result = cmd_begin(
+            port,
+            &write_command_link(self.command_link(segments_commands(1))?, addr.into(), bytes)?,
+            timeout,
+        );
 
-        command_link.master_stop()?;
+        self.track_result(result)
+    }
 
-        self.cmd_begin(&command_link, timeout)
+    pub fn write_read(
+        &mut self,
+        addr: impl Into<I2cAddress>,
+        bytes: &[u8],
+        buffer: &mut [u8],
+        timeout: TickType_t,
+    ) -> Result<(), EspError> {
+        let port = self.port();
+
+        let result = cmd_begin(
+            port,
+            &write_read_command_link(
+                self.command_link(segments_commands(2))?,
+                addr.into(),
+                bytes,
+                buffer,
+            )?,
+            timeout,
+        );
+
+        self.track_result(result)
     }
 
-    pub fn write(&mut self, addr: u8, bytes: &[u8], timeout: TickType_t) -> Result<(), EspError> {
-        let mut command_link = CommandLink::new()?;
+    /// Writes the concatenation of `bufs` in a single transaction, without copying
+    /// them into an intermediate buffer, e.g. a control byte followed by a framebuffer.
+    pub fn write_vectored(
+        &mut self,
+        addr: impl Into<I2cAddress>,
+        bufs: &[&[u8]],
+        timeout: TickType_t,
+    ) -> Result<(), EspError> {
+        let port = self.port();
 
-        command_link.master_start()?;
-        command_link.master_write_byte((addr << 1) | (i2c_rw_t_I2C_MASTER_WRITE as u8), true)?;
+        let result = {
+            // Falls back to a heap-allocated command link, if there are too many buffers
+            let mut command_link = self.command_link(segments_commands(1) + bufs.len())?;
 
-        if !bytes.is_empty() {
-            command_link.master_write(bytes, true)?;
-        }
+            command_link.master_start()?;
+            command_link.master_address(addr.into(), false)?;
 
-        command_link.master_stop()?;
+            for buf in bufs.iter().filter(|buf| !buf.is_empty()) {
+                command_link.master_write(buf, true)?;
+            }
 
-        self.cmd_begin(&command_link, timeout)
+            command_link.master_stop()?;
+
+            cmd_begin(port, &command_link, timeout)
+        };
+
+        self.track_result(result)
     }
 
-    pub fn write_read(
+    /// Writes the bytes produced by `bytes` in a single transaction.
+    ///
+    /// Each byte is added to the command link separately, so the command link
+    /// is always allocated from the heap.
+    pub fn write_iter(
         &mut self,
-        addr: u8,
-        bytes: &[u8],
-        buffer: &mut [u8],
+        addr: impl Into<I2cAddress>,
+        bytes: impl IntoIterator<Item = u8>,
         timeout: TickType_t,
     ) -> Result<(), EspError> {
+        self.check_installed()?;
+
         let mut command_link = CommandLink::new()?;
 
         command_link.master_start()?;
-        command_link.master_write_byte((addr << 1) | (i2c_rw_t_I2C_MASTER_WRITE as u8), true)?;
+        command_link.master_address(addr.into(), false)?;
 
-        if !bytes.is_empty() {
-            command_link.master_write(bytes, true)?;
+        for byte in bytes {
+            command_link.master_write_byte(byte, true)?;
         }
 
-        command_link.master_start()?;
-        command_link.master_write_byte((addr << 1) | (i2c_rw_t_I2C_MASTER_READ as u8), true)?;
+        command_link.master_stop()?;
 
-        if !buffer.is_empty() {
-            command_link.master_read(buffer, AckType::LastNack)?;
-        }
+        let result = cmd_begin(self.port(), &command_link, timeout);
 
-        command_link.master_stop()?;
+        self.track_result(result)
+    }
 
-        self.cmd_begin(&command_link, timeout)
+    /// Like [I2cDriver::write_read], but with a STOP condition - rather than a repeated START -
+    /// between the write and the read, as required by some devices.
+    ///
+    /// Both parts are still executed as a single command link, so no other
+    /// transaction can be interleaved by another task.
+    pub fn write_then_read_with_stop(
+        &mut self,
+        addr: impl Into<I2cAddress>,
+        bytes: &[u8],
+        buffer: &mut [u8],
+        timeout: TickType_t,
+    ) -> Result<(), EspError> {
+        let port = self.port();
+
+        let result = cmd_begin(
+            port,
+            &write_then_read_with_stop_command_link(
+                self.command_link(segments_commands(2))?,
+                addr.into(),
+                bytes,
+                buffer,
+            )?,
+            timeout,
+        );
+
+        self.track_result(result)
     }
 
+    /// Executes the operations as a single command link, with a START condition at the
+    /// beginning, a repeated START whenever the direction changes, and a STOP at the end.
+    ///
+    /// Consecutive operations of the same direction are merged, i.e. there is no START nor
+    /// address byte between them. Zero-length operations are allowed (e.g. for probing
+    /// devices with a zero-length write), and an empty slice of operations is a no-op.
     pub fn transaction(
         &mut self,
-        address: u8,
+        address: impl Into<I2cAddress>,
         operations: &mut [Operation<'_>],
         timeout: TickType_t,
     ) -> Result<(), EspError> {
-        let mut command_link = CommandLink::new()?;
+        if operations.is_empty() {
+            return Ok(());
+        }
 
-        let last_op_index = operations.len() - 1;
-        let mut prev_was_read = None;
-
-        for (i, operation) in operations.iter_mut().enumerate() {
-            match operation {
-                Operation::Read(buf) => {
-                    if Some(true) != prev_was_read {
-                        command_link.master_start()?;
-                        command_link.master_write_byte(
-                            (address << 1) | (i2c_rw_t_I2C_MASTER_READ as u8),
-                            true,
-                        )?;
-                    }
-                    prev_was_read = Some(true);
+        let port = self.port();
 
-                    if !buf.is_empty() {
-                        let ack = if i == last_op_index {
-                            AckType::LastNack
-                        } else {
-                            AckType::Ack
-                        };
+        let result = cmd_begin(
+            port,
+            &transaction_command_link(
+                self.command_link(segments_commands(operations.len()))?,
+                address.into(),
+                operations,
+            )?,
+            timeout,
+        );
 
-                        command_link.master_read(buf, ack)?;
-                    }
-                }
-                Operation::Write(buf) => {
-                    if Some(false) != prev_was_read {
-                        command_link.master_start()?;
-                        command_link.master_write_byte(
-                            (address << 1) | (i2c_rw_t_I2C_MASTER_WRITE as u8),
-                            true,
-                        )?;
-                    }
-                    prev_was_read = Some(false);
+        self.track_result(result)
+    }
 
-                    if !buf.is_empty() {
-                        command_link.master_write(buf, true)?;
-                    }
+    /// Probes all non-reserved 7-bit addresses (`0x08..=0x77`) with a zero-length write,
+    /// and returns the addresses which were ACKed.
+    ///
+    /// Each address is probed with a short timeout. If the bus is stuck (e.g. SDA or SCL held
+    /// low), the scan stops at the first probe which does not end with an ACK or a NACK, and
+    /// its error is returned.
+    pub fn scan(&mut self) -> Result<heapless::Vec<u8, 128>, EspError> {
+        // At least two ticks, so that a probe started just before a tick does not time out
+        let timeout = TickType::new_millis(20).ticks().max(2);
+
+        let mut found = heapless::Vec::new();
+
+        for addr in 0x08..=0x77 {
+            let port = self.port();
+
+            let result = cmd_begin(
+                port,
+                &write_command_link(self.command_link(segments_commands(1))?, addr.into(), &[])?,
+                timeout,
+            );
+
+            match self.track_result(result) {
+                Ok(()) => found.push(addr).unwrap(),
+                Err(err) if err.code() == ESP_FAIL => (),
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(found)
+    }
+
+    /// Recovers the bus from a slave holding SDA low, e.g. because it was reset in the middle
+    /// of a transfer.
+    ///
+    /// SCL is temporarily switched to GPIO mode and clocked up to 9 times, until the slave
+    /// releases SDA, followed by a STOP condition. The pins are then routed back to the I2C
+    /// peripheral, even if the recovery fails midway.
+    ///
+    /// Fails with `ESP_ERR_INVALID_STATE` if SDA is still held low after the 9 clock pulses.
+    pub fn recover_bus(&mut self) -> Result<(), EspError> {
+        self.check_installed()?;
+
+        let result = self.clock_out_bus();
+
+        // Route the pins back to the I2C peripheral in any case
+        esp!(unsafe { i2c_param_config(self.port(), &self.sys_config) })?;
+
+        self.apply_settings()?;
+
+        esp!(unsafe { i2c_reset_tx_fifo(self.port()) })?;
+        esp!(unsafe { i2c_reset_rx_fifo(self.port()) })?;
+
+        self.timeouts = 0;
+
+        result
+    }
+
+    /// Resets the I2C peripheral, e.g. after an error which left its state machine stuck.
+    ///
+    /// The FIFOs are flushed, and the ESP-IDF driver is re-installed with the
+    /// configuration of this driver.
+    ///
+    /// If re-installing the ESP-IDF driver fails, all other operations fail with
+    /// `ESP_ERR_INVALID_STATE` until a subsequent call to this method succeeds.
+    pub fn reset(&mut self) -> Result<(), EspError> {
+        if self.installed {
+            esp!(unsafe { i2c_reset_tx_fifo(self.port()) })?;
+            esp!(unsafe { i2c_reset_rx_fifo(self.port()) })?;
+
+            esp!(unsafe { i2c_driver_delete(self.port()) })?;
+
+            self.installed = false;
+        }
+
+        esp!(unsafe { i2c_param_config(self.port(), &self.sys_config) })?;
+
+        esp!(unsafe {
+            i2c_driver_install(
+                self.port(),
+                i2c_mode_t_I2C_MODE_MASTER,
+                0, // Not used in master mode
+                0, // Not used in master mode
+                self.intr_flags,
+            )
+        })?;
+
+        self.installed = true;
+
+        self.apply_settings()?;
+
+        self.timeouts = 0;
+
+        Ok(())
+    }
+
+    /// Fails with `ESP_ERR_INVALID_STATE` if the ESP-IDF driver is not installed
+    fn check_installed(&self) -> Result<(), EspError> {
+        if self.installed {
+            Ok(())
+        } else {
+            Err(EspError::from_infallible::<ESP_ERR_INVALID_STATE>())
+        }
+    }
+
+    /// Applies the settings which are not part of the `i2c_config_t` configuration
+    fn apply_settings(&self) -> Result<(), EspError> {
+        if let Some(hw_timeout) = self.hw_timeout {
+            esp!(unsafe { i2c_set_timeout(self.port(), hw_timeout) })?;
+        }
+
+        if let Some(cycles) = self.filter {
+            esp!(unsafe { i2c_filter_enable(self.port(), cycles) })?;
+        } else {
+            esp!(unsafe { i2c_filter_disable(self.port()) })?;
+        }
+
+        Ok(())
+    }
+
+    fn clock_out_bus(&mut self) -> Result<(), EspError> {
+        // About 100KHz
+        const HALF_PERIOD_US: u32 = 5;
+
+        let sda = self.sys_config.sda_io_num;
+        let scl = self.sys_config.scl_io_num;
+
+        unsafe {
+            esp!(gpio_set_level(sda, 1))?;
+            esp!(gpio_set_level(scl, 1))?;
+
+            // Also detaches the pins from the I2C peripheral outputs
+            esp!(gpio_set_direction(
+                sda,
+                gpio_mode_t_GPIO_MODE_INPUT_OUTPUT_OD
+            ))?;
+            esp!(gpio_set_direction(
+                scl,
+                gpio_mode_t_GPIO_MODE_INPUT_OUTPUT_OD
+            ))?;
+
+            Ets::delay_us(HALF_PERIOD_US);
+
+            for _ in 0..9 {
+                if gpio_get_level(sda) != 0 {
+                    break;
                 }
+
+                esp!(gpio_set_level(scl, 0))?;
+                Ets::delay_us(HALF_PERIOD_US);
+                esp!(gpio_set_level(scl, 1))?;
+                Ets::delay_us(HALF_PERIOD_US);
+            }
+
+            let released = gpio_get_level(sda) != 0;
+
+            // STOP condition: SDA going high while SCL is high
+            esp!(gpio_set_level(scl, 0))?;
+            Ets::delay_us(HALF_PERIOD_US);
+            esp!(gpio_set_level(sda, 0))?;
+            Ets::delay_us(HALF_PERIOD_US);
+            esp!(gpio_set_level(scl, 1))?;
+            Ets::delay_us(HALF_PERIOD_US);
+            esp!(gpio_set_level(sda, 1))?;
+            Ets::delay_us(HALF_PERIOD_US);
+
+            if released {
+                Ok(())
+            } else {
+                Err(EspError::from_infallible::<ESP_ERR_INVALID_STATE>())
             }
         }
+    }
 
-        command_link.master_stop()?;
+    /// Creates a command link for up to `commands` commands, placed in the preallocated
+    /// buffer of the driver if it is large enough
+    ///
+    /// Fails with `ESP_ERR_INVALID_STATE` if the ESP-IDF driver is not installed.
+    fn command_link<'b>(&'b mut self, commands: usize) -> Result<CommandLink<'b>, EspError> {
+        self.check_installed()?;
+
+        #[cfg(feature = "alloc")]
+        if command_link_size(commands) <= self.link_buffer.len() {
+            return unsafe { CommandLink::new_static(&mut self.link_buffer) };
+        }
 
-        self.cmd_begin(&command_link, timeout)
+        #[cfg(not(feature = "alloc"))]
+        let _ = commands;
+
+        CommandLink::new()
     }
 
-    fn cmd_begin(
-        &mut self,
-        command_link: &CommandLink,
-        timeout: TickType_t,
-    ) -> Result<(), EspError> {
-        esp!(unsafe { i2c_master_cmd_begin(self.port(), command_link.0, timeout) })
+    /// Counts the consecutive timeouts, and recovers the bus after too many of them,
+    /// if `Config::auto_recover` is enabled
+    fn track_result(&mut self, result: Result<(), EspError>) -> Result<(), EspError> {
+        match result {
+            Err(err) if err.code() == ESP_ERR_TIMEOUT => {
+                self.timeouts = self.timeouts.saturating_add(1);
+
+                if self.auto_recover && self.timeouts >= AUTO_RECOVER_TIMEOUTS {
+                    // The timeout is what gets reported, recovered or not
+                    let _ = self.recover_bus();
+                }
+            }
+            _ => self.timeouts = 0,
+        }
+
+        result
     }
 
     pub fn port(&self) -> i2c_port_t {
         self.i2c as _
     }
+
+    /// The timeout in ticks used by the `embedded-hal` trait methods
+    pub fn operation_timeout(&self) -> TickType_t {
+        self.timeout
+    }
+
+    pub fn set_operation_timeout(&mut self, timeout: TickType_t) {
+        self.timeout = timeout;
+    }
 }
 
 impl<'d> Drop for I2cDriver<'d> {
     fn drop(&mut self) {
-        esp!(unsafe { i2c_driver_delete(self.port()) }).unwrap();
+        if self.installed {
+            if let Err(e) = esp!(unsafe { i2c_driver_delete(self.port()) }) {
+                ::log::error!("Failed to delete the I2C driver: {e}");
+            }
+        }
     }
 }
 
 unsafe impl<'d> Send for I2cDriver<'d> {}
 
 impl<'d> embedded_hal_0_2::blocking::i2c::Read for I2cDriver<'d> {
-    type Error = I2cError;
+    type Error = Error;
 
     fn read(&mut self, addr: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
-        I2cDriver::read(self, addr, buffer, BLOCK).map_err(to_i2c_err)
+        let timeout = self.timeout;
+        I2cDriver::read(self, addr, buffer, timeout).map_err(|e| to_i2c_transfer_err(e, false))
     }
 }
 
 impl<'d> embedded_hal_0_2::blocking::i2c::Write for I2cDriver<'d> {
-    type Error = I2cError;
+    type Error = Error;
 
     fn write(&mut self, addr: u8, bytes: &[u8]) -> Result<(), Self::Error> {
-        I2cDriver::write(self, addr, bytes, BLOCK).map_err(to_i2c_err)
+        let timeout = self.timeout;
+        I2cDriver::write(self, addr, bytes, timeout)
+            .map_err(|e| to_i2c_transfer_err(e, !bytes.is_empty()))
     }
 }
 
 impl<'d> embedded_hal_0_2::blocking::i2c::WriteRead for I2cDriver<'d> {
-    type Error = I2cError;
+    type Error = Error;
 
     fn write_read(&mut self, addr: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), Self::Error> {
-        I2cDriver::write_read(self, addr, bytes, buffer, BLOCK).map_err(to_i2c_err)
+        let timeout = self.timeout;
+        I2cDriver::write_read(self, addr, bytes, buffer, timeout)
+            .map_err(|e| to_i2c_transfer_err(e, !bytes.is_empty()))
+    }
+}
+
+impl<'d> embedded_hal_0_2::blocking::i2c::WriteIter for I2cDriver<'d> {
+    type Error = Error;
+
+    fn write<B>(&mut self, addr: u8, bytes: B) -> Result<(), Self::Error>
+    where
+        B: IntoIterator<Item = u8>,
+    {
+        let timeout = self.timeout;
+        I2cDriver::write_iter(self, addr, bytes, timeout).map_err(to_i2c_err)
     }
 }
 
 impl<'d> embedded_hal::i2c::ErrorType for I2cDriver<'d> {
-    type Error = I2cError;
+    type Error = Error;
 }
 
 impl<'d> embedded_hal::i2c::I2c<embedded_hal::i2c::SevenBitAddress> for I2cDriver<'d> {
     fn read(&mut self, addr: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
-        I2cDriver::read(self, addr, buffer, BLOCK).map_err(to_i2c_err)
+        let timeout = self.timeout;
+        I2cDriver::read(self, addr, buffer, timeout).map_err(|e| to_i2c_transfer_err(e, false))
     }
 
     fn write(&mut self, addr: u8, bytes: &[u8]) -> Result<(), Self::Error> {
-        I2cDriver::write(self, addr, bytes, BLOCK).map_err(to_i2c_err)
+        let timeout = self.timeout;
+        I2cDriver::write(self, addr, bytes, timeout)
+            .map_err(|e| to_i2c_transfer_err(e, !bytes.is_empty()))
     }
 
     fn write_read(&mut self, addr: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), Self::Error> {
-        I2cDriver::write_read(self, addr, bytes, buffer, BLOCK).map_err(to_i2c_err)
+        let timeout = self.timeout;
+        I2cDriver::write_read(self, addr, bytes, buffer, timeout)
+            .map_err(|e| to_i2c_transfer_err(e, !bytes.is_empty()))
     }
 
     fn transaction(
@@ -406,18 +987,546 @@ impl<'d> embedded_hal::i2c::I2c<embedded_hal::i2c::SevenBitAddress> for I2cDrive
         address: u8,
         operations: &mut [embedded_hal::i2c::Operation<'_>],
     ) -> Result<(), Self::Error> {
-        I2cDriver::transaction(self, address, operations, BLOCK).map_err(to_i2c_err)
+        let writes = writes_data(operations);
+        let timeout = self.timeout;
+        I2cDriver::transaction(self, address, operations, timeout)
+            .map_err(|e| to_i2c_transfer_err(e, writes))
     }
 }
 
-fn to_i2c_err(err: EspError) -> I2cError {
-    if err.code() == ESP_FAIL {
-        I2cError::new(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Unknown), err)
+impl<'d> embedded_hal::i2c::I2c<embedded_hal::i2c::TenBitAddress> for I2cDriver<'d> {
+    fn read(&mut self, addr: u16, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        let timeout = self.timeout;
+        I2cDriver::read(self, I2cAddress::TenBit(addr), buffer, timeout)
+            .map_err(|e| to_i2c_transfer_err(e, false))
+    }
+
+    fn write(&mut self, addr: u16, bytes: &[u8]) -> Result<(), Self::Error> {
+        let timeout = self.timeout;
+        I2cDriver::write(self, I2cAddress::TenBit(addr), bytes, timeout)
+            .map_err(|e| to_i2c_transfer_err(e, !bytes.is_empty()))
+    }
+
+    fn write_read(
+        &mut self,
+        addr: u16,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        let timeout = self.timeout;
+        I2cDriver::write_read(self, I2cAddress::TenBit(addr), bytes, buffer, timeout)
+            .map_err(|e| to_i2c_transfer_err(e, !bytes.is_empty()))
+    }
+
+    fn transaction(
+        &mut self,
+        address: u16,
+        operations: &mut [embedded_hal::i2c::Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        let writes = writes_data(operations);
+        let timeout = self.timeout;
+        I2cDriver::transaction(self, I2cAddress::TenBit(address), operations, timeout)
+            .map_err(|e| to_i2c_transfer_err(e, writes))
+    }
+}
+
+fn to_i2c_err(err: EspError) -> Error {
+    Error::from(err)
+}
+
+/// Converts the error of a transfer, which is attributed to the address if no data was written,
+/// as the master itself ACKs the data it reads
+fn to_i2c_transfer_err(err: EspError, writes_data: bool) -> Error {
+    Error::from_cause(
+        err,
+        if writes_data {
+            NoAcknowledgeSource::Unknown
+        } else {
+            NoAcknowledgeSource::Address
+        },
+    )
+}
+
+fn writes_data(operations: &[Operation<'_>]) -> bool {
+    operations
+        .iter()
+        .any(|operation| matches!(operation, Operation::Write(buf) if !buf.is_empty()))
+}
+
+/// Checks the interrupt flags, which the ESP-IDF driver otherwise accepts silently
+fn check_intr_flags(flags: EnumSet<InterruptType>) -> Result<(), EspError> {
+    if flags.contains(InterruptType::Iram) && !cfg!(esp_idf_i2c_isr_iram_safe) {
+        ::log::error!("Placing the I2C ISR in IRAM requires CONFIG_I2C_ISR_IRAM_SAFE=y");
+
+        return Err(EspError::from_infallible::<ESP_ERR_NOT_SUPPORTED>());
+    }
+
+    // Only the levels handled by C ISRs
+    let levels = flags
+        & (InterruptType::Level4
+            | InterruptType::Level5
+            | InterruptType::Level6
+            | InterruptType::Nmi
+            | InterruptType::High);
+
+    if !levels.is_empty() {
+        return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
+    }
+
+    Ok(())
+}
+
+/// Converts the timeout to the value expected by `i2c_set_timeout`
+#[cfg(any(esp32, esp32s2))]
+fn hw_timeout(timeout: APBTickType) -> Result<::core::ffi::c_int, EspError> {
+    #[cfg(esp32)]
+    const MAX: ::core::ffi::c_int = 0xfffff;
+    #[cfg(esp32s2)]
+    const MAX: ::core::ffi::c_int = 0xffffff;
+
+    if timeout.0 > MAX {
+        Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>())
     } else {
-        I2cError::other(err)
+        Ok(timeout.0)
     }
 }
 
+/// Converts the timeout to the value expected by `i2c_set_timeout`, which on the newer chips
+/// is the exponent of a power of two of cycles of the I2C source clock (XTAL)
+#[cfg(not(any(esp32, esp32s2)))]
+fn hw_timeout(timeout: APBTickType) -> Result<::core::ffi::c_int, EspError> {
+    const MAX: u32 = 0x1f;
+
+    let sclk_hz = xtal_freq_hz()? as u64;
+
+    // `timeout` is in APB (80MHz) clock cycles
+    let sclk_cycles = (timeout.0.max(1) as u64 * sclk_hz).div_ceil(80_000_000);
+    let exponent = sclk_cycles.next_power_of_two().trailing_zeros();
+
+    if exponent > MAX {
+        Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>())
+    } else {
+        Ok(exponent as _)
+    }
+}
+
+/// Returns the frequency of the XTAL clock, which the I2C peripherals of the newer chips are
+/// clocked from
+#[cfg(all(
+    not(any(esp32, esp32s2)),
+    not(any(esp_idf_version_major = "4", esp_idf_version = "5.0"))
+))]
+fn xtal_freq_hz() -> Result<u32, EspError> {
+    let mut hz = 0;
+
+    esp!(unsafe {
+        esp_clk_tree_src_get_freq_hz(
+            soc_module_clk_t_SOC_MOD_CLK_XTAL,
+            esp_clk_tree_src_freq_precision_t_ESP_CLK_TREE_SRC_FREQ_PRECISION_CACHED,
+            &mut hz,
+        )
+    })?;
+
+    Ok(hz)
+}
+
+/// Returns the frequency of the XTAL clock, which the I2C peripherals of the newer chips are
+/// clocked from
+#[cfg(all(
+    not(any(esp32, esp32s2)),
+    any(esp_idf_version_major = "4", esp_idf_version = "5.0")
+))]
+fn xtal_freq_hz() -> Result<u32, EspError> {
+    // `rtc_xtal_freq_t` is the frequency in MHz
+    Ok(unsafe { rtc_clk_xtal_freq_get() } as u32 * 1_000_000)
+}
+
+/// Async I2C master driver
+///
+/// `i2c_master_cmd_begin` blocks the calling task until the whole command link is executed,
+/// so `AsyncI2cDriver` runs it in a dedicated worker task, and the async operations wait for
+/// the worker to signal the completion instead. Errors - including `ESP_ERR_TIMEOUT` - are the
+/// same as the ones returned by the blocking API of [I2cDriver].
+///
+/// Note that a command link - once started - cannot be aborted. If a future returned by one of
+/// the async operations is dropped before completion, the drop blocks until the worker task
+/// has finished executing the command link (i.e. for up to the operation timeout), because
+/// the command link refers to the buffers of the operation.
+pub struct AsyncI2cDriver<'d, T>
+where
+    T: BorrowMut<I2cDriver<'d>>,
+{
+    driver: T,
+    task: TaskHandle_t,
+    _data: PhantomData<&'d ()>,
+}
+
+impl<'d> AsyncI2cDriver<'d, I2cDriver<'d>> {
+    pub fn new<I2C: I2c>(
+        i2c: impl Peripheral<P = I2C> + 'd,
+        sda: impl Peripheral<P = impl InputPin + OutputPin> + 'd,
+        scl: impl Peripheral<P = impl InputPin + OutputPin> + 'd,
+        config: &config::Config,
+    ) -> Result<Self, EspError> {
+        Self::wrap(I2cDriver::new(i2c, sda, scl, config)?)
+    }
+}
+
+impl<'d, T> AsyncI2cDriver<'d, T>
+where
+    T: BorrowMut<I2cDriver<'d>>,
+{
+    pub fn wrap(driver: T) -> Result<Self, EspError> {
+        Self::wrap_custom(driver, None, None)
+    }
+
+    /// Wraps the driver, running the worker task with the provided priority (default: 6)
+    /// and core affinity (default: none)
+    pub fn wrap_custom(
+        driver: T,
+        priority: Option<u8>,
+        pin_to_core: Option<Core>,
+    ) -> Result<Self, EspError> {
+        let port = driver.borrow().port() as usize;
+
+        BUSY[port].store(false, Ordering::SeqCst);
+        DONE_NOTIFS[port].reset();
+
+        let task = unsafe {
+            task::create(
+                process_commands,
+                CStr::from_bytes_until_nul(b"I2C - Worker task\0").unwrap(),
+                2048,
+                port as _,
+                priority.unwrap_or(6),
+                pin_to_core,
+            )
+        }?;
+
+        Ok(Self {
+            driver,
+            task,
+            _data: PhantomData,
+        })
+    }
+
+    pub fn driver(&self) -> &I2cDriver<'d> {
+        self.driver.borrow()
+    }
+
+    pub fn driver_mut(&mut self) -> &mut I2cDriver<'d> {
+        self.driver.borrow_mut()
+    }
+
+    pub async fn read(
+        &mut self,
+        addr: impl Into<I2cAddress>,
+        buffer: &mut [u8],
+        timeout: TickType_t,
+    ) -> Result<(), EspError> {
+        let port = self.driver().port();
+
+        let result = cmd_begin_async(
+            port,
+            self.task,
+            &read_command_link(
+                self.driver_mut().command_link(segments_commands(1))?,
+                addr.into(),
+                buffer,
+            )?,
+            timeout,
+        )
+        .await;
+
+        self.driver_mut().track_result(result)
+    }
+
+    pub async fn write(
+        &mut self,
+        addr: impl Into<I2cAddress>,
+        bytes: &[u8],
+        timeout: TickType_t,
+    ) -> Result<(), EspError> {
+        let port = self.driver().port();
+
+        let result = cmd_begin_async(
+            port,
+            self.task,
+            &write_command_link(
+                self.driver_mut().command_link(segments_commands(1))?,
+                addr.into(),
+                bytes,
+            )?,
+            timeout,
+        )
+        .await;
+
+        self.driver_mut().track_result(result)
+    }
+
+    pub async fn write_read(
+        &mut self,
+        addr: impl Into<I2cAddress>,
+        bytes: &[u8],
+        buffer: &mut [u8],
+        timeout: TickType_t,
+    ) -> Result<(), EspError> {
+        let port = self.driver().port();
+
+        let result = cmd_begin_async(
+            port,
+            self.task,
+            &write_read_command_link(
+                self.driver_mut().command_link(segments_commands(2))?,
+                addr.into(),
+                bytes,
+                buffer,
+            )?,
+            timeout,
+        )
+        .await;
+
+        self.driver_mut().track_result(result)
+    }
+
+    pub async fn write_then_read_with_stop(
+        &mut self,
+        addr: impl Into<I2cAddress>,
+        bytes: &[u8],
+        buffer: &mut [u8],
+        timeout: TickType_t,
+    ) -> Result<(), EspError> {
+        let port = self.driver().port();
+
+        let result = cmd_begin_async(
+            port,
+            self.task,
+            &write_then_read_with_stop_command_link(
+                self.driver_mut().command_link(segments_commands(2))?,
+                addr.into(),
+                bytes,
+                buffer,
+            )?,
+            timeout,
+        )
+        .await;
+
+        self.driver_mut().track_result(result)
+    }
+
+    pub async fn transaction(
+        &mut self,
+        address: impl Into<I2cAddress>,
+        operations: &mut [Operation<'_>],
+        timeout: TickType_t,
+    ) -> Result<(), EspError> {
+        if operations.is_empty() {
+            return Ok(());
+        }
+
+        let port = self.driver().port();
+
+        let result = cmd_begin_async(
+            port,
+            self.task,
+            &transaction_command_link(
+                self.driver_mut()
+                    .command_link(segments_commands(operations.len()))?,
+                address.into(),
+                operations,
+            )?,
+            timeout,
+        )
+        .await;
+
+        self.driver_mut().track_result(result)
+    }
+}
+
+/// Executes the command link in the worker task of the async driver
+async fn cmd_begin_async(
+    port: i2c_port_t,
+    task: TaskHandle_t,
+    command_link: &CommandLink<'_>,
+    timeout: TickType_t,
+) -> Result<(), EspError> {
+    let port = port as usize;
+
+    unsafe {
+        COMMANDS[port] = command_link.0;
+        TIMEOUTS[port] = timeout;
+    }
+
+    DONE_NOTIFS[port].reset();
+    BUSY[port].store(true, Ordering::SeqCst);
+
+    // Waits for the worker task on drop, in case the future is dropped before completion
+    let _pending = PendingCommand(port);
+
+    unsafe {
+        task::notify(task, NonZeroU32::new(1).unwrap());
+    }
+
+    while BUSY[port].load(Ordering::SeqCst) {
+        DONE_NOTIFS[port].wait().await;
+    }
+
+    esp!(RESULTS[port].load(Ordering::SeqCst))
+}
+
+unsafe impl<'d, T> Send for AsyncI2cDriver<'d, T> where T: BorrowMut<I2cDriver<'d>> + Send {}
+
+impl<'d, T> Drop for AsyncI2cDriver<'d, T>
+where
+    T: BorrowMut<I2cDriver<'d>>,
+{
+    fn drop(&mut self) {
+        let port = self.driver.borrow().port() as usize;
+
+        wait_not_busy(port);
+
+        unsafe {
+            task::destroy(self.task);
+        }
+
+        DONE_NOTIFS[port].reset();
+    }
+}
+
+impl<'d, T> embedded_hal::i2c::ErrorType for AsyncI2cDriver<'d, T>
+where
+    T: BorrowMut<I2cDriver<'d>>,
+{
+    type Error = Error;
+}
+
+impl<'d, T> embedded_hal_async::i2c::I2c<embedded_hal::i2c::SevenBitAddress>
+    for AsyncI2cDriver<'d, T>
+where
+    T: BorrowMut<I2cDriver<'d>>,
+{
+    async fn read(&mut self, addr: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        let timeout = self.driver().timeout;
+        AsyncI2cDriver::read(self, addr, buffer, timeout)
+            .await
+            .map_err(|e| to_i2c_transfer_err(e, false))
+    }
+
+    async fn write(&mut self, addr: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        let timeout = self.driver().timeout;
+        AsyncI2cDriver::write(self, addr, bytes, timeout)
+            .await
+            .map_err(|e| to_i2c_transfer_err(e, !bytes.is_empty()))
+    }
+
+    async fn write_read(
+        &mut self,
+        addr: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        let timeout = self.driver().timeout;
+        AsyncI2cDriver::write_read(self, addr, bytes, buffer, timeout)
+            .await
+            .map_err(|e| to_i2c_transfer_err(e, !bytes.is_empty()))
+    }
+
+    async fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [embedded_hal::i2c::Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        let writes = writes_data(operations);
+        let timeout = self.driver().timeout;
+        AsyncI2cDriver::transaction(self, address, operations, timeout)
+            .await
+            .map_err(|e| to_i2c_transfer_err(e, writes))
+    }
+}
+
+impl<'d, T> embedded_hal_async::i2c::I2c<embedded_hal::i2c::TenBitAddress> for AsyncI2cDriver<'d, T>
+where
+    T: BorrowMut<I2cDriver<'d>>,
+{
+    async fn read(&mut self, addr: u16, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        let timeout = self.driver().timeout;
+        AsyncI2cDriver::read(self, I2cAddress::TenBit(addr), buffer, timeout)
+            .await
+            .map_err(|e| to_i2c_transfer_err(e, false))
+    }
+
+    async fn write(&mut self, addr: u16, bytes: &[u8]) -> Result<(), Self::Error> {
+        let timeout = self.driver().timeout;
+        AsyncI2cDriver::write(self, I2cAddress::TenBit(addr), bytes, timeout)
+            .await
+            .map_err(|e| to_i2c_transfer_err(e, !bytes.is_empty()))
+    }
+
+    async fn write_read(
+        &mut self,
+        addr: u16,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        let timeout = self.driver().timeout;
+        AsyncI2cDriver::write_read(self, I2cAddress::TenBit(addr), bytes, buffer, timeout)
+            .await
+            .map_err(|e| to_i2c_transfer_err(e, !bytes.is_empty()))
+    }
+
+    async fn transaction(
+        &mut self,
+        address: u16,
+        operations: &mut [embedded_hal::i2c::Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        let writes = writes_data(operations);
+        let timeout = self.driver().timeout;
+        AsyncI2cDriver::transaction(self, I2cAddress::TenBit(address), operations, timeout)
+            .await
+            .map_err(|e| to_i2c_transfer_err(e, writes))
+    }
+}
+
+struct PendingCommand(usize);
+
+impl Drop for PendingCommand {
+    fn drop(&mut self) {
+        wait_not_busy(self.0);
+    }
+}
+
+fn wait_not_busy(port: usize) {
+    while BUSY[port].load(Ordering::SeqCst) {
+        FreeRtos::delay_ms(1);
+    }
+}
+
+extern "C" fn process_commands(arg: *mut core::ffi::c_void) {
+    let port: usize = arg as _;
+
+    loop {
+        if task::wait_notification(BLOCK).is_some() && BUSY[port].load(Ordering::SeqCst) {
+            let res = unsafe { i2c_master_cmd_begin(port as _, COMMANDS[port], TIMEOUTS[port]) };
+
+            RESULTS[port].store(res, Ordering::SeqCst);
+            BUSY[port].store(false, Ordering::SeqCst);
+
+            DONE_NOTIFS[port].notify_lsb();
+        }
+    }
+}
+
+/// I2C slave driver
+///
+/// The data written by the master is stored in the RX buffer of the driver, and the data read
+/// by the master is taken from its TX buffer, so both buffer lengths in [config::SlaveConfig]
+/// should be non-zero for the respective direction to work.
+///
+/// [I2cSlaveDriver::read] blocks the calling task until the master has written data (or until
+/// the timeout expires), so request-response protocols do not need to poll the driver.
+///
+/// A callback or notification when the master addresses the slave is not supported, as the
+/// ESP-IDF (legacy) slave driver does not report address matches.
 #[cfg(not(esp32c2))]
 pub struct I2cSlaveDriver<'d> {
     i2c: u8,
@@ -436,6 +1545,13 @@ impl<'d> I2cSlaveDriver<'d> {
         slave_addr: u8,
         config: &config::SlaveConfig,
     ) -> Result<Self, EspError> {
+        // Only 7-bit addresses, outside of the reserved 0b0000xxx and 0b1111xxx ranges
+        if !(0x08..0x78).contains(&slave_addr) {
+            return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
+        }
+
+        check_intr_flags(config.intr_flags)?;
+
         crate::into_ref!(sda, scl);
 
         #[cfg(not(esp_idf_version = "4.3"))]
@@ -461,9 +1577,9 @@ impl<'d> I2cSlaveDriver<'d> {
         )]
         let sys_config = i2c_config_t {
             mode: i2c_mode_t_I2C_MODE_SLAVE,
-            sda_io_num: pins.sda.pin(),
+            sda_io_num: sda.pin(),
             sda_pullup_en: config.sda_pullup_enabled,
-            scl_io_num: pins.scl.pin(),
+            scl_io_num: scl.pin(),
             scl_pullup_en: config.scl_pullup_enabled,
             __bindgen_anon_1: i2c_config_t__bindgen_ty_1 {
                 slave: i2c_config_t__bindgen_ty_1__bindgen_ty_2 {
@@ -492,28 +1608,30 @@ impl<'d> I2cSlaveDriver<'d> {
         })
     }
 
+    /// Reads the data written by the master into `buffer`, waiting for up to `timeout` ticks
+    /// for it to arrive.
+    ///
+    /// Returns the number of bytes read, or `ESP_ERR_TIMEOUT` if the master has not written
+    /// anything in the meantime. Fails with `ESP_FAIL` if ESP-IDF rejected the read.
     pub fn read(&mut self, buffer: &mut [u8], timeout: TickType_t) -> Result<usize, EspError> {
         let n = unsafe {
             i2c_slave_read_buffer(self.port(), buffer.as_mut_ptr(), buffer.len(), timeout)
         };
 
-        if n > 0 {
-            Ok(n as usize)
-        } else {
-            Err(EspError::from_infallible::<ESP_ERR_TIMEOUT>())
-        }
+        slave_result(n)
     }
 
+    /// Puts `bytes` into the TX buffer, to be sent on the next reads by the master, waiting for
+    /// up to `timeout` ticks for enough space in the buffer.
+    ///
+    /// Returns the number of bytes put into the buffer, or `ESP_ERR_TIMEOUT` if there was no
+    /// space in the buffer in the meantime. Fails with `ESP_FAIL` if ESP-IDF rejected the write.
     pub fn write(&mut self, bytes: &[u8], timeout: TickType_t) -> Result<usize, EspError> {
         let n = unsafe {
             i2c_slave_write_buffer(self.port(), bytes.as_ptr(), bytes.len() as i32, timeout)
         };
 
-        if n > 0 {
-            Ok(n as usize)
-        } else {
-            Err(EspError::from_infallible::<ESP_ERR_TIMEOUT>())
-        }
+        slave_result(n)
     }
 
     pub fn port(&self) -> i2c_port_t {
@@ -521,6 +1639,17 @@ impl<'d> I2cSlaveDriver<'d> {
     }
 }
 
+/// Converts the result of `i2c_slave_read_buffer` / `i2c_slave_write_buffer` - the number of
+/// bytes transferred, `0` on timeout, or `ESP_FAIL` on invalid arguments
+#[cfg(not(esp32c2))]
+fn slave_result(n: i32) -> Result<usize, EspError> {
+    match n {
+        n if n > 0 => Ok(n as usize),
+        0 => Err(EspError::from_infallible::<ESP_ERR_TIMEOUT>()),
+        n => Err(EspError::from(n).unwrap_or(EspError::from_infallible::<ESP_FAIL>())),
+    }
+}
+
 #[cfg(not(esp32c2))]
 impl<'d> Drop for I2cSlaveDriver<'d> {
     fn drop(&mut self) {
@@ -528,6 +1657,153 @@ impl<'d> Drop for I2cSlaveDriver<'d> {
     }
 }
 
+fn cmd_begin(
+    port: i2c_port_t,
+    command_link: &CommandLink,
+    timeout: TickType_t,
+) -> Result<(), EspError> {
+    esp!(unsafe { i2c_master_cmd_begin(port, command_link.0, timeout) })
+}
+
+fn read_command_link<'a>(
+    mut command_link: CommandLink<'a>,
+    addr: I2cAddress,
+    buffer: &'a mut [u8],
+) -> Result<CommandLink<'a>, EspError> {
+    command_link.master_start()?;
+    command_link.master_address(addr, true)?;
+
+    if !buffer.is_empty() {
+        command_link.master_read(buffer, AckType::LastNack)?;
+    }
+
+    command_link.master_stop()?;
+
+    Ok(command_link)
+}
+
+fn write_command_link<'a>(
+    mut command_link: CommandLink<'a>,
+    addr: I2cAddress,
+    bytes: &'a [u8],
+) -> Result<CommandLink<'a>, EspError> {
+    command_link.master_start()?;
+    command_link.master_address(addr, false)?;
+
+    if !bytes.is_empty() {
+        command_link.master_write(bytes, true)?;
+    }
+
+    command_link.master_stop()?;
+
+    Ok(command_link)
+}
+
+fn write_read_command_link<'a>(
+    mut command_link: CommandLink<'a>,
+    addr: I2cAddress,
+    bytes: &'a [u8],
+    buffer: &'a mut [u8],
+) -> Result<CommandLink<'a>, EspError> {
+    command_link.master_start()?;
+    command_link.master_address(addr, false)?;
+
+    if !bytes.is_empty() {
+        command_link.master_write(bytes, true)?;
+    }
+
+    command_link.master_start()?;
+    command_link.master_address(addr, true)?;
+
+    if !buffer.is_empty() {
+        command_link.master_read(buffer, AckType::LastNack)?;
+    }
+
+    command_link.master_stop()?;
+
+    Ok(command_link)
+}
+
+fn write_then_read_with_stop_command_link<'a>(
+    mut command_link: CommandLink<'a>,
+    addr: I2cAddress,
+    bytes: &'a [u8],
+    buffer: &'a mut [u8],
+) -> Result<CommandLink<'a>, EspError> {
+    command_link.master_start()?;
+    command_link.master_address(addr, false)?;
+
+    if !bytes.is_empty() {
+        command_link.master_write(bytes, true)?;
+    }
+
+    command_link.master_stop()?;
+
+    command_link.master_start()?;
+    command_link.master_address(addr, true)?;
+
+    if !buffer.is_empty() {
+        command_link.master_read(buffer, AckType::LastNack)?;
+    }
+
+    command_link.master_stop()?;
+
+    Ok(command_link)
+}
+
+fn transaction_command_link<'a>(
+    mut command_link: CommandLink<'a>,
+    address: I2cAddress,
+    operations: &'a mut [Operation<'_>],
+) -> Result<CommandLink<'a>, EspError> {
+    let mut prev_was_read = None;
+
+    // The last byte of the last non-empty read before a direction change (or the end) has
+    // to be NACKed, so each read buffer is only added once it is known what follows it
+    let mut pending_read: Option<&'a mut [u8]> = None;
+
+    for operation in operations.iter_mut() {
+        match operation {
+            Operation::Read(buf) => {
+                if Some(true) != prev_was_read {
+                    command_link.master_start()?;
+                    command_link.master_address(address, true)?;
+                }
+                prev_was_read = Some(true);
+
+                if !buf.is_empty() {
+                    if let Some(pending) = pending_read.replace(&mut **buf) {
+                        command_link.master_read(pending, AckType::Ack)?;
+                    }
+                }
+            }
+            Operation::Write(buf) => {
+                if let Some(pending) = pending_read.take() {
+                    command_link.master_read(pending, AckType::LastNack)?;
+                }
+
+                if Some(false) != prev_was_read {
+                    command_link.master_start()?;
+                    command_link.master_address(address, false)?;
+                }
+                prev_was_read = Some(false);
+
+                if !buf.is_empty() {
+                    command_link.master_write(buf, true)?;
+                }
+            }
+        }
+    }
+
+    if let Some(pending) = pending_read {
+        command_link.master_read(pending, AckType::LastNack)?;
+    }
+
+    command_link.master_stop()?;
+
+    Ok(command_link)
+}
+
 #[repr(u32)]
 enum AckType {
     Ack = i2c_ack_type_t_I2C_MASTER_ACK,
@@ -536,7 +1812,23 @@ enum AckType {
     LastNack = i2c_ack_type_t_I2C_MASTER_LAST_NACK,
 }
 
-struct CommandLink<'buffers>(i2c_cmd_handle_t, PhantomData<&'buffers u8>);
+/// The size of a command link buffer for a maximum of `commands` commands, as per the
+/// `I2C_LINK_RECOMMENDED_SIZE` ESP-IDF macro
+const fn command_link_size(commands: usize) -> usize {
+    const I2C_INTERNAL_STRUCT_SIZE: usize = 24;
+
+    (2 + commands) * I2C_INTERNAL_STRUCT_SIZE
+}
+
+/// The maximum number of commands for `segments` address phases: START, up to four commands
+/// for a 10-bit address and up to two for the data, plus the STOPs
+const fn segments_commands(segments: usize) -> usize {
+    7 * segments + 2
+}
+
+/// A command link, either allocated from the heap (`.1 == false`) or placed
+/// in a preallocated buffer (`.1 == true`)
+struct CommandLink<'buffers>(i2c_cmd_handle_t, bool, PhantomData<&'buffers u8>);
 
 impl<'buffers> CommandLink<'buffers> {
     fn new() -> Result<Self, EspError> {
@@ -546,7 +1838,21 @@ impl<'buffers> CommandLink<'buffers> {
             return Err(EspError::from_infallible::<ESP_ERR_NO_MEM>());
         }
 
-        Ok(CommandLink(handle, PhantomData))
+        Ok(CommandLink(handle, false, PhantomData))
+    }
+
+    /// # Safety
+    ///
+    /// The command link should be dropped before `buffer` is dropped or used otherwise.
+    #[cfg(feature = "alloc")]
+    unsafe fn new_static(buffer: &mut [u8]) -> Result<Self, EspError> {
+        let handle = i2c_cmd_link_create_static(buffer.as_mut_ptr(), buffer.len() as _);
+
+        if handle.is_null() {
+            return Err(EspError::from_infallible::<ESP_ERR_NO_MEM>());
+        }
+
+        Ok(CommandLink(handle, true, PhantomData))
     }
 
     fn master_start(&mut self) -> Result<(), EspError> {
@@ -557,6 +1863,40 @@ impl<'buffers> CommandLink<'buffers> {
         esp!(unsafe { i2c_master_stop(self.0) })
     }
 
+    /// Adds the addressing sequence for `addr`, failing with `ESP_ERR_INVALID_ARG` for an
+    /// out-of-range address
+    ///
+    /// A 10-bit address is sent as the `0b11110xx` prefix (with the two high address bits)
+    /// followed by the low address byte. For reads, this is followed by a repeated START and the
+    /// prefix again with the read bit set.
+    fn master_address(&mut self, addr: I2cAddress, read: bool) -> Result<(), EspError> {
+        let rw = if read {
+            i2c_rw_t_I2C_MASTER_READ as u8
+        } else {
+            i2c_rw_t_I2C_MASTER_WRITE as u8
+        };
+
+        match addr {
+            I2cAddress::SevenBit(addr) if addr <= 0x7f => {
+                self.master_write_byte((addr << 1) | rw, true)
+            }
+            I2cAddress::TenBit(addr) if addr <= 0x3ff => {
+                let prefix = 0b1111_0000 | ((addr >> 7) as u8 & 0b0000_0110);
+
+                self.master_write_byte(prefix | i2c_rw_t_I2C_MASTER_WRITE as u8, true)?;
+                self.master_write_byte(addr as u8, true)?;
+
+                if read {
+                    self.master_start()?;
+                    self.master_write_byte(prefix | rw, true)?;
+                }
+
+                Ok(())
+            }
+            _ => Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>()),
+        }
+    }
+
     fn master_write_byte(&mut self, data: u8, ack_en: bool) -> Result<(), EspError> {
         esp!(unsafe { i2c_master_write_byte(self.0, data, ack_en) })
     }
@@ -573,7 +1913,11 @@ impl<'buffers> CommandLink<'buffers> {
 impl<'buffers> Drop for CommandLink<'buffers> {
     fn drop(&mut self) {
         unsafe {
-            i2c_cmd_link_delete(self.0);
+            if self.1 {
+                i2c_cmd_link_delete_static(self.0);
+            } else {
+                i2c_cmd_link_delete(self.0);
+            }
         }
     }
 }
@@ -594,3 +1938,69 @@ macro_rules! impl_i2c {
 impl_i2c!(I2C0: 0);
 #[cfg(not(any(esp32c3, esp32c2, esp32c6)))]
 impl_i2c!(I2C1: 1);
+#[cfg(esp32c6)]
+pub use lp::LPI2C0;
+
+/// The number of I2C ports, including the LP one
+#[cfg(not(esp32c6))]
+const I2C_PORTS: usize = SOC_I2C_NUM as usize;
+#[cfg(esp32c6)]
+const I2C_PORTS: usize = lp::LP_I2C_PORT as usize + 1;
+
+/// The LP I2C controller of the esp32c6, which keeps working in light sleep
+///
+/// Its SDA and SCL are fixed to GPIO6 and GPIO7, and its maximum frequency is 400KHz.
+/// The legacy driver supports it as of ESP-IDF 5.2.
+#[cfg(esp32c6)]
+mod lp {
+    use esp_idf_sys::*;
+
+    use crate::units::*;
+
+    crate::impl_peripheral!(LPI2C0);
+
+    #[cfg(not(any(
+        esp_idf_version_major = "4",
+        esp_idf_version = "5.0",
+        esp_idf_version = "5.1"
+    )))]
+    impl super::I2c for LPI2C0 {
+        #[inline(always)]
+        fn port() -> i2c_port_t {
+            LP_I2C_PORT
+        }
+    }
+
+    /// The port of the LP I2C controller, following the HP ones
+    pub(super) const LP_I2C_PORT: i2c_port_t = 1;
+
+    /// Fails with `ESP_ERR_INVALID_ARG` if the LP I2C controller is configured with other pins
+    /// than the hard-wired LP IO pins, or a frequency above what its slower RC_FAST clock allows
+    pub(super) fn check_config(
+        port: i2c_port_t,
+        sda: i32,
+        scl: i32,
+        baudrate: Hertz,
+    ) -> Result<(), EspError> {
+        if port == LP_I2C_PORT && (sda != 6 || scl != 7 || baudrate > 400.kHz().into()) {
+            Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[allow(clippy::declare_interior_mutable_const)]
+const NOT_BUSY: AtomicBool = AtomicBool::new(false);
+static BUSY: [AtomicBool; I2C_PORTS] = [NOT_BUSY; I2C_PORTS];
+
+#[allow(clippy::declare_interior_mutable_const)]
+const NO_RESULT: AtomicI32 = AtomicI32::new(ESP_OK);
+static RESULTS: [AtomicI32; I2C_PORTS] = [NO_RESULT; I2C_PORTS];
+
+#[allow(clippy::declare_interior_mutable_const)]
+const NOTIF: Notification = Notification::new();
+static DONE_NOTIFS: [Notification; I2C_PORTS] = [NOTIF; I2C_PORTS];
+
+static mut COMMANDS: [i2c_cmd_handle_t; I2C_PORTS] = [core::ptr::null_mut(); I2C_PORTS];
+static mut TIMEOUTS: [TickType_t; I2C_PORTS] = [0; I2C_PORTS];
@@ -0,0 +1,443 @@
+//! Bit-banged (software) I2C master driver
+//!
+//! `SoftI2cDriver` drives any two IO pins in open-drain mode, so it can provide an additional
+//! I2C bus when all I2C controllers are already in use. It implements the same `embedded-hal`
+//! traits as [super::I2cDriver], for speeds of up to 100KHz.
+//!
+//! Slaves stretching the clock are waited for, for up to the clock stretching timeout. Each
+//! byte can optionally be transferred with the interrupts masked, so that the bit timing is
+//! not disturbed by ISRs - at the price of a higher interrupt latency.
+//!
+//! Multi-master operation is only partially supported: a START is only issued on an idle bus,
+//! and a transfer is aborted with [Error::ArbitrationLoss] when another master pulls SDA low
+//! while a high bit is being sent.
+
+use esp_idf_sys::*;
+
+use crate::delay::Ets;
+use crate::gpio::{AnyIOPin, IOPin, InputOutput, PinDriver, Pull};
+use crate::peripheral::Peripheral;
+use crate::units::*;
+
+use embedded_hal::i2c::NoAcknowledgeSource;
+
+use super::{Error, Operation};
+
+pub type SoftI2cConfig = config::Config;
+
+/// Software I2C configuration
+pub mod config {
+    use core::time::Duration;
+
+    use crate::units::*;
+
+    /// Software I2C master configuration
+    #[derive(Debug, Clone)]
+    pub struct Config {
+        /// Up to 100KHz
+        pub baudrate: Hertz,
+        /// Whether to enable the (weak) internal pull-ups of the SDA and SCL pins
+        pub pullup_enabled: bool,
+        /// How long to wait for a slave stretching the clock
+        pub clock_stretch_timeout: Duration,
+        /// Whether to mask the interrupts while transferring each byte
+        pub mask_interrupts: bool,
+    }
+
+    impl Config {
+        pub fn new() -> Self {
+            Default::default()
+        }
+
+        #[must_use]
+        pub fn baudrate(mut self, baudrate: Hertz) -> Self {
+            self.baudrate = baudrate;
+            self
+        }
+
+        #[must_use]
+        pub fn enable_pullup(mut self, enable: bool) -> Self {
+            self.pullup_enabled = enable;
+            self
+        }
+
+        #[must_use]
+        pub fn clock_stretch_timeout(mut self, timeout: Duration) -> Self {
+            self.clock_stretch_timeout = timeout;
+            self
+        }
+
+        #[must_use]
+        pub fn mask_interrupts(mut self, mask: bool) -> Self {
+            self.mask_interrupts = mask;
+            self
+        }
+    }
+
+    impl Default for Config {
+        fn default() -> Self {
+            Self {
+                baudrate: Hertz(100_000),
+                pullup_enabled: true,
+                clock_stretch_timeout: Duration::from_millis(10),
+                mask_interrupts: false,
+            }
+        }
+    }
+}
+
+pub struct SoftI2cDriver<'d> {
+    sda: PinDriver<'d, AnyIOPin, InputOutput>,
+    scl: PinDriver<'d, AnyIOPin, InputOutput>,
+    half_period_us: u32,
+    stretch_timeout_us: u32,
+    mask_interrupts: bool,
+}
+
+impl<'d> SoftI2cDriver<'d> {
+    pub fn new(
+        sda: impl Peripheral<P = impl IOPin> + 'd,
+        scl: impl Peripheral<P = impl IOPin> + 'd,
+        config: &config::Config,
+    ) -> Result<Self, EspError> {
+        if config.baudrate.0 == 0 || config.baudrate > 100.kHz().into() {
+            return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
+        }
+
+        crate::into_ref!(sda, scl);
+
+        let mut sda = PinDriver::input_output_od(sda.map_into())?;
+        let mut scl = PinDriver::input_output_od(scl.map_into())?;
+
+        let pull = if config.pullup_enabled {
+            Pull::Up
+        } else {
+            Pull::Floating
+        };
+
+        sda.set_pull(pull)?;
+        scl.set_pull(pull)?;
+
+        // Release the bus
+        sda.set_high()?;
+        scl.set_high()?;
+
+        Ok(Self {
+            sda,
+            scl,
+            half_period_us: 500_000_u32.div_ceil(config.baudrate.0),
+            stretch_timeout_us: config
+                .clock_stretch_timeout
+                .as_micros()
+                .min(u32::MAX as u128) as u32,
+            mask_interrupts: config.mask_interrupts,
+        })
+    }
+
+    pub fn read(&mut self, addr: u8, buffer: &mut [u8]) -> Result<(), EspError> {
+        self.transaction(addr, &mut [Operation::Read(buffer)])
+    }
+
+    pub fn write(&mut self, addr: u8, bytes: &[u8]) -> Result<(), EspError> {
+        self.transaction(addr, &mut [Operation::Write(bytes)])
+    }
+
+    pub fn write_read(
+        &mut self,
+        addr: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), EspError> {
+        self.transaction(
+            addr,
+            &mut [Operation::Write(bytes), Operation::Read(buffer)],
+        )
+    }
+
+    /// Executes the operations with the same START, repeated START and STOP placement as
+    /// [super::I2cDriver::transaction].
+    ///
+    /// Fails with `ESP_ERR_INVALID_STATE` if the bus is not idle or the arbitration is lost,
+    /// with `ESP_FAIL` if the address or a written byte is not ACKed, and with
+    /// `ESP_ERR_TIMEOUT` if a slave stretches the clock for longer than the clock stretching
+    /// timeout. The `embedded-hal` traits report these failures in more detail.
+    pub fn transaction(
+        &mut self,
+        addr: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), EspError> {
+        self.execute(addr, operations).map_err(EspError::from)
+    }
+
+    fn execute(&mut self, addr: u8, operations: &mut [Operation<'_>]) -> Result<(), Error> {
+        if operations.is_empty() {
+            return Ok(());
+        }
+
+        if addr > 0x7f {
+            return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>().into());
+        }
+
+        match self.run(addr, operations) {
+            // The bus is owned by another master (or held by a slave), so no STOP is generated
+            Err(err @ (Error::Bus | Error::ArbitrationLoss)) => {
+                self.release()?;
+
+                Err(err)
+            }
+            result => {
+                // Always try to leave the bus idle, even after a failure
+                let stopped = self.stop();
+
+                result.and(stopped)
+            }
+        }
+    }
+
+    fn run(&mut self, addr: u8, operations: &mut [Operation<'_>]) -> Result<(), Error> {
+        if self.sda.is_low() || self.scl.is_low() {
+            return Err(Error::Bus);
+        }
+
+        let mut prev_was_read = None;
+
+        for i in 0..operations.len() {
+            // The last byte read before a direction change (or the end) is NACKed
+            let more_reads = operations[i + 1..]
+                .iter()
+                .take_while(|operation| matches!(operation, Operation::Read(_)))
+                .any(|operation| matches!(operation, Operation::Read(buf) if !buf.is_empty()));
+
+            match &mut operations[i] {
+                Operation::Read(buf) => {
+                    if Some(true) != prev_was_read {
+                        self.address(addr, true, prev_was_read.is_some())?;
+                    }
+                    prev_was_read = Some(true);
+
+                    let len = buf.len();
+
+                    for (index, byte) in buf.iter_mut().enumerate() {
+                        let ack = index < len - 1 || more_reads;
+
+                        *byte = self.with_timing(|this| this.read_byte(ack))?;
+                    }
+                }
+                Operation::Write(buf) => {
+                    if Some(false) != prev_was_read {
+                        self.address(addr, false, prev_was_read.is_some())?;
+                    }
+                    prev_was_read = Some(false);
+
+                    for byte in buf.iter() {
+                        if !self.with_timing(|this| this.write_byte(*byte))? {
+                            return Err(Error::NoAcknowledge(NoAcknowledgeSource::Data));
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn address(&mut self, addr: u8, read: bool, repeated: bool) -> Result<(), Error> {
+        if repeated {
+            self.repeated_start()?;
+        } else {
+            self.start()?;
+        }
+
+        if self.with_timing(|this| this.write_byte((addr << 1) | read as u8))? {
+            Ok(())
+        } else {
+            Err(Error::NoAcknowledge(NoAcknowledgeSource::Address))
+        }
+    }
+
+    fn with_timing<R>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<R, Error>,
+    ) -> Result<R, Error> {
+        if self.mask_interrupts {
+            crate::interrupt::free(|| f(self))
+        } else {
+            f(self)
+        }
+    }
+
+    fn start(&mut self) -> Result<(), Error> {
+        // SDA going low while SCL is high
+        self.sda.set_low()?;
+        self.delay();
+        self.scl.set_low()?;
+        self.delay();
+
+        Ok(())
+    }
+
+    fn repeated_start(&mut self) -> Result<(), Error> {
+        self.sda.set_high()?;
+        self.delay();
+        self.release_scl()?;
+        self.delay();
+
+        self.start()
+    }
+
+    fn stop(&mut self) -> Result<(), Error> {
+        // SDA going high while SCL is high
+        self.sda.set_low()?;
+        self.delay();
+        self.release_scl()?;
+        self.delay();
+        self.sda.set_high()?;
+        self.delay();
+
+        Ok(())
+    }
+
+    /// Releases SDA and SCL, without generating a STOP condition
+    fn release(&mut self) -> Result<(), Error> {
+        self.sda.set_high()?;
+        self.scl.set_high()?;
+
+        Ok(())
+    }
+
+    /// Returns `true` if the byte was ACKed
+    fn write_byte(&mut self, byte: u8) -> Result<bool, Error> {
+        for bit in (0..8).rev() {
+            self.write_bit(byte & (1 << bit) != 0)?;
+        }
+
+        Ok(!self.read_bit()?)
+    }
+
+    fn read_byte(&mut self, ack: bool) -> Result<u8, Error> {
+        let mut byte = 0;
+
+        for _ in 0..8 {
+            byte = (byte << 1) | self.read_bit()? as u8;
+        }
+
+        self.write_bit(!ack)?;
+
+        Ok(byte)
+    }
+
+    fn write_bit(&mut self, bit: bool) -> Result<(), Error> {
+        if bit {
+            self.sda.set_high()?;
+        } else {
+            self.sda.set_low()?;
+        }
+
+        self.delay();
+        self.release_scl()?;
+
+        // Another master is sending a low bit at the same time
+        let lost = bit && self.sda.is_low();
+
+        self.delay();
+        self.scl.set_low()?;
+
+        if lost {
+            Err(Error::ArbitrationLoss)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn read_bit(&mut self) -> Result<bool, Error> {
+        self.sda.set_high()?;
+
+        self.delay();
+        self.release_scl()?;
+
+        let bit = self.sda.is_high();
+
+        self.delay();
+        self.scl.set_low()?;
+
+        Ok(bit)
+    }
+
+    /// Releases SCL, and waits for the slaves to stop stretching the clock
+    fn release_scl(&mut self) -> Result<(), Error> {
+        self.scl.set_high()?;
+
+        let mut waited_us = 0;
+
+        while self.scl.is_low() {
+            if waited_us >= self.stretch_timeout_us {
+                return Err(Error::Timeout);
+            }
+
+            Ets::delay_us(1);
+            waited_us += 1;
+        }
+
+        Ok(())
+    }
+
+    fn delay(&self) {
+        Ets::delay_us(self.half_period_us);
+    }
+}
+
+impl<'d> embedded_hal_0_2::blocking::i2c::Read for SoftI2cDriver<'d> {
+    type Error = Error;
+
+    fn read(&mut self, addr: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        self.execute(addr, &mut [Operation::Read(buffer)])
+    }
+}
+
+impl<'d> embedded_hal_0_2::blocking::i2c::Write for SoftI2cDriver<'d> {
+    type Error = Error;
+
+    fn write(&mut self, addr: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.execute(addr, &mut [Operation::Write(bytes)])
+    }
+}
+
+impl<'d> embedded_hal_0_2::blocking::i2c::WriteRead for SoftI2cDriver<'d> {
+    type Error = Error;
+
+    fn write_read(&mut self, addr: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), Self::Error> {
+        self.execute(
+            addr,
+            &mut [Operation::Write(bytes), Operation::Read(buffer)],
+        )
+    }
+}
+
+impl<'d> embedded_hal::i2c::ErrorType for SoftI2cDriver<'d> {
+    type Error = Error;
+}
+
+impl<'d> embedded_hal::i2c::I2c<embedded_hal::i2c::SevenBitAddress> for SoftI2cDriver<'d> {
+    fn read(&mut self, addr: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        self.execute(addr, &mut [Operation::Read(buffer)])
+    }
+
+    fn write(&mut self, addr: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.execute(addr, &mut [Operation::Write(bytes)])
+    }
+
+    fn write_read(&mut self, addr: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), Self::Error> {
+        self.execute(
+            addr,
+            &mut [Operation::Write(bytes), Operation::Read(buffer)],
+        )
+    }
+
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [embedded_hal::i2c::Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.execute(address, operations)
+    }
+}
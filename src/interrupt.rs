@@ -80,6 +80,184 @@ impl From<InterruptType> for u32 {
     }
 }
 
+/// The priority level of an interrupt allocated with `InterruptHandle`, or of the ISR
+/// of a driver
+///
+/// Only the low and medium levels are offered, as the handlers of the higher ones
+/// need to be written in assembly.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum InterruptLevel {
+    /// Any of the low and medium levels, as chosen by the allocator
+    #[default]
+    LowMed,
+    Level1,
+    Level2,
+    Level3,
+}
+
+impl From<InterruptLevel> for InterruptType {
+    fn from(level: InterruptLevel) -> Self {
+        match level {
+            InterruptLevel::LowMed => Self::LowMed,
+            InterruptLevel::Level1 => Self::Level1,
+            InterruptLevel::Level2 => Self::Level2,
+            InterruptLevel::Level3 => Self::Level3,
+        }
+    }
+}
+
+/// The allocation flags of an interrupt allocated with `InterruptHandle`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct InterruptFlags {
+    pub level: InterruptLevel,
+    /// Whether the handler can be called while the flash cache is disabled,
+    /// i.e. whether the handler and all the data it touches are in IRAM / DRAM
+    pub iram: bool,
+    /// Whether the CPU interrupt can be shared with the handlers of other interrupt sources
+    pub shared: bool,
+    /// Whether the interrupt is edge-triggered, rather than level-triggered
+    pub edge: bool,
+    /// Whether the interrupt is allocated disabled, i.e. only fires after `InterruptHandle::enable`
+    pub disabled: bool,
+}
+
+impl InterruptFlags {
+    pub const fn new() -> Self {
+        Self {
+            level: InterruptLevel::LowMed,
+            iram: false,
+            shared: false,
+            edge: false,
+            disabled: false,
+        }
+    }
+
+    #[must_use]
+    pub fn level(mut self, level: InterruptLevel) -> Self {
+        self.level = level;
+        self
+    }
+
+    #[must_use]
+    pub fn iram(mut self, iram: bool) -> Self {
+        self.iram = iram;
+        self
+    }
+
+    #[must_use]
+    pub fn shared(mut self, shared: bool) -> Self {
+        self.shared = shared;
+        self
+    }
+
+    #[must_use]
+    pub fn edge(mut self, edge: bool) -> Self {
+        self.edge = edge;
+        self
+    }
+
+    #[must_use]
+    pub fn disabled(mut self, disabled: bool) -> Self {
+        self.disabled = disabled;
+        self
+    }
+}
+
+impl From<InterruptFlags> for EnumSet<InterruptType> {
+    fn from(flags: InterruptFlags) -> Self {
+        let mut result: EnumSet<InterruptType> = InterruptType::from(flags.level).into();
+
+        if flags.iram {
+            result |= InterruptType::Iram;
+        }
+
+        if flags.shared {
+            result |= InterruptType::Shared;
+        }
+
+        if flags.edge {
+            result |= InterruptType::Edge;
+        }
+
+        if flags.disabled {
+            result |= InterruptType::IntrDisabled;
+        }
+
+        result
+    }
+}
+
+/// An interrupt handler allocated with `esp_intr_alloc` for a given interrupt source.
+///
+/// The interrupt is freed when the handle is dropped.
+pub struct InterruptHandle(intr_handle_t);
+
+impl InterruptHandle {
+    /// Allocates an interrupt with the given `flags` for interrupt source `source`
+    /// (one of the `ETS_*_INTR_SOURCE` constants), and installs `handler` for it.
+    ///
+    /// The interrupt is allocated on the core where this method is called.
+    ///
+    /// # Safety
+    ///
+    /// `handler` is called from an ISR context with `arg` as its argument, for as long as this handle is alive.
+    /// Therefore, `handler` must be ISR-safe (and placed in IRAM if `InterruptFlags::iram` is set),
+    /// and `arg` must remain valid until the handle is dropped.
+    pub unsafe fn new(
+        source: i32,
+        flags: InterruptFlags,
+        handler: unsafe extern "C" fn(*mut core::ffi::c_void),
+        arg: *mut core::ffi::c_void,
+    ) -> Result<Self, EspError> {
+        let mut handle: intr_handle_t = core::ptr::null_mut();
+
+        esp!(esp_intr_alloc(
+            source,
+            InterruptType::to_native(flags.into()) as _,
+            Some(handler),
+            arg,
+            &mut handle,
+        ))?;
+
+        Ok(Self(handle))
+    }
+
+    /// Enables the interrupt
+    pub fn enable(&mut self) -> Result<(), EspError> {
+        esp!(unsafe { esp_intr_enable(self.0) })
+    }
+
+    /// Disables the interrupt
+    pub fn disable(&mut self) -> Result<(), EspError> {
+        esp!(unsafe { esp_intr_disable(self.0) })
+    }
+
+    /// Returns the core on which the interrupt was allocated
+    pub fn core(&self) -> crate::cpu::Core {
+        unsafe { esp_intr_get_cpu(self.0) }.into()
+    }
+
+    /// Returns the CPU interrupt number allocated for the interrupt source
+    pub fn number(&self) -> i32 {
+        unsafe { esp_intr_get_intno(self.0) }
+    }
+
+    /// Returns the raw ESP-IDF handle of the allocated interrupt
+    pub fn handle(&self) -> intr_handle_t {
+        self.0
+    }
+}
+
+impl Drop for InterruptHandle {
+    fn drop(&mut self) {
+        if let Err(e) = esp!(unsafe { esp_intr_free(self.0) }) {
+            ::log::error!("Failed to free interrupt {}: {e}", self.number());
+        }
+    }
+}
+
+unsafe impl Send for InterruptHandle {}
+
 pub(crate) static CS: IsrCriticalSection = IsrCriticalSection::new();
 
 /// Returns true if the currently active core is executing an ISR request
@@ -89,6 +267,16 @@ pub fn active() -> bool {
     unsafe { xPortInIsrContext() != 0 }
 }
 
+/// Executes closure `cb` and returns `true` if - while executing - `cb` requested
+/// a yield from the ISR (via `task::do_yield`).
+///
+/// If `ISR_YIELDERS_MAX` yielders are already registered in the current ISR call chain,
+/// `cb` is executed without registering the signal, so its yield requests go to the yielder
+/// registered last, and `false` is returned.
+///
+/// # Panics
+///
+/// If not called from an ISR context. Use `try_with_isr_yield_signal` for a non-panicking variant.
 pub fn with_isr_yield_signal(cb: impl FnOnce()) -> bool {
     if !active() {
         panic!("with_isr_yield_signal() can only be called from an ISR context");
@@ -96,39 +284,141 @@ pub fn with_isr_yield_signal(cb: impl FnOnce()) -> bool {
 
     let mut signaled = false;
 
-    let prev_yielder =
-        unsafe { set_isr_yielder(Some((do_yield_signal, &mut signaled as *mut _ as _))) };
-
-    cb();
+    {
+        let _registration =
+            IsrYielderRegistration::new((do_yield_signal, &mut signaled as *mut _ as _)).ok();
 
-    unsafe { set_isr_yielder(prev_yielder) };
+        cb();
+    }
 
     signaled
 }
 
+/// Same as `with_isr_yield_signal`, except that when not called from an ISR context,
+/// closure `cb` is simply executed and `None` is returned.
+pub fn try_with_isr_yield_signal(cb: impl FnOnce()) -> Option<bool> {
+    if active() {
+        Some(with_isr_yield_signal(cb))
+    } else {
+        cb();
+
+        None
+    }
+}
+
 unsafe fn do_yield_signal(arg: *mut ()) {
     let signaled = arg.cast::<bool>().as_mut().unwrap();
 
     *signaled = true
 }
 
-#[allow(clippy::type_complexity)]
-static mut ISR_YIELDER: Option<(unsafe fn(*mut ()), *mut ())> = None;
+/// The maximum number of ISR yielders which can be registered in a single ISR call chain
+pub const ISR_YIELDERS_MAX: usize = 4;
+
+/// An ISR yield function, together with the argument it should be called with
+pub type IsrYielder = (unsafe fn(*mut ()), *mut ());
+
+/// The ISR yielders registered in the current ISR call chain of one core, innermost last
+struct IsrYielderStack(heapless::Vec<IsrYielder, ISR_YIELDERS_MAX>);
+
+impl IsrYielderStack {
+    const fn new() -> Self {
+        Self(heapless::Vec::new())
+    }
+
+    /// The yielder `do_yield` should invoke, i.e. the one registered last
+    #[inline(always)]
+    fn innermost(&self) -> Option<IsrYielder> {
+        self.0.last().copied()
+    }
+
+    /// Registers `yielder` as the innermost yielder, until `unregister` is called
+    #[inline(always)]
+    fn register(&mut self, yielder: IsrYielder) -> Result<(), EspError> {
+        self.0
+            .push(yielder)
+            .map_err(|_| EspError::from_infallible::<ESP_ERR_NO_MEM>())
+    }
+
+    /// Unregisters the innermost yielder, restoring the one registered before it
+    #[inline(always)]
+    fn unregister(&mut self) -> Option<IsrYielder> {
+        self.0.pop()
+    }
+
+    /// Replaces - or with `None`, unregisters - the innermost yielder, returning the replaced one
+    ///
+    /// This is what `set_isr_yielder` does, so that a handler restoring the yielder it got back
+    /// leaves the yielders of the rest of the ISR call chain untouched.
+    #[inline(always)]
+    fn replace_innermost(&mut self, yielder: Option<IsrYielder>) -> Option<IsrYielder> {
+        let previous = self.unregister();
+
+        if let Some(yielder) = yielder {
+            // Cannot fail, as a yielder was just unregistered, or the stack was empty
+            let _ = self.register(yielder);
+        }
+
+        previous
+    }
+}
+
+/// Storage for the ISR yielders registered in the current ISR call chain of one core.
+///
+/// Yielders are kept as plain (function pointer, argument pointer) pairs, i.e. without
+/// any bit-packing, so the representation is correct regardless of the target pointer width.
+///
+/// Each core has its own storage, protected by its own `IsrCriticalSection`. Since the storage
+/// of a core is only ever accessed by ISRs running on that same core, the spinlock of the critical
+/// section is never contended, and accessing the storage boils down to masking the interrupts
+/// of the current core.
+struct IsrYielders(IsrCriticalSection, core::cell::UnsafeCell<IsrYielderStack>);
+
+impl IsrYielders {
+    const fn new() -> Self {
+        Self(
+            IsrCriticalSection::new(),
+            core::cell::UnsafeCell::new(IsrYielderStack::new()),
+        )
+    }
+
+    /// Accesses the ISR yielders of the currently active core
+    #[inline(always)]
+    #[link_section = ".iram1.interrupt_isr_yielders_access"]
+    fn access<R>(f: impl FnOnce(&mut IsrYielderStack) -> R) -> R {
+        let this = &ISR_YIELDERS[crate::cpu::core() as usize];
 
-#[allow(clippy::type_complexity)]
+        let _guard = this.0.enter();
+
+        f(unsafe { this.1.get().as_mut() }.unwrap())
+    }
+}
+
+unsafe impl Sync for IsrYielders {}
+
+#[allow(clippy::declare_interior_mutable_const)]
+const ISR_YIELDERS_INIT: IsrYielders = IsrYielders::new();
+
+static ISR_YIELDERS: [IsrYielders; crate::cpu::CORES as usize] =
+    [ISR_YIELDERS_INIT; crate::cpu::CORES as usize];
+
+/// Invokes the innermost ISR yielder, i.e. the one registered last in the current ISR call chain.
+///
+/// Returns `false` if there are no registered ISR yielders, in which case
+/// the caller is expected to yield from the ISR by itself.
 #[inline(always)]
-#[link_section = ".iram1.interrupt_get_isr_yielder"]
-pub(crate) unsafe fn get_isr_yielder() -> Option<(unsafe fn(*mut ()), *mut ())> {
+#[link_section = ".iram1.interrupt_invoke_isr_yielders"]
+pub(crate) unsafe fn invoke_isr_yielders() -> bool {
     if active() {
-        free(|| {
-            if let Some((func, arg)) = unsafe { ISR_YIELDER } {
-                Some((func, arg))
-            } else {
-                None
-            }
-        })
+        if let Some((func, arg)) = IsrYielders::access(|yielders| yielders.innermost()) {
+            func(arg);
+
+            true
+        } else {
+            false
+        }
     } else {
-        None
+        false
     }
 }
 
@@ -143,22 +433,73 @@ pub(crate) unsafe fn get_isr_yielder() -> Option<(unsafe fn(*mut ()), *mut ())>
 /// Users should not forget to call again `set_isr_yielder` at the end of the
 /// ISR handler so as to reastore the yield function which was valid before the
 /// ISR handler was invoked.
-#[allow(clippy::type_complexity)]
+///
+/// Consider using the scoped `with_isr_yielder` instead, which does not require
+/// a manual restore and which supports multiple yielders in the same ISR call chain.
 #[inline(always)]
 #[link_section = ".iram1.interrupt_set_isr_yielder"]
-pub unsafe fn set_isr_yielder(
-    yielder: Option<(unsafe fn(*mut ()), *mut ())>,
-) -> Option<(unsafe fn(*mut ()), *mut ())> {
+pub unsafe fn set_isr_yielder(yielder: Option<IsrYielder>) -> Option<IsrYielder> {
     if active() {
-        free(|| {
-            let previous = unsafe { ISR_YIELDER };
+        IsrYielders::access(|yielders| yielders.replace_innermost(yielder))
+    } else {
+        None
+    }
+}
 
-            unsafe { ISR_YIELDER = yielder };
+/// Registers `yielder` as the ISR yield function for the duration of `cb`.
+///
+/// Unlike `set_isr_yielder`, the yielder is automatically unregistered once `cb` returns,
+/// restoring the yielder registered before it in the ISR call chain. When some function
+/// further down the ISR call chain invokes `do_yield`, only the innermost yielder - i.e. the
+/// one registered last - is called.
+///
+/// Yielders are registered per core, so ISRs running concurrently on the other core
+/// neither see nor invoke them.
+///
+/// When not called from an ISR context, `cb` is simply executed.
+///
+/// # Safety
+///
+/// `yielder` is called with its argument while `cb` executes, so the argument
+/// should be valid for the yielder for the whole duration of `cb`.
+///
+/// # Errors
+///
+/// Returns `ESP_ERR_NO_MEM` - without executing `cb` - if `ISR_YIELDERS_MAX` yielders
+/// are already registered in the current ISR call chain.
+#[inline(always)]
+#[link_section = ".iram1.interrupt_with_isr_yielder"]
+pub unsafe fn with_isr_yielder<R>(
+    yielder: IsrYielder,
+    cb: impl FnOnce() -> R,
+) -> Result<R, EspError> {
+    if active() {
+        // Unregister the yielder even if `cb` unwinds
+        let _registration = IsrYielderRegistration::new(yielder)?;
 
-            previous
-        })
+        Ok(cb())
     } else {
-        None
+        Ok(cb())
+    }
+}
+
+struct IsrYielderRegistration;
+
+impl IsrYielderRegistration {
+    #[inline(always)]
+    #[link_section = ".iram1.interrupt_isr_yielder_registration_new"]
+    fn new(yielder: IsrYielder) -> Result<Self, EspError> {
+        IsrYielders::access(|yielders| yielders.register(yielder))?;
+
+        Ok(Self)
+    }
+}
+
+impl Drop for IsrYielderRegistration {
+    #[inline(always)]
+    #[link_section = ".iram1.interrupt_isr_yielder_registration_drop"]
+    fn drop(&mut self) {
+        IsrYielders::access(|yielders| yielders.unregister());
     }
 }
 
@@ -196,6 +537,35 @@ fn enter(cs: &IsrCriticalSection) {
     }
 }
 
+#[cfg(not(any(esp32, esp32s2, esp32s3, esp32p4)))]
+#[inline(always)]
+#[link_section = ".iram1.interrupt_try_enter"]
+fn try_enter(cs: &IsrCriticalSection, _timeout: u32) -> bool {
+    enter(cs);
+
+    true
+}
+
+#[cfg(any(esp32, esp32s2, esp32s3, esp32p4))]
+#[inline(always)]
+#[link_section = ".iram1.interrupt_try_enter"]
+fn try_enter(cs: &IsrCriticalSection, timeout: u32) -> bool {
+    #[cfg(esp_idf_version = "4.3")]
+    let entered = {
+        let _ = timeout;
+
+        enter(cs);
+
+        true
+    };
+
+    #[cfg(not(esp_idf_version = "4.3"))]
+    let entered =
+        unsafe { xPortEnterCriticalTimeout(cs.0.get(), timeout.min(i32::MAX as u32) as _) != 0 };
+
+    entered
+}
+
 #[cfg(not(any(esp32, esp32s2, esp32s3, esp32p4)))]
 #[inline(always)]
 #[link_section = ".iram1.interrupt_exit"]
@@ -256,6 +626,102 @@ impl IsrCriticalSection {
 
         IsrCriticalSectionGuard(self)
     }
+
+    /// Same as `IsrCriticalSection::enter`, except that acquiring the spinlock is attempted
+    /// for at most `timeout` CPU cycles (as per `xPortEnterCriticalTimeout`).
+    ///
+    /// Returns `None` if the spinlock could not be acquired within the timeout, in which case
+    /// interrupts on the current core are left untouched.
+    ///
+    /// NOTE: On single-core chips (and with ESP-IDF 4.3), there is no spinlock to wait for
+    /// and this method behaves exactly like `IsrCriticalSection::enter`, i.e. it always succeeds.
+    #[inline(always)]
+    #[link_section = ".iram1.interrupt_cs_try_enter"]
+    pub fn try_enter(&self, timeout: u32) -> Option<IsrCriticalSectionGuard> {
+        if try_enter(self, timeout) {
+            Some(IsrCriticalSectionGuard(self))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(any(esp32, esp32s2, esp32s3, esp32p4))]
+const SPINLOCK_OWNER_ID_0: u32 = 0xCDCD;
+#[cfg(any(esp32, esp32s2, esp32s3, esp32p4))]
+const SPINLOCK_OWNER_ID_1: u32 = 0xABAB;
+
+/// Best-effort diagnostics for debugging deadlocks.
+///
+/// These methods read the state of the underlying spinlock without any synchronization, and
+/// are therefore NOT suitable for implementing any kind of synchronization on top of them.
+///
+/// On chips where `IsrCriticalSection` is not backed by a spinlock (esp32c*, esp32h*), there
+/// is no state to inspect and the methods return constants.
+impl IsrCriticalSection {
+    /// Returns `true` if the critical section is currently entered by the active core.
+    ///
+    /// Always returns `false` on chips without a spinlock.
+    #[inline]
+    #[link_section = ".iram1.interrupt_cs_is_owned_by_current_core"]
+    pub fn is_owned_by_current_core(&self) -> bool {
+        #[cfg(any(esp32, esp32s2, esp32s3, esp32p4))]
+        let owned = {
+            let owner = unsafe { core::ptr::read_volatile(&(*self.0.get()).owner) };
+
+            match crate::cpu::core() {
+                crate::cpu::Core::Core0 => owner == SPINLOCK_OWNER_ID_0,
+                #[cfg(any(esp32, esp32s3))]
+                crate::cpu::Core::Core1 => owner == SPINLOCK_OWNER_ID_1,
+            }
+        };
+
+        #[cfg(not(any(esp32, esp32s2, esp32s3, esp32p4)))]
+        let owned = false;
+
+        owned
+    }
+
+    /// Returns how many times the critical section is currently (recursively) entered by its owner,
+    /// or 0 if it is not entered at all.
+    ///
+    /// Always returns 0 on chips without a spinlock.
+    #[inline]
+    #[link_section = ".iram1.interrupt_cs_nesting_count"]
+    pub fn nesting_count(&self) -> u32 {
+        #[cfg(any(esp32, esp32s2, esp32s3, esp32p4))]
+        let count = unsafe { core::ptr::read_volatile(&(*self.0.get()).count) } as _;
+
+        #[cfg(not(any(esp32, esp32s2, esp32s3, esp32p4)))]
+        let count = 0;
+
+        count
+    }
+
+    /// Same as `IsrCriticalSection::enter`, except that - when the `esp_idf_freertos_portmux_debug`
+    /// configuration is enabled - the name of the locker and the line of the caller are recorded
+    /// in the `lastLockedFn` / `lastLockedLine` debug fields of the spinlock.
+    ///
+    /// Without `esp_idf_freertos_portmux_debug`, this method behaves exactly like `IsrCriticalSection::enter`.
+    #[inline]
+    #[track_caller]
+    #[link_section = ".iram1.interrupt_cs_enter_named"]
+    pub fn enter_named(&self, name: &'static core::ffi::CStr) -> IsrCriticalSectionGuard {
+        let guard = self.enter();
+
+        #[cfg(all(any(esp32, esp32s2, esp32s3, esp32p4), esp_idf_freertos_portmux_debug))]
+        unsafe {
+            let mux = self.0.get();
+
+            (*mux).lastLockedFn = name.as_ptr() as _;
+            (*mux).lastLockedLine = core::panic::Location::caller().line() as _;
+        }
+
+        #[cfg(not(all(any(esp32, esp32s2, esp32s3, esp32p4), esp_idf_freertos_portmux_debug)))]
+        let _ = name;
+
+        guard
+    }
 }
 
 impl Default for IsrCriticalSection {
@@ -295,6 +761,101 @@ pub fn free<R>(f: impl FnOnce() -> R) -> R {
     f()
 }
 
+/// Executes closure f in an interrupt-free context and additionally returns `true` if - while executing -
+/// closure f requested a yield from the ISR (i.e. some HAL API called by f invoked `task::do_yield`).
+///
+/// When not called from an ISR context, this function behaves like `free` and always returns `false`
+/// as the second element of the tuple.
+#[inline(always)]
+#[link_section = ".iram1.interrupt_free_with_yield_signal"]
+pub fn free_with_yield_signal<R>(f: impl FnOnce() -> R) -> (R, bool) {
+    if active() {
+        let mut result = None;
+
+        let yield_pending = with_isr_yield_signal(|| result = Some(free(f)));
+
+        (result.unwrap(), yield_pending)
+    } else {
+        (free(f), false)
+    }
+}
+
+/// Same as `free_with_yield_signal`, except that if a yield was requested while executing closure f,
+/// the yield is performed - via `task::do_yield` - once the interrupt-free context is exited.
+///
+/// When not called from an ISR context, this function behaves exactly like `free`.
+#[inline(always)]
+#[link_section = ".iram1.interrupt_free_yielding"]
+pub fn free_yielding<R>(f: impl FnOnce() -> R) -> R {
+    let (result, yield_pending) = free_with_yield_signal(f);
+
+    if yield_pending {
+        crate::task::do_yield();
+    }
+
+    result
+}
+
+/// Same as `free`, except that entering the interrupt-free context is attempted
+/// for at most `timeout` CPU cycles (see `IsrCriticalSection::try_enter`).
+///
+/// Returns `None` - without executing closure f - if the interrupt-free context
+/// could not be entered within the timeout.
+#[inline(always)]
+#[link_section = ".iram1.interrupt_try_free_with_timeout"]
+pub fn try_free_with_timeout<R>(timeout: u32, f: impl FnOnce() -> R) -> Option<R> {
+    let _guard = CS.try_enter(timeout)?;
+
+    Some(f())
+}
+
+/// An implementation of the `critical-section` crate API which - unlike the default one in
+/// `task::critical_section` - disables interrupts and is therefore also safe to use from an ISR context.
+///
+/// The implementation uses the same HAL-global `IsrCriticalSection` as `interrupt::free`, so the two compose:
+/// calling `critical_section::with` from within `interrupt::free` (or vice versa) simply nests the critical section.
+///
+/// Nesting is handled by the critical section itself, which is recursive: each `acquire` enters it and
+/// each `release` exits it, and interrupts are only re-enabled once the outermost `release` on the
+/// owning core exits. All the nesting state therefore lives in the critical section the restore token
+/// stands for, so the implementation works with the default `critical-section` restore state (`()`),
+/// and does not force any `restore-state-*` feature on the dependency graph.
+///
+/// No yield is performed on release: from a task context, FreeRTOS takes any pending context switch
+/// once interrupts are re-enabled, while from an ISR context (`interrupt::active()`) the switch is deferred
+/// to the end of the ISR, as usual.
+#[cfg(feature = "isr-critical-section")]
+pub mod critical_section {
+    pub struct EspIsrCriticalSection {}
+
+    unsafe impl critical_section::Impl for EspIsrCriticalSection {
+        #[inline(always)]
+        #[link_section = ".iram1.interrupt_critical_section_acquire"]
+        unsafe fn acquire() {
+            super::enter(&super::CS);
+        }
+
+        #[inline(always)]
+        #[link_section = ".iram1.interrupt_critical_section_release"]
+        unsafe fn release(_token: ()) {
+            super::exit(&super::CS);
+        }
+    }
+
+    pub type LinkWorkaround = [*mut (); 2];
+
+    static mut __INTERNAL_REFERENCE: LinkWorkaround = [
+        _critical_section_1_0_acquire as *mut _,
+        _critical_section_1_0_release as *mut _,
+    ];
+
+    pub fn link() -> LinkWorkaround {
+        unsafe { __INTERNAL_REFERENCE }
+    }
+
+    critical_section::set_impl!(EspIsrCriticalSection);
+}
+
 #[cfg(feature = "wake-from-isr")]
 pub mod asynch {
     pub type HalIsrNotification = crate::task::asynch::Notification;
@@ -665,32 +1226,155 @@ pub mod asynch {
 
 #[cfg(feature = "embassy-sync")]
 pub mod embassy_sync {
-    use core::marker::PhantomData;
-
     use embassy_sync::blocking_mutex::raw::RawMutex;
 
     /// A mutex that allows borrowing data across executors and interrupts.
     ///
+    /// Each `IsrRawMutex` instance is backed by its own `IsrCriticalSection` (and thus - on
+    /// chips which have one - by its own spinlock), so unrelated mutexes do not contend with
+    /// each other, nor with the HAL-global critical section used by `interrupt::free`.
+    ///
     /// # Safety
     ///
     /// This mutex is safe to share between different executors and interrupts.
-    pub struct IsrRawMutex(());
+    pub struct IsrRawMutex(super::IsrCriticalSection);
 
     unsafe impl Send for IsrRawMutex {}
     unsafe impl Sync for IsrRawMutex {}
 
+    impl Default for IsrRawMutex {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
     impl IsrRawMutex {
         /// Create a new `IsrRawMutex`.
         pub const fn new() -> Self {
-            Self(())
+            Self(super::IsrCriticalSection::new())
         }
     }
 
     unsafe impl RawMutex for IsrRawMutex {
+        #[allow(clippy::declare_interior_mutable_const)]
         const INIT: Self = Self::new();
 
         fn lock<R>(&self, f: impl FnOnce() -> R) -> R {
-            super::free(f)
+            let _guard = self.0.enter();
+
+            f()
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use esp_idf_sys::ESP_ERR_NO_MEM;
+
+    use super::{IsrYielder, IsrYielderStack, ISR_YIELDERS_MAX};
+
+    unsafe fn yielder_a(_arg: *mut ()) {}
+    unsafe fn yielder_b(_arg: *mut ()) {}
+
+    const PATTERNS: [usize; 7] = [
+        0,
+        1,
+        usize::MAX,
+        usize::MAX >> 1,
+        !(usize::MAX >> 1),
+        0x5555_5555_5555_5555u64 as usize,
+        0xaaaa_aaaa_aaaa_aaaau64 as usize,
+    ];
+
+    fn yielder(arg: usize) -> IsrYielder {
+        let func = if arg % 2 == 0 { yielder_a } else { yielder_b };
+
+        (func, arg as *mut ())
+    }
+
+    fn arg(yielder: Option<IsrYielder>) -> Option<usize> {
+        yielder.map(|(_, arg)| arg as usize)
+    }
+
+    #[test]
+    fn yielders_any_pointer_bits() {
+        for pattern in PATTERNS {
+            let mut stack = IsrYielderStack::new();
+
+            stack.register(yielder(pattern)).unwrap();
+
+            let (func, arg) = stack.innermost().unwrap();
+            let (expected, _) = yielder(pattern);
+
+            assert_eq!(arg as usize, pattern);
+            assert_eq!(func as usize, expected as usize);
+        }
+    }
+
+    #[test]
+    fn yielders_innermost_wins() {
+        let mut stack = IsrYielderStack::new();
+
+        assert!(stack.innermost().is_none());
+
+        for pattern in &PATTERNS[..ISR_YIELDERS_MAX] {
+            stack.register(yielder(*pattern)).unwrap();
+
+            assert_eq!(arg(stack.innermost()), Some(*pattern));
+        }
+
+        // A full stack rejects the registration, and yields still go to the last registered yielder
+        assert_eq!(
+            stack.register(yielder(0)).unwrap_err().code(),
+            ESP_ERR_NO_MEM
+        );
+        assert_eq!(arg(stack.innermost()), Some(PATTERNS[ISR_YIELDERS_MAX - 1]));
+
+        // Unregistering restores the yielders registered before, in order
+        for index in (0..ISR_YIELDERS_MAX).rev() {
+            assert_eq!(arg(stack.innermost()), Some(PATTERNS[index]));
+            assert_eq!(arg(stack.unregister()), Some(PATTERNS[index]));
+        }
+
+        assert!(stack.innermost().is_none());
+        assert!(stack.unregister().is_none());
+    }
+
+    #[test]
+    fn yielders_set_and_restore() {
+        let mut stack = IsrYielderStack::new();
+
+        stack.register(yielder(1)).unwrap();
+
+        // `set_isr_yielder(Some(..))` replaces the innermost yielder, and the handler restores it afterwards
+        let previous = stack.replace_innermost(Some(yielder(2)));
+
+        assert_eq!(arg(previous), Some(1));
+        assert_eq!(arg(stack.innermost()), Some(2));
+
+        assert_eq!(arg(stack.replace_innermost(previous)), Some(2));
+        assert_eq!(arg(stack.innermost()), Some(1));
+
+        // On an empty stack, setting registers and restoring `None` unregisters
+        stack.unregister();
+
+        let previous = stack.replace_innermost(Some(yielder(3)));
+
+        assert!(previous.is_none());
+        assert_eq!(arg(stack.innermost()), Some(3));
+
+        assert_eq!(arg(stack.replace_innermost(previous)), Some(3));
+        assert!(stack.innermost().is_none());
+
+        // Replacing works on a full stack as well
+        for pattern in &PATTERNS[..ISR_YIELDERS_MAX] {
+            stack.register(yielder(*pattern)).unwrap();
+        }
+
+        assert_eq!(
+            arg(stack.replace_innermost(Some(yielder(4)))),
+            Some(PATTERNS[ISR_YIELDERS_MAX - 1])
+        );
+        assert_eq!(arg(stack.innermost()), Some(4));
+    }
+}
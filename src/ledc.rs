@@ -4,7 +4,7 @@
 //! peripheral](https://docs.espressif.com/projects/esp-idf/en/latest/esp32c3/api-reference/peripherals/ledc.html)
 //!
 //! This is an initial implementation supporting the generation of PWM signals
-//! but no chrome and spoilers like fading.
+//! and hardware fading of the duty cycle.
 //!
 //! # Examples
 //!
@@ -25,12 +25,15 @@
 //! See the `examples/` folder of this repository for more.
 
 use core::borrow::Borrow;
+use core::ffi::c_void;
 use core::marker::PhantomData;
 use core::sync::atomic::{AtomicBool, Ordering};
 
 use esp_idf_sys::*;
 
+use crate::delay::FreeRtos;
 use crate::gpio::OutputPin;
+use crate::interrupt::asynch::HalIsrNotification;
 use crate::peripheral::Peripheral;
 use crate::task::CriticalSection;
 use crate::units::*;
@@ -45,6 +48,49 @@ const IDLE_LEVEL: u32 = 0;
 static FADE_FUNC_INSTALLED: AtomicBool = AtomicBool::new(false);
 static FADE_FUNC_INSTALLED_CS: CriticalSection = CriticalSection::new();
 
+#[cfg(esp_idf_soc_ledc_support_hs_mode)]
+const SPEED_MODES: usize = 2;
+#[cfg(not(esp_idf_soc_ledc_support_hs_mode))]
+const SPEED_MODES: usize = 1;
+
+const CHANNELS: usize = 8;
+
+#[allow(clippy::declare_interior_mutable_const)]
+const FADING_INIT: AtomicBool = AtomicBool::new(false);
+/// Whether a fade is ongoing, per speed mode and channel
+static FADING: [AtomicBool; SPEED_MODES * CHANNELS] = [FADING_INIT; SPEED_MODES * CHANNELS];
+
+#[allow(clippy::declare_interior_mutable_const)] // OK because this is only used as an array initializer
+const FADE_END_NOTIF_INIT: HalIsrNotification = HalIsrNotification::new();
+static FADE_END_NOTIF: [HalIsrNotification; SPEED_MODES * CHANNELS] =
+    [FADE_END_NOTIF_INIT; SPEED_MODES * CHANNELS];
+
+#[cfg(feature = "alloc")]
+#[allow(clippy::declare_interior_mutable_const)] // OK because this is only used as an array initializer
+const FADE_END_HANDLER_INIT: Option<alloc::boxed::Box<dyn FnMut() + Send + 'static>> = None;
+/// Only accessed from within `interrupt::free` (or with `interrupt::CS` entered, in the ISR),
+/// so that a handler is never replaced or dropped while the ISR runs it on the other core
+#[cfg(feature = "alloc")]
+static mut FADE_END_HANDLER: [Option<alloc::boxed::Box<dyn FnMut() + Send + 'static>>;
+    SPEED_MODES * CHANNELS] = [FADE_END_HANDLER_INIT; SPEED_MODES * CHANNELS];
+
+// The ESP-IDF LEDC HAL, which is not part of the public headers
+#[allow(non_camel_case_types)]
+#[repr(C)]
+struct ledc_hal_context_t {
+    dev: *mut c_void,
+    speed_mode: ledc_mode_t,
+}
+
+extern "C" {
+    fn ledc_hal_init(hal: *mut ledc_hal_context_t, speed_mode: ledc_mode_t);
+    fn ledc_hal_get_clk_cfg(
+        hal: *mut ledc_hal_context_t,
+        timer_sel: ledc_timer_t,
+        clk_cfg: *mut ledc_clk_cfg_t,
+    );
+}
+
 crate::embedded_hal_error!(
     PwmError,
     embedded_hal::pwm::Error,
@@ -62,6 +108,7 @@ pub mod config {
         pub frequency: Hertz,
         pub resolution: Resolution,
         pub speed_mode: SpeedMode,
+        pub clock_source: ClockSource,
     }
 
     impl TimerConfig {
@@ -86,6 +133,12 @@ pub mod config {
             self.speed_mode = mode;
             self
         }
+
+        #[must_use]
+        pub fn clock_source(mut self, source: ClockSource) -> Self {
+            self.clock_source = source;
+            self
+        }
     }
 
     impl Default for TimerConfig {
@@ -94,15 +147,180 @@ pub mod config {
                 frequency: 1000.Hz(),
                 resolution: Resolution::Bits8,
                 speed_mode: SpeedMode::LowSpeed,
+                clock_source: ClockSource::Auto,
             }
         }
     }
 }
 
+/// The maximum number of segments of a gamma fade
+pub const MAX_GAMMA_SEGMENTS: usize = 16;
+
+/// The maximum step count, step size and cycle count of a segment of a gamma fade
+const MAX_FADE_PARAM: u32 = (1 << 10) - 1;
+
+/// A segment of a gamma fade, fading linearly to `target_duty`
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub struct GammaSegment {
+    pub target_duty: u32,
+    /// The amount the duty changes by at each step, 1023 at most
+    pub scale: u32,
+    /// The number of PWM cycles between two steps, 1023 at most
+    pub cycles: u32,
+}
+
+impl GammaSegment {
+    pub const fn new(target_duty: u32, scale: u32, cycles: u32) -> Self {
+        Self {
+            target_duty,
+            scale,
+            cycles,
+        }
+    }
+
+    /// Fills `segments` with a fade between the CIE 1931 lightnesses `from` and `to`,
+    /// in percent, which looks linear to the human eye, and returns the number of
+    /// segments filled
+    ///
+    /// Each segment takes about `cycles` PWM cycles.
+    pub fn cie1931(
+        from: f32,
+        to: f32,
+        max_duty: u32,
+        cycles: u32,
+        segments: &mut [GammaSegment],
+    ) -> usize {
+        let count = segments.len().min(MAX_GAMMA_SEGMENTS);
+
+        let mut duty = cie1931_duty(from, max_duty);
+
+        for (index, segment) in segments[..count].iter_mut().enumerate() {
+            let lightness = from + (to - from) * (index + 1) as f32 / count as f32;
+            let target_duty = cie1931_duty(lightness, max_duty);
+
+            let delta = target_duty.abs_diff(duty);
+
+            // The step count is limited, so bigger steps have to be taken for big changes
+            let scale = ((delta + MAX_FADE_PARAM - 1) / MAX_FADE_PARAM).clamp(1, MAX_FADE_PARAM);
+            let steps = (delta / scale).max(1);
+
+            *segment = GammaSegment::new(
+                target_duty,
+                scale,
+                (cycles / steps).clamp(1, MAX_FADE_PARAM),
+            );
+
+            duty = target_duty;
+        }
+
+        count
+    }
+}
+
+/// A validated segment of a gamma fade, in the terms of the hardware
+#[allow(dead_code)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+struct GammaStep {
+    increase: bool,
+    steps: u32,
+    scale: u32,
+    cycles: u32,
+}
+
+/// Converts `segments` into the steps of a fade from `start_duty`, skipping the segments
+/// without any step, and returns the number of steps and the duty the fade ends at
+#[allow(dead_code)]
+fn gamma_steps(
+    segments: &[GammaSegment],
+    start_duty: u32,
+    max_duty: u32,
+    steps: &mut [GammaStep; MAX_GAMMA_SEGMENTS],
+) -> Result<(usize, u32), EspError> {
+    if segments.is_empty() || segments.len() > MAX_GAMMA_SEGMENTS {
+        return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
+    }
+
+    let mut count = 0;
+    let mut duty = start_duty;
+
+    for segment in segments {
+        if segment.scale == 0
+            || segment.scale > MAX_FADE_PARAM
+            || segment.cycles == 0
+            || segment.cycles > MAX_FADE_PARAM
+        {
+            return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
+        }
+
+        let target_duty = segment.target_duty.min(max_duty);
+        let step_count = target_duty.abs_diff(duty) / segment.scale;
+
+        if step_count > MAX_FADE_PARAM {
+            return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
+        }
+
+        if step_count == 0 {
+            continue;
+        }
+
+        let increase = target_duty > duty;
+
+        steps[count] = GammaStep {
+            increase,
+            steps: step_count,
+            scale: segment.scale,
+            cycles: segment.cycles,
+        };
+
+        if increase {
+            duty += step_count * segment.scale;
+        } else {
+            duty -= step_count * segment.scale;
+        }
+
+        count += 1;
+    }
+
+    Ok((count, duty))
+}
+
+/// The duty of the CIE 1931 `lightness`, in percent
+fn cie1931_duty(lightness: f32, max_duty: u32) -> u32 {
+    let lightness = lightness.clamp(0.0, 100.0);
+
+    let luminance = if lightness <= 8.0 {
+        lightness / 903.3
+    } else {
+        let x = (lightness + 16.0) / 116.0;
+        x * x * x
+    };
+
+    (luminance * max_duty as f32 + 0.5) as u32
+}
+
+/// Whether starting a fade waits for it to be done
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum FadeMode {
+    /// Returns once the fade is done
+    Blocking,
+    /// Returns right away, with the fade going on in the background
+    NonBlocking,
+}
+
+impl From<FadeMode> for ledc_fade_mode_t {
+    fn from(mode: FadeMode) -> Self {
+        match mode {
+            FadeMode::Blocking => ledc_fade_mode_t_LEDC_FADE_WAIT_DONE,
+            FadeMode::NonBlocking => ledc_fade_mode_t_LEDC_FADE_NO_WAIT,
+        }
+    }
+}
+
 /// LED Control timer driver
 pub struct LedcTimerDriver<'d> {
     timer: u8,
     speed_mode: SpeedMode,
+    clock_source: ClockSource,
     max_duty: Duty,
     _p: PhantomData<&'d mut ()>,
 }
@@ -112,6 +330,12 @@ impl<'d> LedcTimerDriver<'d> {
         _timer: impl Peripheral<P = T> + 'd,
         config: &config::TimerConfig,
     ) -> Result<Self, EspError> {
+        // The high speed timers can only be clocked by the APB clock or REF_TICK
+        #[cfg(esp_idf_soc_ledc_support_hs_mode)]
+        if config.speed_mode == SpeedMode::HighSpeed && config.clock_source == ClockSource::RcFast {
+            return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
+        }
+
         let timer_config = ledc_timer_config_t {
             speed_mode: config.speed_mode.into(),
             timer_num: T::timer(),
@@ -122,10 +346,7 @@ impl<'d> LedcTimerDriver<'d> {
             #[cfg(not(esp_idf_version_major = "4"))]
             duty_resolution: config.resolution.timer_bits(),
             freq_hz: config.frequency.into(),
-            #[cfg(any(esp_idf_version_major = "4", esp_idf_version_minor = "0"))]
-            clk_cfg: ledc_clk_cfg_t_LEDC_AUTO_CLK,
-            #[cfg(not(any(esp_idf_version_major = "4", esp_idf_version_minor = "0")))]
-            clk_cfg: soc_periph_ledc_clk_src_legacy_t_LEDC_AUTO_CLK,
+            clk_cfg: config.clock_source.into(),
             #[cfg(not(any(
                 esp_idf_version_major = "4",
                 all(esp_idf_version_major = "5", esp_idf_version_minor = "0"),
@@ -140,6 +361,7 @@ impl<'d> LedcTimerDriver<'d> {
         Ok(Self {
             timer: T::timer() as _,
             speed_mode: config.speed_mode,
+            clock_source: config.clock_source,
             max_duty: config.resolution.max_duty(),
             _p: PhantomData,
         })
@@ -158,13 +380,41 @@ impl<'d> LedcTimerDriver<'d> {
     }
 
     /// Set the frequency of the timer.
+    ///
+    /// The resolution of the timer is kept, so the duty values - and thus the duty
+    /// cycles - of the channels bound to the timer stay valid, and nothing has to be
+    /// reapplied.
+    ///
+    /// Returns `ESP_ERR_INVALID_ARG` if the frequency can't be derived from the clock
+    /// of the timer at its resolution, in which case the frequency is left unchanged.
     pub fn set_frequency(&mut self, frequency: Hertz) -> Result<(), EspError> {
-        esp!(unsafe {
-            ledc_set_freq(self.speed_mode.into(), self.timer.into(), frequency.into())
-        })?;
+        if frequency.0 == 0 {
+            return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
+        }
+
+        esp!(unsafe { ledc_set_freq(self.speed_mode.into(), self.timer.into(), frequency.into()) })
+            .map_err(|err| {
+                // The divider being out of range is reported as ESP_FAIL
+                if err.code() == ESP_FAIL {
+                    EspError::from_infallible::<ESP_ERR_INVALID_ARG>()
+                } else {
+                    err
+                }
+            })?;
         Ok(())
     }
 
+    /// The actual frequency of the timer, which differs from the configured one by the
+    /// rounding of the clock divider
+    pub fn frequency(&self) -> Hertz {
+        Hertz(unsafe { ledc_get_freq(self.speed_mode.into(), self.timer()) })
+    }
+
+    /// The maximum duty of the channels bound to the timer, given by its resolution
+    pub fn max_duty(&self) -> u32 {
+        self.max_duty
+    }
+
     fn reset(&mut self) -> Result<(), EspError> {
         esp!(unsafe { ledc_timer_rst(self.speed_mode.into(), self.timer()) })?;
         Ok(())
@@ -173,6 +423,37 @@ impl<'d> LedcTimerDriver<'d> {
     pub fn timer(&self) -> ledc_timer_t {
         self.timer as _
     }
+
+    pub fn speed_mode(&self) -> SpeedMode {
+        self.speed_mode
+    }
+
+    /// The clock source of the timer: the configured one, or - with `ClockSource::Auto` -
+    /// the one ESP-IDF picked, as read back from the timer
+    pub fn clock_source(&self) -> ClockSource {
+        if self.clock_source != ClockSource::Auto {
+            return self.clock_source;
+        }
+
+        let mut hal = ledc_hal_context_t {
+            dev: core::ptr::null_mut(),
+            speed_mode: self.speed_mode.into(),
+        };
+
+        let mut clk_cfg: ledc_clk_cfg_t = ClockSource::Auto.into();
+
+        // The same as what `ledc_get_freq` does, to know the frequency of the clock source
+        unsafe {
+            ledc_hal_init(&mut hal, self.speed_mode.into());
+            ledc_hal_get_clk_cfg(&mut hal, self.timer(), &mut clk_cfg);
+        }
+
+        ClockSource::SOURCES
+            .iter()
+            .copied()
+            .find(|source| ledc_clk_cfg_t::from(*source) == clk_cfg)
+            .unwrap_or(ClockSource::Auto)
+    }
 }
 
 impl<'d> Drop for LedcTimerDriver<'d> {
@@ -240,7 +521,7 @@ impl<'d> LedcDriver<'d> {
         // it.
         esp!(unsafe { ledc_channel_config(&channel_config) })?;
 
-        Ok(LedcDriver {
+        let driver = LedcDriver {
             duty,
             hpoint,
             speed_mode: timer_driver.borrow().speed_mode,
@@ -248,7 +529,23 @@ impl<'d> LedcDriver<'d> {
             timer: timer_driver.borrow().timer() as _,
             channel: C::channel() as _,
             _p: PhantomData,
-        })
+        };
+
+        FADING[driver.fade_index()].store(false, Ordering::SeqCst);
+        FADE_END_NOTIF[driver.fade_index()].reset();
+
+        esp!(unsafe {
+            ledc_cb_register(
+                driver.speed_mode.into(),
+                driver.channel(),
+                &mut ledc_cbs_t {
+                    fade_cb: Some(dispatch_fade_end),
+                },
+                driver.fade_index() as *mut c_void,
+            )
+        })?;
+
+        Ok(driver)
     }
 
     pub fn get_duty(&self) -> Duty {
@@ -259,10 +556,37 @@ impl<'d> LedcDriver<'d> {
         self.hpoint
     }
 
+    /// The duty of a 100% duty cycle, with no off-time, unless the resolution of the
+    /// timer is the maximum one
     pub fn get_max_duty(&self) -> Duty {
         self.max_duty
     }
 
+    pub fn get_max_hpoint(&self) -> HPoint {
+        // The maximum duty is either `2^bits` or `2^bits - 1`
+        self.max_duty.next_power_of_two() - 1
+    }
+
+    /// Reads the current duty back from the hardware, which differs from `get_duty`
+    /// while the channel is disabled or fading
+    pub fn read_duty(&self) -> Duty {
+        unsafe { ledc_get_duty(self.speed_mode.into(), self.channel()) }
+    }
+
+    /// Sets a duty cycle of `num / den`, saturating at 100%
+    pub fn set_duty_fraction(&mut self, num: u32, den: u32) -> Result<(), EspError> {
+        if den == 0 {
+            return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
+        }
+
+        let num = num.min(den) as u64;
+        let den = den as u64;
+
+        let duty = (num * self.get_max_duty() as u64 + den / 2) / den;
+
+        self.set_duty(duty as Duty)
+    }
+
     pub fn disable(&mut self) -> Result<(), EspError> {
         self.update_duty(0, 0)?;
         Ok(())
@@ -281,24 +605,332 @@ impl<'d> LedcDriver<'d> {
         self.set_duty_with_hpoint(self.duty, hpoint)
     }
 
+    /// Sets the duty and the hpoint, i.e. the counter value at which the output goes
+    /// high, which shifts the phase of the output relative to the timer
+    ///
+    /// Both are latched together, so that no PWM cycle has the new duty with the old
+    /// hpoint or vice versa.
+    ///
+    /// Returns `ESP_ERR_INVALID_ARG` if `hpoint` is above `get_max_hpoint`.
     pub fn set_duty_with_hpoint(&mut self, duty: Duty, hpoint: HPoint) -> Result<(), EspError> {
+        if hpoint > self.get_max_hpoint() {
+            return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
+        }
+
         // Clamp the actual duty cycle to the current maximum as done by other
         // Pwm/PwmPin implementations.
         let max_duty = self.get_max_duty();
         let clamped_duty = duty.min(max_duty);
-        let clamped_hpoint = hpoint.min(max_duty);
         self.duty = clamped_duty;
-        self.hpoint = clamped_hpoint;
-        self.update_duty(clamped_duty, clamped_hpoint)?;
+        self.hpoint = hpoint;
+        self.update_duty(clamped_duty, hpoint)?;
+        Ok(())
+    }
+
+    /// Sets the duty without latching it, so that it can be latched together with the
+    /// duties of other channels with `update_synchronized`
+    ///
+    /// Until then, the output keeps the previous duty.
+    pub fn set_duty_deferred(&mut self, duty: Duty) -> Result<(), EspError> {
+        self.stop_fade()?;
+
+        let duty = duty.min(self.get_max_duty());
+
+        esp!(unsafe {
+            ledc_set_duty_with_hpoint(self.speed_mode.into(), self.channel(), duty, self.hpoint)
+        })?;
+
+        self.duty = duty;
+
+        Ok(())
+    }
+
+    /// Shifts the phase of the output relative to the timer by `degrees`, keeping the
+    /// duty, by setting the hpoint accordingly
+    pub fn phase_degrees(&mut self, degrees: f32) -> Result<(), EspError> {
+        if !degrees.is_finite() {
+            return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
+        }
+
+        let period = self.get_max_hpoint() + 1;
+
+        let turns = degrees / 360.0;
+        let mut fraction = turns - (turns as i32) as f32;
+        if fraction < 0.0 {
+            fraction += 1.0;
+        }
+
+        let hpoint = (fraction * period as f32 + 0.5) as u32 % period;
+
+        self.set_duty_with_hpoint(self.duty, hpoint)
+    }
+
+    /// Fades the duty to `target_duty` within `ms` milliseconds, in hardware
+    ///
+    /// Any ongoing fade is aborted first on ESP-IDF 5, while on ESP-IDF 4
+    /// `ESP_ERR_INVALID_STATE` is returned instead.
+    pub fn fade_with_time(
+        &mut self,
+        target_duty: Duty,
+        ms: u32,
+        mode: FadeMode,
+    ) -> Result<(), EspError> {
+        let target_duty = target_duty.min(self.get_max_duty());
+
+        self.stop_fade()?;
+
+        esp!(unsafe {
+            ledc_set_fade_with_time(self.speed_mode.into(), self.channel(), target_duty, ms as _)
+        })?;
+
+        let (speed_mode, channel): (ledc_mode_t, _) = (self.speed_mode.into(), self.channel());
+
+        self.start_fade(target_duty, move || unsafe {
+            ledc_fade_start(speed_mode, channel, mode.into())
+        })
+    }
+
+    /// Fades the duty to `target_duty` in hardware, changing it by `scale` every `cycles`
+    /// PWM cycles
+    ///
+    /// Any ongoing fade is aborted first on ESP-IDF 5, while on ESP-IDF 4
+    /// `ESP_ERR_INVALID_STATE` is returned instead.
+    pub fn fade_with_step(
+        &mut self,
+        target_duty: Duty,
+        scale: u32,
+        cycles: u32,
+        mode: FadeMode,
+    ) -> Result<(), EspError> {
+        let target_duty = target_duty.min(self.get_max_duty());
+
+        self.stop_fade()?;
+
+        let (speed_mode, channel): (ledc_mode_t, _) = (self.speed_mode.into(), self.channel());
+
+        self.start_fade(target_duty, move || unsafe {
+            ledc_set_fade_step_and_start(
+                speed_mode,
+                channel,
+                target_duty,
+                scale,
+                cycles,
+                mode.into(),
+            )
+        })
+    }
+
+    /// Whether a fade started with `FadeMode::NonBlocking` is still going on
+    pub fn is_fading(&self) -> bool {
+        FADING[self.fade_index()].load(Ordering::SeqCst)
+    }
+
+    /// Waits until the ongoing fade, if any, is done
+    pub fn wait_fade_done(&self) {
+        while self.is_fading() {
+            FreeRtos::delay_ms(1);
+        }
+    }
+
+    /// Like `fade_with_time`, waiting asynchronously for the fade to be done
+    pub async fn fade_with_time_async(
+        &mut self,
+        target_duty: Duty,
+        ms: u32,
+    ) -> Result<(), EspError> {
+        FADE_END_NOTIF[self.fade_index()].reset();
+
+        self.fade_with_time(target_duty, ms, FadeMode::NonBlocking)?;
+
+        self.wait_fade_done_async().await;
+
+        Ok(())
+    }
+
+    /// Waits asynchronously until the ongoing fade, if any, is done
+    pub async fn wait_fade_done_async(&self) {
+        while self.is_fading() {
+            FADE_END_NOTIF[self.fade_index()].wait().await;
+        }
+    }
+
+    /// Subscribes the provided callback for the end of each fade of the channel.
+    ///
+    /// The subscription is kept across fades, until `unsubscribe_fade_end` is called or
+    /// the driver is dropped, which only affects this channel.
+    ///
+    /// # Safety
+    ///
+    /// Care should be taken not to call STD, libc or FreeRTOS APIs (except for a few allowed ones)
+    /// in the callback passed to this function, as it is executed in an ISR context.
+    #[cfg(feature = "alloc")]
+    pub unsafe fn subscribe_fade_end<F: FnMut() + Send + 'static>(
+        &mut self,
+        callback: F,
+    ) -> Result<(), EspError> {
+        let callback: alloc::boxed::Box<dyn FnMut() + Send + 'static> =
+            alloc::boxed::Box::new(callback);
+
+        let index = self.fade_index();
+
+        let previous = crate::interrupt::free(|| FADE_END_HANDLER[index].replace(callback));
+
+        // Dropped outside of the critical section
+        drop(previous);
+
+        Ok(())
+    }
+
+    /// Unsubscribes the callback subscribed with `subscribe_fade_end`, if any
+    #[cfg(feature = "alloc")]
+    pub fn unsubscribe_fade_end(&mut self) -> Result<(), EspError> {
+        let index = self.fade_index();
+
+        let callback = crate::interrupt::free(|| unsafe { FADE_END_HANDLER[index].take() });
+
+        // Dropped outside of the critical section
+        drop(callback);
+
         Ok(())
     }
 
+    /// Aborts the ongoing fade, if any, with the duty staying at the level reached
+    #[cfg(not(esp_idf_version_major = "4"))]
+    pub fn stop_fade(&mut self) -> Result<(), EspError> {
+        if self.is_fading() {
+            esp!(unsafe { ledc_fade_stop(self.speed_mode.into(), self.channel()) })?;
+
+            FADING[self.fade_index()].store(false, Ordering::SeqCst);
+            self.duty = unsafe { ledc_get_duty(self.speed_mode.into(), self.channel()) };
+        }
+
+        Ok(())
+    }
+
+    /// Returns `ESP_ERR_INVALID_STATE` if a fade is going on, as ESP-IDF 4 can't abort
+    /// fades
+    #[cfg(esp_idf_version_major = "4")]
+    pub fn stop_fade(&mut self) -> Result<(), EspError> {
+        if self.is_fading() {
+            Err(EspError::from_infallible::<ESP_ERR_INVALID_STATE>())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Fades the duty in hardware through the linear `segments`, which form e.g. a
+    /// gamma corrected fade, see `GammaSegment::cie1931`
+    ///
+    /// The steps of each segment are of `scale`, so the target duty of a segment is
+    /// reached up to a remainder of `scale`, which the next segment starts from.
+    ///
+    /// Returns `ESP_ERR_NOT_SUPPORTED` on chips without gamma curve fades or before
+    /// ESP-IDF 5.3.
+    #[cfg(all(
+        esp_idf_soc_ledc_gamma_curve_fade_supported,
+        not(any(
+            esp_idf_version_major = "4",
+            esp_idf_version = "5.0",
+            esp_idf_version = "5.1",
+            esp_idf_version = "5.2"
+        ))
+    ))]
+    pub fn fade_gamma(
+        &mut self,
+        segments: &[GammaSegment],
+        mode: FadeMode,
+    ) -> Result<(), EspError> {
+        if segments.is_empty() || segments.len() > MAX_GAMMA_SEGMENTS {
+            return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
+        }
+
+        self.stop_fade()?;
+
+        let start_duty = self.duty;
+
+        let mut steps = [GammaStep::default(); MAX_GAMMA_SEGMENTS];
+        let (count, duty) = gamma_steps(segments, start_duty, self.get_max_duty(), &mut steps)?;
+
+        if count == 0 {
+            return Ok(());
+        }
+
+        let mut params = [ledc_fade_param_config_t::default(); MAX_GAMMA_SEGMENTS];
+
+        for (param, step) in params.iter_mut().zip(&steps[..count]) {
+            param.set_dir(step.increase as u32);
+            param.set_cycle_num(step.cycles);
+            param.set_scale(step.scale);
+            param.set_step_num(step.steps);
+        }
+
+        let (speed_mode, channel): (ledc_mode_t, _) = (self.speed_mode.into(), self.channel());
+
+        self.start_fade(duty, move || unsafe {
+            ledc_set_multi_fade_and_start(
+                speed_mode,
+                channel,
+                start_duty,
+                params.as_ptr(),
+                count as _,
+                mode.into(),
+            )
+        })
+    }
+
+    /// Fades the duty in hardware through the linear `segments`, which form e.g. a
+    /// gamma corrected fade, see `GammaSegment::cie1931`
+    ///
+    /// Returns `ESP_ERR_NOT_SUPPORTED` on chips without gamma curve fades or before
+    /// ESP-IDF 5.3.
+    #[cfg(not(all(
+        esp_idf_soc_ledc_gamma_curve_fade_supported,
+        not(any(
+            esp_idf_version_major = "4",
+            esp_idf_version = "5.0",
+            esp_idf_version = "5.1",
+            esp_idf_version = "5.2"
+        ))
+    )))]
+    pub fn fade_gamma(
+        &mut self,
+        _segments: &[GammaSegment],
+        _mode: FadeMode,
+    ) -> Result<(), EspError> {
+        Err(EspError::from_infallible::<ESP_ERR_NOT_SUPPORTED>())
+    }
+
+    fn start_fade(
+        &mut self,
+        target_duty: Duty,
+        start: impl FnOnce() -> esp_err_t,
+    ) -> Result<(), EspError> {
+        // Marked before starting, as a blocking fade is done once started
+        FADING[self.fade_index()].store(true, Ordering::SeqCst);
+
+        if let Err(err) = esp!(start()) {
+            FADING[self.fade_index()].store(false, Ordering::SeqCst);
+
+            return Err(err);
+        }
+
+        self.duty = target_duty;
+
+        Ok(())
+    }
+
+    fn fade_index(&self) -> usize {
+        ledc_mode_t::from(self.speed_mode) as usize * CHANNELS + self.channel as usize
+    }
+
     fn stop(&mut self) -> Result<(), EspError> {
         esp!(unsafe { ledc_stop(self.speed_mode.into(), self.channel(), IDLE_LEVEL,) })?;
         Ok(())
     }
 
     fn update_duty(&mut self, duty: Duty, hpoint: HPoint) -> Result<(), EspError> {
+        self.stop_fade()?;
+
         esp!(unsafe {
             ledc_set_duty_and_update(self.speed_mode.into(), self.channel(), duty, hpoint)
         })?;
@@ -314,14 +946,82 @@ impl<'d> LedcDriver<'d> {
     }
 }
 
+/// Latches the duties set with `LedcDriver::set_duty_deferred` on all `channels`
+/// back to back, with interrupts disabled, so that they take effect in the same PWM
+/// period - e.g. for the colors of an RGB LED to change together
+///
+/// The channels may be bound to different timers, but have to share a speed mode, or
+/// `ESP_ERR_INVALID_ARG` is returned.
+pub fn update_synchronized(channels: &mut [&mut LedcDriver<'_>]) -> Result<(), EspError> {
+    if let Some(first) = channels.first() {
+        if channels
+            .iter()
+            .any(|channel| channel.speed_mode != first.speed_mode)
+        {
+            return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
+        }
+    }
+
+    crate::interrupt::free(|| {
+        for channel in channels.iter() {
+            esp!(unsafe { ledc_update_duty(channel.speed_mode.into(), channel.channel()) })?;
+        }
+
+        Ok(())
+    })
+}
+
 impl<'d> Drop for LedcDriver<'d> {
     fn drop(&mut self) {
+        #[cfg(not(esp_idf_version_major = "4"))]
+        self.stop_fade().unwrap();
+
+        esp!(unsafe {
+            ledc_cb_register(
+                self.speed_mode.into(),
+                self.channel(),
+                &mut ledc_cbs_t { fade_cb: None },
+                core::ptr::null_mut(),
+            )
+        })
+        .unwrap();
+
+        #[cfg(feature = "alloc")]
+        self.unsubscribe_fade_end().unwrap();
+
+        FADE_END_NOTIF[self.fade_index()].reset();
+
         self.stop().unwrap();
     }
 }
 
 unsafe impl<'d> Send for LedcDriver<'d> {}
 
+unsafe extern "C" fn dispatch_fade_end(
+    param: *const ledc_cb_param_t,
+    user_arg: *mut c_void,
+) -> bool {
+    let index = user_arg as usize;
+
+    let mut woken = false;
+
+    if (*param).event == ledc_cb_event_t_LEDC_FADE_END_EVT {
+        FADING[index].store(false, Ordering::SeqCst);
+        woken = FADE_END_NOTIF[index].notify_lsb();
+
+        #[cfg(feature = "alloc")]
+        {
+            let _guard = crate::interrupt::CS.enter();
+
+            if let Some(callback) = &mut FADE_END_HANDLER[index] {
+                callback();
+            }
+        }
+    }
+
+    woken
+}
+
 impl<'d> embedded_hal::pwm::ErrorType for LedcDriver<'d> {
     type Error = PwmError;
 }
@@ -342,7 +1042,9 @@ impl<'d> embedded_hal::pwm::SetDutyCycle for LedcDriver<'d> {
     }
 
     fn set_duty_cycle(&mut self, duty: u16) -> Result<(), PwmError> {
-        self.set_duty(duty as u32).map_err(to_pwm_err)
+        // Above 16 bits of resolution, the duty cycle is scaled to the actual duty
+        self.set_duty_fraction(duty as u32, self.max_duty_cycle() as u32)
+            .map_err(to_pwm_err)
     }
 
     fn set_duty_cycle_fully_on(&mut self) -> Result<(), PwmError> {
@@ -354,8 +1056,8 @@ impl<'d> embedded_hal::pwm::SetDutyCycle for LedcDriver<'d> {
     }
 
     fn set_duty_cycle_fraction(&mut self, num: u16, denom: u16) -> Result<(), PwmError> {
-        let duty = num as u32 * self.max_duty_cycle() as u32 / denom as u32;
-        self.set_duty_cycle(duty as u16)
+        self.set_duty_fraction(num as u32, denom as u32)
+            .map_err(to_pwm_err)
     }
 
     fn set_duty_cycle_percent(&mut self, percent: u8) -> Result<(), PwmError> {
@@ -393,9 +1095,252 @@ impl<'d> embedded_hal_0_2::PwmPin for LedcDriver<'d> {
     }
 }
 
+/// Hobby servo control, with pulses of e.g. 500us to 2500us at 50Hz
+pub mod servo {
+    use core::time::Duration;
+
+    use esp_idf_sys::*;
+
+    use crate::gpio::OutputPin;
+    use crate::peripheral::Peripheral;
+    use crate::units::*;
+
+    use super::{config::TimerConfig, LedcChannel, LedcDriver, LedcTimer, LedcTimerDriver};
+    use super::{Resolution, SpeedMode};
+
+    /// The frequency of the slowest clock the timers are usually clocked from
+    const TIMER_CLOCK_FREQ: u32 = 80_000_000;
+
+    /// The resolutions of the timers, by number of bits minus 1
+    const RESOLUTIONS: &[Resolution] = &[
+        Resolution::Bits1,
+        Resolution::Bits2,
+        Resolution::Bits3,
+        Resolution::Bits4,
+        Resolution::Bits5,
+        Resolution::Bits6,
+        Resolution::Bits7,
+        Resolution::Bits8,
+        Resolution::Bits9,
+        Resolution::Bits10,
+        Resolution::Bits11,
+        Resolution::Bits12,
+        Resolution::Bits13,
+        Resolution::Bits14,
+        #[cfg(esp32)]
+        Resolution::Bits15,
+        #[cfg(esp32)]
+        Resolution::Bits16,
+        #[cfg(esp32)]
+        Resolution::Bits17,
+        #[cfg(esp32)]
+        Resolution::Bits18,
+        #[cfg(esp32)]
+        Resolution::Bits19,
+        #[cfg(esp32)]
+        Resolution::Bits20,
+    ];
+
+    /// The configuration of a servo
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct ServoConfig {
+        pub frequency: Hertz,
+        /// The pulse width at an angle of 0 degrees
+        pub min_pulse_us: u16,
+        /// The pulse width at `max_angle`
+        pub max_pulse_us: u16,
+        /// The angle range of the servo, in degrees
+        pub max_angle: f32,
+    }
+
+    impl ServoConfig {
+        pub fn new() -> Self {
+            Default::default()
+        }
+
+        #[must_use]
+        pub fn frequency(mut self, frequency: Hertz) -> Self {
+            self.frequency = frequency;
+            self
+        }
+
+        #[must_use]
+        pub fn pulse_range_us(mut self, min_pulse_us: u16, max_pulse_us: u16) -> Self {
+            self.min_pulse_us = min_pulse_us;
+            self.max_pulse_us = max_pulse_us;
+            self
+        }
+
+        #[must_use]
+        pub fn max_angle(mut self, max_angle: f32) -> Self {
+            self.max_angle = max_angle;
+            self
+        }
+    }
+
+    impl Default for ServoConfig {
+        fn default() -> Self {
+            Self {
+                frequency: 50.Hz(),
+                min_pulse_us: 500,
+                max_pulse_us: 2500,
+                max_angle: 180.0,
+            }
+        }
+    }
+
+    /// A driver of a hobby servo, owning a timer and a channel
+    ///
+    /// The timer is configured with the highest resolution achievable at the frequency
+    /// of the servo, so that the pulse width is as precise as possible.
+    pub struct ServoDriver<'d> {
+        channel: LedcDriver<'d>,
+        timer: LedcTimerDriver<'d>,
+        config: ServoConfig,
+    }
+
+    impl<'d> ServoDriver<'d> {
+        pub fn new<C: LedcChannel, T: LedcTimer>(
+            timer: impl Peripheral<P = T> + 'd,
+            channel: impl Peripheral<P = C> + 'd,
+            pin: impl Peripheral<P = impl OutputPin> + 'd,
+            config: &ServoConfig,
+        ) -> Result<Self, EspError> {
+            if config.frequency.0 == 0
+                || config.min_pulse_us > config.max_pulse_us
+                || config.max_pulse_us as u64 * config.frequency.0 as u64 >= 1_000_000
+                || config.max_angle.is_nan()
+                || config.max_angle <= 0.0
+            {
+                return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
+            }
+
+            // The clock divider has to be at least 1
+            let bits = 31
+                - (TIMER_CLOCK_FREQ / config.frequency.0)
+                    .max(2)
+                    .leading_zeros();
+            let resolution = RESOLUTIONS[(bits as usize).min(RESOLUTIONS.len()) - 1];
+
+            let timer = LedcTimerDriver::new(
+                timer,
+                &TimerConfig::new()
+                    .frequency(config.frequency)
+                    .resolution(resolution)
+                    .speed_mode(SpeedMode::LowSpeed),
+            )?;
+
+            let channel = LedcDriver::new(channel, &timer, pin)?;
+
+            Ok(Self {
+                channel,
+                timer,
+                config: config.clone(),
+            })
+        }
+
+        pub fn config(&self) -> &ServoConfig {
+            &self.config
+        }
+
+        /// Changes the pulse widths at an angle of 0 degrees and at `max_angle`, e.g. to
+        /// calibrate the servo
+        pub fn set_pulse_range_us(
+            &mut self,
+            min_pulse_us: u16,
+            max_pulse_us: u16,
+        ) -> Result<(), EspError> {
+            if min_pulse_us > max_pulse_us
+                || max_pulse_us as u64 * self.timer.frequency().0 as u64 >= 1_000_000
+            {
+                return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
+            }
+
+            self.config.min_pulse_us = min_pulse_us;
+            self.config.max_pulse_us = max_pulse_us;
+
+            Ok(())
+        }
+
+        /// Turns the servo to `degrees`, clamped to the angle range of the servo
+        pub fn set_angle(&mut self, degrees: f32) -> Result<(), EspError> {
+            if degrees.is_nan() {
+                return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
+            }
+
+            let fraction = degrees.clamp(0.0, self.config.max_angle) / self.config.max_angle;
+            let range = (self.config.max_pulse_us - self.config.min_pulse_us) as f32;
+
+            let pulse_ns = (self.config.min_pulse_us as f32 + range * fraction) * 1000.0;
+
+            self.set_pulse_width_ns(pulse_ns as u64)
+        }
+
+        /// Outputs pulses of `us` microseconds, clamped to the pulse range of the servo
+        pub fn set_pulse_width_us(&mut self, us: u16) -> Result<(), EspError> {
+            self.set_pulse_width_ns(us as u64 * 1000)
+        }
+
+        /// Outputs pulses of `width`, clamped to the pulse range of the servo
+        pub fn set_pulse_width(&mut self, width: Duration) -> Result<(), EspError> {
+            self.set_pulse_width_ns(width.as_nanos().min(u64::MAX as u128) as u64)
+        }
+
+        /// The actual width of the pulses, which is quantized by the resolution of the
+        /// timer
+        pub fn pulse_width(&self) -> Duration {
+            let period_ns = 1_000_000_000 / self.timer.frequency().0.max(1) as u64;
+
+            Duration::from_nanos(self.channel.get_duty() as u64 * period_ns / self.counts())
+        }
+
+        pub fn resolution(&self) -> u32 {
+            self.counts().trailing_zeros()
+        }
+
+        /// The actual frequency of the pulses
+        pub fn frequency(&self) -> Hertz {
+            self.timer.frequency()
+        }
+
+        pub fn disable(&mut self) -> Result<(), EspError> {
+            self.channel.disable()
+        }
+
+        pub fn enable(&mut self) -> Result<(), EspError> {
+            self.channel.enable()
+        }
+
+        fn set_pulse_width_ns(&mut self, ns: u64) -> Result<(), EspError> {
+            let ns = ns.clamp(
+                self.config.min_pulse_us as u64 * 1000,
+                self.config.max_pulse_us as u64 * 1000,
+            );
+
+            let period_ns = 1_000_000_000 / self.timer.frequency().0.max(1) as u64;
+
+            // Rounded to the nearest duty, rather than truncated
+            let duty = (ns * self.counts() + period_ns / 2) / period_ns;
+
+            self.channel.set_duty(duty as u32)
+        }
+
+        /// The number of timer counts of a period
+        fn counts(&self) -> u64 {
+            self.channel.get_max_hpoint() as u64 + 1
+        }
+    }
+}
+
 mod chip {
     use esp_idf_sys::*;
 
+    /// The maximum resolution of the timers
+    #[cfg(any(esp32, esp32c6, esp32h2))]
+    const MAX_BITS: usize = 20;
+    #[cfg(not(any(esp32, esp32c6, esp32h2)))]
+    const MAX_BITS: usize = 14;
+
     #[derive(Debug, Copy, Clone, Eq, PartialEq)]
     pub enum Resolution {
         Bits1,
@@ -458,7 +1403,18 @@ mod chip {
             }
         }
 
+        /// The duty of a 100% duty cycle, i.e. `2^bits`, except at the maximum resolution
+        /// of the timers, where a 100% duty cycle can't be reached and it is `2^bits - 1`
         pub const fn max_duty(&self) -> u32 {
+            if self.bits() == MAX_BITS {
+                (1 << self.bits()) - 1
+            } else {
+                1 << self.bits()
+            }
+        }
+
+        /// The maximum hpoint, i.e. `2^bits - 1`
+        pub const fn max_hpoint(&self) -> u32 {
             (1 << self.bits()) - 1
         }
 
@@ -520,6 +1476,79 @@ mod chip {
         }
     }
 
+    /// Ledc Clock Source
+    #[derive(PartialEq, Eq, Copy, Clone, Debug, Default)]
+    pub enum ClockSource {
+        /// Picked by ESP-IDF, depending on the frequency and the resolution of the timer
+        #[default]
+        Auto,
+        /// The APB clock, which is lowered by dynamic frequency scaling
+        #[cfg(not(esp_idf_soc_ledc_support_pll_div_clock))]
+        Apb,
+        /// The divided PLL clock
+        #[cfg(esp_idf_soc_ledc_support_pll_div_clock)]
+        PllDiv,
+        /// The 1MHz REF_TICK clock, which keeps its frequency with dynamic frequency scaling
+        #[cfg(esp_idf_soc_ledc_support_ref_tick)]
+        RefTick,
+        /// The RC_FAST clock, which keeps running in light sleep. Low speed mode only.
+        RcFast,
+        /// The XTAL clock, which keeps its frequency with dynamic frequency scaling
+        #[cfg(esp_idf_soc_ledc_support_xtal_clock)]
+        Xtal,
+    }
+
+    impl ClockSource {
+        /// The actual clock sources of the chip, i.e. all but `ClockSource::Auto`
+        pub(crate) const SOURCES: &'static [ClockSource] = &[
+            #[cfg(not(esp_idf_soc_ledc_support_pll_div_clock))]
+            ClockSource::Apb,
+            #[cfg(esp_idf_soc_ledc_support_pll_div_clock)]
+            ClockSource::PllDiv,
+            #[cfg(esp_idf_soc_ledc_support_ref_tick)]
+            ClockSource::RefTick,
+            ClockSource::RcFast,
+            #[cfg(esp_idf_soc_ledc_support_xtal_clock)]
+            ClockSource::Xtal,
+        ];
+    }
+
+    #[cfg(any(esp_idf_version_major = "4", esp_idf_version_minor = "0"))]
+    impl From<ClockSource> for ledc_clk_cfg_t {
+        fn from(source: ClockSource) -> Self {
+            match source {
+                ClockSource::Auto => ledc_clk_cfg_t_LEDC_AUTO_CLK,
+                #[cfg(not(esp_idf_soc_ledc_support_pll_div_clock))]
+                ClockSource::Apb => ledc_clk_cfg_t_LEDC_USE_APB_CLK,
+                #[cfg(esp_idf_soc_ledc_support_pll_div_clock)]
+                ClockSource::PllDiv => ledc_clk_cfg_t_LEDC_USE_PLL_DIV_CLK,
+                #[cfg(esp_idf_soc_ledc_support_ref_tick)]
+                ClockSource::RefTick => ledc_clk_cfg_t_LEDC_USE_REF_TICK,
+                ClockSource::RcFast => ledc_clk_cfg_t_LEDC_USE_RTC8M_CLK,
+                #[cfg(esp_idf_soc_ledc_support_xtal_clock)]
+                ClockSource::Xtal => ledc_clk_cfg_t_LEDC_USE_XTAL_CLK,
+            }
+        }
+    }
+
+    #[cfg(not(any(esp_idf_version_major = "4", esp_idf_version_minor = "0")))]
+    impl From<ClockSource> for soc_periph_ledc_clk_src_legacy_t {
+        fn from(source: ClockSource) -> Self {
+            match source {
+                ClockSource::Auto => soc_periph_ledc_clk_src_legacy_t_LEDC_AUTO_CLK,
+                #[cfg(not(esp_idf_soc_ledc_support_pll_div_clock))]
+                ClockSource::Apb => soc_periph_ledc_clk_src_legacy_t_LEDC_USE_APB_CLK,
+                #[cfg(esp_idf_soc_ledc_support_pll_div_clock)]
+                ClockSource::PllDiv => soc_periph_ledc_clk_src_legacy_t_LEDC_USE_PLL_DIV_CLK,
+                #[cfg(esp_idf_soc_ledc_support_ref_tick)]
+                ClockSource::RefTick => soc_periph_ledc_clk_src_legacy_t_LEDC_USE_REF_TICK,
+                ClockSource::RcFast => soc_periph_ledc_clk_src_legacy_t_LEDC_USE_RC_FAST_CLK,
+                #[cfg(esp_idf_soc_ledc_support_xtal_clock)]
+                ClockSource::Xtal => soc_periph_ledc_clk_src_legacy_t_LEDC_USE_XTAL_CLK,
+            }
+        }
+    }
+
     /// LED Control peripheral timer
     pub trait LedcTimer {
         fn timer() -> ledc_timer_t;
@@ -618,3 +1647,137 @@ mod chip {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use esp_idf_sys::{esp_err_t, ESP_ERR_INVALID_ARG};
+
+    use super::{cie1931_duty, gamma_steps, GammaSegment, GammaStep, MAX_GAMMA_SEGMENTS};
+
+    const MAX_DUTY: u32 = (1 << 13) - 1;
+
+    type Steps = [GammaStep; MAX_GAMMA_SEGMENTS];
+
+    /// The steps of a fade from `start_duty`, their number and the duty the fade ends at
+    fn steps(segments: &[GammaSegment], start_duty: u32) -> Result<(Steps, usize, u32), esp_err_t> {
+        let mut steps = [GammaStep::default(); MAX_GAMMA_SEGMENTS];
+
+        gamma_steps(segments, start_duty, MAX_DUTY, &mut steps)
+            .map(|(count, duty)| (steps, count, duty))
+            .map_err(|e| e.code())
+    }
+
+    #[test]
+    fn cie1931_duty_bounds() {
+        assert_eq!(cie1931_duty(0.0, MAX_DUTY), 0);
+        assert_eq!(cie1931_duty(100.0, MAX_DUTY), MAX_DUTY);
+        assert_eq!(cie1931_duty(-10.0, MAX_DUTY), 0);
+        assert_eq!(cie1931_duty(150.0, MAX_DUTY), MAX_DUTY);
+    }
+
+    #[test]
+    fn cie1931_fade_up() {
+        let mut segments = [GammaSegment::default(); MAX_GAMMA_SEGMENTS + 4];
+
+        let count = GammaSegment::cie1931(0.0, 100.0, MAX_DUTY, 1000, &mut segments);
+
+        assert_eq!(count, MAX_GAMMA_SEGMENTS);
+        assert_eq!(segments[count - 1].target_duty, MAX_DUTY);
+        assert!(segments[count..]
+            .iter()
+            .all(|s| *s == GammaSegment::default()));
+
+        for pair in segments[..count].windows(2) {
+            assert!(pair[0].target_duty < pair[1].target_duty);
+        }
+
+        for segment in &segments[..count] {
+            assert!((1..=1023).contains(&segment.scale));
+            assert!((1..=1023).contains(&segment.cycles));
+        }
+
+        let (_, _, duty) = steps(&segments[..count], 0).unwrap();
+        assert!(MAX_DUTY - duty < segments[count - 1].scale);
+    }
+
+    #[test]
+    fn cie1931_fade_down() {
+        let mut segments = [GammaSegment::default(); 4];
+
+        let count = GammaSegment::cie1931(100.0, 0.0, MAX_DUTY, 1000, &mut segments);
+
+        assert_eq!(count, 4);
+        assert_eq!(segments[count - 1].target_duty, 0);
+
+        for pair in segments[..count].windows(2) {
+            assert!(pair[0].target_duty > pair[1].target_duty);
+        }
+
+        let (steps, step_count, _) = steps(&segments[..count], MAX_DUTY).unwrap();
+        assert!(steps[..step_count].iter().all(|step| !step.increase));
+    }
+
+    #[test]
+    fn gamma_steps_remainder() {
+        let (steps, step_count, duty) = steps(
+            &[GammaSegment::new(10, 3, 5), GammaSegment::new(0, 4, 5)],
+            0,
+        )
+        .unwrap();
+
+        // The first segment stops at 9, and the second one starts from there
+        assert_eq!(
+            steps[..step_count],
+            [
+                GammaStep {
+                    increase: true,
+                    steps: 3,
+                    scale: 3,
+                    cycles: 5
+                },
+                GammaStep {
+                    increase: false,
+                    steps: 2,
+                    scale: 4,
+                    cycles: 5
+                },
+            ]
+        );
+        assert_eq!(duty, 1);
+    }
+
+    #[test]
+    fn gamma_steps_skip_and_clamp() {
+        // No step for the first segment, and the target of the second one is clamped
+        let (steps, step_count, duty) = steps(
+            &[
+                GammaSegment::new(0, 1, 1),
+                GammaSegment::new(u32::MAX, 1023, 1),
+            ],
+            0,
+        )
+        .unwrap();
+
+        assert_eq!(step_count, 1);
+        assert_eq!(steps[0].steps, MAX_DUTY / 1023);
+        assert_eq!(duty, MAX_DUTY / 1023 * 1023);
+    }
+
+    #[test]
+    fn gamma_steps_invalid() {
+        let invalid = [
+            &[][..],
+            &[GammaSegment::new(100, 1, 1); MAX_GAMMA_SEGMENTS + 1][..],
+            &[GammaSegment::new(100, 0, 1)][..],
+            &[GammaSegment::new(100, 1024, 1)][..],
+            &[GammaSegment::new(100, 1, 0)][..],
+            &[GammaSegment::new(100, 1, 1024)][..],
+            // 2000 steps of 1
+            &[GammaSegment::new(2000, 1, 1)][..],
+        ];
+
+        for segments in invalid {
+            assert_eq!(steps(segments, 0), Err(ESP_ERR_INVALID_ARG));
+        }
+    }
+}
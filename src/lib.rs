@@ -23,7 +23,15 @@ extern crate alloc;
 pub mod adc;
 pub mod can;
 pub mod cpu;
+#[cfg(any(esp32, esp32s2))]
+pub mod dac;
 pub mod delay;
+pub mod dma;
+#[cfg(all(
+    esp_idf_soc_etm_supported,
+    not(any(esp_idf_version_major = "4", esp_idf_version = "5.0"))
+))]
+pub mod etm;
 pub mod gpio;
 #[cfg(all(esp32, esp_idf_version_major = "4"))]
 pub mod hall;
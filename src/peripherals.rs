@@ -37,6 +37,8 @@ pub struct Peripherals {
     pub i2c0: i2c::I2C0,
     #[cfg(not(any(esp32c3, esp32c2, esp32c6)))]
     pub i2c1: i2c::I2C1,
+    #[cfg(esp32c6)]
+    pub lp_i2c0: i2c::LPI2C0,
     #[cfg(esp_idf_soc_i2s_supported)]
     pub i2s0: i2s::I2S0,
     #[cfg(all(esp_idf_soc_i2s_supported, any(esp32, esp32s3)))]
@@ -133,6 +135,8 @@ impl Peripherals {
             i2c0: i2c::I2C0::new(),
             #[cfg(not(any(esp32c3, esp32c2, esp32c6)))]
             i2c1: i2c::I2C1::new(),
+            #[cfg(esp32c6)]
+            lp_i2c0: i2c::LPI2C0::new(),
             #[cfg(esp_idf_soc_i2s_supported)]
             i2s0: i2s::I2S0::new(),
             #[cfg(all(esp_idf_soc_i2s_supported, any(esp32, esp32s3)))]
@@ -16,7 +16,7 @@
 //! |   | 1-3 devices      |           Y          |              N               |              Y             |         N         |
 //! |   | 4-6 devices      |    Only on esp32CX   |              N               |              Y             |         N         |
 //! |   | More than 6      |           N          |              N               |              Y             |         N         |
-//! |   | DMA              |           N          |              N               |              N             |         N         |
+//! |   | DMA              |           Y          |              Y               |              Y             |         Y         |
 //! |   | Polling transmit |           Y          |              Y               |              Y             |         Y         |
 //! |   | ISR transmit     |           Y          |              Y               |              Y             |         Y         |
 //! |   | Async support*   |           Y          |              Y               |              Y             |         Y         |
@@ -29,9 +29,18 @@
 //! The primitive [FullDuplex::read] and [FullDuplex::send] do not lock the APB frequency and
 //! therefore may run at a different frequency.
 //!
-//! # TODO
-//! - Quad SPI
-//! - Slave SPI
+//! # DMA
+//!
+//! DMA is enabled for the whole bus with [config::DriverConfig::dma]. With DMA enabled, all drivers
+//! on the bus transparently use it for their transfers, and buffers longer than the maximum transfer
+//! size of the bus are split into several (queued) transactions.
+//!
+//! Buffers which are not DMA-capable (e.g. placed in PSRAM or in flash) are copied by ESP-IDF into
+//! a temporary DMA-capable buffer allocated from the internal heap for the duration of each
+//! transaction. If that allocation fails, the transfer fails with `ESP_ERR_NO_MEM`. Use buffers in
+//! internal RAM (e.g. a [crate::dma::DmaBuffer] with the `xxx_dma()` methods) to avoid the extra copy.
+//!
+//! For operating the SPI peripheral as a slave, see the [slave] module.
 
 use core::borrow::{Borrow, BorrowMut};
 use core::cell::Cell;
@@ -40,8 +49,13 @@ use core::cmp::{max, min, Ordering};
 use core::future::Future;
 use core::iter::once;
 use core::marker::PhantomData;
+#[cfg(feature = "alloc")]
+use core::mem::ManuallyDrop;
 use core::ptr;
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
 use embassy_sync::mutex::Mutex;
 use embedded_hal::spi::{SpiBus, SpiDevice};
 
@@ -49,7 +63,8 @@ use esp_idf_sys::*;
 use heapless::Deque;
 
 use crate::delay::{self, Ets, BLOCK};
-use crate::gpio::{AnyOutputPin, InputPin, Level, Output, OutputMode, OutputPin, PinDriver};
+use crate::dma::DmaBuffer;
+use crate::gpio::{AnyOutputPin, IOPin, InputPin, Level, Output, OutputMode, OutputPin, PinDriver};
 use crate::interrupt::asynch::HalIsrNotification;
 use crate::interrupt::InterruptType;
 use crate::peripheral::Peripheral;
@@ -58,12 +73,20 @@ use crate::task::CriticalSection;
 
 pub use embedded_hal::spi::Operation;
 
+pub mod slave;
+
 crate::embedded_hal_error!(
     SpiError,
     embedded_hal::spi::Error,
     embedded_hal::spi::ErrorKind
 );
 
+/// The maximum width in bits of the command phase of a transaction (see `SpiDeviceDriver::transaction_ext`)
+pub const MAX_COMMAND_BITS: u8 = 16;
+
+/// The maximum width in bits of the address phase of a transaction (see `SpiDeviceDriver::transaction_ext`)
+pub const MAX_ADDRESS_BITS: u8 = 64;
+
 pub trait Spi: Send {
     fn device() -> spi_host_device_t;
 }
@@ -72,11 +95,20 @@ pub trait Spi: Send {
 /// should use a fixed set of pins
 pub trait SpiAnyPins: Spi {}
 
+/// The DMA configuration of an SPI bus
+///
+/// The `usize` value of each DMA variant is the maximum size in bytes of a single transaction.
+/// It must be a multiple of 4. Longer buffers are automatically split into several transactions.
+///
+/// Without DMA, the maximum transaction size is the size of the hardware FIFO (64 bytes).
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum Dma {
     Disabled,
+    /// DMA channel 1. Only valid on the esp32 and esp32s2, which have dedicated SPI DMA channels
     Channel1(usize),
+    /// DMA channel 2. Only valid on the esp32 and esp32s2, which have dedicated SPI DMA channels
     Channel2(usize),
+    /// A DMA channel allocated automatically by ESP-IDF
     Auto(usize),
 }
 
@@ -92,6 +124,7 @@ impl From<Dma> for spi_dma_chan_t {
 }
 
 impl Dma {
+    /// Returns the maximum size in bytes of a single transaction
     pub const fn max_transfer_size(&self) -> usize {
         let max_transfer_size = match self {
             Dma::Disabled => TRANS_LEN,
@@ -196,6 +229,35 @@ pub mod config {
         }
     }
 
+    /// Specifies the number of data lines used in the data phase of the transactions
+    ///
+    /// The command and address phases of the transactions (see `SpiDeviceDriver::transaction_ext`)
+    /// are always sent on a single line, as expected by most Dual/Quad/Octal SPI devices
+    #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+    pub enum LineMode {
+        /// A single data line in each direction (default)
+        Single,
+        /// Two data lines (requires half duplex)
+        Dual,
+        /// Four data lines (requires half duplex, and a bus created with `SpiDriver::new_quad`)
+        Quad,
+        /// Eight data lines (requires half duplex, and a bus created with `SpiDriver::new_octal`)
+        #[cfg(esp32s3)]
+        Octal,
+    }
+
+    impl LineMode {
+        pub fn as_flags(&self) -> u32 {
+            match self {
+                Self::Single => 0,
+                Self::Dual => SPI_TRANS_MODE_DIO,
+                Self::Quad => SPI_TRANS_MODE_QIO,
+                #[cfg(esp32s3)]
+                Self::Octal => SPI_TRANS_MODE_OCT,
+            }
+        }
+    }
+
     /// SPI Driver configuration
     #[derive(Debug, Clone)]
     pub struct DriverConfig {
@@ -208,6 +270,9 @@ pub mod config {
             Default::default()
         }
 
+        /// Configures DMA for the bus. The maximum transfer size of the bus can be raised
+        /// with the size of the DMA variant (e.g. `Dma::Auto(32 * 1024)`), so that large
+        /// buffers are transferred with fewer (and longer) transactions.
         #[must_use]
         pub fn dma(mut self, dma: Dma) -> Self {
             self.dma = dma;
@@ -243,10 +308,42 @@ pub mod config {
         pub duplex: Duplex,
         pub bit_order: BitOrder,
         pub cs_active_high: bool,
+        /// The maximum delay in nanoseconds between the SCLK edge and the data becoming valid
+        /// on the slave output, including the delays of the PCB traces and level shifters.
+        ///
+        /// This only matters when reading at high frequencies (above ~26 MHz): the master then
+        /// samples the input line before the data from the slave is valid, and the received
+        /// data ends up shifted by one (or more) bits. Setting the delay makes the driver
+        /// compensate for it, or reject a baudrate which cannot work with such a delay.
+        /// See the "Timing considerations" section of the ESP-IDF SPI master documentation.
         pub input_delay_ns: i32,
+        /// The number of SPI bit-cycles the CS line is activated before the transmission (0-16).
+        /// Only supported in half duplex mode
+        pub cs_setup_cycles: u8,
+        /// The number of SPI bit-cycles the CS line stays active after the transmission (0-16)
+        pub cs_hold_cycles: u8,
+        /// Use polling (`spi_device_polling_transmit`) rather than interrupt-driven transactions
+        /// for the blocking methods of the device (the default).
+        ///
+        /// Polling busy-waits for the end of each transaction, but avoids the latency of the
+        /// interrupt round trip and of the task switches (typically several tens of microseconds
+        /// per transaction), so it is the better choice for short transactions, e.g. register
+        /// reads. Interrupt-driven transactions free the CPU during long transfers.
+        ///
+        /// The async methods and [SpiDeviceDriver::queue] always use interrupt-driven transactions.
+        /// Mixing them with polling transactions on the same device is safe, as a device never has
+        /// interrupt-driven transactions in flight while it executes a polling one, and ESP-IDF
+        /// acquires the bus for the duration of each polling transaction.
         pub polling: bool,
         pub allow_pre_post_delays: bool,
         pub queue_size: usize,
+        /// The width in bits of the command phase of the transactions
+        /// issued with `SpiDeviceDriver::transaction_ext` (up to `MAX_COMMAND_BITS`)
+        pub command_bits: u8,
+        /// The width in bits of the address phase of the transactions
+        /// issued with `SpiDeviceDriver::transaction_ext` (up to `MAX_ADDRESS_BITS`)
+        pub address_bits: u8,
+        pub line_mode: LineMode,
     }
 
     impl Config {
@@ -284,6 +381,20 @@ pub mod config {
             self
         }
 
+        /// Uses MOSI for both sending and receiving data (i.e. `Duplex::Half3Wire`) when `true`.
+        /// When `false`, a device configured for 3-wire mode is reverted to full duplex.
+        #[must_use]
+        pub fn three_wire(mut self, three_wire: bool) -> Self {
+            if three_wire {
+                self.duplex = Duplex::Half3Wire;
+            } else if self.duplex == Duplex::Half3Wire {
+                self.duplex = Duplex::Full;
+            }
+
+            self
+        }
+
+        /// Configures the CS line to be active high, rather than active low
         #[must_use]
         pub fn cs_active_high(mut self) -> Self {
             self.cs_active_high = true;
@@ -296,6 +407,18 @@ pub mod config {
             self
         }
 
+        #[must_use]
+        pub fn cs_setup_cycles(mut self, cs_setup_cycles: u8) -> Self {
+            self.cs_setup_cycles = cs_setup_cycles;
+            self
+        }
+
+        #[must_use]
+        pub fn cs_hold_cycles(mut self, cs_hold_cycles: u8) -> Self {
+            self.cs_hold_cycles = cs_hold_cycles;
+            self
+        }
+
         #[must_use]
         pub fn polling(mut self, polling: bool) -> Self {
             self.polling = polling;
@@ -313,6 +436,39 @@ pub mod config {
             self.queue_size = queue_size;
             self
         }
+
+        #[must_use]
+        pub fn command_bits(mut self, command_bits: u8) -> Self {
+            self.command_bits = command_bits;
+            self
+        }
+
+        #[must_use]
+        pub fn address_bits(mut self, address_bits: u8) -> Self {
+            self.address_bits = address_bits;
+            self
+        }
+
+        #[must_use]
+        pub fn line_mode(mut self, line_mode: LineMode) -> Self {
+            self.line_mode = line_mode;
+            self
+        }
+
+        /// Returns the `spi_device_interface_config_t` flags corresponding to this configuration
+        pub fn device_flags(&self) -> u32 {
+            let mut flags = self.duplex.as_flags() | self.bit_order.as_flags();
+
+            if self.write_only {
+                flags |= SPI_DEVICE_NO_DUMMY;
+            }
+
+            if self.cs_active_high {
+                flags |= SPI_DEVICE_POSITIVE_CS;
+            }
+
+            flags
+        }
     }
 
     impl Default for Config {
@@ -325,9 +481,14 @@ pub mod config {
                 duplex: Duplex::Full,
                 bit_order: BitOrder::MsbFirst,
                 input_delay_ns: 0,
+                cs_setup_cycles: 0,
+                cs_hold_cycles: 0,
                 polling: true,
                 allow_pre_post_delays: false,
                 queue_size: 1,
+                command_bits: 8,
+                address_bits: 24,
+                line_mode: LineMode::Single,
             }
         }
     }
@@ -335,6 +496,7 @@ pub mod config {
 
 pub struct SpiDriver<'d> {
     host: u8,
+    dma: Dma,
     max_transfer_size: usize,
     #[allow(dead_code)]
     bus_async_lock: Mutex<EspRawMutex, ()>,
@@ -357,6 +519,7 @@ impl<'d> SpiDriver<'d> {
 
         Ok(Self {
             host: SPI1::device() as _,
+            dma: config.dma,
             max_transfer_size,
             bus_async_lock: Mutex::new(()),
             _p: PhantomData,
@@ -375,6 +538,95 @@ impl<'d> SpiDriver<'d> {
 
         Ok(Self {
             host: SPI::device() as _,
+            dma: config.dma,
+            max_transfer_size,
+            bus_async_lock: Mutex::new(()),
+            _p: PhantomData,
+        })
+    }
+
+    /// Create new instance of SPI controller with four data lines (Quad SPI)
+    ///
+    /// `data0` and `data1` double as the SDO (MOSI) and SDI (MISO) lines of the transactions
+    /// on a single data line. Devices on the bus select the number of data lines with
+    /// [config::Config::line_mode].
+    pub fn new_quad<SPI: SpiAnyPins>(
+        _spi: impl Peripheral<P = SPI> + 'd,
+        sclk: impl Peripheral<P = impl OutputPin> + 'd,
+        data0: impl Peripheral<P = impl IOPin> + 'd,
+        data1: impl Peripheral<P = impl IOPin> + 'd,
+        data2: impl Peripheral<P = impl IOPin> + 'd,
+        data3: impl Peripheral<P = impl IOPin> + 'd,
+        config: &config::DriverConfig,
+    ) -> Result<Self, EspError> {
+        crate::into_ref!(sclk, data0, data1, data2, data3);
+
+        let max_transfer_size = Self::init_bus(
+            SPI::device(),
+            sclk.pin(),
+            [
+                data0.pin(),
+                data1.pin(),
+                data2.pin(),
+                data3.pin(),
+                -1,
+                -1,
+                -1,
+                -1,
+            ],
+            SPICOMMON_BUSFLAG_QUAD,
+            config,
+        )?;
+
+        Ok(Self {
+            host: SPI::device() as _,
+            dma: config.dma,
+            max_transfer_size,
+            bus_async_lock: Mutex::new(()),
+            _p: PhantomData,
+        })
+    }
+
+    /// Create new instance of SPI controller with eight data lines (Octal SPI)
+    ///
+    /// Octal SPI is only supported by SPI2 of the esp32s3.
+    #[cfg(esp32s3)]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_octal(
+        _spi: impl Peripheral<P = SPI2> + 'd,
+        sclk: impl Peripheral<P = impl OutputPin> + 'd,
+        data0: impl Peripheral<P = impl IOPin> + 'd,
+        data1: impl Peripheral<P = impl IOPin> + 'd,
+        data2: impl Peripheral<P = impl IOPin> + 'd,
+        data3: impl Peripheral<P = impl IOPin> + 'd,
+        data4: impl Peripheral<P = impl IOPin> + 'd,
+        data5: impl Peripheral<P = impl IOPin> + 'd,
+        data6: impl Peripheral<P = impl IOPin> + 'd,
+        data7: impl Peripheral<P = impl IOPin> + 'd,
+        config: &config::DriverConfig,
+    ) -> Result<Self, EspError> {
+        crate::into_ref!(sclk, data0, data1, data2, data3, data4, data5, data6, data7);
+
+        let max_transfer_size = Self::init_bus(
+            SPI2::device(),
+            sclk.pin(),
+            [
+                data0.pin(),
+                data1.pin(),
+                data2.pin(),
+                data3.pin(),
+                data4.pin(),
+                data5.pin(),
+                data6.pin(),
+                data7.pin(),
+            ],
+            SPICOMMON_BUSFLAG_OCTAL,
+            config,
+        )?;
+
+        Ok(Self {
+            host: SPI2::device() as _,
+            dma: config.dma,
             max_transfer_size,
             bus_async_lock: Mutex::new(()),
             _p: PhantomData,
@@ -385,6 +637,17 @@ impl<'d> SpiDriver<'d> {
         self.host as _
     }
 
+    /// Returns the DMA configuration of the bus
+    pub fn dma(&self) -> Dma {
+        self.dma
+    }
+
+    /// Returns the maximum size in bytes of a single transaction on the bus.
+    /// Longer transfers are split into several transactions.
+    pub fn max_transfer_size(&self) -> usize {
+        self.max_transfer_size
+    }
+
     fn new_internal(
         host: spi_host_device_t,
         sclk: impl Peripheral<P = impl OutputPin> + 'd,
@@ -395,33 +658,58 @@ impl<'d> SpiDriver<'d> {
         crate::into_ref!(sclk, sdo);
         let sdi = sdi.map(|sdi| sdi.into_ref());
 
+        Self::init_bus(
+            host,
+            sclk.pin(),
+            [
+                sdo.pin(),
+                sdi.as_ref().map_or(-1, |p| p.pin()),
+                -1,
+                -1,
+                -1,
+                -1,
+                -1,
+                -1,
+            ],
+            0,
+            config,
+        )
+    }
+
+    fn init_bus(
+        host: spi_host_device_t,
+        sclk: i32,
+        data: [i32; 8],
+        flags: u32,
+        config: &config::DriverConfig,
+    ) -> Result<usize, EspError> {
         let max_transfer_sz = config.dma.max_transfer_size();
         let dma_chan: spi_dma_chan_t = config.dma.into();
 
         #[allow(clippy::needless_update)]
         #[cfg(not(esp_idf_version = "4.3"))]
         let bus_config = spi_bus_config_t {
-            flags: SPICOMMON_BUSFLAG_MASTER,
-            sclk_io_num: sclk.pin(),
+            flags: SPICOMMON_BUSFLAG_MASTER | flags,
+            sclk_io_num: sclk,
 
-            data4_io_num: -1,
-            data5_io_num: -1,
-            data6_io_num: -1,
-            data7_io_num: -1,
+            data4_io_num: data[4],
+            data5_io_num: data[5],
+            data6_io_num: data[6],
+            data7_io_num: data[7],
             __bindgen_anon_1: spi_bus_config_t__bindgen_ty_1 {
-                mosi_io_num: sdo.pin(),
+                mosi_io_num: data[0],
                 //data0_io_num: -1,
             },
             __bindgen_anon_2: spi_bus_config_t__bindgen_ty_2 {
-                miso_io_num: sdi.as_ref().map_or(-1, |p| p.pin()),
+                miso_io_num: data[1],
                 //data1_io_num: -1,
             },
             __bindgen_anon_3: spi_bus_config_t__bindgen_ty_3 {
-                quadwp_io_num: -1,
+                quadwp_io_num: data[2],
                 //data2_io_num: -1,
             },
             __bindgen_anon_4: spi_bus_config_t__bindgen_ty_4 {
-                quadhd_io_num: -1,
+                quadhd_io_num: data[3],
                 //data3_io_num: -1,
             },
             max_transfer_sz: max_transfer_sz as i32,
@@ -435,13 +723,13 @@ impl<'d> SpiDriver<'d> {
             note = "Using ESP-IDF 4.3 is untested, please upgrade to 4.4 or newer. Support will be removed in the next major release."
         )]
         let bus_config = spi_bus_config_t {
-            flags: SPICOMMON_BUSFLAG_MASTER,
-            sclk_io_num: sclk.pin(),
+            flags: SPICOMMON_BUSFLAG_MASTER | flags,
+            sclk_io_num: sclk,
 
-            mosi_io_num: sdo.pin(),
-            miso_io_num: sdi.as_ref().map_or(-1, |p| p.pin()),
-            quadwp_io_num: -1,
-            quadhd_io_num: -1,
+            mosi_io_num: data[0],
+            miso_io_num: data[1],
+            quadwp_io_num: data[2],
+            quadhd_io_num: data[3],
 
             max_transfer_sz: max_transfer_sz as i32,
             intr_flags: InterruptType::to_native(config.intr_flags) as _,
@@ -471,6 +759,7 @@ where
     driver: T,
     polling: bool,
     queue_size: usize,
+    bit_order: config::BitOrder,
     _d: PhantomData<&'d ()>,
 }
 
@@ -484,12 +773,8 @@ where
             clock_speed_hz: config.baudrate.0 as i32,
             mode: data_mode_to_u8(config.data_mode),
             queue_size: config.queue_size as i32,
-            flags: if config.write_only {
-                SPI_DEVICE_NO_DUMMY
-            } else {
-                0_u32
-            } | config.duplex.as_flags()
-                | config.bit_order.as_flags(),
+            input_delay_ns: config.input_delay_ns,
+            flags: config.device_flags(),
             post_cb: Some(spi_notify),
             ..Default::default()
         };
@@ -505,6 +790,7 @@ where
             driver,
             polling: config.polling,
             queue_size: config.queue_size,
+            bit_order: config.bit_order,
             _d: PhantomData,
         })
     }
@@ -611,98 +897,718 @@ where
         Ok(())
     }
 
-    #[cfg(not(esp_idf_spi_master_isr_in_iram))]
-    pub async fn transfer_in_place_async(&mut self, words: &mut [u8]) -> Result<(), EspError> {
-        let chunk_size = self.driver.borrow().max_transfer_size;
+    #[cfg(not(esp_idf_spi_master_isr_in_iram))]
+    pub async fn transfer_in_place_async(&mut self, words: &mut [u8]) -> Result<(), EspError> {
+        let chunk_size = self.driver.borrow().max_transfer_size;
+
+        let transactions = spi_transfer_in_place_transactions(words, chunk_size);
+        core::pin::pin!(spi_transmit_async(
+            self.handle,
+            transactions,
+            self.queue_size
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), EspError> {
+        Ok(())
+    }
+
+    /// Reads 16 or 32 bit words.
+    ///
+    /// As per the `embedded-hal` contract, each word is received with its most significant
+    /// byte first (or least significant byte first, if the receiving bit order is LSB first).
+    pub fn read_words<W: SpiWord>(&mut self, words: &mut [W]) -> Result<(), EspError> {
+        self.read(words_as_bytes_mut(words))?;
+
+        if swap_rx(self.bit_order) {
+            swap_words(words);
+        }
+
+        Ok(())
+    }
+
+    #[cfg(not(esp_idf_spi_master_isr_in_iram))]
+    pub async fn read_words_async<W: SpiWord>(&mut self, words: &mut [W]) -> Result<(), EspError> {
+        self.read_async(words_as_bytes_mut(words)).await?;
+
+        if swap_rx(self.bit_order) {
+            swap_words(words);
+        }
+
+        Ok(())
+    }
+
+    /// Writes 16 or 32 bit words.
+    ///
+    /// As per the `embedded-hal` contract, each word is sent with its most significant
+    /// byte first (or least significant byte first, if the sending bit order is LSB first).
+    ///
+    /// In LSB first mode, the words are sent directly from the provided buffer. Otherwise
+    /// their bytes are swapped into a DMA-capable buffer (see [crate::dma::DmaBuffer]) of
+    /// the same size, which is sent in one go. [SpiBusDriver::transfer_in_place_words] swaps
+    /// the bytes in place instead.
+    pub fn write_words<W: SpiWord>(&mut self, words: &[W]) -> Result<(), EspError> {
+        if swap_tx(self.bit_order) {
+            self.write(&swapped_words(words)?)
+        } else {
+            self.write(words_as_bytes(words))
+        }
+    }
+
+    #[cfg(not(esp_idf_spi_master_isr_in_iram))]
+    pub async fn write_words_async<W: SpiWord>(&mut self, words: &[W]) -> Result<(), EspError> {
+        if swap_tx(self.bit_order) {
+            self.write_async(&swapped_words(words)?).await
+        } else {
+            self.write_async(words_as_bytes(words)).await
+        }
+    }
+
+    /// Reads and writes 16 or 32 bit words. See [SpiBusDriver::read_words] and
+    /// [SpiBusDriver::write_words] for the order of the bytes of each word.
+    pub fn transfer_words<W: SpiWord>(
+        &mut self,
+        read: &mut [W],
+        write: &[W],
+    ) -> Result<(), EspError> {
+        if swap_tx(self.bit_order) {
+            self.transfer(words_as_bytes_mut(read), &swapped_words(write)?)?;
+        } else {
+            self.transfer(words_as_bytes_mut(read), words_as_bytes(write))?;
+        }
+
+        if swap_rx(self.bit_order) {
+            swap_words(read);
+        }
+
+        Ok(())
+    }
+
+    #[cfg(not(esp_idf_spi_master_isr_in_iram))]
+    pub async fn transfer_words_async<W: SpiWord>(
+        &mut self,
+        read: &mut [W],
+        write: &[W],
+    ) -> Result<(), EspError> {
+        if swap_tx(self.bit_order) {
+            self.transfer_async(words_as_bytes_mut(read), &swapped_words(write)?)
+                .await?;
+        } else {
+            self.transfer_async(words_as_bytes_mut(read), words_as_bytes(write))
+                .await?;
+        }
+
+        if swap_rx(self.bit_order) {
+            swap_words(read);
+        }
+
+        Ok(())
+    }
+
+    /// Writes the 16 or 32 bit words of `words` and replaces them with the words read back.
+    /// See [SpiBusDriver::read_words] and [SpiBusDriver::write_words] for the order of
+    /// the bytes of each word.
+    pub fn transfer_in_place_words<W: SpiWord>(&mut self, words: &mut [W]) -> Result<(), EspError> {
+        if swap_tx(self.bit_order) {
+            swap_words(words);
+        }
+
+        self.transfer_in_place(words_as_bytes_mut(words))?;
+
+        if swap_rx(self.bit_order) {
+            swap_words(words);
+        }
+
+        Ok(())
+    }
+
+    #[cfg(not(esp_idf_spi_master_isr_in_iram))]
+    pub async fn transfer_in_place_words_async<W: SpiWord>(
+        &mut self,
+        words: &mut [W],
+    ) -> Result<(), EspError> {
+        if swap_tx(self.bit_order) {
+            swap_words(words);
+        }
+
+        self.transfer_in_place_async(words_as_bytes_mut(words))
+            .await?;
+
+        if swap_rx(self.bit_order) {
+            swap_words(words);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the clock frequency actually used by the bus, which might be lower than the
+    /// configured baudrate, as it is derived from the source clock with an integer divider.
+    #[cfg(not(esp_idf_version_major = "4"))]
+    pub fn actual_baudrate(&self) -> Result<crate::units::Hertz, EspError> {
+        spi_actual_baudrate(self.handle)
+    }
+}
+
+impl<'d, T> Drop for SpiBusDriver<'d, T>
+where
+    T: BorrowMut<SpiDriver<'d>>,
+{
+    fn drop(&mut self) {
+        esp!(unsafe { spi_bus_remove_device(self.handle) }).unwrap();
+    }
+}
+
+impl<'d, T> embedded_hal::spi::ErrorType for SpiBusDriver<'d, T>
+where
+    T: BorrowMut<SpiDriver<'d>>,
+{
+    type Error = SpiError;
+}
+
+impl<'d, T> SpiBus for SpiBusDriver<'d, T>
+where
+    T: BorrowMut<SpiDriver<'d>>,
+{
+    fn read(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+        SpiBusDriver::read(self, words).map_err(to_spi_err)
+    }
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+        SpiBusDriver::write(self, words).map_err(to_spi_err)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        SpiBusDriver::flush(self).map_err(to_spi_err)
+    }
+
+    fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), Self::Error> {
+        SpiBusDriver::transfer(self, read, write).map_err(to_spi_err)
+    }
+
+    fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+        SpiBusDriver::transfer_in_place(self, words).map_err(to_spi_err)
+    }
+}
+
+macro_rules! impl_spi_bus_words {
+    ($word:ty) => {
+        impl<'d, T> SpiBus<$word> for SpiBusDriver<'d, T>
+        where
+            T: BorrowMut<SpiDriver<'d>>,
+        {
+            fn read(&mut self, words: &mut [$word]) -> Result<(), Self::Error> {
+                SpiBusDriver::read_words(self, words).map_err(to_spi_err)
+            }
+
+            fn write(&mut self, words: &[$word]) -> Result<(), Self::Error> {
+                SpiBusDriver::write_words(self, words).map_err(to_spi_err)
+            }
+
+            fn flush(&mut self) -> Result<(), Self::Error> {
+                SpiBusDriver::flush(self).map_err(to_spi_err)
+            }
+
+            fn transfer(&mut self, read: &mut [$word], write: &[$word]) -> Result<(), Self::Error> {
+                SpiBusDriver::transfer_words(self, read, write).map_err(to_spi_err)
+            }
+
+            fn transfer_in_place(&mut self, words: &mut [$word]) -> Result<(), Self::Error> {
+                SpiBusDriver::transfer_in_place_words(self, words).map_err(to_spi_err)
+            }
+        }
+    };
+}
+
+impl_spi_bus_words!(u16);
+impl_spi_bus_words!(u32);
+
+/// A word wider than a byte, which can be transferred with [SpiBusDriver] and [SpiDeviceDriver]
+///
+/// This trait is sealed, and implemented for `u16` and `u32` only.
+pub trait SpiWord: sealed::Sealed + Copy + 'static {
+    fn swap_bytes(self) -> Self;
+}
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl Sealed for u16 {}
+    impl Sealed for u32 {}
+}
+
+impl SpiWord for u16 {
+    fn swap_bytes(self) -> Self {
+        u16::swap_bytes(self)
+    }
+}
+
+impl SpiWord for u32 {
+    fn swap_bytes(self) -> Self {
+        u32::swap_bytes(self)
+    }
+}
+
+fn swap_tx(bit_order: config::BitOrder) -> bool {
+    !matches!(
+        bit_order,
+        config::BitOrder::LsbFirst | config::BitOrder::TxLsbFirst
+    )
+}
+
+fn swap_rx(bit_order: config::BitOrder) -> bool {
+    !matches!(
+        bit_order,
+        config::BitOrder::LsbFirst | config::BitOrder::RxLsbFirst
+    )
+}
+
+fn words_as_bytes<W: SpiWord>(words: &[W]) -> &[u8] {
+    unsafe {
+        core::slice::from_raw_parts(words.as_ptr() as *const u8, core::mem::size_of_val(words))
+    }
+}
+
+fn words_as_bytes_mut<W: SpiWord>(words: &mut [W]) -> &mut [u8] {
+    unsafe {
+        core::slice::from_raw_parts_mut(
+            words.as_mut_ptr() as *mut u8,
+            core::mem::size_of_val(words),
+        )
+    }
+}
+
+fn swapped_words<W: SpiWord>(words: &[W]) -> Result<DmaBuffer, EspError> {
+    let mut buf = DmaBuffer::new(core::mem::size_of_val(words))?;
+
+    swap_words_into(words, &mut buf);
+
+    Ok(buf)
+}
+
+fn swap_words_into<W: SpiWord>(words: &[W], buf: &mut [u8]) {
+    assert!(buf.len() >= core::mem::size_of_val(words));
+
+    for (index, word) in words.iter().enumerate() {
+        unsafe {
+            ptr::write_unaligned(
+                buf.as_mut_ptr().add(index * core::mem::size_of::<W>()) as *mut W,
+                word.swap_bytes(),
+            );
+        }
+    }
+}
+
+fn swap_words<W: SpiWord>(words: &mut [W]) {
+    for word in words {
+        *word = word.swap_bytes();
+    }
+}
+
+/// Swaps the bytes of the words written by `operations`, if the sending bit order is MSB first:
+/// in place for `Operation::TransferInPlace`, and into the returned buffers otherwise
+#[cfg(feature = "alloc")]
+fn swap_tx_operations<W: SpiWord>(
+    operations: &mut [Operation<'_, W>],
+    bit_order: config::BitOrder,
+) -> Result<alloc::vec::Vec<Option<DmaBuffer>>, EspError> {
+    let swap = swap_tx(bit_order);
+
+    operations
+        .iter_mut()
+        .map(|operation| match operation {
+            Operation::Write(write) | Operation::Transfer(_, write) if swap => {
+                swapped_words(write).map(Some)
+            }
+            Operation::TransferInPlace(words) if swap => {
+                swap_words(words);
+                Ok(None)
+            }
+            _ => Ok(None),
+        })
+        .collect()
+}
+
+/// Swaps the bytes of the words read by `operations`, if the receiving bit order is MSB first
+#[cfg(feature = "alloc")]
+fn swap_rx_operations<W: SpiWord>(
+    operations: &mut [Operation<'_, W>],
+    bit_order: config::BitOrder,
+) {
+    if swap_rx(bit_order) {
+        for operation in operations {
+            match operation {
+                Operation::Read(read)
+                | Operation::Transfer(read, _)
+                | Operation::TransferInPlace(read) => swap_words(read),
+                _ => (),
+            }
+        }
+    }
+}
+
+/// Maps word operations to byte operations, writing from the buffers returned by
+/// `swap_tx_operations` when present
+#[cfg(feature = "alloc")]
+fn byte_operations<'a, W: SpiWord>(
+    operations: &'a mut [Operation<'_, W>],
+    swapped: &'a [Option<DmaBuffer>],
+) -> alloc::vec::Vec<Operation<'a, u8>> {
+    operations
+        .iter_mut()
+        .zip(swapped)
+        .map(|(operation, swapped)| match operation {
+            Operation::Read(read) => Operation::Read(words_as_bytes_mut(read)),
+            Operation::Write(write) => {
+                Operation::Write(swapped.as_deref().unwrap_or(words_as_bytes(write)))
+            }
+            Operation::Transfer(read, write) => Operation::Transfer(
+                words_as_bytes_mut(read),
+                swapped.as_deref().unwrap_or(words_as_bytes(write)),
+            ),
+            Operation::TransferInPlace(words) => {
+                Operation::TransferInPlace(words_as_bytes_mut(words))
+            }
+            Operation::DelayNs(delay) => Operation::DelayNs(*delay),
+        })
+        .collect()
+}
+
+#[cfg(not(esp_idf_spi_master_isr_in_iram))]
+macro_rules! impl_async_spi_bus_words {
+    ($word:ty) => {
+        impl<'d, T> embedded_hal_async::spi::SpiBus<$word> for SpiBusDriver<'d, T>
+        where
+            T: BorrowMut<SpiDriver<'d>>,
+        {
+            async fn read(&mut self, words: &mut [$word]) -> Result<(), Self::Error> {
+                SpiBusDriver::read_words_async(self, words)
+                    .await
+                    .map_err(to_spi_err)
+            }
+
+            async fn write(&mut self, words: &[$word]) -> Result<(), Self::Error> {
+                SpiBusDriver::write_words_async(self, words)
+                    .await
+                    .map_err(to_spi_err)
+            }
+
+            async fn transfer(
+                &mut self,
+                read: &mut [$word],
+                write: &[$word],
+            ) -> Result<(), Self::Error> {
+                SpiBusDriver::transfer_words_async(self, read, write)
+                    .await
+                    .map_err(to_spi_err)
+            }
+
+            async fn transfer_in_place(&mut self, words: &mut [$word]) -> Result<(), Self::Error> {
+                SpiBusDriver::transfer_in_place_words_async(self, words)
+                    .await
+                    .map_err(to_spi_err)
+            }
+
+            async fn flush(&mut self) -> Result<(), Self::Error> {
+                SpiBusDriver::flush(self).map_err(to_spi_err)
+            }
+        }
+    };
+}
+
+#[cfg(not(esp_idf_spi_master_isr_in_iram))]
+impl_async_spi_bus_words!(u16);
+#[cfg(not(esp_idf_spi_master_isr_in_iram))]
+impl_async_spi_bus_words!(u32);
+
+#[cfg(not(esp_idf_spi_master_isr_in_iram))]
+impl<'d, T> embedded_hal_async::spi::SpiBus for SpiBusDriver<'d, T>
+where
+    T: BorrowMut<SpiDriver<'d>>,
+{
+    async fn read(&mut self, buf: &mut [u8]) -> Result<(), Self::Error> {
+        SpiBusDriver::read_async(self, buf)
+            .await
+            .map_err(to_spi_err)
+    }
+
+    async fn write(&mut self, buf: &[u8]) -> Result<(), Self::Error> {
+        SpiBusDriver::write_async(self, buf)
+            .await
+            .map_err(to_spi_err)
+    }
+
+    async fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), Self::Error> {
+        SpiBusDriver::transfer_async(self, read, write)
+            .await
+            .map_err(to_spi_err)
+    }
+
+    async fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+        SpiBusDriver::transfer_in_place_async(self, words)
+            .await
+            .map_err(to_spi_err)
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        SpiBusDriver::flush(self).map_err(to_spi_err)
+    }
+}
+
+/// A guard keeping the bus acquired by a device, see [SpiDeviceDriver::acquire_bus]
+///
+/// The device is accessible via the guard for the lifetime of the guard.
+/// The bus is released when the guard is dropped.
+pub struct BusGuard<'a, 'd, T>
+where
+    T: Borrow<SpiDriver<'d>> + 'd,
+{
+    device: &'a mut SpiDeviceDriver<'d, T>,
+}
+
+impl<'a, 'd, T> core::ops::Deref for BusGuard<'a, 'd, T>
+where
+    T: Borrow<SpiDriver<'d>> + 'd,
+{
+    type Target = SpiDeviceDriver<'d, T>;
+
+    fn deref(&self) -> &Self::Target {
+        self.device
+    }
+}
+
+impl<'a, 'd, T> core::ops::DerefMut for BusGuard<'a, 'd, T>
+where
+    T: Borrow<SpiDriver<'d>> + 'd,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.device
+    }
+}
+
+impl<'a, 'd, T> Drop for BusGuard<'a, 'd, T>
+where
+    T: Borrow<SpiDriver<'d>> + 'd,
+{
+    fn drop(&mut self) {
+        unsafe {
+            spi_device_release_bus(self.device.handle);
+        }
+
+        self.device.bus_acquired = false;
+    }
+}
+
+/// The error returned by [TransactionScope::queue]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum QueueError {
+    /// `queue_size` (see [config::Config::queue_size]) transactions are in flight already
+    Full,
+    Other(EspError),
+}
+
+impl From<EspError> for QueueError {
+    fn from(e: EspError) -> Self {
+        Self::Other(e)
+    }
+}
+
+impl From<QueueError> for EspError {
+    fn from(e: QueueError) -> Self {
+        match e {
+            QueueError::Full => EspError::from_infallible::<ESP_ERR_TIMEOUT>(),
+            QueueError::Other(e) => e,
+        }
+    }
+}
+
+impl core::fmt::Display for QueueError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Full => write!(f, "SPI transaction queue is full"),
+            Self::Other(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for QueueError {}
+
+/// A scope for queueing transactions in the background, see [SpiDeviceDriver::queue_scope]
+///
+/// The scope waits for all transactions queued in it to complete before it ends, so the buffers
+/// of the transactions stay borrowed while the hardware uses them, even if a
+/// [TransactionHandle] is leaked.
+#[cfg(feature = "alloc")]
+pub struct TransactionScope<'scope, 'env: 'scope> {
+    device: spi_device_handle_t,
+    max_transfer_size: usize,
+    queue_size: usize,
+    line_flags: u32,
+    queued: Cell<u32>,
+    completed: Cell<u32>,
+    _scope: PhantomData<&'scope mut &'scope ()>,
+    _env: PhantomData<&'env mut &'env ()>,
+}
+
+#[cfg(feature = "alloc")]
+impl<'scope, 'env> TransactionScope<'scope, 'env> {
+    /// Queues a single operation for execution in the background and returns immediately.
+    ///
+    /// The returned handle can be used to wait for the transaction, see [TransactionHandle::wait]
+    /// and [TransactionHandle::try_complete]. Dropping the handle waits for the transaction
+    /// to complete.
+    ///
+    /// Up to `queue_size` (see [config::Config::queue_size]) operations can be in flight at any
+    /// time. If the queue is full, this method fails with `QueueError::Full` rather than blocking.
+    ///
+    /// The operation is not chunked, so it should not exceed [SpiDriver::max_transfer_size].
+    /// `Operation::DelayNs` is not supported.
+    pub fn queue(
+        &'scope self,
+        operation: Operation<'scope, u8>,
+    ) -> Result<TransactionHandle<'scope>, QueueError> {
+        let mut transaction = match operation {
+            Operation::Read(read) => {
+                spi_create_transaction(read.as_mut_ptr(), ptr::null(), read.len(), read.len())
+            }
+            Operation::Write(write) => {
+                spi_create_transaction(ptr::null_mut(), write.as_ptr(), write.len(), 0)
+            }
+            Operation::Transfer(read, write) => spi_create_transaction(
+                read.as_mut_ptr(),
+                write.as_ptr(),
+                max(read.len(), write.len()),
+                read.len(),
+            ),
+            Operation::TransferInPlace(words) => spi_create_transaction(
+                words.as_mut_ptr(),
+                words.as_mut_ptr(),
+                words.len(),
+                words.len(),
+            ),
+            Operation::DelayNs(_) => Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>())?,
+        };
+
+        if transaction.length as usize > self.max_transfer_size * 8 {
+            Err(EspError::from_infallible::<ESP_ERR_INVALID_SIZE>())?;
+        }
+
+        if self.in_flight() as usize >= self.queue_size {
+            return Err(QueueError::Full);
+        }
+
+        transaction.flags |= self.line_flags;
+
+        let mut transaction = alloc::boxed::Box::new(transaction);
+
+        match esp!(unsafe {
+            spi_device_queue_trans(self.device, &mut *transaction, delay::NON_BLOCK)
+        }) {
+            Err(e) if e.code() == ESP_ERR_TIMEOUT => return Err(QueueError::Full),
+            other => other?,
+        }
 
-        let transactions = spi_transfer_in_place_transactions(words, chunk_size);
-        core::pin::pin!(spi_transmit_async(
-            self.handle,
-            transactions,
-            self.queue_size
-        ))
-        .await?;
+        let seq = self.queued.get();
+        self.queued.set(seq.wrapping_add(1));
 
-        Ok(())
+        Ok(TransactionHandle {
+            device: self.device,
+            completed: &self.completed,
+            seq,
+            done: false,
+            transaction: ManuallyDrop::new(transaction),
+            _p: PhantomData,
+        })
     }
 
-    pub fn flush(&mut self) -> Result<(), EspError> {
-        Ok(())
+    fn in_flight(&self) -> u32 {
+        self.queued.get().wrapping_sub(self.completed.get())
     }
 }
 
-impl<'d, T> Drop for SpiBusDriver<'d, T>
-where
-    T: BorrowMut<SpiDriver<'d>>,
-{
+#[cfg(feature = "alloc")]
+impl<'scope, 'env> Drop for TransactionScope<'scope, 'env> {
     fn drop(&mut self) {
-        esp!(unsafe { spi_bus_remove_device(self.handle) }).unwrap();
-    }
-}
+        // Retrieve the results of the transactions whose handles were leaked
+        while self.in_flight() > 0 {
+            let mut rtrans = ptr::null_mut();
 
-impl<'d, T> embedded_hal::spi::ErrorType for SpiBusDriver<'d, T>
-where
-    T: BorrowMut<SpiDriver<'d>>,
-{
-    type Error = SpiError;
-}
+            if let Err(e) =
+                esp!(unsafe { spi_device_get_trans_result(self.device, &mut rtrans, BLOCK) })
+            {
+                ::log::error!("Failed to wait for the queued SPI transactions: {e}");
+                break;
+            }
 
-impl<'d, T> SpiBus for SpiBusDriver<'d, T>
-where
-    T: BorrowMut<SpiDriver<'d>>,
-{
-    fn read(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
-        SpiBusDriver::read(self, words).map_err(to_spi_err)
+            self.completed.set(self.completed.get().wrapping_add(1));
+        }
     }
+}
 
-    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
-        SpiBusDriver::write(self, words).map_err(to_spi_err)
-    }
+/// A transaction queued with [TransactionScope::queue]
+///
+/// Dropping the handle of a transaction which is not complete yet blocks until the transaction
+/// completes.
+#[cfg(feature = "alloc")]
+pub struct TransactionHandle<'a> {
+    device: spi_device_handle_t,
+    completed: &'a Cell<u32>,
+    seq: u32,
+    done: bool,
+    transaction: ManuallyDrop<alloc::boxed::Box<spi_transaction_t>>,
+    _p: PhantomData<&'a mut [u8]>,
+}
 
-    fn flush(&mut self) -> Result<(), Self::Error> {
-        SpiBusDriver::flush(self).map_err(to_spi_err)
-    }
+#[cfg(feature = "alloc")]
+impl<'a> TransactionHandle<'a> {
+    /// Waits for up to `timeout` ticks for the transaction to complete.
+    ///
+    /// Fails with `ESP_ERR_TIMEOUT` if the transaction did not complete in time.
+    pub fn wait(&mut self, timeout: TickType_t) -> Result<(), EspError> {
+        // The results of the transactions are retrieved in the order in which the transactions
+        // were queued, so the results of the transactions queued before this one are retrieved
+        // (and accounted for) first
+        while !self.is_complete() {
+            let mut rtrans = ptr::null_mut();
+            esp!(unsafe { spi_device_get_trans_result(self.device, &mut rtrans, timeout) })?;
 
-    fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), Self::Error> {
-        SpiBusDriver::transfer(self, read, write).map_err(to_spi_err)
-    }
+            self.completed.set(self.completed.get().wrapping_add(1));
+        }
 
-    fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
-        SpiBusDriver::transfer_in_place(self, words).map_err(to_spi_err)
-    }
-}
+        self.done = true;
 
-#[cfg(not(esp_idf_spi_master_isr_in_iram))]
-impl<'d, T> embedded_hal_async::spi::SpiBus for SpiBusDriver<'d, T>
-where
-    T: BorrowMut<SpiDriver<'d>>,
-{
-    async fn read(&mut self, buf: &mut [u8]) -> Result<(), Self::Error> {
-        SpiBusDriver::read_async(self, buf)
-            .await
-            .map_err(to_spi_err)
+        Ok(())
     }
 
-    async fn write(&mut self, buf: &[u8]) -> Result<(), Self::Error> {
-        SpiBusDriver::write_async(self, buf)
-            .await
-            .map_err(to_spi_err)
+    /// Returns `true` if the transaction is complete, without blocking.
+    pub fn try_complete(&mut self) -> Result<bool, EspError> {
+        match self.wait(delay::NON_BLOCK) {
+            Ok(()) => Ok(true),
+            Err(e) if e.code() == ESP_ERR_TIMEOUT => Ok(false),
+            Err(e) => Err(e),
+        }
     }
 
-    async fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), Self::Error> {
-        SpiBusDriver::transfer_async(self, read, write)
-            .await
-            .map_err(to_spi_err)
+    fn is_complete(&self) -> bool {
+        self.completed.get().wrapping_sub(self.seq) as i32 > 0
     }
+}
 
-    async fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
-        SpiBusDriver::transfer_in_place_async(self, words)
-            .await
-            .map_err(to_spi_err)
-    }
+#[cfg(feature = "alloc")]
+impl<'a> Drop for TransactionHandle<'a> {
+    fn drop(&mut self) {
+        if !self.done {
+            if let Err(e) = self.wait(BLOCK) {
+                // The transaction might still be in use by the driver, so leak it
+                ::log::error!("Failed to wait for a queued SPI transaction: {e}");
+                return;
+            }
+        }
 
-    async fn flush(&mut self) -> Result<(), Self::Error> {
-        SpiBusDriver::flush(self).map_err(to_spi_err)
+        unsafe { ManuallyDrop::drop(&mut self.transaction) };
     }
 }
 
@@ -733,6 +1639,12 @@ where
     polling: bool,
     allow_pre_post_delays: bool,
     queue_size: usize,
+    duplex: config::Duplex,
+    command_bits: u8,
+    address_bits: u8,
+    line_flags: u32,
+    bit_order: config::BitOrder,
+    bus_acquired: bool,
     _d: PhantomData<&'d ()>,
 }
 
@@ -776,6 +1688,15 @@ where
         cs: Option<impl Peripheral<P = impl OutputPin> + 'd>,
         config: &config::Config,
     ) -> Result<Self, EspError> {
+        if config.line_mode != config::LineMode::Single && config.duplex == config::Duplex::Full {
+            // Dual, Quad and Octal modes are only supported in half duplex
+            Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>())?;
+        }
+
+        if config.command_bits > MAX_COMMAND_BITS || config.address_bits > MAX_ADDRESS_BITS {
+            Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>())?;
+        }
+
         let cs = cs.map(|cs| cs.into_ref().pin()).unwrap_or(-1);
 
         let conf = spi_device_interface_config_t {
@@ -784,16 +1705,9 @@ where
             mode: data_mode_to_u8(config.data_mode),
             queue_size: config.queue_size as i32,
             input_delay_ns: config.input_delay_ns,
-            flags: if config.write_only {
-                SPI_DEVICE_NO_DUMMY
-            } else {
-                0_u32
-            } | if config.cs_active_high {
-                SPI_DEVICE_POSITIVE_CS
-            } else {
-                0_u32
-            } | config.duplex.as_flags()
-                | config.bit_order.as_flags(),
+            cs_ena_pretrans: config.cs_setup_cycles as _,
+            cs_ena_posttrans: config.cs_hold_cycles as _,
+            flags: config.device_flags(),
             post_cb: Some(spi_notify),
             ..Default::default()
         };
@@ -808,6 +1722,12 @@ where
             polling: config.polling,
             allow_pre_post_delays: config.allow_pre_post_delays,
             queue_size: config.queue_size,
+            duplex: config.duplex,
+            command_bits: config.command_bits,
+            address_bits: config.address_bits,
+            line_flags: config.line_mode.as_flags(),
+            bit_order: config.bit_order,
+            bus_acquired: false,
             _d: PhantomData,
         })
     }
@@ -816,6 +1736,65 @@ where
         self.handle
     }
 
+    /// Acquires the bus for exclusive use by this device, until the returned guard is dropped.
+    ///
+    /// While the bus is acquired, the transactions of the other devices on the bus block until
+    /// it is released, and the transactions of this device (done via the guard) skip acquiring
+    /// the bus, which also reduces the overhead of polling transactions.
+    ///
+    /// ESP-IDF currently only supports acquiring the bus with a `BLOCK` timeout.
+    /// Fails with `ESP_ERR_INVALID_STATE` if the bus is already acquired by this device, which
+    /// can happen when calling this method through the `BusGuard` (as it derefs to the device).
+    pub fn acquire_bus(&mut self, timeout: TickType_t) -> Result<BusGuard<'_, 'd, T>, EspError> {
+        if self.bus_acquired {
+            Err(EspError::from_infallible::<ESP_ERR_INVALID_STATE>())?;
+        }
+
+        esp!(unsafe { spi_device_acquire_bus(self.handle, timeout) })?;
+
+        self.bus_acquired = true;
+
+        Ok(BusGuard { device: self })
+    }
+
+    /// Returns the clock frequency actually used by the device, which might be lower than the
+    /// configured baudrate, as it is derived from the source clock with an integer divider.
+    #[cfg(not(esp_idf_version_major = "4"))]
+    pub fn actual_baudrate(&self) -> Result<crate::units::Hertz, EspError> {
+        spi_actual_baudrate(self.handle)
+    }
+
+    /// Opens a scope for queueing transactions in the background with [TransactionScope::queue].
+    ///
+    /// All transactions queued in the scope are complete once this method returns, so
+    /// the buffers of the transactions only have to outlive the scope, rather than being `'static`
+    /// or owned - similarly to `std::thread::scope`.
+    #[cfg(feature = "alloc")]
+    pub fn queue_scope<'env, F, R>(&mut self, f: F) -> R
+    where
+        F: for<'scope> FnOnce(&'scope TransactionScope<'scope, 'env>) -> R,
+    {
+        let scope = TransactionScope {
+            device: self.handle,
+            max_transfer_size: self.driver.borrow().max_transfer_size,
+            queue_size: self.queue_size,
+            line_flags: self.line_flags,
+            queued: Cell::new(0),
+            completed: Cell::new(0),
+            _scope: PhantomData,
+            _env: PhantomData,
+        };
+
+        f(&scope)
+    }
+
+    /// Executes the operations as a single transaction, with the CS line (if configured) kept
+    /// asserted throughout the whole transaction.
+    ///
+    /// Buffers longer than [SpiDriver::max_transfer_size] are transparently split into several
+    /// ESP-IDF transactions. The bus is acquired for the duration of the transaction, and the
+    /// hardware CS line is kept active between the split transactions (`SPI_TRANS_CS_KEEP_ACTIVE`),
+    /// so the device sees a single logical transaction.
     pub fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), EspError> {
         self.run(
             self.hardware_cs_ctl(operations.iter_mut().map(copy_operation))?,
@@ -835,6 +1814,38 @@ where
         .await
     }
 
+    /// Executes the operations on 16 or 32 bit words as a single transaction, as per
+    /// [SpiDeviceDriver::transaction]. See [SpiBusDriver::read_words] and
+    /// [SpiBusDriver::write_words] for the order of the bytes of each word.
+    #[cfg(feature = "alloc")]
+    pub fn transaction_words<W: SpiWord>(
+        &mut self,
+        operations: &mut [Operation<'_, W>],
+    ) -> Result<(), EspError> {
+        let swapped = swap_tx_operations(operations, self.bit_order)?;
+
+        self.transaction(&mut byte_operations(operations, &swapped))?;
+
+        swap_rx_operations(operations, self.bit_order);
+
+        Ok(())
+    }
+
+    #[cfg(all(feature = "alloc", not(esp_idf_spi_master_isr_in_iram)))]
+    pub async fn transaction_words_async<W: SpiWord>(
+        &mut self,
+        operations: &mut [Operation<'_, W>],
+    ) -> Result<(), EspError> {
+        let swapped = swap_tx_operations(operations, self.bit_order)?;
+
+        self.transaction_async(&mut byte_operations(operations, &swapped))
+            .await?;
+
+        swap_rx_operations(operations, self.bit_order);
+
+        Ok(())
+    }
+
     pub fn read(&mut self, read: &mut [u8]) -> Result<(), EspError> {
         self.transaction(&mut [Operation::Read(read)])
     }
@@ -871,6 +1882,97 @@ where
         core::pin::pin!(self.transaction_async(&mut [Operation::Transfer(read, write)])).await
     }
 
+    /// Same as [SpiDeviceDriver::read], but with a buffer which is guaranteed to be DMA-capable,
+    /// so that ESP-IDF never copies the data through a temporary bounce buffer.
+    pub fn read_dma(&mut self, read: &mut DmaBuffer) -> Result<(), EspError> {
+        self.read(read)
+    }
+
+    /// Same as [SpiDeviceDriver::write], but with a buffer which is guaranteed to be DMA-capable,
+    /// so that ESP-IDF never copies the data through a temporary bounce buffer.
+    pub fn write_dma(&mut self, write: &DmaBuffer) -> Result<(), EspError> {
+        self.write(write)
+    }
+
+    /// Same as [SpiDeviceDriver::transfer], but with buffers which are guaranteed to be
+    /// DMA-capable, so that ESP-IDF never copies the data through temporary bounce buffers.
+    pub fn transfer_dma(
+        &mut self,
+        read: &mut DmaBuffer,
+        write: &DmaBuffer,
+    ) -> Result<(), EspError> {
+        self.transfer(read, write)
+    }
+
+    /// Executes a single transaction with optional command and address phases, followed by
+    /// `dummy_bits` dummy clock cycles and then by the data phase described by `operation`.
+    ///
+    /// The widths of the command and address phases are the `command_bits` and `address_bits`
+    /// of the device configuration, and the lowest `command_bits` / `address_bits` bits of `cmd` / `addr`
+    /// are sent. A `None` command or address skips the respective phase.
+    ///
+    /// On half-duplex devices (see [config::Duplex]), `Operation::Transfer` first writes and then
+    /// reads its data. `Operation::TransferInPlace` and `Operation::DelayNs` are not supported,
+    /// and the data is not chunked, so it should not exceed [SpiDriver::max_transfer_size].
+    ///
+    /// The regular (`read`, `write`, `transaction`, ...) methods of the device do not have
+    /// command and address phases, so they can be freely mixed with this method.
+    pub fn transaction_ext(
+        &mut self,
+        cmd: Option<u16>,
+        addr: Option<u64>,
+        dummy_bits: u8,
+        operation: Operation<'_, u8>,
+    ) -> Result<(), EspError> {
+        let (read, write, rx_len, tx_len) = match operation {
+            Operation::Read(read) => (read.as_mut_ptr(), ptr::null(), read.len(), 0),
+            Operation::Write(write) => (ptr::null_mut(), write.as_ptr(), 0, write.len()),
+            Operation::Transfer(read, write) => {
+                (read.as_mut_ptr(), write.as_ptr(), read.len(), write.len())
+            }
+            Operation::TransferInPlace(_) | Operation::DelayNs(_) => {
+                Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>())?
+            }
+        };
+
+        if (cmd.is_some() && self.command_bits == 0) || (addr.is_some() && self.address_bits == 0) {
+            Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>())?;
+        }
+
+        if max(rx_len, tx_len) > self.driver.borrow().max_transfer_size {
+            Err(EspError::from_infallible::<ESP_ERR_INVALID_SIZE>())?;
+        }
+
+        let length = if self.duplex == config::Duplex::Full {
+            max(rx_len, tx_len)
+        } else {
+            tx_len
+        };
+
+        let mut base = spi_create_transaction(read, write, length, rx_len);
+        base.flags |= SPI_TRANS_VARIABLE_CMD
+            | SPI_TRANS_VARIABLE_ADDR
+            | SPI_TRANS_VARIABLE_DUMMY
+            | self.line_flags;
+        base.cmd = cmd.unwrap_or(0);
+        base.addr = addr.unwrap_or(0);
+
+        let mut transaction = spi_transaction_ext_t {
+            base,
+            command_bits: if cmd.is_some() { self.command_bits } else { 0 },
+            address_bits: if addr.is_some() { self.address_bits } else { 0 },
+            dummy_bits,
+        };
+
+        let transaction = &mut transaction as *mut spi_transaction_ext_t as *mut spi_transaction_t;
+
+        if self.polling {
+            esp!(unsafe { spi_device_polling_transmit(self.handle, transaction) })
+        } else {
+            esp!(unsafe { spi_device_transmit(self.handle, transaction) })
+        }
+    }
+
     fn run<'a, 'c, 'p, P, M>(
         &mut self,
         mut cs_pin: CsCtl<'c, 'p, P, M>,
@@ -880,7 +1982,7 @@ where
         P: OutputPin,
         M: OutputMode,
     {
-        let _lock = if cs_pin.needs_bus_lock() {
+        let _lock = if cs_pin.needs_bus_lock() && !self.bus_acquired {
             Some(BusLock::new(self.device())?)
         } else {
             None
@@ -897,12 +1999,13 @@ where
             })
             .peekable();
 
-        let delay_impl = crate::delay::Delay::new_default();
+        let mut delay_impl = crate::delay::Delay::new_default();
         let mut result = Ok(());
 
         while spi_operations.peek().is_some() {
             if let Some(SpiOperation::Delay(delay)) = spi_operations.peek() {
-                delay_impl.delay_us(*delay / 1000);
+                // `delay_ns` rounds up, so sub-microsecond delays are not dropped
+                embedded_hal::delay::DelayNs::delay_ns(&mut delay_impl, *delay);
                 spi_operations.next();
             } else {
                 let transactions = core::iter::from_fn(|| {
@@ -941,7 +2044,7 @@ where
             None
         };
 
-        let _lock = if cs_pin.needs_bus_lock() {
+        let _lock = if cs_pin.needs_bus_lock() && !self.bus_acquired {
             Some(BusLock::new(self.device())?)
         } else {
             None
@@ -949,7 +2052,7 @@ where
 
         cs_pin.raise_cs()?;
 
-        let delay_impl = crate::delay::Delay::new_default(); // TODO: Need to wait asnchronously if in async mode
+        let mut delay_impl = crate::delay::Delay::new_default(); // TODO: Need to wait asnchronously if in async mode
         let mut result = Ok(());
 
         let mut spi_operations = self
@@ -963,7 +2066,8 @@ where
 
         while spi_operations.peek().is_some() {
             if let Some(SpiOperation::Delay(delay)) = spi_operations.peek() {
-                delay_impl.delay_us(*delay);
+                // `delay_ns` rounds up, so sub-microsecond delays are not dropped
+                embedded_hal::delay::DelayNs::delay_ns(&mut delay_impl, *delay);
                 spi_operations.next();
             } else {
                 let transactions = core::iter::from_fn(|| {
@@ -1074,25 +2178,35 @@ where
         }
 
         let chunk_size = self.driver.borrow().max_transfer_size;
+        let line_flags = self.line_flags;
+
+        operations
+            .flat_map(move |op| match op {
+                Operation::Read(words) => OperationsIter::Read(
+                    spi_read_transactions(words, chunk_size).map(SpiOperation::Transaction),
+                ),
+                Operation::Write(words) => OperationsIter::Write(
+                    spi_write_transactions(words, chunk_size).map(SpiOperation::Transaction),
+                ),
+                Operation::Transfer(read, write) => OperationsIter::Transfer(
+                    spi_transfer_transactions(read, write, chunk_size)
+                        .map(SpiOperation::Transaction),
+                ),
+                Operation::TransferInPlace(words) => OperationsIter::TransferInPlace(
+                    spi_transfer_in_place_transactions(words, chunk_size)
+                        .map(SpiOperation::Transaction),
+                ),
+                Operation::DelayNs(delay) => {
+                    OperationsIter::Delay(core::iter::once(SpiOperation::Delay(delay)))
+                }
+            })
+            .map(move |mut operation| {
+                if let SpiOperation::Transaction(transaction) = &mut operation {
+                    transaction.flags |= line_flags;
+                }
 
-        operations.flat_map(move |op| match op {
-            Operation::Read(words) => OperationsIter::Read(
-                spi_read_transactions(words, chunk_size).map(SpiOperation::Transaction),
-            ),
-            Operation::Write(words) => OperationsIter::Write(
-                spi_write_transactions(words, chunk_size).map(SpiOperation::Transaction),
-            ),
-            Operation::Transfer(read, write) => OperationsIter::Transfer(
-                spi_transfer_transactions(read, write, chunk_size).map(SpiOperation::Transaction),
-            ),
-            Operation::TransferInPlace(words) => OperationsIter::TransferInPlace(
-                spi_transfer_in_place_transactions(words, chunk_size)
-                    .map(SpiOperation::Transaction),
-            ),
-            Operation::DelayNs(delay) => {
-                OperationsIter::Delay(core::iter::once(SpiOperation::Delay(delay)))
-            }
-        })
+                operation
+            })
     }
 }
 
@@ -1189,8 +2303,9 @@ where
 
             let mut transaction =
                 spi_create_transaction(core::ptr::null_mut(), buf[..offset].as_ptr(), offset, 0);
+            transaction.flags |= self.line_flags;
 
-            if lock.is_none() && words.peek().is_some() {
+            if lock.is_none() && !self.bus_acquired && words.peek().is_some() {
                 lock = Some(BusLock::new(self.handle)?);
             }
 
@@ -1262,6 +2377,43 @@ where
     }
 }
 
+#[cfg(feature = "alloc")]
+macro_rules! impl_spi_device_words {
+    ($word:ty) => {
+        impl<'d, T> SpiDevice<$word> for SpiDeviceDriver<'d, T>
+        where
+            T: Borrow<SpiDriver<'d>> + 'd,
+        {
+            fn transaction(
+                &mut self,
+                operations: &mut [Operation<'_, $word>],
+            ) -> Result<(), Self::Error> {
+                Self::transaction_words(self, operations).map_err(to_spi_err)
+            }
+        }
+
+        #[cfg(not(esp_idf_spi_master_isr_in_iram))]
+        impl<'d, T> embedded_hal_async::spi::SpiDevice<$word> for SpiDeviceDriver<'d, T>
+        where
+            T: Borrow<SpiDriver<'d>> + 'd,
+        {
+            async fn transaction(
+                &mut self,
+                operations: &mut [Operation<'_, $word>],
+            ) -> Result<(), Self::Error> {
+                Self::transaction_words_async(self, operations)
+                    .await
+                    .map_err(to_spi_err)
+            }
+        }
+    };
+}
+
+#[cfg(feature = "alloc")]
+impl_spi_device_words!(u16);
+#[cfg(feature = "alloc")]
+impl_spi_device_words!(u32);
+
 pub struct SpiSharedDeviceDriver<'d, T>
 where
     T: Borrow<SpiDriver<'d>> + 'd,
@@ -1310,9 +2462,16 @@ where
     }
 }
 
+/// An SPI device with a chip select line driven in software via a GPIO
+///
+/// This allows for more devices on a bus than the number of hardware CS lines of the
+/// SPI peripheral. Each transaction acquires the bus, asserts the CS line, executes the
+/// operations and de-asserts the CS line - even if the operations fail or an async
+/// transaction is cancelled - before releasing the bus.
 pub struct SpiSoftCsDeviceDriver<'d, DEVICE, DRIVER> {
     shared_device: DEVICE,
     cs_pin: PinDriver<'d, AnyOutputPin, Output>,
+    cs_level: Level,
     pre_delay_us: Option<u32>,
     post_delay_us: Option<u32>,
     _p: PhantomData<fn() -> DRIVER>,
@@ -1323,6 +2482,8 @@ where
     DEVICE: Borrow<SpiSharedDeviceDriver<'d, DRIVER>>,
     DRIVER: Borrow<SpiDriver<'d>> + 'd,
 {
+    /// Creates a new device with `cs` as its chip select line, where `cs_level` is the
+    /// level of the line while inactive (i.e. `Level::High` for an active low chip select).
     pub fn new(
         shared_device: DEVICE,
         cs: impl Peripheral<P = impl OutputPin> + 'd,
@@ -1337,6 +2498,7 @@ where
         Ok(Self {
             shared_device,
             cs_pin,
+            cs_level,
             pre_delay_us: None,
             post_delay_us: None,
             _p: PhantomData,
@@ -1413,6 +2575,8 @@ where
     ) -> Result<(), EspError> {
         let cs_pin = CsCtl::Software {
             cs: &mut self.cs_pin,
+            inactive_level: self.cs_level,
+            asserted: false,
             pre_delay: self.pre_delay_us,
             post_delay: self.post_delay_us,
         };
@@ -1429,6 +2593,8 @@ where
     ) -> Result<(), EspError> {
         let cs_pin = CsCtl::Software {
             cs: &mut self.cs_pin,
+            inactive_level: self.cs_level,
+            asserted: false,
             pre_delay: self.pre_delay_us,
             post_delay: self.post_delay_us,
         };
@@ -1544,6 +2710,8 @@ where
     },
     Software {
         cs: &'c mut PinDriver<'p, P, M>,
+        inactive_level: Level,
+        asserted: bool,
         pre_delay: Option<u32>,
         post_delay: Option<u32>,
     },
@@ -1564,8 +2732,16 @@ where
     }
 
     fn raise_cs(&mut self) -> Result<(), EspError> {
-        if let CsCtl::Software { cs, pre_delay, .. } = self {
-            cs.toggle()?;
+        if let CsCtl::Software {
+            cs,
+            inactive_level,
+            asserted,
+            pre_delay,
+            ..
+        } = self
+        {
+            cs.set_level(!*inactive_level)?;
+            *asserted = true;
 
             // TODO: Need to wait asnchronously if in async mode
             if let Some(delay) = pre_delay {
@@ -1577,8 +2753,16 @@ where
     }
 
     fn lower_cs(&mut self) -> Result<(), EspError> {
-        if let CsCtl::Software { cs, post_delay, .. } = self {
-            cs.toggle()?;
+        if let CsCtl::Software {
+            cs,
+            inactive_level,
+            asserted,
+            post_delay,
+            ..
+        } = self
+        {
+            cs.set_level(*inactive_level)?;
+            *asserted = false;
 
             // TODO: Need to wait asnchronously if in async mode
             if let Some(delay) = post_delay {
@@ -1607,6 +2791,25 @@ where
     }
 }
 
+impl<'c, 'p, P, M> Drop for CsCtl<'c, 'p, P, M>
+where
+    P: OutputPin,
+    M: OutputMode,
+{
+    fn drop(&mut self) {
+        // De-assert the CS line, if the transaction failed early or was cancelled
+        if let CsCtl::Software {
+            cs,
+            inactive_level,
+            asserted: true,
+            ..
+        } = self
+        {
+            let _ = cs.set_level(*inactive_level);
+        }
+    }
+}
+
 fn spi_read_transactions(
     words: &mut [u8],
     chunk_size: usize,
@@ -1888,6 +3091,15 @@ extern "C" fn spi_notify(transaction: *mut spi_transaction_t) {
     }
 }
 
+#[cfg(not(esp_idf_version_major = "4"))]
+fn spi_actual_baudrate(handle: spi_device_handle_t) -> Result<crate::units::Hertz, EspError> {
+    let mut freq_khz = 0;
+
+    esp!(unsafe { spi_device_get_actual_freq(handle, &mut freq_khz) })?;
+
+    Ok(crate::units::Hertz(freq_khz as u32 * 1000))
+}
+
 fn copy_operation<'b>(operation: &'b mut Operation<'_, u8>) -> Operation<'b, u8> {
     match operation {
         Operation::Read(read) => Operation::Read(read),
@@ -1965,3 +3177,168 @@ impl_spi!(SPI3: spi_host_device_t_SPI3_HOST);
 impl_spi_any_pins!(SPI2);
 #[cfg(any(esp32, esp32s2, esp32s3))]
 impl_spi_any_pins!(SPI3);
+
+#[cfg(test)]
+mod tests {
+    use core::marker::PhantomData;
+    use core::ptr;
+
+    use embassy_sync::mutex::Mutex;
+    use esp_idf_sys::*;
+
+    use crate::delay::BLOCK;
+
+    use super::config::{BitOrder, Config, Duplex};
+    use super::{
+        swap_rx, swap_tx, swap_words, swap_words_into, words_as_bytes, Dma, SpiDeviceDriver,
+        SpiDriver,
+    };
+
+    #[test]
+    fn device_flags_default() {
+        assert_eq!(Config::new().device_flags(), 0);
+    }
+
+    #[test]
+    fn device_flags_combine() {
+        let config = Config::new()
+            .duplex(Duplex::Half)
+            .bit_order(BitOrder::LsbFirst)
+            .write_only(true)
+            .cs_active_high();
+
+        assert_eq!(
+            config.device_flags(),
+            SPI_DEVICE_HALFDUPLEX
+                | SPI_DEVICE_BIT_LSBFIRST
+                | SPI_DEVICE_NO_DUMMY
+                | SPI_DEVICE_POSITIVE_CS
+        );
+    }
+
+    #[test]
+    fn device_flags_three_wire() {
+        let config = Config::new().three_wire(true);
+
+        assert_eq!(
+            config.device_flags(),
+            SPI_DEVICE_HALFDUPLEX | SPI_DEVICE_3WIRE
+        );
+
+        let config = config.three_wire(false);
+
+        assert_eq!(config.duplex, Duplex::Full);
+        assert_eq!(config.device_flags(), 0);
+    }
+
+    #[test]
+    fn acquire_bus_through_guard() {
+        let mut device = SpiDeviceDriver {
+            handle: ptr::null_mut(),
+            driver: SpiDriver {
+                host: 0,
+                dma: Dma::Disabled,
+                max_transfer_size: 64,
+                bus_async_lock: Mutex::new(()),
+                _p: PhantomData,
+            },
+            cs_pin_configured: false,
+            polling: true,
+            allow_pre_post_delays: false,
+            queue_size: 1,
+            duplex: Duplex::Full,
+            command_bits: 0,
+            address_bits: 0,
+            line_flags: 0,
+            bit_order: BitOrder::MsbFirst,
+            // As if called through a `BusGuard` of the device
+            bus_acquired: true,
+            _d: PhantomData,
+        };
+
+        let err = device.acquire_bus(BLOCK).err().unwrap();
+        assert_eq!(err.code(), ESP_ERR_INVALID_STATE);
+
+        // Neither the device nor the bus were ever added to ESP-IDF
+        core::mem::forget(device);
+    }
+
+    #[test]
+    fn swap_words_into_odd_length() {
+        let mut buf = [0_u8; 8];
+
+        swap_words_into(&[0x0102_u16, 0x0304, 0x0506], &mut buf);
+        assert_eq!(buf, [1, 2, 3, 4, 5, 6, 0, 0]);
+
+        swap_words_into(&[0x0102_0304_u32], &mut buf);
+        assert_eq!(buf, [1, 2, 3, 4, 5, 6, 0, 0]);
+    }
+
+    #[test]
+    fn words_as_bytes_lsb_first() {
+        // In LSB first mode the words are sent as they are laid out in memory
+        assert_eq!(
+            words_as_bytes(&[0x0102_u16, 0x0304, 0x0506]),
+            [2, 1, 4, 3, 6, 5]
+        );
+        assert_eq!(
+            words_as_bytes(&[0x0102_0304_u32, 0x0506_0708, 0x090a_0b0c]),
+            [4, 3, 2, 1, 8, 7, 6, 5, 12, 11, 10, 9]
+        );
+    }
+
+    #[test]
+    fn swap_words_odd_length() {
+        let mut words = [0x0102_u16, 0x0304, 0x0506];
+
+        swap_words(&mut words);
+        assert_eq!(words, [0x0201, 0x0403, 0x0605]);
+
+        swap_words(&mut words);
+        assert_eq!(words, [0x0102, 0x0304, 0x0506]);
+    }
+
+    #[test]
+    fn bit_order_swaps() {
+        assert!(swap_tx(BitOrder::MsbFirst) && swap_rx(BitOrder::MsbFirst));
+        assert!(!swap_tx(BitOrder::LsbFirst) && !swap_rx(BitOrder::LsbFirst));
+        assert!(!swap_tx(BitOrder::TxLsbFirst) && swap_rx(BitOrder::TxLsbFirst));
+        assert!(swap_tx(BitOrder::RxLsbFirst) && !swap_rx(BitOrder::RxLsbFirst));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn word_operations_lsb_first() {
+        use embedded_hal::spi::Operation;
+
+        use super::{byte_operations, swap_rx_operations, swap_tx_operations};
+
+        let mut read = [0x0102_u16; 3];
+        let mut in_place = [0x0304_u16; 3];
+        let write = [0x0506_u16; 3];
+
+        let mut operations = [
+            Operation::Read(&mut read),
+            Operation::Write(&write),
+            Operation::TransferInPlace(&mut in_place),
+            Operation::DelayNs(10),
+        ];
+
+        // Nothing is swapped (or allocated) in LSB first mode
+        let swapped = swap_tx_operations(&mut operations, BitOrder::LsbFirst).unwrap();
+        assert!(swapped.iter().all(Option::is_none));
+
+        let bytes = byte_operations(&mut operations, &swapped);
+        assert!(matches!(bytes[0], Operation::Read(ref read) if read.len() == 6));
+        assert!(matches!(bytes[1], Operation::Write(write) if write == [6, 5, 6, 5, 6, 5]));
+        assert!(matches!(bytes[2], Operation::TransferInPlace(ref words) if words.len() == 6));
+        assert!(matches!(bytes[3], Operation::DelayNs(10)));
+        drop(bytes);
+
+        swap_rx_operations(&mut operations, BitOrder::LsbFirst);
+        swap_rx_operations(&mut operations, BitOrder::TxLsbFirst);
+
+        assert_eq!(read, [0x0201; 3]);
+        assert_eq!(in_place, [0x0403; 3]);
+    }
+}
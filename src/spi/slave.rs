@@ -0,0 +1,356 @@
+//! SPI slave driver
+//!
+//! `SpiSlaveDriver` operates SPI2 or SPI3 as an SPI slave (peripheral), clocked by an external
+//! SPI master. Since the master decides when (and for how long) a transaction runs, each
+//! transaction has to be armed with its buffers before the master starts clocking. A blocking
+//! [SpiSlaveDriver::transfer] does both in one step, while [SpiSlaveDriver::queue_transaction]
+//! and [SpiSlaveDriver::wait_transaction] allow arming several transactions in advance.
+//!
+//! With DMA enabled, the ESP-IDF slave driver does not copy the buffers through bounce buffers,
+//! so the buffers should be in DMA-capable internal RAM (see [crate::dma::DmaBuffer]), and their
+//! lengths should be a multiple of 4 bytes. Buffers which are not DMA-capable are rejected with
+//! `ESP_ERR_INVALID_ARG`, and buffers with other lengths - with `ESP_ERR_INVALID_SIZE`.
+
+use core::cmp::max;
+use core::marker::PhantomData;
+use core::ptr;
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+#[cfg(feature = "alloc")]
+use alloc::collections::VecDeque;
+
+use esp_idf_sys::*;
+
+use crate::delay::BLOCK;
+use crate::dma::is_dma_capable_slice;
+use crate::gpio::{InputPin, OutputPin};
+use crate::interrupt::InterruptType;
+use crate::peripheral::Peripheral;
+
+use super::{Dma, SpiAnyPins};
+
+pub type SpiSlaveConfig = config::Config;
+
+/// SPI slave configuration
+pub mod config {
+    use enumset::EnumSet;
+    use esp_idf_sys::*;
+
+    use crate::interrupt::InterruptType;
+
+    use super::super::config::{BitOrder, Mode};
+    use super::Dma;
+
+    /// SPI slave configuration
+    #[derive(Debug, Clone)]
+    pub struct Config {
+        pub data_mode: Mode,
+        pub bit_order: BitOrder,
+        /// The number of transactions which can be queued with `SpiSlaveDriver::queue_transaction`
+        pub queue_size: usize,
+        pub dma: Dma,
+        pub intr_flags: EnumSet<InterruptType>,
+    }
+
+    impl Config {
+        pub fn new() -> Self {
+            Default::default()
+        }
+
+        #[must_use]
+        pub fn data_mode(mut self, data_mode: Mode) -> Self {
+            self.data_mode = data_mode;
+            self
+        }
+
+        #[must_use]
+        pub fn bit_order(mut self, bit_order: BitOrder) -> Self {
+            self.bit_order = bit_order;
+            self
+        }
+
+        #[must_use]
+        pub fn queue_size(mut self, queue_size: usize) -> Self {
+            self.queue_size = queue_size;
+            self
+        }
+
+        #[must_use]
+        pub fn dma(mut self, dma: Dma) -> Self {
+            self.dma = dma;
+            self
+        }
+
+        #[must_use]
+        pub fn intr_flags(mut self, intr_flags: EnumSet<InterruptType>) -> Self {
+            self.intr_flags = intr_flags;
+            self
+        }
+
+        pub(super) fn flags(&self) -> u32 {
+            match self.bit_order {
+                BitOrder::MsbFirst => 0,
+                BitOrder::LsbFirst => SPI_SLAVE_BIT_LSBFIRST,
+                BitOrder::TxLsbFirst => SPI_SLAVE_TXBIT_LSBFIRST,
+                BitOrder::RxLsbFirst => SPI_SLAVE_RXBIT_LSBFIRST,
+            }
+        }
+    }
+
+    impl Default for Config {
+        fn default() -> Self {
+            Self {
+                data_mode: embedded_hal::spi::MODE_0,
+                bit_order: BitOrder::MsbFirst,
+                queue_size: 1,
+                dma: Dma::Disabled,
+                intr_flags: EnumSet::<InterruptType>::empty(),
+            }
+        }
+    }
+}
+
+pub struct SpiSlaveDriver<'d> {
+    host: u8,
+    dma: Dma,
+    max_transfer_size: usize,
+    #[cfg(feature = "alloc")]
+    queued: VecDeque<Box<spi_slave_transaction_t>>,
+    _p: PhantomData<&'d mut ()>,
+}
+
+impl<'d> SpiSlaveDriver<'d> {
+    /// Create new instance of an SPI slave
+    ///
+    /// `sdi` is the input (MOSI) line and `sdo` is the output (MISO) line of the slave.
+    pub fn new<SPI: SpiAnyPins>(
+        _spi: impl Peripheral<P = SPI> + 'd,
+        sclk: impl Peripheral<P = impl InputPin> + 'd,
+        sdi: impl Peripheral<P = impl InputPin> + 'd,
+        sdo: Option<impl Peripheral<P = impl OutputPin> + 'd>,
+        cs: impl Peripheral<P = impl InputPin> + 'd,
+        config: &config::Config,
+    ) -> Result<Self, EspError> {
+        crate::into_ref!(sclk, sdi, cs);
+        let sdo = sdo.map(|sdo| sdo.into_ref());
+
+        let max_transfer_size = config.dma.max_transfer_size();
+
+        #[allow(clippy::needless_update)]
+        #[cfg(not(esp_idf_version = "4.3"))]
+        let bus_config = spi_bus_config_t {
+            flags: SPICOMMON_BUSFLAG_SLAVE,
+            sclk_io_num: sclk.pin(),
+
+            data4_io_num: -1,
+            data5_io_num: -1,
+            data6_io_num: -1,
+            data7_io_num: -1,
+            __bindgen_anon_1: spi_bus_config_t__bindgen_ty_1 {
+                mosi_io_num: sdi.pin(),
+            },
+            __bindgen_anon_2: spi_bus_config_t__bindgen_ty_2 {
+                miso_io_num: sdo.as_ref().map_or(-1, |p| p.pin()),
+            },
+            __bindgen_anon_3: spi_bus_config_t__bindgen_ty_3 { quadwp_io_num: -1 },
+            __bindgen_anon_4: spi_bus_config_t__bindgen_ty_4 { quadhd_io_num: -1 },
+            max_transfer_sz: max_transfer_size as i32,
+            intr_flags: InterruptType::to_native(config.intr_flags) as _,
+            ..Default::default()
+        };
+
+        #[allow(clippy::needless_update)]
+        #[cfg(esp_idf_version = "4.3")]
+        let bus_config = spi_bus_config_t {
+            flags: SPICOMMON_BUSFLAG_SLAVE,
+            sclk_io_num: sclk.pin(),
+
+            mosi_io_num: sdi.pin(),
+            miso_io_num: sdo.as_ref().map_or(-1, |p| p.pin()),
+            quadwp_io_num: -1,
+            quadhd_io_num: -1,
+
+            max_transfer_sz: max_transfer_size as i32,
+            intr_flags: InterruptType::to_native(config.intr_flags) as _,
+            ..Default::default()
+        };
+
+        let slave_config = spi_slave_interface_config_t {
+            spics_io_num: cs.pin(),
+            flags: config.flags(),
+            queue_size: config.queue_size as i32,
+            mode: super::data_mode_to_u8(config.data_mode),
+            ..Default::default()
+        };
+
+        esp!(unsafe {
+            spi_slave_initialize(SPI::device(), &bus_config, &slave_config, config.dma.into())
+        })?;
+
+        Ok(Self {
+            host: SPI::device() as _,
+            dma: config.dma,
+            max_transfer_size,
+            #[cfg(feature = "alloc")]
+            queued: VecDeque::new(),
+            _p: PhantomData,
+        })
+    }
+
+    pub fn host(&self) -> spi_host_device_t {
+        self.host as _
+    }
+
+    /// Returns the maximum size in bytes of a single transaction
+    pub fn max_transfer_size(&self) -> usize {
+        self.max_transfer_size
+    }
+
+    /// Arms a transaction with the provided buffers and waits for the master to execute it.
+    ///
+    /// As an armed transaction cannot be withdrawn, this method blocks until the master
+    /// has clocked it, for as long as that takes. Use `SpiSlaveDriver::queue_transaction` and
+    /// `SpiSlaveDriver::wait_transaction` to wait with a timeout instead.
+    ///
+    /// Returns the number of bytes actually clocked by the master, which might be less
+    /// than the length of the buffers.
+    ///
+    /// Either buffer might be empty. If both are not, they should be of the same length.
+    ///
+    /// Fails with `ESP_ERR_INVALID_STATE` if there are transactions queued with
+    /// `SpiSlaveDriver::queue_transaction` which are not waited for yet.
+    pub fn transfer(&mut self, rx: &mut [u8], tx: &[u8]) -> Result<usize, EspError> {
+        #[cfg(feature = "alloc")]
+        if !self.queued.is_empty() {
+            Err(EspError::from_infallible::<ESP_ERR_INVALID_STATE>())?;
+        }
+
+        let mut transaction = self.create_transaction(rx, tx)?;
+
+        // With `BLOCK`, `spi_slave_transmit` only returns once the transaction is done, so
+        // the driver never keeps a pointer to the transaction (or the buffers) on the stack
+        esp!(unsafe { spi_slave_transmit(self.host(), &mut transaction, BLOCK) })?;
+
+        Ok(transaction.trans_len.div_ceil(8))
+    }
+
+    /// Arms a transaction with the provided buffers, waiting for up to `timeout` ticks
+    /// for a free slot in the transactions' queue.
+    ///
+    /// The queued transactions are executed by the master in order, and their results
+    /// should be retrieved in the same order with `SpiSlaveDriver::wait_transaction`.
+    ///
+    /// # Safety
+    ///
+    /// Once this method returns `Ok`, the hardware (and possibly the DMA) reads `tx` and writes
+    /// `rx` whenever the master clocks the transaction. Until the transaction is returned by
+    /// `SpiSlaveDriver::wait_transaction`, or until the driver is dropped, the caller must
+    /// guarantee that:
+    /// - the memory of both buffers is neither freed nor reused, even if the borrows of `rx`
+    ///   and `tx` end earlier - this includes leaking the driver with `core::mem::forget`,
+    ///   in which case the buffers must stay valid forever;
+    /// - `rx` is neither read nor written, and `tx` is not written, by anything else.
+    #[cfg(feature = "alloc")]
+    pub unsafe fn queue_transaction(
+        &mut self,
+        rx: &mut [u8],
+        tx: &[u8],
+        timeout: TickType_t,
+    ) -> Result<(), EspError> {
+        let transaction = Box::new(self.create_transaction(rx, tx)?);
+
+        esp!(spi_slave_queue_trans(self.host(), &*transaction, timeout))?;
+
+        self.queued.push_back(transaction);
+
+        Ok(())
+    }
+
+    /// Waits for up to `timeout` ticks for the oldest transaction queued with
+    /// `SpiSlaveDriver::queue_transaction` to be executed by the master.
+    ///
+    /// Returns the number of bytes actually clocked by the master.
+    ///
+    /// Fails with `ESP_ERR_INVALID_STATE` if there are no queued transactions, or if the
+    /// ESP-IDF driver returned a transaction other than the oldest queued one.
+    #[cfg(feature = "alloc")]
+    pub fn wait_transaction(&mut self, timeout: TickType_t) -> Result<usize, EspError> {
+        let Some(oldest) = self.queued.front() else {
+            return Err(EspError::from_infallible::<ESP_ERR_INVALID_STATE>());
+        };
+
+        let oldest: *const spi_slave_transaction_t = &**oldest;
+
+        let mut rtrans: *mut spi_slave_transaction_t = ptr::null_mut();
+
+        esp!(unsafe { spi_slave_get_trans_result(self.host(), &mut rtrans, timeout) })?;
+
+        if !ptr::eq(rtrans, oldest) {
+            // Keep the transaction, as its buffers might still be in use
+            Err(EspError::from_infallible::<ESP_ERR_INVALID_STATE>())?;
+        }
+
+        let transaction = self.queued.pop_front().unwrap();
+
+        Ok(transaction.trans_len.div_ceil(8))
+    }
+
+    /// Returns the number of queued transactions, which are not waited for yet
+    #[cfg(feature = "alloc")]
+    pub fn queued(&self) -> usize {
+        self.queued.len()
+    }
+
+    fn create_transaction(
+        &self,
+        rx: &mut [u8],
+        tx: &[u8],
+    ) -> Result<spi_slave_transaction_t, EspError> {
+        let rx_len = rx.len();
+
+        if rx_len != 0 && !tx.is_empty() && rx_len != tx.len() {
+            // The slave driver would write past the end of a shorter `rx` buffer
+            Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>())?;
+        }
+
+        let len = max(rx_len, tx.len());
+
+        if len > self.max_transfer_size || (self.dma != Dma::Disabled && len % 4 != 0) {
+            Err(EspError::from_infallible::<ESP_ERR_INVALID_SIZE>())?;
+        }
+
+        if self.dma != Dma::Disabled && !(is_dma_capable_slice(rx) && is_dma_capable_slice(tx)) {
+            // Unlike the master driver, the slave driver does not use bounce buffers
+            Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>())?;
+        }
+
+        Ok(spi_slave_transaction_t {
+            length: len * 8,
+            tx_buffer: if tx.is_empty() {
+                ptr::null()
+            } else {
+                tx.as_ptr() as *const _
+            },
+            rx_buffer: if rx_len == 0 {
+                ptr::null_mut()
+            } else {
+                rx.as_mut_ptr() as *mut _
+            },
+            ..Default::default()
+        })
+    }
+}
+
+impl<'d> Drop for SpiSlaveDriver<'d> {
+    fn drop(&mut self) {
+        if let Err(e) = esp!(unsafe { spi_slave_free(self.host()) }) {
+            ::log::error!("Failed to free the SPI slave driver: {e}");
+        }
+    }
+}
+
+unsafe impl<'d> Send for SpiSlaveDriver<'d> {}
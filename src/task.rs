@@ -88,9 +88,7 @@ pub unsafe fn destroy(task: TaskHandle_t) {
 pub fn do_yield() {
     if interrupt::active() {
         unsafe {
-            if let Some((yielder, arg)) = interrupt::get_isr_yielder() {
-                yielder(arg);
-            } else {
+            if !interrupt::invoke_isr_yielders() {
                 #[cfg(any(esp32c3, esp32c2, esp32h2, esp32c5, esp32c6))]
                 vPortYieldFromISR();
 
@@ -766,7 +764,7 @@ pub mod watchdog {
     crate::impl_peripheral!(TWDT);
 }
 
-#[cfg(feature = "critical-section")]
+#[cfg(all(feature = "critical-section", not(feature = "isr-critical-section")))]
 pub mod critical_section {
     static CS: super::CriticalSection = super::CriticalSection::new();
 
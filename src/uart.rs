@@ -33,7 +33,7 @@
 //! ```
 //!
 //! # TODO
-//! - Add all extra features esp32 supports (eg rs485, etc. etc.)
+//! - Add all extra features esp32 supports
 //! - Free APB lock when TX is idle (and no RX used)
 //! - Address errata 3.17: UART fifo_cnt is inconsistent with FIFO pointer
 
@@ -42,10 +42,11 @@ use core::ffi::CStr;
 use core::marker::PhantomData;
 use core::mem::ManuallyDrop;
 use core::ptr;
-use core::sync::atomic::{AtomicU8, Ordering};
+use core::sync::atomic::{AtomicBool, AtomicU32, AtomicU8, Ordering};
+use core::time::Duration;
 
 use crate::cpu::Core;
-use crate::delay::{self, NON_BLOCK};
+use crate::delay::{self, TickType, NON_BLOCK};
 use crate::interrupt::InterruptType;
 use crate::io::EspIOError;
 use crate::task::asynch::Notification;
@@ -54,6 +55,7 @@ use crate::units::*;
 use crate::{gpio::*, task};
 
 use embedded_hal_nb::serial::ErrorKind;
+use enumset::EnumSet;
 use esp_idf_sys::*;
 
 use crate::peripheral::Peripheral;
@@ -101,7 +103,9 @@ pub mod config {
         }
     }
 
-    /// Number of data bits
+    /// Flow control
+    ///
+    /// The hardware flow control modes require the corresponding RTS and/or CTS pins.
     #[derive(PartialEq, Eq, Copy, Clone, Debug)]
     pub enum FlowControl {
         None,
@@ -109,12 +113,30 @@ pub mod config {
         CTS,
         CTSRTS,
         MAX,
+        /// Software (XON/XOFF) flow control: XOFF is sent once the RX FIFO contains more than
+        /// `xoff_threshold` bytes, and XON once it contains less than `xon_threshold` bytes
+        Software {
+            xon_threshold: u8,
+            xoff_threshold: u8,
+        },
+    }
+
+    impl FlowControl {
+        pub(super) fn needs_rts(&self) -> bool {
+            matches!(self, Self::RTS | Self::CTSRTS)
+        }
+
+        pub(super) fn needs_cts(&self) -> bool {
+            matches!(self, Self::CTS | Self::CTSRTS)
+        }
     }
 
     impl From<FlowControl> for uart_hw_flowcontrol_t {
         fn from(flow_control: FlowControl) -> Self {
             match flow_control {
-                FlowControl::None => uart_hw_flowcontrol_t_UART_HW_FLOWCTRL_DISABLE,
+                FlowControl::None | FlowControl::Software { .. } => {
+                    uart_hw_flowcontrol_t_UART_HW_FLOWCTRL_DISABLE
+                }
                 FlowControl::RTS => uart_hw_flowcontrol_t_UART_HW_FLOWCTRL_RTS,
                 FlowControl::CTS => uart_hw_flowcontrol_t_UART_HW_FLOWCTRL_CTS,
                 FlowControl::CTSRTS => uart_hw_flowcontrol_t_UART_HW_FLOWCTRL_CTS_RTS,
@@ -137,6 +159,56 @@ pub mod config {
         }
     }
 
+    /// UART mode
+    #[derive(PartialEq, Eq, Copy, Clone, Debug)]
+    pub enum Mode {
+        /// Regular UART mode
+        UART,
+        /// RS485 half-duplex mode, with the RTS pin driving the direction (DE/RE)
+        /// of the transceiver
+        ///
+        /// RTS is asserted while transmitting, and is de-asserted by the driver once the
+        /// stop bit of the last byte has left the shift register - i.e. by the time
+        /// `wait_tx_done` returns. Requires the RTS pin and no hardware flow control.
+        RS485HalfDuplex,
+        /// Same as `RS485HalfDuplex`, but also detects collisions, i.e. differences between
+        /// the sent and the echoed data, which are reported by `collision_detected`
+        RS485CollisionDetect,
+        /// RS485 mode with the direction controlled by the application, either with
+        /// `set_rts` or with any other GPIO pin (for transceivers wired differently),
+        /// and with collisions reported by `collision_detected`
+        RS485AppControl,
+        /// IrDA (SIR) mode, with the TX pulses encoded and the RX pulses decoded by the
+        /// UART, for IrDA transceivers. The mode is set with `uart_set_mode`, so whether it is
+        /// available depends on the chip's UART and the ESP-IDF driver; creating the driver
+        /// fails if it is not.
+        ///
+        /// IrDA is half duplex, but neither ESP-IDF nor this driver switch the direction of
+        /// the IrDA encoder/decoder, and most transceivers echo the transmitted pulses on their
+        /// RX output. So the bytes received while transmitting - until `wait_tx_done` returns -
+        /// should be discarded, e.g. with `clear_rx`, and a transceiver with a shutdown or
+        /// mode pin has to be controlled with a GPIO pin. Requires no hardware flow control.
+        IrDA,
+    }
+
+    impl Mode {
+        pub(super) fn is_rs485_auto(&self) -> bool {
+            matches!(self, Self::RS485HalfDuplex | Self::RS485CollisionDetect)
+        }
+    }
+
+    impl From<Mode> for uart_mode_t {
+        fn from(mode: Mode) -> Self {
+            match mode {
+                Mode::UART => uart_mode_t_UART_MODE_UART,
+                Mode::RS485HalfDuplex => uart_mode_t_UART_MODE_RS485_HALF_DUPLEX,
+                Mode::RS485CollisionDetect => uart_mode_t_UART_MODE_RS485_COLLISION_DETECT,
+                Mode::RS485AppControl => uart_mode_t_UART_MODE_RS485_APP_CTRL,
+                Mode::IrDA => uart_mode_t_UART_MODE_IRDA,
+            }
+        }
+    }
+
     /// Parity check
     #[derive(PartialEq, Eq, Copy, Clone, Debug)]
     pub enum Parity {
@@ -447,6 +519,76 @@ pub mod config {
         CmdCharDetected = 18,
     }
 
+    /// A UART signal, which can be inverted
+    #[derive(Debug, EnumSetType)]
+    pub enum SignalInversion {
+        Txd,
+        Rxd,
+        Rts,
+        Cts,
+    }
+
+    impl SignalInversion {
+        pub(super) fn to_native(signals: EnumSet<Self>) -> u32 {
+            signals
+                .iter()
+                .map(|signal| match signal {
+                    Self::Txd => uart_signal_inv_t_UART_SIGNAL_TXD_INV,
+                    Self::Rxd => uart_signal_inv_t_UART_SIGNAL_RXD_INV,
+                    Self::Rts => uart_signal_inv_t_UART_SIGNAL_RTS_INV,
+                    Self::Cts => uart_signal_inv_t_UART_SIGNAL_CTS_INV,
+                })
+                .fold(uart_signal_inv_t_UART_SIGNAL_INV_DISABLE, |mask, signal| {
+                    mask | signal
+                }) as _
+        }
+    }
+
+    /// Timing of the pattern detection, in baud cycles (bit periods)
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    pub struct PatternTiming {
+        /// The maximum idle time between the characters of the pattern
+        pub char_timeout: u16,
+        /// The minimum idle time after the last character of the pattern
+        pub post_idle: u16,
+        /// The minimum idle time before the first character of the pattern
+        pub pre_idle: u16,
+    }
+
+    impl PatternTiming {
+        pub const fn new() -> Self {
+            Self {
+                char_timeout: 9,
+                post_idle: 0,
+                pre_idle: 0,
+            }
+        }
+
+        #[must_use]
+        pub fn char_timeout(mut self, char_timeout: u16) -> Self {
+            self.char_timeout = char_timeout;
+            self
+        }
+
+        #[must_use]
+        pub fn post_idle(mut self, post_idle: u16) -> Self {
+            self.post_idle = post_idle;
+            self
+        }
+
+        #[must_use]
+        pub fn pre_idle(mut self, pre_idle: u16) -> Self {
+            self.pre_idle = pre_idle;
+            self
+        }
+    }
+
+    impl Default for PatternTiming {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
     /// UART configuration
     #[derive(Debug, Clone)]
     pub struct Config {
@@ -457,6 +599,11 @@ pub mod config {
         pub flow_control: FlowControl,
         pub flow_control_rts_threshold: u8,
         pub source_clock: SourceClock,
+        pub mode: Mode,
+        /// Whether to internally connect TX to RX (and RTS to CTS), e.g. for self-tests
+        pub loopback: bool,
+        /// The signals to invert, e.g. for inverted-logic devices
+        pub signal_inversion: EnumSet<SignalInversion>,
         /// Configures the flags to use for interrupt allocation,
         /// e.g. priority to use for the interrupt.
         ///
@@ -465,9 +612,12 @@ pub mod config {
         pub intr_flags: EnumSet<InterruptType>,
         /// Configures the interrupts the driver should enable.
         pub event_config: EventConfig,
-        /// The size of the software rx buffer. Must be bigger than the hardware FIFO.
+        /// The size of the software rx (ring) buffer. Must be bigger than the hardware FIFO.
+        ///
+        /// A bigger buffer avoids losing data at high baudrates, when the buffer is not read
+        /// from often enough.
         pub rx_fifo_size: usize,
-        /// The size of the software tx buffer. Must be bigger than the hardware FIFO
+        /// The size of the software tx (ring) buffer. Must be bigger than the hardware FIFO
         /// or 0 to disable transmit buffering (note that this will make write operations
         /// block until data has been sent out).
         pub tx_fifo_size: usize,
@@ -491,6 +641,9 @@ pub mod config {
                 flow_control: FlowControl::None,
                 flow_control_rts_threshold: 122,
                 source_clock: SourceClock::default(),
+                mode: Mode::UART,
+                loopback: false,
+                signal_inversion: EnumSet::EMPTY,
                 intr_flags: EnumSet::EMPTY,
                 event_config: EventConfig::new(),
                 rx_fifo_size: super::UART_FIFO_SIZE * 2,
@@ -557,6 +710,24 @@ pub mod config {
             self
         }
 
+        #[must_use]
+        pub fn mode(mut self, mode: Mode) -> Self {
+            self.mode = mode;
+            self
+        }
+
+        #[must_use]
+        pub fn loopback(mut self, loopback: bool) -> Self {
+            self.loopback = loopback;
+            self
+        }
+
+        #[must_use]
+        pub fn signal_inversion(mut self, signal_inversion: EnumSet<SignalInversion>) -> Self {
+            self.signal_inversion = signal_inversion;
+            self
+        }
+
         #[must_use]
         pub fn tx_fifo_size(mut self, tx_fifo_size: usize) -> Self {
             self.tx_fifo_size = tx_fifo_size;
@@ -593,12 +764,60 @@ crate::embedded_hal_error!(
     embedded_hal_nb::serial::ErrorKind
 );
 
+/// The error of `read_exact_timeout`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ReadTimeoutError {
+    /// The timeout expired before the buffer was filled
+    Timeout {
+        /// The number of bytes read into the buffer
+        read: usize,
+    },
+    Other(EspError),
+}
+
+impl From<EspError> for ReadTimeoutError {
+    fn from(e: EspError) -> Self {
+        Self::Other(e)
+    }
+}
+
+impl core::fmt::Display for ReadTimeoutError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Timeout { read } => write!(f, "Timeout after reading {read} bytes"),
+            Self::Other(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ReadTimeoutError {}
+
+/// The error of `pop_detected_pattern_position`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PatternPositionLost;
+
+impl core::fmt::Display for PatternPositionLost {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "The position of the detected pattern was lost")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PatternPositionLost {}
+
 #[derive(Clone, Copy)]
 #[repr(transparent)]
 pub struct UartEvent {
     raw: uart_event_t,
 }
 
+impl core::fmt::Debug for UartEvent {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("UartEvent").field(&self.payload()).finish()
+    }
+}
+
 impl UartEvent {
     pub fn payload(&self) -> UartEventPayload {
         #[allow(non_upper_case_globals)]
@@ -632,17 +851,81 @@ pub enum UartEventPayload {
         /// processed both interrupts at the same time.
         timeout: bool,
     },
-    /// Represents DATA event with timeout_flag set
+    /// A break (the RX line held low for longer than a frame) was received
     Break,
+    /// The RX ring buffer is full. The received data stays in the hardware FIFO
+    /// until the ring buffer is read from, and is lost if the FIFO overflows meanwhile
     RxBufferFull,
+    /// The hardware RX FIFO overflowed. The driver has reset the FIFO, so the data
+    /// in it is lost
     RxFifoOverflow,
+    /// A byte was received with a framing error
     FrameError,
+    /// A byte was received with a parity error
     ParityError,
+    /// A break was sent after the TX data
     DataBreak,
+    /// The configured pattern was detected in the RX data
     PatternDetected,
     Unknown,
 }
 
+impl UartEventPayload {
+    /// Returns `true` if the driver discarded received data while handling the event
+    pub fn data_lost(&self) -> bool {
+        matches!(self, Self::RxFifoOverflow)
+    }
+}
+
+/// A blocking iterator over the events of a UART driver, as returned by `UartDriver::listen`
+///
+/// The iterator only borrows the event queue, which is deleted together with the driver.
+///
+/// Each event is received only once, so an iterator should not be used while the driver
+/// is wrapped by one of the async drivers, as these consume the events as well.
+pub struct UartEventIter<'a> {
+    queue: &'a Queue<UartEvent>,
+}
+
+impl<'a> UartEventIter<'a> {
+    /// Waits for up to `timeout` ticks for the next event
+    pub fn recv(&mut self, timeout: TickType_t) -> Option<UartEvent> {
+        self.queue.recv_front(timeout).map(|(event, _)| event)
+    }
+
+    /// Returns the next event, if one is already pending
+    pub fn try_recv(&mut self) -> Option<UartEvent> {
+        self.recv(NON_BLOCK)
+    }
+
+    /// Same as `recv`, but also returns the time since boot when the event was taken from
+    /// the queue, e.g. to frame DMX512 packets using the `Break` events
+    ///
+    /// The timestamp is *not* the time of the event itself: the ESP-IDF driver does not
+    /// timestamp its events, so the time is read only once the event is dequeued by this
+    /// method. It therefore lags behind the event by the interrupt and scheduling latency of
+    /// the receiving task, which is unbounded if the task is preempted, and events which
+    /// queued up meanwhile all get about the same timestamp. It is good enough to tell
+    /// packets apart, but not to measure the timing of the line itself (e.g. the length of
+    /// a break), for which the RX pin should be captured with e.g. the RMT or PCNT drivers.
+    pub fn recv_with_timestamp(&mut self, timeout: TickType_t) -> Option<(UartEvent, Duration)> {
+        self.recv(timeout).map(|event| {
+            let now = unsafe { esp_timer_get_time() };
+
+            (event, Duration::from_micros(now as _))
+        })
+    }
+}
+
+impl<'a> Iterator for UartEventIter<'a> {
+    type Item = UartEvent;
+
+    /// Blocks until the next event
+    fn next(&mut self) -> Option<Self::Item> {
+        self.recv(delay::BLOCK)
+    }
+}
+
 /// Serial abstraction
 pub struct UartDriver<'d> {
     port: u8,
@@ -707,7 +990,17 @@ impl<'d> UartDriver<'d> {
         self.queue.as_ref()
     }
 
+    /// Returns an iterator over the events of this UART, with both blocking and
+    /// timeout-based retrieval.
+    ///
+    /// Fails with `ESP_ERR_INVALID_STATE` if the config specified 0 for `queue_size`.
+    pub fn listen(&self) -> Result<UartEventIter<'_>, EspError> {
+        listen(self.queue.as_ref())
+    }
+
     /// Change the number of stop bits
+    ///
+    /// Waits for the pending TX data to be sent first. The pending RX data is preserved.
     pub fn change_stop_bits(&self, stop_bits: config::StopBits) -> Result<&Self, EspError> {
         change_stop_bits(self.port(), stop_bits).map(|_| self)
     }
@@ -718,6 +1011,8 @@ impl<'d> UartDriver<'d> {
     }
 
     /// Change the number of data bits
+    ///
+    /// Waits for the pending TX data to be sent first. The pending RX data is preserved.
     pub fn change_data_bits(&self, data_bits: config::DataBits) -> Result<&Self, EspError> {
         change_data_bits(self.port(), data_bits).map(|_| self)
     }
@@ -728,6 +1023,8 @@ impl<'d> UartDriver<'d> {
     }
 
     /// Change the type of parity checking
+    ///
+    /// Waits for the pending TX data to be sent first. The pending RX data is preserved.
     pub fn change_parity(&self, parity: config::Parity) -> Result<&Self, EspError> {
         change_parity(self.port(), parity).map(|_| self)
     }
@@ -739,6 +1036,8 @@ impl<'d> UartDriver<'d> {
 
     /// Change the baudrate.
     ///
+    /// Waits for the pending TX data to be sent first. The pending RX data is preserved.
+    ///
     /// Will automatically select the clock source. When possible the reference clock (1MHz) will
     /// be used, because this is constant when the clock source/frequency changes.
     /// However if one of the clock frequencies is below 10MHz or if the baudrate is above
@@ -749,6 +1048,9 @@ impl<'d> UartDriver<'d> {
     }
 
     /// Returns the current baudrate
+    ///
+    /// This is the baudrate actually achieved by the clock divider, which can slightly differ
+    /// from the requested one.
     pub fn baudrate(&self) -> Result<Hertz, EspError> {
         baudrate(self.port())
     }
@@ -779,7 +1081,9 @@ impl<'d> UartDriver<'d> {
 
     /// Split the serial driver in separate TX and RX drivers.
     ///
-    /// Unlike [`split`], the halves are owned and reference counted.
+    /// Unlike [`UartDriver::split`], the halves are owned and reference counted, so each of them
+    /// can be moved to a different thread. The driver is uninstalled once both halves are
+    /// dropped, unless they are rejoined with [`UartDriver::reunite`].
     pub fn into_split(self) -> (UartTxDriver<'d>, UartRxDriver<'d>) {
         let port = self.port;
         let tx_queue = self
@@ -808,16 +1112,91 @@ impl<'d> UartDriver<'d> {
         )
     }
 
+    /// Rejoins the halves returned by [`UartDriver::into_split`] into a serial driver.
+    ///
+    /// Gives the halves back if they were not split from the same driver.
+    pub fn reunite(
+        tx: UartTxDriver<'d>,
+        rx: UartRxDriver<'d>,
+    ) -> Result<Self, (UartTxDriver<'d>, UartRxDriver<'d>)> {
+        if !matches!(tx.owner, Owner::Shared)
+            || !matches!(rx.owner, Owner::Shared)
+            || tx.port != rx.port
+        {
+            return Err((tx, rx));
+        }
+
+        let port = rx.port;
+        let queue = rx
+            .queue
+            .as_ref()
+            .map(|queue| unsafe { Queue::new_borrowed(queue.as_raw()) });
+        let _ = ManuallyDrop::new(tx);
+        let _ = ManuallyDrop::new(rx);
+        REFS[port as usize].fetch_sub(2, Ordering::SeqCst);
+
+        Ok(Self {
+            port,
+            queue,
+            _p: PhantomData,
+        })
+    }
+
     /// Read multiple bytes into a slice
     pub fn read(&self, buf: &mut [u8], timeout: TickType_t) -> Result<usize, EspError> {
         self.rx().read(buf, timeout)
     }
 
+    /// Read multiple bytes into a slice, waiting for up to `first_byte_timeout` ticks for
+    /// the first byte, and then only until the line is idle for `inter_byte_timeout`
+    ///
+    /// See `UartRxDriver::read_timeout` for details.
+    pub fn read_timeout(
+        &self,
+        buf: &mut [u8],
+        first_byte_timeout: TickType_t,
+        inter_byte_timeout: Duration,
+    ) -> Result<usize, EspError> {
+        self.rx()
+            .read_timeout(buf, first_byte_timeout, inter_byte_timeout)
+    }
+
+    /// Fill the whole slice, waiting for up to `timeout` ticks
+    pub fn read_exact_timeout(
+        &self,
+        buf: &mut [u8],
+        timeout: TickType_t,
+    ) -> Result<(), ReadTimeoutError> {
+        self.rx().read_exact_timeout(buf, timeout)
+    }
+
     /// Write multiple bytes from a slice
     pub fn write(&self, bytes: &[u8]) -> Result<usize, EspError> {
         self.tx().write(bytes)
     }
 
+    /// Write multiple bytes from a slice, followed by a break of `break_bits` bit times
+    ///
+    /// See `UartTxDriver::write_with_break` for details.
+    pub fn write_with_break(&self, bytes: &[u8], break_bits: u16) -> Result<usize, EspError> {
+        self.tx().write_with_break(bytes, break_bits)
+    }
+
+    /// Write an address byte with the 9th (parity) bit set, followed by `data` with the 9th
+    /// bit cleared
+    ///
+    /// See `UartTxDriver::write_9bit` for details.
+    pub fn write_9bit(&self, addr_byte: u8, data: &[u8]) -> Result<(), EspError> {
+        self.tx().write_9bit(addr_byte, data)
+    }
+
+    /// Send a break of `duration`, without any data
+    ///
+    /// See `UartTxDriver::send_break` for details.
+    pub fn send_break(&self, duration: Duration) -> Result<(), EspError> {
+        self.tx().send_break(duration)
+    }
+
     /// Write multiple bytes from a slice directly to the TX FIFO hardware.
     /// Returns the number of bytes written, where 0 would mean that the TX FIFO is full.
     ///
@@ -842,6 +1221,80 @@ impl<'d> UartDriver<'d> {
         self.rx().clear()
     }
 
+    /// Sets the timeout of the `embedded_io::Read` implementation, which otherwise blocks
+    /// until at least one byte is received
+    ///
+    /// The setting is shared with the halves of the driver.
+    pub fn set_io_read_timeout(&self, timeout: TickType_t) {
+        self.rx().set_io_read_timeout(timeout)
+    }
+
+    /// Returns the timeout of the `embedded_io::Read` implementation
+    pub fn io_read_timeout(&self) -> TickType_t {
+        self.rx().io_read_timeout()
+    }
+
+    /// Enables waking up the chip from light sleep once `rising_edges_threshold` rising edges
+    /// are received on the RX line
+    ///
+    /// See `UartRxDriver::enable_light_sleep_wakeup` for details.
+    pub fn enable_light_sleep_wakeup(&self, rising_edges_threshold: u16) -> Result<(), EspError> {
+        self.rx().enable_light_sleep_wakeup(rising_edges_threshold)
+    }
+
+    /// Disables waking up the chip from light sleep
+    pub fn disable_light_sleep_wakeup(&self) -> Result<(), EspError> {
+        self.rx().disable_light_sleep_wakeup()
+    }
+
+    /// Enables the detection of `count` consecutive `pattern_char` characters in the RX data,
+    /// e.g. `b'+', 3` for the "+++" escape sequence, or `b'\n', 1` for newline-delimited frames.
+    ///
+    /// See `UartRxDriver::enable_pattern_detection` for details.
+    pub fn enable_pattern_detection(
+        &self,
+        pattern_char: u8,
+        count: u8,
+        timing: config::PatternTiming,
+    ) -> Result<(), EspError> {
+        self.rx()
+            .enable_pattern_detection(pattern_char, count, timing)
+    }
+
+    /// Disables the pattern detection
+    pub fn disable_pattern_detection(&self) -> Result<(), EspError> {
+        self.rx().disable_pattern_detection()
+    }
+
+    /// Allocates a queue for up to `len` pattern positions, discarding the queued positions
+    pub fn pattern_queue_reset(&self, len: usize) -> Result<(), EspError> {
+        self.rx().pattern_queue_reset(len)
+    }
+
+    /// Removes and returns the position of the oldest detected pattern
+    ///
+    /// See `UartRxDriver::pop_pattern_position` for details.
+    pub fn pop_pattern_position(&self) -> Option<usize> {
+        self.rx().pop_pattern_position()
+    }
+
+    /// Removes and returns the position of the pattern reported by a `PatternDetected` event
+    ///
+    /// See `UartRxDriver::pop_detected_pattern_position` for details.
+    pub fn pop_detected_pattern_position(&self) -> Result<usize, PatternPositionLost> {
+        self.rx().pop_detected_pattern_position()
+    }
+
+    /// Returns the number of pattern positions lost since the last `pattern_queue_reset`
+    pub fn pattern_positions_lost(&self) -> u32 {
+        self.rx().pattern_positions_lost()
+    }
+
+    /// Returns the position of the oldest detected pattern, without removing it
+    pub fn pattern_position(&self) -> Option<usize> {
+        self.rx().pattern_position()
+    }
+
     /// Waits for the transmission to complete.
     #[deprecated(since = "0.41.3", note = "Use UartDriver::wait_tx_done instead")]
     pub fn flush_write(&self) -> Result<(), EspError> {
@@ -853,6 +1306,31 @@ impl<'d> UartDriver<'d> {
         self.tx().wait_done(timeout)
     }
 
+    /// Changes the inverted signals
+    pub fn set_signal_inversion(
+        &self,
+        signals: EnumSet<config::SignalInversion>,
+    ) -> Result<(), EspError> {
+        set_signal_inversion(self.port(), signals)
+    }
+
+    /// Enables or disables the internal loopback of TX to RX (and RTS to CTS)
+    pub fn set_loopback(&self, loopback: bool) -> Result<(), EspError> {
+        set_loopback(self.port(), loopback)
+    }
+
+    /// Asserts (`true`, RTS low) or de-asserts (`false`, RTS high) the RTS line,
+    /// e.g. to control the direction of an RS485 transceiver in `Mode::RS485AppControl`
+    pub fn set_rts(&self, active: bool) -> Result<(), EspError> {
+        self.tx().set_rts(active)
+    }
+
+    /// Returns `true` if a collision was detected during the last transmission,
+    /// in `Mode::RS485CollisionDetect` or `Mode::RS485AppControl`
+    pub fn collision_detected(&self) -> Result<bool, EspError> {
+        self.tx().collision_detected()
+    }
+
     pub fn port(&self) -> uart_port_t {
         self.port as _
     }
@@ -911,13 +1389,19 @@ impl<'d> embedded_io::ErrorType for UartDriver<'d> {
 
 impl<'d> embedded_io::Read for UartDriver<'d> {
     fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
-        UartDriver::read(self, buf, delay::BLOCK).map_err(EspIOError)
+        self.rx().read_io(buf).map_err(EspIOError)
+    }
+}
+
+impl<'d> embedded_io::ReadReady for UartDriver<'d> {
+    fn read_ready(&mut self) -> Result<bool, Self::Error> {
+        self.rx().read_ready_io().map_err(EspIOError)
     }
 }
 
 impl<'d> embedded_io::Write for UartDriver<'d> {
     fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
-        UartDriver::write(self, buf).map_err(EspIOError)
+        self.tx().write_io(buf).map_err(EspIOError)
     }
 
     fn flush(&mut self) -> Result<(), Self::Error> {
@@ -925,6 +1409,12 @@ impl<'d> embedded_io::Write for UartDriver<'d> {
     }
 }
 
+impl<'d> embedded_io::WriteReady for UartDriver<'d> {
+    fn write_ready(&mut self) -> Result<bool, Self::Error> {
+        self.tx().write_ready_io().map_err(EspIOError)
+    }
+}
+
 impl<'d> embedded_hal_0_2::serial::Read<u8> for UartDriver<'d> {
     type Error = SerialError;
 
@@ -1017,7 +1507,17 @@ impl<'d> UartRxDriver<'d> {
         self.queue.as_ref()
     }
 
+    /// Returns an iterator over the events of this UART, with both blocking and
+    /// timeout-based retrieval.
+    ///
+    /// Fails with `ESP_ERR_INVALID_STATE` if the config specified 0 for `queue_size`.
+    pub fn listen(&self) -> Result<UartEventIter<'_>, EspError> {
+        listen(self.queue.as_ref())
+    }
+
     /// Change the number of stop bits
+    ///
+    /// Waits for the pending TX data to be sent first. The pending RX data is preserved.
     pub fn change_stop_bits(&self, stop_bits: config::StopBits) -> Result<&Self, EspError> {
         change_stop_bits(self.port(), stop_bits).map(|_| self)
     }
@@ -1028,6 +1528,8 @@ impl<'d> UartRxDriver<'d> {
     }
 
     /// Change the number of data bits
+    ///
+    /// Waits for the pending TX data to be sent first. The pending RX data is preserved.
     pub fn change_data_bits(&self, data_bits: config::DataBits) -> Result<&Self, EspError> {
         change_data_bits(self.port(), data_bits).map(|_| self)
     }
@@ -1038,6 +1540,8 @@ impl<'d> UartRxDriver<'d> {
     }
 
     /// Change the type of parity checking
+    ///
+    /// Waits for the pending TX data to be sent first. The pending RX data is preserved.
     pub fn change_parity(&self, parity: config::Parity) -> Result<&Self, EspError> {
         change_parity(self.port(), parity).map(|_| self)
     }
@@ -1049,6 +1553,8 @@ impl<'d> UartRxDriver<'d> {
 
     /// Change the baudrate.
     ///
+    /// Waits for the pending TX data to be sent first. The pending RX data is preserved.
+    ///
     /// Will automatically select the clock source. When possible the reference clock (1MHz) will
     /// be used, because this is constant when the clock source/frequency changes.
     /// However if one of the clock frequencies is below 10MHz or if the baudrate is above
@@ -1059,6 +1565,9 @@ impl<'d> UartRxDriver<'d> {
     }
 
     /// Returns the current baudrate
+    ///
+    /// This is the baudrate actually achieved by the clock divider, which can slightly differ
+    /// from the requested one.
     pub fn baudrate(&self) -> Result<Hertz, EspError> {
         baudrate(self.port())
     }
@@ -1083,6 +1592,84 @@ impl<'d> UartRxDriver<'d> {
         }
     }
 
+    /// Read multiple bytes into a slice, waiting for up to `first_byte_timeout` ticks for
+    /// the first byte, and then only until the line is idle for `inter_byte_timeout`,
+    /// or until the slice is filled. Returns 0 if no byte was received in time.
+    ///
+    /// The inter-byte timeout is converted to character times at the current baudrate,
+    /// and set as the hardware RX timeout for the duration of the read, so that the received
+    /// bytes are delivered as soon as the line is idle. The hardware RX timeout is capped at
+    /// the chip maximum (126 character times on the ESP32, about 100 at 8N1 on the other
+    /// chips), and waiting for the idle line itself is rounded up to ticks.
+    pub fn read_timeout(
+        &self,
+        buf: &mut [u8],
+        first_byte_timeout: TickType_t,
+        inter_byte_timeout: Duration,
+    ) -> Result<usize, EspError> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let symbols = rx_timeout_symbols(self.port(), inter_byte_timeout)?;
+        esp!(unsafe { uart_set_rx_timeout(self.port(), symbols) })?;
+
+        let result = self.read_inter_byte_timeout(buf, first_byte_timeout, inter_byte_timeout);
+
+        let rx_timeout = RX_TIMEOUTS[self.port as usize].load(Ordering::SeqCst);
+        let restored = esp!(unsafe { uart_set_rx_timeout(self.port(), rx_timeout) });
+
+        let len = result?;
+        restored?;
+
+        Ok(len)
+    }
+
+    fn read_inter_byte_timeout(
+        &self,
+        buf: &mut [u8],
+        first_byte_timeout: TickType_t,
+        inter_byte_timeout: Duration,
+    ) -> Result<usize, EspError> {
+        let mut len = self.read(&mut buf[..1], first_byte_timeout)?;
+
+        if len > 0 {
+            // At least the inter-byte timeout, whatever the phase of the tick - this also
+            // covers the part of the timeout beyond the capped hardware RX timeout
+            let gap = TickType::from(inter_byte_timeout).ticks() + 1;
+
+            while len < buf.len() {
+                let read = self.read(&mut buf[len..], gap)?;
+
+                if read == 0 {
+                    break;
+                }
+
+                len += read;
+            }
+        }
+
+        Ok(len)
+    }
+
+    /// Fill the whole slice, waiting for up to `timeout` ticks
+    ///
+    /// Fails with `ReadTimeoutError::Timeout` - carrying the number of bytes read into
+    /// the slice - if the timeout expires first.
+    pub fn read_exact_timeout(
+        &self,
+        buf: &mut [u8],
+        timeout: TickType_t,
+    ) -> Result<(), ReadTimeoutError> {
+        let read = self.read(buf, timeout)?;
+
+        if read < buf.len() {
+            Err(ReadTimeoutError::Timeout { read })
+        } else {
+            Ok(())
+        }
+    }
+
     /// Clears the receive buffer.
     #[deprecated(since = "0.41.3", note = "Use `UartRxDriver::clear` instead")]
     pub fn flush(&self) -> Result<(), EspError> {
@@ -1095,6 +1682,153 @@ impl<'d> UartRxDriver<'d> {
         Ok(())
     }
 
+    /// Sets the timeout of the `embedded_io::Read` implementation, which otherwise blocks
+    /// until at least one byte is received
+    ///
+    /// The `embedded_io` reads fail with `ESP_ERR_TIMEOUT` once the timeout expires.
+    pub fn set_io_read_timeout(&self, timeout: TickType_t) {
+        IO_READ_TIMEOUTS[self.port as usize].store(timeout, Ordering::SeqCst);
+    }
+
+    /// Returns the timeout of the `embedded_io::Read` implementation
+    pub fn io_read_timeout(&self) -> TickType_t {
+        IO_READ_TIMEOUTS[self.port as usize].load(Ordering::SeqCst)
+    }
+
+    /// Waits for at least one byte, and reads as many of the received bytes as fit into `buf`
+    fn read_io(&self, buf: &mut [u8]) -> Result<usize, EspError> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        if self.read(&mut buf[..1], self.io_read_timeout())? == 0 {
+            return Err(EspError::from_infallible::<ESP_ERR_TIMEOUT>());
+        }
+
+        Ok(1 + self.read(&mut buf[1..], NON_BLOCK)?)
+    }
+
+    fn read_ready_io(&self) -> Result<bool, EspError> {
+        Ok(self.count()? > 0)
+    }
+
+    /// Enables waking up the chip from light sleep once `rising_edges_threshold` rising edges
+    /// (3 to 1023) are received on the RX line. The bytes causing the wakeup are lost.
+    ///
+    /// Only UART0 and UART1 support the wakeup, and only when their RX signal is routed via
+    /// the IOMUX, i.e. when the default RX pin of the UART is used (e.g. the console pins of
+    /// UART0), which is not checked. Fails with `ESP_ERR_INVALID_ARG` for the other UARTs.
+    ///
+    /// The wakeup is disabled again when the driver is dropped.
+    pub fn enable_light_sleep_wakeup(&self, rising_edges_threshold: u16) -> Result<(), EspError> {
+        if !(MIN_WAKEUP_THRESHOLD..=MAX_WAKEUP_THRESHOLD).contains(&rising_edges_threshold)
+            || self.port() > 1
+        {
+            return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
+        }
+
+        let _guard = WAKEUP_CS.enter();
+
+        esp!(unsafe { uart_set_wakeup_threshold(self.port(), rising_edges_threshold as _) })?;
+        esp!(unsafe { esp_sleep_enable_uart_wakeup(self.port()) })?;
+
+        WAKEUP_ENABLED[self.port as usize].store(true, Ordering::SeqCst);
+
+        Ok(())
+    }
+
+    /// Disables waking up the chip from light sleep
+    ///
+    /// The wakeup by the other UARTs, if enabled, stays enabled.
+    pub fn disable_light_sleep_wakeup(&self) -> Result<(), EspError> {
+        disable_light_sleep_wakeup(self.port())
+    }
+
+    /// Enables the detection of `count` consecutive `pattern_char` characters in the RX data,
+    /// e.g. `b'+', 3` for the "+++" escape sequence, or `b'\n', 1` for newline-delimited frames.
+    ///
+    /// Each detected pattern is reported by a `UartEventPayload::PatternDetected` event, and
+    /// its position in the RX buffer is queued. `pattern_queue_reset` should be called first,
+    /// to allocate the positions' queue.
+    pub fn enable_pattern_detection(
+        &self,
+        pattern_char: u8,
+        count: u8,
+        timing: config::PatternTiming,
+    ) -> Result<(), EspError> {
+        esp!(unsafe {
+            uart_enable_pattern_det_baud_intr(
+                self.port(),
+                pattern_char as _,
+                count,
+                timing.char_timeout as _,
+                timing.post_idle as _,
+                timing.pre_idle as _,
+            )
+        })
+    }
+
+    /// Disables the pattern detection
+    pub fn disable_pattern_detection(&self) -> Result<(), EspError> {
+        esp!(unsafe { uart_disable_pattern_det_intr(self.port()) })
+    }
+
+    /// Allocates a queue for up to `len` pattern positions, discarding the queued positions
+    /// and clearing the `pattern_positions_lost` counter
+    pub fn pattern_queue_reset(&self, len: usize) -> Result<(), EspError> {
+        // The ring buffer of the driver keeps one slot free
+        esp!(unsafe { uart_pattern_queue_reset(self.port(), len as i32 + 1) })?;
+
+        PATTERN_POSITIONS_LOST[self.port as usize].store(0, Ordering::SeqCst);
+
+        Ok(())
+    }
+
+    /// Removes and returns the position of the oldest detected pattern, i.e. the number of
+    /// bytes preceding the pattern in the RX buffer. Reading these bytes, followed by the
+    /// pattern itself, moves the next position to the start of the RX buffer.
+    ///
+    /// When the queue is full, the driver drops the positions of the newly detected patterns,
+    /// while still reporting their `PatternDetected` events. So if `None` is returned after a
+    /// `PatternDetected` event, the position of the pattern was lost, and the RX buffer
+    /// should be resynchronized, e.g. by clearing it. `pop_detected_pattern_position`
+    /// reports and counts these losses.
+    ///
+    /// The positions of patterns which were read past with `read` are dropped as well.
+    pub fn pop_pattern_position(&self) -> Option<usize> {
+        let pos = unsafe { uart_pattern_pop_pos(self.port()) };
+
+        (pos >= 0).then_some(pos as _)
+    }
+
+    /// Removes and returns the position of the pattern reported by a `PatternDetected` event,
+    /// and is meant to be called once for each such event
+    ///
+    /// Fails with `PatternPositionLost` if no position is queued, i.e. if the driver dropped
+    /// the position as the queue was full, or if the pattern was read past with `read`. Each
+    /// failure increments the `pattern_positions_lost` counter, and the RX buffer should be
+    /// resynchronized, e.g. by clearing it.
+    pub fn pop_detected_pattern_position(&self) -> Result<usize, PatternPositionLost> {
+        self.pop_pattern_position().ok_or_else(|| {
+            PATTERN_POSITIONS_LOST[self.port as usize].fetch_add(1, Ordering::SeqCst);
+
+            PatternPositionLost
+        })
+    }
+
+    /// Returns the number of pattern positions `pop_detected_pattern_position` found lost
+    /// since the last `pattern_queue_reset`
+    pub fn pattern_positions_lost(&self) -> u32 {
+        PATTERN_POSITIONS_LOST[self.port as usize].load(Ordering::SeqCst)
+    }
+
+    /// Returns the position of the oldest detected pattern, without removing it
+    pub fn pattern_position(&self) -> Option<usize> {
+        let pos = unsafe { uart_pattern_get_pos(self.port()) };
+
+        (pos >= 0).then_some(pos as _)
+    }
+
     pub fn port(&self) -> uart_port_t {
         self.port as _
     }
@@ -1117,7 +1851,13 @@ impl<'d> embedded_io::ErrorType for UartRxDriver<'d> {
 
 impl<'d> embedded_io::Read for UartRxDriver<'d> {
     fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
-        UartRxDriver::read(self, buf, delay::BLOCK).map_err(EspIOError)
+        self.read_io(buf).map_err(EspIOError)
+    }
+}
+
+impl<'d> embedded_io::ReadReady for UartRxDriver<'d> {
+    fn read_ready(&mut self) -> Result<bool, Self::Error> {
+        self.read_ready_io().map_err(EspIOError)
     }
 }
 
@@ -1194,6 +1934,8 @@ impl<'d> UartTxDriver<'d> {
     }
 
     /// Change the number of stop bits
+    ///
+    /// Waits for the pending TX data to be sent first. The pending RX data is preserved.
     pub fn change_stop_bits(&self, stop_bits: config::StopBits) -> Result<&Self, EspError> {
         change_stop_bits(self.port(), stop_bits).map(|_| self)
     }
@@ -1204,6 +1946,8 @@ impl<'d> UartTxDriver<'d> {
     }
 
     /// Change the number of data bits
+    ///
+    /// Waits for the pending TX data to be sent first. The pending RX data is preserved.
     pub fn change_data_bits(&self, data_bits: config::DataBits) -> Result<&Self, EspError> {
         change_data_bits(self.port(), data_bits).map(|_| self)
     }
@@ -1214,6 +1958,8 @@ impl<'d> UartTxDriver<'d> {
     }
 
     /// Change the type of parity checking
+    ///
+    /// Waits for the pending TX data to be sent first. The pending RX data is preserved.
     pub fn change_parity(&self, parity: config::Parity) -> Result<&Self, EspError> {
         change_parity(self.port(), parity).map(|_| self)
     }
@@ -1225,6 +1971,8 @@ impl<'d> UartTxDriver<'d> {
 
     /// Change the baudrate.
     ///
+    /// Waits for the pending TX data to be sent first. The pending RX data is preserved.
+    ///
     /// Will automatically select the clock source. When possible the reference clock (1MHz) will
     /// be used, because this is constant when the clock source/frequency changes.
     /// However if one of the clock frequencies is below 10MHz or if the baudrate is above
@@ -1235,6 +1983,9 @@ impl<'d> UartTxDriver<'d> {
     }
 
     /// Returns the current baudrate
+    ///
+    /// This is the baudrate actually achieved by the clock divider, which can slightly differ
+    /// from the requested one.
     pub fn baudrate(&self) -> Result<Hertz, EspError> {
         baudrate(self.port())
     }
@@ -1251,6 +2002,94 @@ impl<'d> UartTxDriver<'d> {
         }
     }
 
+    /// Write multiple bytes from a slice, followed by a break of `break_bits` bit times
+    /// (1 to 255), e.g. to end a LIN or DMX512 frame
+    ///
+    /// If the TX ring buffer is enabled, the bytes and the break are queued after the
+    /// data already in the ring buffer. Otherwise, this call blocks until the break is sent.
+    pub fn write_with_break(&mut self, bytes: &[u8], break_bits: u16) -> Result<usize, EspError> {
+        if bytes.is_empty() || break_bits == 0 || break_bits > 255 {
+            return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
+        }
+
+        let len = unsafe {
+            uart_write_bytes_with_break(
+                self.port(),
+                bytes.as_ptr().cast(),
+                bytes.len(),
+                break_bits as _,
+            )
+        };
+
+        if len >= 0 {
+            Ok(len as usize)
+        } else {
+            Err(EspError::from_infallible::<ESP_ERR_INVALID_STATE>())
+        }
+    }
+
+    /// Write an address byte with the 9th (parity) bit set, followed by `data` with the 9th
+    /// bit cleared, as used by the 9-bit multidrop protocols (e.g. on RS485 buses)
+    ///
+    /// The UART has no mark/space parity, so it is emulated by switching between even and odd
+    /// parity depending on the bits of each byte. As the TX has to be done before each switch,
+    /// this call blocks until all bytes are sent. The configured parity is restored afterwards.
+    ///
+    /// On the receiving side, the ESP-IDF driver reports parity errors only as
+    /// `UartEventPayload::ParityError` events, without the offending byte, so the received
+    /// address bytes cannot be reliably told apart from the data bytes.
+    pub fn write_9bit(&mut self, addr_byte: u8, data: &[u8]) -> Result<(), EspError> {
+        let parity = self.parity()?;
+
+        let result = self
+            .write_with_9th_bit(&[addr_byte], true)
+            .and_then(|_| self.write_with_9th_bit(data, false));
+
+        change_parity(self.port(), parity)?;
+
+        result
+    }
+
+    fn write_with_9th_bit(&mut self, bytes: &[u8], bit: bool) -> Result<(), EspError> {
+        let mut start = 0;
+
+        while start < bytes.len() {
+            let parity = ninth_bit_parity(bytes[start], bit);
+            let end = bytes[start..]
+                .iter()
+                .position(|byte| ninth_bit_parity(*byte, bit) != parity)
+                .map_or(bytes.len(), |len| start + len);
+
+            // Waits for the bytes with the previous parity to be sent
+            change_parity(self.port(), parity)?;
+
+            self.write(&bytes[start..end])?;
+
+            start = end;
+        }
+
+        self.wait_done(delay::BLOCK)
+    }
+
+    /// Send a break of `duration`, without any data, e.g. to start a DMX512 frame
+    ///
+    /// Waits until the data still in the TX ring buffer and FIFO is sent first. The break
+    /// is generated by inverting the idle TX line, busy-waiting for `duration`.
+    pub fn send_break(&mut self, duration: Duration) -> Result<(), EspError> {
+        self.wait_done(delay::BLOCK)?;
+
+        let inversion = INVERSIONS[self.port as usize].load(Ordering::SeqCst);
+
+        esp!(unsafe {
+            uart_set_line_inverse(
+                self.port(),
+                inversion ^ uart_signal_inv_t_UART_SIGNAL_TXD_INV,
+            )
+        })?;
+        delay::Ets::delay_us(duration.as_micros().min(u32::MAX as _) as _);
+        esp!(unsafe { uart_set_line_inverse(self.port(), inversion) })
+    }
+
     /// Write multiple bytes from a slice directly to the TX FIFO hardware.
     /// Returns the number of bytes written, where 0 would mean that the TX FIFO is full.
     ///
@@ -1271,12 +2110,60 @@ impl<'d> UartTxDriver<'d> {
     }
 
     /// Waits until the transmission is complete or until the specified timeout expires.
+    ///
+    /// The transmission is complete once the stop bit of the last byte has been sent,
+    /// which in `Mode::RS485HalfDuplex` is also when the RTS line is de-asserted.
     pub fn wait_done(&self, timeout: TickType_t) -> Result<(), EspError> {
         esp!(unsafe { uart_wait_tx_done(self.port(), timeout) })?;
 
         Ok(())
     }
 
+    /// Writes as many bytes as fit into the TX ring buffer, and only blocks if it is full
+    fn write_io(&mut self, bytes: &[u8]) -> Result<usize, EspError> {
+        match write_nb_buffered(self.port(), bytes)? {
+            0 if !bytes.is_empty() => self.write(bytes),
+            len => Ok(len),
+        }
+    }
+
+    fn write_ready_io(&self) -> Result<bool, EspError> {
+        #[cfg(any(
+            not(esp_idf_version_major = "4"),
+            all(
+                esp_idf_version_minor = "4",
+                not(any(esp_idf_version_patch = "0", esp_idf_version_patch = "1")),
+            ),
+        ))]
+        if TX_BUFFERED[self.port as usize].load(Ordering::SeqCst) {
+            return Ok(self.count()? > 0);
+        }
+
+        // Without a TX ring buffer, `write_io` writes into the TX FIFO, and only blocks if the FIFO
+        // is full. ESP-IDF does not report the free space of the FIFO, but a FIFO which is done
+        // transmitting is empty, so only then is the next write known not to block
+        match self.wait_done(0) {
+            Ok(()) => Ok(true),
+            Err(e) if e.code() == ESP_ERR_TIMEOUT => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Asserts (`true`, RTS low) or de-asserts (`false`, RTS high) the RTS line,
+    /// e.g. to control the direction of an RS485 transceiver in `Mode::RS485AppControl`
+    pub fn set_rts(&self, active: bool) -> Result<(), EspError> {
+        esp!(unsafe { uart_set_rts(self.port(), active as _) })
+    }
+
+    /// Returns `true` if a collision was detected during the last transmission,
+    /// in `Mode::RS485CollisionDetect` or `Mode::RS485AppControl`
+    pub fn collision_detected(&self) -> Result<bool, EspError> {
+        let mut collision = false;
+        esp!(unsafe { uart_get_collision_flag(self.port(), &mut collision) })?;
+
+        Ok(collision)
+    }
+
     /// Waits until the transmission is complete.
     #[deprecated(since = "0.41.3", note = "Use `UartTxDriver::wait_done` instead")]
     pub fn flush(&mut self) -> Result<(), EspError> {
@@ -1308,7 +2195,7 @@ impl<'d> Drop for UartTxDriver<'d> {
 
 impl<'d> embedded_io::Write for UartTxDriver<'d> {
     fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
-        UartTxDriver::write(self, buf).map_err(EspIOError)
+        self.write_io(buf).map_err(EspIOError)
     }
 
     fn flush(&mut self) -> Result<(), Self::Error> {
@@ -1316,6 +2203,12 @@ impl<'d> embedded_io::Write for UartTxDriver<'d> {
     }
 }
 
+impl<'d> embedded_io::WriteReady for UartTxDriver<'d> {
+    fn write_ready(&mut self) -> Result<bool, Self::Error> {
+        self.write_ready_io().map_err(EspIOError)
+    }
+}
+
 impl<'d> embedded_io::ErrorType for UartTxDriver<'d> {
     type Error = EspIOError;
 }
@@ -1439,6 +2332,11 @@ where
         )
     }
 
+    /// Waits until at least one byte is received, and reads as many of the received
+    /// bytes as fit into `buf`, without waiting for `buf` to be filled.
+    ///
+    /// The waiting is signalled by the data events of the driver. The future can be
+    /// dropped at any time, without affecting the driver.
     pub async fn read(&self, buf: &mut [u8]) -> Result<usize, EspError> {
         if buf.is_empty() {
             Ok(0)
@@ -1458,12 +2356,16 @@ where
         }
     }
 
+    /// Waits until at least one byte can be written, and writes as many of the bytes as
+    /// fit into the TX ring buffer, or into the TX FIFO if the TX ring buffer is disabled.
+    ///
+    /// The future can be dropped at any time, without affecting the driver.
     pub async fn write(&self, bytes: &[u8]) -> Result<usize, EspError> {
         if bytes.is_empty() {
             Ok(0)
         } else {
             loop {
-                let res = self.driver.borrow().write_nb(bytes);
+                let res = write_nb_buffered(self.driver.borrow().port(), bytes);
 
                 match res {
                     Ok(len) if len > 0 => return Ok(len),
@@ -1471,7 +2373,7 @@ where
                     _ => (),
                 }
 
-                // We cannot properly wait for the TX FIFO queue to become non-full
+                // We cannot properly wait for the TX ring buffer or FIFO to become non-full
                 // because the ESP IDF UART ISR does not notify us on that
                 //
                 // Instead, spin a busy loop, however still allowing other futures to be polled too.
@@ -1598,6 +2500,11 @@ where
         self.driver.borrow_mut()
     }
 
+    /// Waits until at least one byte is received, and reads as many of the received
+    /// bytes as fit into `buf`, without waiting for `buf` to be filled.
+    ///
+    /// The waiting is signalled by the data events of the driver. The future can be
+    /// dropped at any time, without affecting the driver.
     pub async fn read(&self, buf: &mut [u8]) -> Result<usize, EspError> {
         if buf.is_empty() {
             Ok(0)
@@ -1701,12 +2608,16 @@ where
         self.driver.borrow_mut()
     }
 
+    /// Waits until at least one byte can be written, and writes as many of the bytes as
+    /// fit into the TX ring buffer, or into the TX FIFO if the TX ring buffer is disabled.
+    ///
+    /// The future can be dropped at any time, without affecting the driver.
     pub async fn write(&self, bytes: &[u8]) -> Result<usize, EspError> {
         if bytes.is_empty() {
             Ok(0)
         } else {
             loop {
-                let res = self.driver.borrow().write_nb(bytes);
+                let res = write_nb_buffered(self.driver.borrow().port(), bytes);
 
                 match res {
                     Ok(len) if len > 0 => return Ok(len),
@@ -1714,7 +2625,7 @@ where
                     _ => (),
                 }
 
-                // We cannot properly wait for the TX FIFO queue to become non-full
+                // We cannot properly wait for the TX ring buffer or FIFO to become non-full
                 // because the ESP IDF UART ISR does not notify us on that
                 //
                 // Instead, spin a busy loop, however still allowing other futures to be polled too.
@@ -1858,6 +2769,24 @@ fn new_common<UART: Uart>(
     let cts = cts.map(|cts| cts.into_ref());
     let rts = rts.map(|rts| rts.into_ref());
 
+    if (config.mode != config::Mode::UART && config.flow_control != config::FlowControl::None)
+        || (config.mode.is_rs485_auto() && rts.is_none())
+    {
+        return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
+    }
+
+    if (config.flow_control.needs_rts() && rts.is_none())
+        || (config.flow_control.needs_cts() && cts.is_none())
+    {
+        return Err(EspError::from_infallible::<ESP_ERR_INVALID_ARG>());
+    }
+
+    if (rx.is_some() && config.rx_fifo_size <= UART_FIFO_SIZE)
+        || (tx.is_some() && config.tx_fifo_size != 0 && config.tx_fifo_size <= UART_FIFO_SIZE)
+    {
+        return Err(EspError::from_infallible::<ESP_ERR_INVALID_SIZE>());
+    }
+
     #[allow(clippy::needless_update)]
     let uart_config = uart_config_t {
         baud_rate: config.baudrate.0 as i32,
@@ -1901,7 +2830,8 @@ fn new_common<UART: Uart>(
             if rx.is_some() {
                 config.rx_fifo_size as _
             } else {
-                0
+                // The driver needs an rx buffer even without an rx pin
+                UART_FIFO_SIZE as i32 + 1
             },
             if tx.is_some() {
                 config.tx_fifo_size as _
@@ -1914,6 +2844,27 @@ fn new_common<UART: Uart>(
         )
     })?;
 
+    esp!(unsafe { uart_set_mode(UART::port(), config.mode.into()) })?;
+
+    set_signal_inversion(UART::port(), config.signal_inversion)?;
+    set_loopback(UART::port(), config.loopback)?;
+
+    if let config::FlowControl::Software {
+        xon_threshold,
+        xoff_threshold,
+    } = config.flow_control
+    {
+        esp!(unsafe { uart_set_sw_flow_ctrl(UART::port(), true, xon_threshold, xoff_threshold) })?;
+    }
+
+    IO_READ_TIMEOUTS[UART::port() as usize].store(delay::BLOCK, Ordering::SeqCst);
+    RX_TIMEOUTS[UART::port() as usize].store(
+        config.event_config.receive_timeout.unwrap_or(0),
+        Ordering::SeqCst,
+    );
+    TX_BUFFERED[UART::port() as usize]
+        .store(tx.is_some() && config.tx_fifo_size > 0, Ordering::SeqCst);
+
     // Configure interrupts after installing the driver
     // so it won't get overwritten.
     let usr_intrs = config.event_config.clone().into();
@@ -1931,6 +2882,7 @@ fn stop_bits(port: uart_port_t) -> Result<config::StopBits, EspError> {
 }
 
 fn change_stop_bits(port: uart_port_t, stop_bits: config::StopBits) -> Result<(), EspError> {
+    esp!(unsafe { uart_wait_tx_done(port, delay::BLOCK) })?;
     esp!(unsafe { uart_set_stop_bits(port, stop_bits.into()) })
 }
 
@@ -1943,6 +2895,7 @@ fn data_bits(port: uart_port_t) -> Result<config::DataBits, EspError> {
 }
 
 fn change_data_bits(port: uart_port_t, data_bits: config::DataBits) -> Result<(), EspError> {
+    esp!(unsafe { uart_wait_tx_done(port, delay::BLOCK) })?;
     esp!(unsafe { uart_set_word_length(port, data_bits.into()) })
 }
 
@@ -1952,6 +2905,7 @@ fn parity(port: uart_port_t) -> Result<config::Parity, EspError> {
 }
 
 fn change_parity(port: uart_port_t, parity: config::Parity) -> Result<(), EspError> {
+    esp!(unsafe { uart_wait_tx_done(port, delay::BLOCK) })?;
     esp!(unsafe { uart_set_parity(port, parity.into()) })
 }
 
@@ -1964,13 +2918,153 @@ fn baudrate(port: uart_port_t) -> Result<Hertz, EspError> {
 }
 
 fn change_baudrate<T: Into<Hertz> + Copy>(port: uart_port_t, baudrate: T) -> Result<(), EspError> {
+    esp!(unsafe { uart_wait_tx_done(port, delay::BLOCK) })?;
     esp!(unsafe { uart_set_baudrate(port, baudrate.into().into()) })
 }
 
+fn listen(queue: Option<&Queue<UartEvent>>) -> Result<UartEventIter<'_>, EspError> {
+    queue
+        .map(|queue| UartEventIter { queue })
+        .ok_or_else(EspError::from_infallible::<ESP_ERR_INVALID_STATE>)
+}
+
+fn set_signal_inversion(
+    port: uart_port_t,
+    signals: EnumSet<config::SignalInversion>,
+) -> Result<(), EspError> {
+    let inversion = config::SignalInversion::to_native(signals);
+
+    esp!(unsafe { uart_set_line_inverse(port, inversion) })?;
+
+    INVERSIONS[port as usize].store(inversion, Ordering::SeqCst);
+
+    Ok(())
+}
+
+#[cfg(not(any(esp_idf_version_major = "4", esp_idf_version = "5.0")))]
+fn set_loopback(port: uart_port_t, loopback: bool) -> Result<(), EspError> {
+    esp!(unsafe { uart_set_loop_back(port, loopback) })
+}
+
+#[cfg(any(esp_idf_version_major = "4", esp_idf_version = "5.0"))]
+fn set_loopback(_port: uart_port_t, loopback: bool) -> Result<(), EspError> {
+    // `uart_set_loop_back` is only available since ESP-IDF 5.1
+    if loopback {
+        Err(EspError::from_infallible::<ESP_ERR_NOT_SUPPORTED>())
+    } else {
+        Ok(())
+    }
+}
+
+/// Returns the parity resulting in the 9th (parity) bit of `byte` being `bit`
+fn ninth_bit_parity(byte: u8, bit: bool) -> config::Parity {
+    // With even parity, the parity bit is set for an odd number of ones
+    if (byte.count_ones() % 2 == 1) == bit {
+        config::Parity::ParityEven
+    } else {
+        config::Parity::ParityOdd
+    }
+}
+
+/// Converts `timeout` to the number of character times at the current baudrate, capped at
+/// the maximum hardware RX timeout
+fn rx_timeout_symbols(port: uart_port_t, timeout: Duration) -> Result<u8, EspError> {
+    let data_bits = match data_bits(port)? {
+        config::DataBits::DataBits5 => 5,
+        config::DataBits::DataBits6 => 6,
+        config::DataBits::DataBits7 => 7,
+        config::DataBits::DataBits8 => 8,
+    };
+
+    let parity_bits = match parity(port)? {
+        config::Parity::ParityNone => 0,
+        _ => 1,
+    };
+
+    let stop_bits = match stop_bits(port)? {
+        config::StopBits::STOP1 => 1,
+        _ => 2,
+    };
+
+    // Including the start bit
+    let symbol_bits: u128 = 1 + data_bits + parity_bits + stop_bits;
+    let baudrate = baudrate(port)?.0 as u128;
+
+    let symbols = (timeout.as_micros() * baudrate).div_ceil(symbol_bits * 1_000_000);
+
+    // The ESP32 counts the RX timeout in character times, in a 7-bit field, while the
+    // other chips count it in bit times, in a 10-bit field
+    #[cfg(esp32)]
+    let max_symbols = 126;
+    #[cfg(not(esp32))]
+    let max_symbols = 0x3ff / symbol_bits;
+
+    Ok(symbols.clamp(1, max_symbols) as _)
+}
+
+fn disable_light_sleep_wakeup(port: uart_port_t) -> Result<(), EspError> {
+    let _guard = WAKEUP_CS.enter();
+
+    WAKEUP_ENABLED[port as usize].store(false, Ordering::SeqCst);
+
+    // The UARTs share a single wakeup source, so it is disabled as a whole, and then
+    // re-enabled for the UARTs still waking up the chip
+    esp!(unsafe { esp_sleep_disable_wakeup_source(esp_sleep_source_t_ESP_SLEEP_WAKEUP_UART) })?;
+
+    for (other, enabled) in WAKEUP_ENABLED.iter().enumerate() {
+        if enabled.load(Ordering::SeqCst) {
+            esp!(unsafe { esp_sleep_enable_uart_wakeup(other as _) })?;
+        }
+    }
+
+    Ok(())
+}
+
 fn delete_driver(port: uart_port_t) -> Result<(), EspError> {
+    if WAKEUP_ENABLED[port as usize].load(Ordering::SeqCst) {
+        disable_light_sleep_wakeup(port)?;
+    }
+
     esp!(unsafe { uart_driver_delete(port) })
 }
 
+/// Writes as many bytes as possible without blocking: into the TX ring buffer if the driver
+/// has one, so that the bytes are not sent ahead of the ones still in the ring buffer, or
+/// directly into the TX FIFO otherwise
+fn write_nb_buffered(port: uart_port_t, bytes: &[u8]) -> Result<usize, EspError> {
+    #[cfg(any(
+        not(esp_idf_version_major = "4"),
+        all(
+            esp_idf_version_minor = "4",
+            not(any(esp_idf_version_patch = "0", esp_idf_version_patch = "1")),
+        ),
+    ))]
+    if TX_BUFFERED[port as usize].load(Ordering::SeqCst) {
+        let len = remaining_write_capacity(port)?.min(bytes.len());
+
+        if len == 0 {
+            return Ok(0);
+        }
+
+        // Does not block, as the bytes fit into the ring buffer
+        let len = unsafe { uart_write_bytes(port, bytes.as_ptr().cast(), len) };
+
+        return if len >= 0 {
+            Ok(len as usize)
+        } else {
+            Err(EspError::from_infallible::<ESP_ERR_INVALID_STATE>())
+        };
+    }
+
+    let len = unsafe { uart_tx_chars(port, bytes.as_ptr().cast(), bytes.len() as _) };
+
+    if len < 0 {
+        esp!(len)?;
+    }
+
+    Ok(len as usize)
+}
+
 pub fn remaining_unread_bytes(port: uart_port_t) -> Result<usize, EspError> {
     let mut size = 0;
     esp_result!(unsafe { uart_get_buffered_data_len(port, &mut size) }, size)
@@ -2002,7 +3096,8 @@ impl Owner {
         let needs_drop = match self {
             Owner::Owned => true,
             Owner::Borrowed => false,
-            Owner::Shared => REFS[port as usize].fetch_sub(1, Ordering::SeqCst) == 0,
+            // The last of the halves
+            Owner::Shared => REFS[port as usize].fetch_sub(1, Ordering::SeqCst) == 1,
         };
         needs_drop.then(|| delete_driver(port)).unwrap_or(Ok(()))
     }
@@ -2051,6 +3146,41 @@ impl_uart!(UART2: 2);
 const NO_REFS: AtomicU8 = AtomicU8::new(0);
 static REFS: [AtomicU8; SOC_UART_NUM as usize] = [NO_REFS; SOC_UART_NUM as usize];
 
+#[allow(clippy::declare_interior_mutable_const)]
+const NOT_BUFFERED: AtomicBool = AtomicBool::new(false);
+static TX_BUFFERED: [AtomicBool; SOC_UART_NUM as usize] = [NOT_BUFFERED; SOC_UART_NUM as usize];
+
+#[allow(clippy::declare_interior_mutable_const)]
+const IO_READ_TIMEOUT: AtomicU32 = AtomicU32::new(delay::BLOCK);
+static IO_READ_TIMEOUTS: [AtomicU32; SOC_UART_NUM as usize] =
+    [IO_READ_TIMEOUT; SOC_UART_NUM as usize];
+
+// The hardware RX timeout configured with `EventConfig::receive_timeout`, restored after
+// `read_timeout`
+#[allow(clippy::declare_interior_mutable_const)]
+const NO_RX_TIMEOUT: AtomicU8 = AtomicU8::new(0);
+static RX_TIMEOUTS: [AtomicU8; SOC_UART_NUM as usize] = [NO_RX_TIMEOUT; SOC_UART_NUM as usize];
+
+#[allow(clippy::declare_interior_mutable_const)]
+const NO_INVERSION: AtomicU32 = AtomicU32::new(0);
+static INVERSIONS: [AtomicU32; SOC_UART_NUM as usize] = [NO_INVERSION; SOC_UART_NUM as usize];
+
+// The rising edges threshold of `uart_set_wakeup_threshold`, which is stored - less the
+// minimum - in a 10-bit field on all chips
+const MIN_WAKEUP_THRESHOLD: u16 = 3;
+const MAX_WAKEUP_THRESHOLD: u16 = 0x3ff;
+
+static WAKEUP_CS: task::CriticalSection = task::CriticalSection::new();
+
+#[allow(clippy::declare_interior_mutable_const)]
+const NO_WAKEUP: AtomicBool = AtomicBool::new(false);
+static WAKEUP_ENABLED: [AtomicBool; SOC_UART_NUM as usize] = [NO_WAKEUP; SOC_UART_NUM as usize];
+
+#[allow(clippy::declare_interior_mutable_const)]
+const NO_LOST_POSITIONS: AtomicU32 = AtomicU32::new(0);
+static PATTERN_POSITIONS_LOST: [AtomicU32; SOC_UART_NUM as usize] =
+    [NO_LOST_POSITIONS; SOC_UART_NUM as usize];
+
 #[allow(clippy::declare_interior_mutable_const)]
 const NOTIF: Notification = Notification::new();
 static READ_NOTIFS: [Notification; SOC_UART_NUM as usize] = [NOTIF; SOC_UART_NUM as usize];